@@ -1,13 +1,15 @@
-use std::{fmt, collections::BTreeMap};
+use std::fmt;
+use std::iter::FromIterator;
 
-use combine::{parser, ParseResult, Parser};
-use combine::easy::Error;
+use combine::{parser, ParseResult, Parser, Positioned};
+use combine::easy::{Error, Errors};
+use combine::error::Consumed;
 use combine::error::StreamError;
-use combine::combinator::{many, many1, optional, position, choice};
+use combine::combinator::{many, many1, optional, position};
 
-use crate::tokenizer::{Kind as T, Token, TokenStream};
+use crate::tokenizer::{int_out_of_range_error, Kind as T, Token, TokenStream};
 use crate::helpers::{punct, ident, kind, name};
-use crate::position::Pos;
+use crate::position::{advance, Pos};
 
 /// Text abstracts over types that hold a string value.
 /// It is used to make the AST generic over the string type.
@@ -27,7 +29,28 @@ impl<'a> Text<'a> for std::borrow::Cow<'a, str> {
     type Value = Self;
 }
 
+impl<'a> Text<'a> for std::sync::Arc<str> {
+    type Value = Self;
+}
+
+#[cfg(feature = "smolstr")]
+impl<'a> Text<'a> for smol_str::SmolStr {
+    type Value = Self;
+}
+
+/// `Bytes` can't be used as text directly, as it isn't known to be UTF-8,
+/// see `parse_query_bytes` for slicing names out of a `Bytes` source
+#[cfg(feature = "bytes")]
+impl<'a> Text<'a> for bytestring::ByteString {
+    type Value = Self;
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct Directive<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: T::Value,
@@ -40,41 +63,492 @@ pub struct Directive<'a, T: Text<'a>> {
 /// (only in implemetation), we do a trick similar to the one
 /// in `serde_json`: encapsulate value in new-type, allowing type
 /// to be extended later.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "i64", into = "i64"))]
 // we use i64 as a reference implementation: graphql-js thinks even 32bit
 // integers is enough. We might consider lift this limit later though
-pub struct Number(pub(crate) i64);
+pub struct Number {
+    pub(crate) value: i64,
+    /// The literal as written in the source, kept only when it isn't
+    /// printed the same way as `value`, i.e. for `-0`
+    pub(crate) raw: Option<Box<str>>,
+}
+
+/// This represents a float number
+///
+/// The literal is kept as written in the source (e.g. `1.50` or `1e3`)
+/// so that it can be printed back exactly, see `persisted::print`.
+/// Comparison only looks at the value.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "f64", into = "f64"))]
+pub struct Float {
+    pub(crate) value: f64,
+    pub(crate) raw: Option<Box<str>>,
+}
+
+/// Fields of an input object literal, in the order of the source
+///
+/// Behaves like a map: setting a field twice keeps the last value, and
+/// comparison doesn't depend on the order of the fields.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct ObjectFields<'a, T: Text<'a>> {
+    fields: Vec<(T::Value, Value<'a, T>)>,
+}
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub enum Value<'a, T: Text<'a>> {
     Variable(T::Value),
     Int(Number),
-    Float(f64),
+    Float(Float),
     String(String),
+    /// A `"""block string"""` literal
+    ///
+    /// Has the same meaning as `String`, but is printed as a block string
+    /// so that multi-line text keeps its formatting.
+    BlockString(String),
     Boolean(bool),
     Null,
     Enum(T::Value),
     List(Vec<Value<'a, T>>),
-    Object(BTreeMap<T::Value, Value<'a, T>>),
+    Object(ObjectFields<'a, T>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub enum Type<'a, T: Text<'a>> {
     NamedType(T::Value),
     ListType(Box<Type<'a, T>>),
     NonNullType(Box<Type<'a, T>>),
 }
 
+/// Implements `map` and `into_owned` for AST nodes having a `map_text`
+/// method
+macro_rules! impl_map_text {
+    ($( $typ: ident ),* $(,)?) => {
+        $(
+            impl<'a, T: Text<'a>> $typ<'a, T> {
+                /// Converts the node to another text type, applying `f` to
+                /// every name in it
+                ///
+                /// String values and descriptions are always `String`, so
+                /// they are kept as is.
+                pub fn map<'b, U, F>(self, mut f: F) -> $typ<'b, U>
+                    where U: Text<'b>,
+                          F: FnMut(T::Value) -> U::Value,
+                {
+                    self.map_text(&mut f)
+                }
+
+                /// Converts the node into one owning all of its text
+                ///
+                /// The result doesn't borrow from the source string, so it
+                /// can outlive the source and be sent between threads.
+                pub fn into_owned(self) -> $typ<'static, String> {
+                    self.map(|value: T::Value| value.as_ref().to_string())
+                }
+            }
+        )*
+    };
+}
+
+impl<'a, T: Text<'a>> Directive<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> Directive<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        Directive {
+            position: self.position,
+            name: f(self.name),
+            arguments: map_arguments(self.arguments, f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Value<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> Value<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        match self {
+            Value::Variable(name) => Value::Variable(f(name)),
+            Value::Int(num) => Value::Int(num),
+            Value::Float(num) => Value::Float(num),
+            Value::String(val) => Value::String(val),
+            Value::BlockString(val) => Value::BlockString(val),
+            Value::Boolean(val) => Value::Boolean(val),
+            Value::Null => Value::Null,
+            Value::Enum(name) => Value::Enum(f(name)),
+            Value::List(items) => Value::List(
+                items.into_iter().map(|item| item.map_text(f)).collect()),
+            Value::Object(fields) => Value::Object(
+                fields.into_iter()
+                    .map(|(name, value)| (f(name), value.map_text(f)))
+                    .collect()),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Type<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> Type<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        match self {
+            Type::NamedType(name) => Type::NamedType(f(name)),
+            Type::ListType(inner) => Type::ListType(Box::new(inner.map_text(f))),
+            Type::NonNullType(inner) => {
+                Type::NonNullType(Box::new(inner.map_text(f)))
+            }
+        }
+    }
+}
+
+pub(crate) fn map_arguments<'a, 'b, T, U, F>(arguments: Arguments<'a, T>,
+    f: &mut F)
+    -> Arguments<'b, U>
+    where T: Text<'a>,
+          U: Text<'b>,
+          F: FnMut(T::Value) -> U::Value,
+{
+    arguments.into_iter()
+        .map(|(name, value)| (f(name), value.map_text(f)))
+        .collect()
+}
+
+pub(crate) fn map_directives<'a, 'b, T, U, F>(
+    directives: Vec<Directive<'a, T>>, f: &mut F)
+    -> Vec<Directive<'b, U>>
+    where T: Text<'a>,
+          U: Text<'b>,
+          F: FnMut(T::Value) -> U::Value,
+{
+    directives.into_iter().map(|dir| dir.map_text(f)).collect()
+}
+
+impl_map_text!(Directive, Value, Type);
+
+impl<'a, T> Directive<'a, T>
+    where T: Text<'a>
+{
+    pub fn new(name: T::Value) -> Self {
+        Self {
+            position: Pos::default(),
+            name,
+            arguments: vec![],
+        }
+    }
+
+    pub fn argument(mut self, name: T::Value, value: Value<'a, T>) -> Self {
+        self.arguments.push((name, value));
+        self
+    }
+
+    /// Returns the value of the argument `name`, if it's present
+    pub fn get_argument(&self, name: &str) -> Option<&Value<'a, T>> {
+        self.arguments.iter()
+            .find(|(arg, _)| arg.as_ref() == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the value of the argument `name` if it's a boolean literal
+    ///
+    /// Returns `None` for variables, use `apply_variables` first to
+    /// substitute them.
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        match self.get_argument(name) {
+            Some(Value::Boolean(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the argument `name` if it's a string literal
+    pub fn get_string(&self, name: &str) -> Option<&str> {
+        match self.get_argument(name) {
+            Some(Value::String(value)) | Some(Value::BlockString(value)) => {
+                Some(value)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<'a, T> Type<'a, T>
+    where T: Text<'a>
+{
+    /// Returns a type referenced by name, e.g. `String`
+    pub fn named<N: Into<T::Value>>(name: N) -> Self {
+        Type::NamedType(name.into())
+    }
+
+    /// Returns a list of `item` type, `[item]`
+    pub fn list(item: Type<'a, T>) -> Self {
+        Type::ListType(Box::new(item))
+    }
+
+    /// Returns a non-null variant of the type, `inner!`
+    pub fn non_null(inner: Type<'a, T>) -> Self {
+        Type::NonNullType(Box::new(inner))
+    }
+}
+
+/// Returns the name of the type with all list and non-null wrappers
+/// stripped, e.g. `String` for `[String!]!`
+pub fn get_named_type<'t, 'a, T>(typ: &'t Type<'a, T>) -> &'t T::Value
+    where T: Text<'a>,
+{
+    match *typ {
+        Type::NamedType(ref name) => name,
+        Type::ListType(ref inner) | Type::NonNullType(ref inner) => {
+            get_named_type(inner)
+        }
+    }
+}
+
+/// Checks whether the type is non-null, e.g. `String!` or `[String]!`
+pub fn is_non_null<'a, T: Text<'a>>(typ: &Type<'a, T>) -> bool {
+    matches!(*typ, Type::NonNullType(_))
+}
+
+/// Checks whether the type is a list, either nullable or not, e.g.
+/// `[String]` or `[String]!`
+pub fn is_list<'a, T: Text<'a>>(typ: &Type<'a, T>) -> bool {
+    matches!(*unwrap_non_null(typ), Type::ListType(_))
+}
+
+/// Returns the nullable variant of the type, e.g. `[String!]` for
+/// `[String!]!`
+pub fn unwrap_non_null<'t, 'a, T>(typ: &'t Type<'a, T>) -> &'t Type<'a, T>
+    where T: Text<'a>,
+{
+    match *typ {
+        Type::NonNullType(ref inner) => inner,
+        _ => typ,
+    }
+}
+
 impl Number {
     /// Returns a number as i64 if it fits the type
     pub fn as_i64(&self) -> Option<i64> {
-        Some(self.0)
+        Some(self.value)
+    }
+
+    /// Returns a number as f64, possibly losing precision
+    pub fn as_f64(&self) -> f64 {
+        self.value as f64
+    }
+
+    /// Returns the literal as written in the source, if it isn't printed
+    /// the same way as the value
+    pub(crate) fn raw(&self) -> Option<&str> {
+        self.raw.as_deref()
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Number) -> bool {
+        self.value == other.value
+    }
+}
+
+impl From<i64> for Number {
+    fn from(value: i64) -> Self {
+        Number { value, raw: None }
     }
 }
 
 impl From<i32> for Number {
     fn from(i: i32) -> Self {
-        Number(i as i64)
+        Number::from(i as i64)
+    }
+}
+
+impl From<Number> for i64 {
+    fn from(num: Number) -> i64 {
+        num.value
+    }
+}
+
+impl Float {
+    /// Returns the number as f64
+    pub fn as_f64(&self) -> f64 {
+        self.value
+    }
+
+    /// Returns the literal as written in the source, if the value was
+    /// parsed
+    pub(crate) fn raw(&self) -> Option<&str> {
+        self.raw.as_deref()
+    }
+}
+
+impl PartialEq for Float {
+    fn eq(&self, other: &Float) -> bool {
+        self.value == other.value
+    }
+}
+
+impl From<f64> for Float {
+    fn from(value: f64) -> Self {
+        Float { value, raw: None }
+    }
+}
+
+impl From<Float> for f64 {
+    fn from(num: Float) -> f64 {
+        num.value
+    }
+}
+
+impl<'a, T: Text<'a>> ObjectFields<'a, T> {
+    pub fn new() -> Self {
+        ObjectFields { fields: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Returns the value of the field `name`, if it's present
+    pub fn get(&self, name: &str) -> Option<&Value<'a, T>> {
+        self.fields.iter()
+            .find(|(field, _)| field.as_ref() == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Sets the field `name`, returning its previous value
+    ///
+    /// A new field is added at the end, an existing one keeps its place.
+    pub fn insert(&mut self, name: T::Value, value: Value<'a, T>)
+        -> Option<Value<'a, T>>
+    {
+        match self.fields.iter_mut().find(|(field, _)| *field == name) {
+            Some((_, old)) => Some(std::mem::replace(old, value)),
+            None => {
+                self.fields.push((name, value));
+                None
+            }
+        }
+    }
+
+    /// Keeps only the fields for which `f` returns `true`
+    pub fn retain<F>(&mut self, mut f: F)
+        where F: FnMut(&T::Value, &mut Value<'a, T>) -> bool,
+    {
+        self.fields.retain_mut(|(name, value)| f(name, value))
+    }
+
+    /// Iterates over the fields in the order of the source
+    pub fn iter(&self)
+        -> impl Iterator<Item=(&T::Value, &Value<'a, T>)> + '_
+    {
+        self.fields.iter().map(|(name, value)| (name, value))
+    }
+
+    pub fn iter_mut(&mut self)
+        -> impl Iterator<Item=(&T::Value, &mut Value<'a, T>)> + '_
+    {
+        self.fields.iter_mut().map(|(name, value)| (&*name, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item=&T::Value> + '_ {
+        self.fields.iter().map(|(name, _)| name)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item=&Value<'a, T>> + '_ {
+        self.fields.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self)
+        -> impl Iterator<Item=&mut Value<'a, T>> + '_
+    {
+        self.fields.iter_mut().map(|(_, value)| value)
+    }
+
+    /// Returns the fields sorted by name
+    pub fn sorted(&self) -> Vec<(&T::Value, &Value<'a, T>)> {
+        let mut fields = self.iter().collect::<Vec<_>>();
+        fields.sort_by(|a, b| a.0.cmp(b.0));
+        fields
+    }
+}
+
+impl<'a, T: Text<'a>> Default for ObjectFields<'a, T> {
+    fn default() -> Self {
+        ObjectFields::new()
+    }
+}
+
+impl<'a, T> PartialEq for ObjectFields<'a, T>
+    where T: Text<'a> + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() &&
+            self.iter().all(|(name, value)| {
+                other.get(name.as_ref()) == Some(value)
+            })
+    }
+}
+
+impl<'a, T: Text<'a>> FromIterator<(T::Value, Value<'a, T>)>
+    for ObjectFields<'a, T>
+{
+    fn from_iter<I>(iter: I) -> Self
+        where I: IntoIterator<Item=(T::Value, Value<'a, T>)>,
+    {
+        let mut fields = ObjectFields::new();
+        fields.extend(iter);
+        fields
+    }
+}
+
+impl<'a, T: Text<'a>> Extend<(T::Value, Value<'a, T>)>
+    for ObjectFields<'a, T>
+{
+    fn extend<I>(&mut self, iter: I)
+        where I: IntoIterator<Item=(T::Value, Value<'a, T>)>,
+    {
+        for (name, value) in iter {
+            self.insert(name, value);
+        }
+    }
+}
+
+impl<'f, 'a, T: Text<'a>> IntoIterator for &'f ObjectFields<'a, T> {
+    type Item = &'f (T::Value, Value<'a, T>);
+    type IntoIter = std::slice::Iter<'f, (T::Value, Value<'a, T>)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.fields.iter()
+    }
+}
+
+impl<'a, T: Text<'a>> IntoIterator for ObjectFields<'a, T> {
+    type Item = (T::Value, Value<'a, T>);
+    type IntoIter = std::vec::IntoIter<(T::Value, Value<'a, T>)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.fields.into_iter()
     }
 }
 
@@ -92,8 +566,12 @@ pub fn directives<'a, T>(input: &mut TokenStream<'a>)
     .parse_stream(input)
 }
 
+/// A list of `name: value` pairs as used for field and directive arguments
+#[allow(type_alias_bounds)]
+pub(crate) type Arguments<'a, T: Text<'a>> = Vec<(T::Value, Value<'a, T>)>;
+
 pub fn arguments<'a, T>(input: &mut TokenStream<'a>)
-    -> ParseResult<Vec<(T::Value, Value<'a, T>)>, TokenStream<'a>>
+    -> ParseResult<Arguments<'a, T>, TokenStream<'a>>
     where T: Text<'a>,
 {
     optional(
@@ -112,119 +590,195 @@ pub fn int_value<'a, S>(input: &mut TokenStream<'a>)
     -> ParseResult<Value<'a, S>, TokenStream<'a>>
     where S: Text<'a>
 {
-    kind(T::IntValue).and_then(|tok| tok.value.parse())
-            .map(Number).map(Value::Int)
-    .parse_stream(input)
+    let start = input.position();
+    let (token, consumed) = kind(T::IntValue).parse_stream(input)?;
+    // the tokenizer only lets through valid literals, so they only fail to
+    // parse when they don't fit
+    match token.value.parse() {
+        Ok(value) => {
+            // `-0` is the only literal not printed back as written
+            let raw = if value == 0 && token.value.starts_with('-') {
+                Some(token.value.into())
+            } else {
+                None
+            };
+            Ok((Value::Int(Number { value, raw }), consumed))
+        }
+        Err(_) => Err(Consumed::Consumed(Errors::new(start,
+            int_out_of_range_error(token.value)).into())),
+    }
 }
 
 pub fn float_value<'a, S>(input: &mut TokenStream<'a>)
     -> ParseResult<Value<'a, S>, TokenStream<'a>>
     where S: Text<'a>
 {
-    kind(T::FloatValue).and_then(|tok| tok.value.parse())
-            .map(Value::Float)
+    kind(T::FloatValue).and_then(|tok| tok.value.parse().map(|value| {
+            Value::Float(Float { value, raw: Some(tok.value.into()) })
+        }))
     .parse_stream(input)
 }
 
-fn unquote_block_string<'a>(src: &'a str) -> Result<String, Error<Token<'a>, Token<'a>>> {
+pub(crate) fn unquote_block_string<'a>(src: &'a str) -> Result<String, Error<Token<'a>, Token<'a>>> {
     debug_assert!(src.starts_with("\"\"\"") && src.ends_with("\"\"\""));
-    let indent = src[3..src.len()-3].lines().skip(1)
-        .filter_map(|line| {
-            let trimmed = line.trim_start().len();
-            if trimmed > 0 {
-                Some(line.len() - trimmed)
-            } else {
-                None  // skip whitespace-only lines
-            }
-        })
+    let raw = src[3..src.len()-3].replace(r#"\""""#, r#"""""#);
+    let raw = if raw.contains('\r') {
+        raw.replace("\r\n", "\n").replace('\r', "\n")
+    } else {
+        raw
+    };
+    let lines = raw.split('\n').collect::<Vec<_>>();
+    let is_blank = |line: &str| line.chars().all(|c| c == ' ' || c == '\t');
+    // the first line is not taken into account, so that
+    // `"""text` aligns with the lines below
+    let indent = lines[1..].iter()
+        .filter(|line| !is_blank(line))
+        .map(|line| line.len() - line.trim_start_matches(&[' ', '\t'][..]).len())
         .min().unwrap_or(0);
-    let mut result = String::with_capacity(src.len()-6);
-    let mut lines = src[3..src.len()-3].lines();
-    if let Some(first) = lines.next() {
-        let stripped = first.trim();
-        if !stripped.is_empty() {
-            result.push_str(stripped);
+    let first = match lines.iter().position(|line| !is_blank(line)) {
+        Some(first) => first,
+        None => return Ok(String::new()),
+    };
+    let last = lines.iter().rposition(|line| !is_blank(line))
+        .unwrap_or(first);
+
+    let mut result = String::with_capacity(raw.len());
+    for (idx, line) in lines.iter().enumerate().take(last + 1).skip(first) {
+        if idx > first {
             result.push('\n');
         }
-    }
-    let mut last_line = 0;
-    for line in lines {
-        last_line = result.len();
-        if line.len() > indent {
-            result.push_str(&line[indent..].replace(r#"\""""#, r#"""""#));
+        if idx == 0 {
+            result.push_str(line);
+        } else {
+            result.push_str(line.get(indent..).unwrap_or(""));
         }
-        result.push('\n');
-    }
-    if result[last_line..].trim().is_empty() {
-        result.truncate(last_line);
     }
 
     Ok(result)
 }
 
-fn unquote_string<'a>(s: &'a str) -> Result<String, Error<Token, Token>> 
+/// Unescapes a string literal
+///
+/// Errors come with the offset of the invalid escape sequence in the
+/// literal.
+fn unquote_string(s: &str)
+    -> Result<String, (usize, Error<Token<'_>, Token<'_>>)>
 {
     let mut res = String::with_capacity(s.len());
     debug_assert!(s.starts_with('"') && s.ends_with('"'));
-    let mut chars = s[1..s.len()-1].chars();
-    let mut temp_code_point = String::with_capacity(4);
-    while let Some(c) = chars.next() {
-        match c {
-            '\\' => {
-                match chars.next().expect("slash cant be at the end") {
-                    c@'"' | c@'\\' | c@'/' => res.push(c),
-                    'b' => res.push('\u{0010}'),
-                    'f' => res.push('\u{000C}'),
-                    'n' => res.push('\n'),
-                    'r' => res.push('\r'),
-                    't' => res.push('\t'),
-                    'u' => {
-                        temp_code_point.clear();
-                        for _ in 0..4 {
-                            match chars.next() {
-                                Some(inner_c) => temp_code_point.push(inner_c),
-                                None => return Err(Error::unexpected_message(
-                                    format_args!("\\u must have 4 characters after it, only found '{}'", temp_code_point)
-                                )),
-                            }
-                        }
-
-                        // convert our hex string into a u32, then convert that into a char
-                        match u32::from_str_radix(&temp_code_point, 16).map(std::char::from_u32) {
-                            Ok(Some(unicode_char)) => res.push(unicode_char),
-                            _ => {
-                                return Err(Error::unexpected_message(
-                                    format_args!("{} is not a valid unicode code point", temp_code_point)))
-                            }
-                        }
-                    },
-                    c => {
-                        return Err(Error::unexpected_message(
-                            format_args!("bad escaped char {:?}", c)));
-                    }
+    let inner = &s[1..s.len()-1];
+    let mut chars = inner.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        if c != '\\' {
+            res.push(c);
+            continue;
+        }
+        match chars.next().expect("slash cant be at the end").1 {
+            c@'"' | c@'\\' | c@'/' => res.push(c),
+            'b' => res.push('\u{0008}'),
+            'f' => res.push('\u{000C}'),
+            'n' => res.push('\n'),
+            'r' => res.push('\r'),
+            't' => res.push('\t'),
+            'u' => {
+                let (c, len) = unicode_escape(&inner[idx..])
+                    .map_err(|e| (idx + 1, e))?;
+                res.push(c);
+                // escapes are ASCII, so a byte is a char
+                for _ in 2..len {
+                    chars.next();
                 }
             }
-            c => res.push(c),
+            c => {
+                return Err((idx + 1, Error::unexpected_message(
+                    format_args!("bad escaped char {:?}", c))));
+            }
         }
     }
 
     Ok(res)
 }
 
-pub fn string<'a>(input: &mut TokenStream<'a>)
+/// Reads a `\uXXXX` escape, a surrogate pair of such escapes or a
+/// `\u{X...}` escape at the start of the string
+///
+/// Returns the character and the length of the escape.
+fn unicode_escape(s: &str)
+    -> Result<(char, usize), Error<Token<'_>, Token<'_>>>
+{
+    debug_assert!(s.starts_with("\\u"));
+    let invalid = |len: usize| Error::unexpected_message(format_args!(
+        "invalid unicode escape {}",
+        s.get(..len).unwrap_or(s)));
+    let hex = |digits: &str| {
+        !digits.is_empty() && digits.bytes().all(|c| c.is_ascii_hexdigit())
+    };
+    if s[2..].starts_with('{') {
+        let end = match s.find('}') {
+            Some(end) => end,
+            None => return Err(invalid(s.len().min(3))),
+        };
+        let digits = &s[3..end];
+        if !hex(digits) {
+            return Err(invalid(end + 1));
+        }
+        return u32::from_str_radix(digits, 16).ok()
+            .and_then(char::from_u32)
+            .map(|c| (c, end + 1))
+            .ok_or_else(|| Error::unexpected_message(format_args!(
+                "{} is not a unicode scalar value", &s[..end + 1])));
+    }
+    let fixed = |s: &str| {
+        s.get(2..6).filter(|digits| hex(digits))
+            .map(|digits| u32::from_str_radix(digits, 16).unwrap())
+    };
+    let code = fixed(s).ok_or_else(|| invalid(s.len().min(6)))?;
+    match code {
+        0xD800..=0xDBFF => {
+            let low = s.get(6..).filter(|rest| rest.starts_with("\\u"))
+                .and_then(fixed)
+                .filter(|low| (0xDC00..=0xDFFF).contains(low));
+            match low {
+                Some(low) => {
+                    let code = 0x10000 + ((code - 0xD800) << 10)
+                        + (low - 0xDC00);
+                    Ok((char::from_u32(code).unwrap(), 12))
+                }
+                None => Err(Error::unexpected_message(format_args!(
+                    "{} is a lone surrogate", &s[..6]))),
+            }
+        }
+        0xDC00..=0xDFFF => Err(Error::unexpected_message(format_args!(
+            "{} is a lone surrogate", &s[..6]))),
+        _ => Ok((char::from_u32(code).unwrap(), 6)),
+    }
+}
+
+/// Parses a string token, reporting invalid escapes at their position
+pub(crate) fn unquoted<'a>(input: &mut TokenStream<'a>, token_kind: T)
     -> ParseResult<String, TokenStream<'a>>
 {
-    choice((
-        kind(T::StringValue).and_then(|tok| unquote_string(tok.value)),
-        kind(T::BlockString).and_then(|tok| unquote_block_string(tok.value)),
-    )).parse_stream(input)
+    let start = input.position();
+    let (token, consumed) = kind(token_kind).parse_stream(input)?;
+    let value = if token_kind == T::BlockString {
+        unquote_block_string(token.value).map_err(|e| (0, e))
+    } else {
+        unquote_string(token.value)
+    };
+    match value {
+        Ok(value) => Ok((value, consumed)),
+        Err((offset, err)) => {
+            let position = advance(start, &token.value[..offset]);
+            Err(Consumed::Consumed(Errors::new(position, err).into()))
+        }
+    }
 }
 
 pub fn string_value<'a, S>(input: &mut TokenStream<'a>)
     -> ParseResult<Value<'a, S>, TokenStream<'a>>
     where S: Text<'a>,
 {
-    kind(T::StringValue).and_then(|tok| unquote_string(tok.value))
+    parser(|input| unquoted(input, T::StringValue))
         .map(Value::String)
     .parse_stream(input)
 }
@@ -233,8 +787,8 @@ pub fn block_string_value<'a, S>(input: &mut TokenStream<'a>)
     -> ParseResult<Value<'a, S>, TokenStream<'a>>
     where S: Text<'a>,
 {
-    kind(T::BlockString).and_then(|tok| unquote_block_string(tok.value))
-        .map(Value::String)
+    parser(|input| unquoted(input, T::BlockString))
+        .map(Value::BlockString)
     .parse_stream(input)
 }
 
@@ -305,15 +859,272 @@ pub fn parse_type<'a, T>(input: &mut TokenStream<'a>)
 
 #[cfg(test)]
 mod tests {
-    use super::Number;
-    use super::unquote_string;
+    use super::{Directive, Number, ObjectFields, Type, Value};
+    use super::{get_named_type, is_list, is_non_null, unwrap_non_null};
+    use super::{unquote_string, unquote_block_string};
 
     #[test]
     fn number_from_i32_and_to_i64_conversion() {
         assert_eq!(Number::from(1).as_i64(), Some(1));
         assert_eq!(Number::from(584).as_i64(), Some(584));
-        assert_eq!(Number::from(i32::min_value()).as_i64(), Some(i32::min_value() as i64));
-        assert_eq!(Number::from(i32::max_value()).as_i64(), Some(i32::max_value() as i64));
+        assert_eq!(Number::from(i32::MIN).as_i64(), Some(i32::MIN as i64));
+        assert_eq!(Number::from(i32::MAX).as_i64(), Some(i32::MAX as i64));
+    }
+
+    #[test]
+    fn type_wrappers() {
+        let named = Type::<&str>::named("String");
+        let nested = Type::non_null(Type::list(Type::non_null(
+            Type::list(Type::non_null(named.clone())))));
+        assert_eq!(nested.to_string(), "[[String!]!]!");
+        assert_eq!(*get_named_type(&nested), "String");
+        assert_eq!(*get_named_type(&named), "String");
+        assert!(is_non_null(&nested));
+        assert!(is_list(&nested));
+        assert_eq!(unwrap_non_null(&nested).to_string(), "[[String!]!]");
+        assert_eq!(unwrap_non_null(unwrap_non_null(&nested)).to_string(),
+                   "[[String!]!]");
+        let item = match *unwrap_non_null(&nested) {
+            Type::ListType(ref item) => item,
+            _ => unreachable!(),
+        };
+        assert!(is_non_null(item) && is_list(item));
+        assert!(!is_non_null(&named) && !is_list(&named));
+        assert!(!is_list(&Type::non_null(named.clone())));
+        assert_eq!(unwrap_non_null(&named), &named);
+    }
+
+    #[test]
+    fn directive_arguments() {
+        let include = Directive::<&str>::new("include")
+            .argument("if", Value::Boolean(true));
+        assert_eq!(include.get_argument("if"), Some(&Value::Boolean(true)));
+        assert_eq!(include.get_bool("if"), Some(true));
+        assert_eq!(include.get_string("if"), None);
+        assert_eq!(include.get_argument("unless"), None);
+        assert_eq!(include.get_bool("unless"), None);
+
+        let deprecated = Directive::<String>::new("deprecated".into())
+            .argument("reason".into(), Value::String("old".into()));
+        assert_eq!(deprecated.get_string("reason"), Some("old"));
+        assert_eq!(deprecated.get_bool("reason"), None);
+    }
+
+    #[test]
+    fn value_display() {
+        use crate::query::{parse_query, Definition, OperationDefinition};
+        use crate::query::Selection;
+
+        let reparse = |value: &Value<'static, String>| {
+            let query = format!("{{ f(a: {}) }}", value);
+            let doc = parse_query::<String>(&query).unwrap().into_static();
+            match doc.definitions.into_iter().next() {
+                Some(Definition::Operation(
+                    OperationDefinition::SelectionSet(mut set)
+                )) => match set.items.remove(0) {
+                    Selection::Field(mut field) => {
+                        field.arguments.remove(0).1
+                    }
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            }
+        };
+        let object = vec![
+            ("b".to_string(), Value::Enum("RED".to_string())),
+            ("a".to_string(), Value::Variable("var".to_string())),
+        ];
+        let values = vec![
+            Value::Variable("var".to_string()),
+            Value::Int(Number::from(-17)),
+            Value::Float(1.0.into()),
+            Value::Float((-2.5e-7).into()),
+            Value::String("hello".into()),
+            Value::String("quote \" slash \\ tab \t nul \u{0}".into()),
+            Value::String("two\nlines".into()),
+            Value::BlockString("block\n  \"\"\" indented".into()),
+            Value::BlockString("\n not block compatible ".into()),
+            Value::Boolean(true),
+            Value::Boolean(false),
+            Value::Null,
+            Value::Enum("RED".to_string()),
+            Value::List(vec![]),
+            Value::List(vec![Value::Int(Number::from(1)),
+                             Value::List(vec![Value::Null])]),
+            Value::Object(ObjectFields::new()),
+            Value::Object(object.into_iter().collect()),
+        ];
+        assert_eq!(values[12].to_string(), "RED");
+        assert_eq!(values[14].to_string(), "[1, [null]]");
+        for value in &values {
+            let printed = value.to_string();
+            match (value, reparse(value)) {
+                // multi-line strings are printed as block strings when
+                // possible, and block strings as regular strings if not
+                (Value::String(a), Value::BlockString(b))
+                | (Value::BlockString(a), Value::String(b)) => {
+                    assert_eq!(a, &b, "{}", printed);
+                }
+                (a, b) => assert_eq!(a, &b, "{}", printed),
+            }
+        }
+    }
+
+    fn literal(text: &str)
+        -> Result<Value<'static, String>, crate::query::ParseError>
+    {
+        use crate::query::{parse_query, Definition, OperationDefinition};
+        use crate::query::Selection;
+
+        let query = format!("{{ f(a: {}) }}", text);
+        let mut doc = parse_query::<String>(&query)?.into_static();
+        match doc.definitions.remove(0) {
+            Definition::Operation(OperationDefinition::SelectionSet(mut set))
+            => match set.items.remove(0) {
+                Selection::Field(mut field) => Ok(field.arguments.remove(0).1),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn numeric_literals() {
+        let corpus = [
+            ("0", "0"),
+            ("-0", "0"),
+            ("9223372036854775807", "9223372036854775807"),
+            ("-9223372036854775808", "-9223372036854775808"),
+            ("0.0", "0.0"),
+            ("-0.0", "-0.0"),
+            ("1e10", "1e10"),
+            ("1.5E-3", "0.0015"),
+            ("-1.5e+300", "-1.5e300"),
+            ("2e-7", "2e-7"),
+            ("100.0", "100.0"),
+            ("1e4", "10000.0"),
+            ("1e5", "1e5"),
+            ("1e6", "1e6"),
+            ("123456789e1", "1234567890.0"),
+            ("0.1", "0.1"),
+            ("1e999", "1e999"),
+            ("-1e999", "-1e999"),
+            ("5e-324", "5e-324"),
+            ("1.7976931348623157e308", "1.7976931348623157e308"),
+        ];
+        for &(text, printed) in &corpus {
+            let value = literal(text).unwrap();
+            assert_eq!(value.to_string(), printed, "{}", text);
+            let reparsed = literal(printed).unwrap();
+            match (&value, &reparsed) {
+                (Value::Int(a), Value::Int(b)) => assert_eq!(a, b),
+                (Value::Float(a), Value::Float(b)) => {
+                    assert_eq!(a.as_f64().to_bits(), b.as_f64().to_bits(),
+                               "{}", text);
+                }
+                _ => panic!("{} is read back as {:?}", text, reparsed),
+            }
+        }
+        assert_eq!(literal("-0.0").unwrap(), Value::Float((-0.0).into()));
+        assert_eq!(Value::<String>::Float(f64::NAN.into()).to_string(),
+                   "null");
+    }
+
+    #[test]
+    fn literals_as_written() {
+        let raw = |text: &str| match literal(text).unwrap() {
+            Value::Int(num) => num.raw().map(String::from),
+            Value::Float(num) => num.raw().map(String::from),
+            other => panic!("{} is read as {:?}", text, other),
+        };
+        assert_eq!(raw("-0"), Some("-0".into()));
+        assert_eq!(raw("0"), None);
+        assert_eq!(raw("-12"), None);
+        assert_eq!(raw("1.50"), Some("1.50".into()));
+        assert_eq!(raw("-1E3"), Some("-1E3".into()));
+        assert_eq!(literal("-0").unwrap().to_string(), "0");
+        assert_eq!(literal("1.50").unwrap(), literal("1.5").unwrap());
+
+        let object = literal("{b: 1, a: {d: 2, c: 3}}").unwrap();
+        match object {
+            Value::Object(ref fields) => {
+                assert_eq!(fields.keys().collect::<Vec<_>>(), ["b", "a"]);
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(object.to_string(), "{a: {c: 3, d: 2}, b: 1}");
+        assert_eq!(object, literal("{a: {c: 3, d: 2}, b: 1}").unwrap());
+        assert_ne!(object, literal("{a: {c: 3, d: 2}}").unwrap());
+
+        let mut fields = ObjectFields::<&str>::new();
+        assert_eq!(fields.insert("b", Value::Null), None);
+        fields.insert("a", Value::Null);
+        assert_eq!(fields.insert("b", Value::Boolean(true)),
+                   Some(Value::Null));
+        assert_eq!(fields.iter().collect::<Vec<_>>(),
+                   [(&"b", &Value::Boolean(true)), (&"a", &Value::Null)]);
+    }
+
+    #[test]
+    fn float_round_trip() {
+        // xorshift, to cover all kinds of bit patterns deterministically
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        for _ in 0..10_000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let value = f64::from_bits(state);
+            if value.is_nan() {
+                continue;
+            }
+            let printed = Value::<String>::Float(value.into()).to_string();
+            match literal(&printed) {
+                Ok(Value::Float(read)) => {
+                    assert_eq!(read.as_f64().to_bits(), value.to_bits(),
+                               "{}", printed);
+                }
+                other => panic!("{} is read back as {:?}", printed, other),
+            }
+        }
+    }
+
+    #[test]
+    fn int_out_of_range() {
+        assert_eq!(literal("-9223372036854775809").unwrap_err()
+                   .int_out_of_range(),
+                   Some(("-9223372036854775809",
+                         crate::Pos { line: 1, column: 8, offset: 7 })));
+        assert_eq!(literal("99999999999999999999999").unwrap_err()
+                   .int_out_of_range().map(|(text, _)| text),
+                   Some("99999999999999999999999"));
+        assert_eq!(literal("{").unwrap_err().int_out_of_range(), None);
+        assert_eq!(Number::from(-3).as_i64(), Some(-3));
+        assert_eq!(Number::from(-3).as_f64(), -3.0);
+    }
+
+    #[test]
+    fn directive_display() {
+        let include = Directive::<&str>::new("include");
+        assert_eq!(include.to_string(), "@include");
+        let deprecated = Directive::<&str>::new("deprecated")
+            .argument("reason", Value::String("Use newField instead".into()));
+        assert_eq!(deprecated.to_string(),
+                   r#"@deprecated(reason: "Use newField instead")"#);
+        let range = Directive::<&str>::new("range")
+            .argument("min", Value::Int(Number::from(0)))
+            .argument("max", Value::Variable("max"));
+        assert_eq!(format!("{}", range), "@range(min: 0, max: $max)");
+    }
+
+    #[test]
+    fn type_display() {
+        let named = || Type::<&str>::NamedType("String");
+        let list = Type::ListType(Box::new(named()));
+        assert_eq!(Type::NonNullType(Box::new(list)).to_string(), "[String]!");
+        let item = Type::NonNullType(Box::new(named()));
+        let list = Type::NonNullType(Box::new(Type::ListType(Box::new(item))));
+        assert_eq!(format!("{}", list), "[String!]!");
+        assert_eq!(named().to_string(), "String");
     }
 
     #[test]
@@ -328,4 +1139,83 @@ mod tests {
         // a more complex string
         assert_eq!(unquote_string(r#""\u0009 hello \u000A there""#).expect(""), "\u{0009} hello \u{000A} there");
     }
+
+    #[test]
+    fn unquote_unicode_escapes() {
+        assert_eq!(unquote_string(r#""\u0041\u00e9""#).unwrap(), "A\u{e9}");
+        assert_eq!(unquote_string(r#""\ud83d\ude00""#).unwrap(), "\u{1F600}");
+        assert_eq!(unquote_string(r#""\uD83D\uDE00!""#).unwrap(),
+                   "\u{1F600}!");
+        assert_eq!(unquote_string(r#""\u{1F600}""#).unwrap(), "\u{1F600}");
+        assert_eq!(unquote_string(r#""\u{41}\u{0}""#).unwrap(), "A\u{0}");
+        assert_eq!(unquote_string(r#""\u{0000041}""#).unwrap(), "A");
+    }
+
+    #[test]
+    fn invalid_unicode_escapes() {
+        let offset = |s| unquote_string(s).unwrap_err().0;
+        // lone surrogates
+        assert_eq!(offset(r#""ab\ud83d""#), 3);
+        assert_eq!(offset(r#""ab\ud83dx""#), 3);
+        assert_eq!(offset(r#""ab\ud83d\u0041""#), 3);
+        assert_eq!(offset(r#""ab\ude00\ud83d""#), 3);
+        assert_eq!(offset(r#""\u{D83D}""#), 1);
+        // out of range
+        assert_eq!(offset(r#""a\u{110000}""#), 2);
+        assert_eq!(offset(r#""a\u{100000000}""#), 2);
+        // malformed
+        assert_eq!(offset(r#""a\u{}""#), 2);
+        assert_eq!(offset(r#""a\u{12""#), 2);
+        assert_eq!(offset(r#""a\u{1 2}""#), 2);
+        assert_eq!(offset(r#""a\u12""#), 2);
+        assert_eq!(offset(r#""a\u12G4""#), 2);
+    }
+
+    #[test]
+    fn unquote_block_strings() {
+        assert_eq!(unquote_block_string(r#""""""""#).expect(""), "");
+        assert_eq!(unquote_block_string(r#""""  """"#).expect(""), "");
+        assert_eq!(unquote_block_string(r#""""hello""""#).expect(""), "hello");
+        assert_eq!(unquote_block_string("\"\"\"\n\n  a\n    b\n\n  c\n  \n\"\"\"")
+                   .expect(""), "a\n  b\n\nc");
+        assert_eq!(unquote_block_string("\"\"\"first\n    second\"\"\"")
+                   .expect(""), "first\nsecond");
+        assert_eq!(unquote_block_string("\"\"\"\r\n  a\r\n  b\r\n\"\"\"")
+                   .expect(""), "a\nb");
+        assert_eq!(unquote_block_string(r#""""
+            uses \""" inside
+        """"#).expect(""), r#"uses """ inside"#);
+    }
+
+    #[test]
+    fn block_string_spec_vectors() {
+        let unquote = |lines: &[&str]| {
+            unquote_block_string(&format!("\"\"\"{}\"\"\"", lines.join("\n")))
+                .unwrap()
+        };
+        let expected = "Hello,\n  World!\n\nYours,\n  GraphQL.";
+        // removes uniform indentation
+        assert_eq!(unquote(&["", "    Hello,", "      World!", "",
+                             "    Yours,", "      GraphQL."]), expected);
+        // removes empty leading and trailing lines
+        assert_eq!(unquote(&["", "", "    Hello,", "      World!", "",
+                             "    Yours,", "      GraphQL.", "", ""]),
+                   expected);
+        // removes blank leading and trailing lines
+        assert_eq!(unquote(&["  ", "        ", "    Hello,", "      World!",
+                             "", "    Yours,", "      GraphQL.", "        ",
+                             "  "]), expected);
+        // retains indentation from first line
+        assert_eq!(unquote(&["    Hello,", "      World!", "", "    Yours,",
+                             "      GraphQL."]),
+                   "    Hello,\n  World!\n\nYours,\n  GraphQL.");
+        // does not alter trailing spaces
+        assert_eq!(unquote(&["               ", "    Hello,     ",
+                             "      World!   ", "    ", "    Yours,     ",
+                             "      GraphQL. ", "               "]),
+                   "Hello,     \n  World!   \n\nYours,     \n  GraphQL. ");
+        // escaped triple quotes don't end the string and aren't indented
+        assert_eq!(unquote(&["", "    a \\\"\"\" b", "      \\\"\"\"", ""]),
+                   "a \"\"\" b\n  \"\"\"");
+    }
 }
@@ -0,0 +1,278 @@
+//! Documents mixing executable and type system definitions
+//!
+use combine::{eof, many1, parser, ParseResult, Parser};
+use thiserror::Error;
+
+use crate::common::Text;
+use crate::parse_options::ParseOptions;
+use crate::position::Pos;
+use crate::query;
+use crate::query::Spanned;
+use crate::schema;
+use crate::tokenizer::{is_depth_limit_exceeded, int_out_of_range};
+use crate::tokenizer::TokenStream;
+
+
+/// A document which may contain both operations and type definitions
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
+pub struct Document<'a, T: Text<'a>> {
+    pub definitions: Vec<Definition<'a, T>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
+pub enum Definition<'a, T: Text<'a>> {
+    Operation(query::OperationDefinition<'a, T>),
+    Fragment(query::FragmentDefinition<'a, T>),
+    /// A schema, type, type extension or directive definition
+    TypeSystem(schema::Definition<'a, T>),
+}
+
+/// Error parsing document
+///
+/// This structure is opaque for forward compatibility. We are exploring a
+/// way to improve both error message and API.
+#[derive(Error, Debug)]
+#[error("document parse error: {}", message)]
+pub struct ParseError {
+    message: String,
+    depth_limit_exceeded: bool,
+    int_out_of_range: Option<(String, Pos)>,
+}
+
+/// Error converting a document which has definitions of the other kind
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("{kind} definition at {position} is not allowed in {target} \
+         document")]
+pub struct ConversionError {
+    kind: &'static str,
+    target: &'static str,
+    position: Pos,
+}
+
+impl ParseError {
+    /// Returns `true` if the document is nested deeper than
+    /// `ParseOptions::max_depth`
+    pub fn is_depth_limit_exceeded(&self) -> bool {
+        self.depth_limit_exceeded
+    }
+
+    /// Returns the literal and the position of the integer which doesn't
+    /// fit `i64`, if that's what failed the parsing
+    pub fn int_out_of_range(&self) -> Option<(&str, Pos)> {
+        self.int_out_of_range.as_ref()
+            .map(|(literal, pos)| (&literal[..], *pos))
+    }
+}
+
+impl ConversionError {
+    /// Returns the position of the first definition which can't be
+    /// converted
+    pub fn position(&self) -> Pos {
+        self.position
+    }
+}
+
+impl<'a, T: Text<'a>> Definition<'a, T> {
+    /// Returns the position of the definition
+    pub fn position(&self) -> Pos {
+        match *self {
+            Definition::Operation(ref op) => op.span().0,
+            Definition::Fragment(ref frag) => frag.position,
+            Definition::TypeSystem(ref def) => def.position(),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    /// Returns the query document with all the definitions, fails if any
+    /// of them is a type system definition
+    pub fn try_into_query_document(self)
+        -> Result<query::Document<'a, T>, ConversionError>
+    {
+        let mut definitions = Vec::with_capacity(self.definitions.len());
+        for def in self.definitions {
+            definitions.push(match def {
+                Definition::Operation(op) => query::Definition::Operation(op),
+                Definition::Fragment(frag) => {
+                    query::Definition::Fragment(frag)
+                }
+                Definition::TypeSystem(def) => return Err(ConversionError {
+                    kind: "type system",
+                    target: "a query",
+                    position: def.position(),
+                }),
+            });
+        }
+        Ok(query::Document { definitions })
+    }
+
+    /// Returns the schema document with all the definitions, fails if any
+    /// of them is an operation or a fragment
+    pub fn try_into_schema_document(self)
+        -> Result<schema::Document<'a, T>, ConversionError>
+    {
+        let mut definitions = Vec::with_capacity(self.definitions.len());
+        for def in self.definitions {
+            match def {
+                Definition::TypeSystem(def) => definitions.push(def),
+                def => return Err(ConversionError {
+                    kind: "executable",
+                    target: "a schema",
+                    position: def.position(),
+                }),
+            }
+        }
+        Ok(schema::Document { definitions })
+    }
+}
+
+fn definition<'a, T>(input: &mut TokenStream<'a>)
+    -> ParseResult<Definition<'a, T>, TokenStream<'a>>
+    where T: Text<'a>,
+{
+    parser(query::grammar::definition)
+        .map(|def| match def {
+            query::Definition::Operation(op) => Definition::Operation(op),
+            query::Definition::Fragment(frag) => Definition::Fragment(frag),
+        })
+    .or(parser(schema::grammar::definition).map(Definition::TypeSystem))
+    .parse_stream(input)
+}
+
+/// Parses a document with any definitions and returns an AST
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::parse_document;
+///
+/// let doc = parse_document::<&str>("
+///     type Query { user: User }
+///     query { user { name } }
+///     type User { name: String }
+/// ").unwrap();
+/// assert_eq!(doc.definitions.len(), 3);
+/// assert!(doc.try_into_schema_document().is_err());
+/// ```
+pub fn parse_document<'a, T>(s: &'a str)
+    -> Result<Document<'a, T>, ParseError>
+    where T: Text<'a>,
+{
+    parse_document_with(s, &ParseOptions::default())
+}
+
+/// Parses a document with any definitions with the given options
+pub fn parse_document_with<'a, T>(s: &'a str, options: &ParseOptions)
+    -> Result<Document<'a, T>, ParseError>
+    where T: Text<'a>,
+{
+    let mut tokens = TokenStream::with_recursion_limit(s, options.max_depth);
+    let (doc, _) = many1(parser(definition))
+        .map(|d| Document { definitions: d })
+        .skip(eof())
+        .parse_stream(&mut tokens)
+        .map_err(|e| {
+            let e = e.into_inner().error;
+            ParseError {
+                message: format!("{}", e),
+                depth_limit_exceeded: is_depth_limit_exceeded(&e),
+                int_out_of_range: int_out_of_range(&e),
+            }
+        })?;
+
+    Ok(doc)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_document, Definition};
+    use crate::position::Pos;
+    use crate::{query, schema};
+
+    const MIXED: &str = "\
+\"\"\"
+The root
+\"\"\"
+type Query { user(id: ID!): User }
+
+query GetUser($id: ID!) {
+  user(id: $id) { ...UserFields }
+}
+
+extend type Query { me: User }
+
+fragment UserFields on User { name }
+
+{ me { name } }
+
+directive @key on OBJECT
+";
+
+    #[test]
+    fn interleaved() {
+        let doc = parse_document::<&str>(MIXED).unwrap();
+        let kinds = doc.definitions.iter()
+            .map(|def| match *def {
+                Definition::Operation(_) => "operation",
+                Definition::Fragment(_) => "fragment",
+                Definition::TypeSystem(schema::Definition::TypeDefinition(_))
+                => "type",
+                Definition::TypeSystem(schema::Definition::TypeExtension(_))
+                => "extension",
+                Definition::TypeSystem(_) => "other",
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, ["type", "operation", "extension", "fragment",
+                           "operation", "other"]);
+        let positions = doc.definitions.iter()
+            .map(|def| def.position().line)
+            .collect::<Vec<_>>();
+        assert_eq!(positions, [4, 6, 10, 12, 14, 16]);
+    }
+
+    #[test]
+    fn single_kind() {
+        let source = "query A { a } fragment F on T { b }";
+        let doc = parse_document::<&str>(source).unwrap()
+            .try_into_query_document().unwrap();
+        assert_eq!(doc, query::parse_query::<&str>(source).unwrap());
+
+        let source = "schema { query: Q } type Q { a: Int } scalar S";
+        let doc = parse_document::<&str>(source).unwrap()
+            .try_into_schema_document().unwrap();
+        assert_eq!(doc, schema::parse_schema::<&str>(source).unwrap());
+    }
+
+    #[test]
+    fn conversion_errors() {
+        let err = parse_document::<&str>(MIXED).unwrap()
+            .try_into_query_document().unwrap_err();
+        assert_eq!(err.position(), Pos::new(4, 1));
+        assert_eq!(err.to_string(), "type system definition at 4:1 is not \
+                                     allowed in a query document");
+
+        let err = parse_document::<&str>(MIXED).unwrap()
+            .try_into_schema_document().unwrap_err();
+        assert_eq!(err.position(), Pos::new(6, 1));
+        assert_eq!(err.to_string(), "executable definition at 6:1 is not \
+                                     allowed in a schema document");
+    }
+
+    #[test]
+    fn parse_error() {
+        let err = parse_document::<&str>("type Query { a: Int }\nquery {")
+            .unwrap_err();
+        assert_eq!(err.to_string(), "document parse error: \
+            Parse error at 2:8\nUnexpected `end of input`\n\
+            Expected `Name`\n");
+    }
+}
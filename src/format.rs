@@ -1,29 +1,140 @@
 //! Formatting graphql
 use std::default::Default;
+use std::fmt;
+use std::io;
 
 use crate::common::Directive;
 
 
-#[derive(Debug, PartialEq)]
 pub(crate) struct Formatter<'a> {
-    buf: String,
+    out: &'a mut dyn fmt::Write,
     style: &'a Style,
     indent: u32,
+    empty: bool,
+    result: fmt::Result,
+    // minification state
+    verbatim: bool,
+    separate: bool,
+    last: char,
 }
 
 /// A configuration of formatting style
 ///
-/// Currently we only have indentation configured, other things might be
-/// added later (such as minification).
+/// Currently we have indentation and minification configured, other
+/// things might be added later.
 #[derive(Debug, PartialEq, Clone)]
 pub struct Style {
     indent: u32,
+    level: u32,
+    minify: bool,
+    tabs: bool,
+    sort_definitions: bool,
+    sort_fields: bool,
+    sort_arguments: bool,
+    descriptions: BlockOrString,
+    args_per_line: Option<usize>,
+    trailing_commas: bool,
+}
+
+/// Options of the pretty-printer, see `Document::format_with`
+///
+/// The default options produce the same output as `Display`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FormatOptions {
+    /// Indentation of nested blocks
+    pub indent: Indent,
+    /// Sort definitions of the document by name
+    ///
+    /// Anonymous operations and the schema definition go first.
+    pub sort_definitions: bool,
+    /// Sort fields of types, input types and enum values by name and
+    /// fields of selection sets by response key
+    ///
+    /// Fragment spreads and inline fragments are placed after the fields
+    /// of a selection set.
+    pub sort_fields: bool,
+    /// Sort arguments of fields and directives by name
+    pub sort_arguments: bool,
+    /// How descriptions are written
+    pub description_style: BlockOrString,
+    /// Maximum number of arguments of a field or a directive in a query
+    /// written on a single line
+    ///
+    /// Longer argument lists are written one argument per line. `None`
+    /// keeps all arguments on a single line.
+    pub args_per_line: Option<usize>,
+    /// Write a comma after the last argument of argument lists written
+    /// one argument per line
+    pub trailing_commas: bool,
+}
+
+/// Indentation of nested blocks
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Indent {
+    /// Indent by the number of spaces per level
+    Spaces(u32),
+    /// Indent by a single tab per level
+    Tabs,
+}
+
+/// How descriptions are written
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BlockOrString {
+    /// Block strings for multi-line descriptions, strings otherwise
+    Auto,
+    /// Block strings, unless the description can't be written as one
+    Block,
+    /// Always strings, with newlines escaped
+    String,
 }
 
 impl Default for Style {
     fn default() -> Style {
         Style {
             indent: 2,
+            level: 0,
+            minify: false,
+            tabs: false,
+            sort_definitions: false,
+            sort_fields: false,
+            sort_arguments: false,
+            descriptions: BlockOrString::Auto,
+            args_per_line: None,
+            trailing_commas: false,
+        }
+    }
+}
+
+impl Default for FormatOptions {
+    fn default() -> FormatOptions {
+        FormatOptions {
+            indent: Indent::Spaces(2),
+            sort_definitions: false,
+            sort_fields: false,
+            sort_arguments: false,
+            description_style: BlockOrString::Auto,
+            args_per_line: None,
+            trailing_commas: false,
+        }
+    }
+}
+
+impl<'a> From<&'a FormatOptions> for Style {
+    fn from(options: &'a FormatOptions) -> Style {
+        let (indent, tabs) = match options.indent {
+            Indent::Spaces(n) => (n, false),
+            Indent::Tabs => (1, true),
+        };
+        Style {
+            indent,
+            tabs,
+            sort_definitions: options.sort_definitions,
+            sort_fields: options.sort_fields,
+            sort_arguments: options.sort_arguments,
+            descriptions: options.description_style,
+            args_per_line: options.args_per_line,
+            trailing_commas: options.trailing_commas,
+            ..Style::default()
         }
     }
 }
@@ -34,102 +145,323 @@ impl Style {
         self.indent = indent;
         self
     }
+
+    /// Change the nesting level the output starts at
+    ///
+    /// This is useful to print a single node (e.g. a field) which is
+    /// inserted into an already formatted document. Each level adds
+    /// `indent` spaces.
+    pub fn nesting_level(&mut self, level: u32) -> &mut Self {
+        self.level = level;
+        self
+    }
+
+    /// Print everything on a single line with as little whitespace as
+    /// possible
+    ///
+    /// Commas are omitted and tokens are separated by a single space only
+    /// where they would merge otherwise. Block strings are written as
+    /// regular strings.
+    pub fn minify(&mut self, minify: bool) -> &mut Self {
+        self.minify = minify;
+        self
+    }
+}
+
+/// Returns items in their original order or stably sorted by key
+pub(crate) fn sorted<'i, I, K, F>(items: &'i [I], sort: bool, key: F)
+    -> Vec<&'i I>
+    where K: Ord,
+          F: Fn(&'i I) -> K,
+{
+    let mut items = items.iter().collect::<Vec<_>>();
+    if sort {
+        items.sort_by_key(|&item| key(item));
+    }
+    items
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
 }
 
 pub(crate) trait Displayable {
     fn display(&self, f: &mut Formatter);
 }
 
+/// Adapts an `io::Write` to be used as a `fmt::Write`, keeping the error
+struct IoWriter<'a, W: io::Write> {
+    inner: &'a mut W,
+    error: Option<io::Error>,
+}
+
+impl<'a, W: io::Write> fmt::Write for IoWriter<'a, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+pub(crate) fn format_to<D, W>(node: &D, out: &mut W, style: &Style)
+    -> fmt::Result
+    where D: Displayable + ?Sized,
+          W: fmt::Write,
+{
+    let mut formatter = Formatter::new(out, style);
+    node.display(&mut formatter);
+    formatter.result
+}
+
+pub(crate) fn write_to<D, W>(node: &D, out: &mut W, style: &Style)
+    -> io::Result<()>
+    where D: Displayable + ?Sized,
+          W: io::Write,
+{
+    let mut writer = IoWriter { inner: out, error: None };
+    format_to(node, &mut writer, style).map_err(|_| {
+        writer.error.take().unwrap_or_else(|| {
+            io::Error::other("formatter error")
+        })
+    })
+}
+
+pub(crate) fn to_string<D: Displayable + ?Sized>(node: &D, style: &Style)
+    -> String
+{
+    let mut buf = String::with_capacity(1024);
+    format_to(node, &mut buf, style).expect("writing to a string never fails");
+    buf
+}
+
 impl<'a> Formatter<'a> {
-    pub fn new(style: &Style) -> Formatter {
+    pub fn new(out: &'a mut dyn fmt::Write, style: &'a Style)
+        -> Formatter<'a>
+    {
         Formatter {
-            buf: String::with_capacity(1024),
+            out,
             style,
-            indent: 0,
+            indent: style.indent * style.level,
+            empty: true,
+            result: Ok(()),
+            verbatim: false,
+            separate: false,
+            last: ' ',
+        }
+    }
+
+    fn push_str(&mut self, s: &str) {
+        if self.style.minify && !self.verbatim {
+            for c in s.chars() {
+                self.push(c);
+            }
+        } else if self.result.is_ok() && !s.is_empty() {
+            self.result = self.out.write_str(s);
+            self.empty = false;
+        }
+    }
+
+    fn push(&mut self, c: char) {
+        if self.style.minify && !self.verbatim {
+            // whitespace and commas are insignificant, only keep a space
+            // between two names or numbers, before a negative number
+            // following one (so that `1 -2` doesn't become a float) and
+            // between two strings (so that `"" "a"` doesn't become a block
+            // string)
+            if c.is_whitespace() || c == ',' {
+                self.separate = true;
+                return;
+            }
+            if self.separate && (
+                is_name_char(self.last) && (is_name_char(c) || c == '-')
+                || self.last == '"' && c == '"'
+            ) {
+                self.write_char(' ');
+            }
+            self.separate = false;
+            self.last = c;
+        }
+        self.write_char(c);
+    }
+
+    fn write_char(&mut self, c: char) {
+        if self.result.is_ok() {
+            self.result = self.out.write_char(c);
+            self.empty = false;
         }
     }
 
     pub fn indent(&mut self) {
-        for _ in 0..self.indent {
-            self.buf.push(' ');
+        if self.style.minify {
+            return;
+        }
+        const SPACES: &str = "                                ";
+        const TABS: &str = "\t\t\t\t\t\t\t\t\t\t\t\t\t\t\t\t";
+        let chars = if self.style.tabs { TABS } else { SPACES };
+        let mut left = self.indent as usize;
+        while left > 0 {
+            let chunk = left.min(chars.len());
+            self.push_str(&chars[..chunk]);
+            left -= chunk;
         }
     }
 
+    pub fn sort_definitions(&self) -> bool {
+        self.style.sort_definitions
+    }
+
+    pub fn sort_fields(&self) -> bool {
+        self.style.sort_fields
+    }
+
+    pub fn sort_arguments(&self) -> bool {
+        self.style.sort_arguments
+    }
+
+    pub fn args_per_line(&self) -> Option<usize> {
+        self.style.args_per_line
+    }
+
+    pub fn trailing_commas(&self) -> bool {
+        self.style.trailing_commas
+    }
+
     pub fn endline(&mut self) {
-        self.buf.push('\n');
+        self.push('\n');
     }
 
     pub fn start_block(&mut self) {
-        self.buf.push('{');
+        self.push('{');
         self.endline();
-        self.indent += self.style.indent;
+        self.push_indent();
     }
 
     pub fn end_block(&mut self) {
-        self.indent = self.indent.checked_sub(self.style.indent)
-            .expect("negative indent");
+        self.pop_indent();
         self.indent();
-        self.buf.push('}');
+        self.push('}');
         self.endline();
     }
 
+    pub fn push_indent(&mut self) {
+        self.indent += self.style.indent;
+    }
+
+    pub fn pop_indent(&mut self) {
+        self.indent = self.indent.checked_sub(self.style.indent)
+            .expect("negative indent");
+    }
+
     pub fn margin(&mut self) {
-        if !self.buf.is_empty() {
-            self.buf.push('\n');
+        if !self.empty {
+            self.push('\n');
         }
     }
 
     pub fn write(&mut self, s: &str) {
-        self.buf.push_str(s);
+        self.push_str(s);
     }
 
-    pub fn into_string(self) -> String {
-        self.buf
+    /// Writes a string literal
+    pub fn write_quoted(&mut self, s: &str) {
+        if s.contains('\n') {
+            self.write_block_quoted(s);
+        } else {
+            self.write_escaped(s);
+        }
     }
 
-    pub fn write_quoted(&mut self, s: &str) {
-        let mut has_newline = false;
-        let mut has_nonprintable = false;
-        for c in s.chars() {
-            match c {
-                '\n' => has_newline = true,
-                '\r' | '\t' | '\u{0020}'..='\u{FFFF}' => {}
-                _ => has_nonprintable = true,
-            }
+    /// Writes a block string literal indented one level deeper than the
+    /// current line
+    ///
+    /// Falls back to a regular string if the block string syntax can't
+    /// represent the value.
+    pub fn write_block_quoted(&mut self, s: &str) {
+        if !self.style.minify && is_block_string_compatible(s) {
+            self.push_indent();
+            self.write_block_string(s);
+            self.pop_indent();
+            self.indent();
+            self.push_str(r#"""""#);
+        } else {
+            self.write_escaped(s);
         }
-        if !has_newline || has_nonprintable {
-            use std::fmt::Write;
-            self.buf.push('"');
-            for c in s.chars() {
-                match c {
-                    '\r' => self.write(r"\r"),
-                    '\n' => self.write(r"\n"),
-                    '\t' => self.write(r"\t"),
-                    '"' => self.write("\\\""),
-                    '\\' => self.write(r"\\"),
-                    '\u{0020}'..='\u{FFFF}' => self.buf.push(c),
-                    _ => write!(&mut self.buf, "\\u{:04}", c as u32).unwrap(),
-                }
-            }
-            self.buf.push('"');
+    }
+
+    /// Writes a description, block strings and multi-line descriptions are
+    /// written as block strings aligned with the current line
+    ///
+    /// Ends the line, so the caller should start the described item with
+    /// `indent()`.
+    pub fn write_description(&mut self, s: &str, was_block: bool) {
+        let block = match self.style.descriptions {
+            BlockOrString::Auto => was_block || s.contains('\n'),
+            BlockOrString::Block => true,
+            BlockOrString::String => false,
+        };
+        if !self.style.minify && block && is_block_string_compatible(s) {
+            self.write_block_string(s);
+            self.indent();
+            self.push_str(r#"""""#);
         } else {
-            self.buf.push_str(r#"""""#);
+            self.write_escaped(s);
+        }
+        self.endline();
+    }
+
+    fn write_block_string(&mut self, s: &str) {
+        self.push_str(r#"""""#);
+        self.endline();
+        for line in s.split('\n') {
+            if !line.is_empty() {
+                self.indent();
+                self.write(&line.replace(r#"""""#, r#"\""""#));
+            }
             self.endline();
-            self.indent += self.style.indent;
-            for line in s.lines() {
-                if !line.trim().is_empty() {
-                    self.indent();
-                    self.write(&line.replace(r#"""""#, r#"\""""#));
+        }
+    }
+
+    fn write_escaped(&mut self, s: &str) {
+        self.push('"');
+        self.verbatim = true;
+        for c in s.chars() {
+            match c {
+                '\r' => self.write(r"\r"),
+                '\n' => self.write(r"\n"),
+                '\t' => self.write(r"\t"),
+                '"' => self.write("\\\""),
+                '\\' => self.write(r"\\"),
+                '\u{8}' => self.write(r"\b"),
+                '\u{c}' => self.write(r"\f"),
+                '\u{0}'..='\u{1F}' | '\u{7F}'..='\u{9F}' => {
+                    self.write(&format!("\\u{:04X}", c as u32));
                 }
-                self.endline();
+                _ => self.push(c),
             }
-            self.indent -= self.style.indent;
-            self.indent();
-            self.buf.push_str(r#"""""#);
         }
+        self.verbatim = false;
+        self.push('"');
     }
 }
 
+/// Checks whether the block string syntax can represent the string exactly
+///
+/// Parsing a block string strips common indentation as well as leading and
+/// trailing blank lines and it has no escape sequences other than `\"""`.
+pub(crate) fn is_block_string_compatible(s: &str) -> bool {
+    let printable = s.chars()
+        .all(|c| matches!(c, '\t' | '\n') || c >= '\u{0020}');
+    let is_blank = |line: &str| line.chars().all(|c| c == ' ' || c == '\t');
+    let indent = s.split('\n')
+        .filter(|line| !is_blank(line))
+        .map(|line| line.len() - line.trim_start_matches(&[' ', '\t'][..]).len())
+        .min().unwrap_or(0);
+    let first = s.split('\n').next().unwrap_or("");
+    let last = s.rsplit('\n').next().unwrap_or("");
+    printable && indent == 0 && !is_blank(first) && !is_blank(last)
+}
+
 pub(crate) fn format_directives<'a, T>(dirs: &[Directive<'a, T>], f: &mut Formatter) 
     where T: crate::common::Text<'a>,
 {
@@ -140,23 +472,45 @@ pub(crate) fn format_directives<'a, T>(dirs: &[Directive<'a, T>], f: &mut Format
 }
 
 macro_rules! impl_display {
-    ($( $typ: ident, )+) => {
+    ('a $($typ: ident, )+) => {
         $(
-            impl fmt::Display for $typ {
+            impl<'a, T> fmt::Display for $typ<'a, T>
+                where T: Text<'a>,
+            {
                 fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-                    f.write_str(&to_string(self))
+                    crate::format::format_to(self, f, &Style::default())
                 }
             }
-        )+
-    };
 
-    ('a $($typ: ident, )+) => {
-        $(
-            impl<'a, T> fmt::Display for $typ<'a, T> 
+            impl<'a, T> $typ<'a, T>
                 where T: Text<'a>,
             {
-                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-                    f.write_str(&to_string(self))
+                /// Writes formatted node into an `io::Write`
+                ///
+                /// The output is written in many small pieces, so wrap
+                /// unbuffered writers into an `io::BufWriter`.
+                pub fn write_to<W: std::io::Write>(&self, w: &mut W)
+                    -> std::io::Result<()>
+                {
+                    crate::format::write_to(self, w, &Style::default())
+                }
+
+                /// Writes node formatted according to style into an
+                /// `io::Write`
+                pub fn write_styled_to<W: std::io::Write>(&self, w: &mut W,
+                    style: &Style)
+                    -> std::io::Result<()>
+                {
+                    crate::format::write_to(self, w, style)
+                }
+
+                /// Writes node formatted according to style into a
+                /// `fmt::Write`
+                pub fn format_to<W: fmt::Write>(&self, w: &mut W,
+                    style: &Style)
+                    -> fmt::Result
+                {
+                    crate::format::format_to(self, w, style)
                 }
             }
         )+
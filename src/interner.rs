@@ -0,0 +1,198 @@
+//! Interning of names shared by many documents
+//!
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::common::Text;
+
+
+/// A reference-counted string, usually shared through a `StringInterner`
+///
+/// Comparison is by value, use `ptr_eq` to check whether two strings
+/// share the memory. Converting from `&str` allocates a new string which
+/// isn't shared, so parse documents with `&str` text and then `map` them
+/// with `StringInterner::intern`.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InternedStr(Arc<str>);
+
+/// A pool of strings, each stored only once
+///
+/// Strings stay alive while the pool or any `InternedStr` refers to them,
+/// so dropping the pool (or calling `clear`) reclaims the memory of the
+/// strings which aren't used by any document.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::{parse_schema, InternedStr, StringInterner};
+/// use graphql_parser::schema::Document;
+///
+/// let mut interner = StringInterner::new();
+/// let source = "type Query { users: [User] } type User { name: String }";
+/// let a: Document<InternedStr> = parse_schema::<&str>(source).unwrap()
+///     .map(|name| interner.intern(name));
+/// let b: Document<InternedStr> = parse_schema::<&str>(source).unwrap()
+///     .map(|name| interner.intern(name));
+/// assert_eq!(a, b);
+/// assert_eq!(interner.len(), 5);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StringInterner {
+    strings: HashSet<Arc<str>>,
+}
+
+impl<'a> Text<'a> for InternedStr {
+    type Value = Self;
+}
+
+impl InternedStr {
+    /// Returns `true` if both strings share the same memory
+    pub fn ptr_eq(this: &InternedStr, other: &InternedStr) -> bool {
+        Arc::ptr_eq(&this.0, &other.0)
+    }
+}
+
+impl StringInterner {
+    pub fn new() -> StringInterner {
+        StringInterner::default()
+    }
+
+    /// Returns the pooled copy of the string, adding it to the pool first
+    /// if needed
+    pub fn intern(&mut self, s: &str) -> InternedStr {
+        if let Some(existing) = self.strings.get(s) {
+            return InternedStr(existing.clone());
+        }
+        let string: Arc<str> = Arc::from(s);
+        self.strings.insert(string.clone());
+        InternedStr(string)
+    }
+
+    /// Returns the number of strings in the pool
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Removes all the strings from the pool
+    ///
+    /// Strings still used by documents are kept alive by them, but are
+    /// not shared with the strings interned later.
+    pub fn clear(&mut self) {
+        self.strings.clear();
+    }
+}
+
+impl<'a> From<&'a str> for InternedStr {
+    fn from(s: &'a str) -> InternedStr {
+        InternedStr(Arc::from(s))
+    }
+}
+
+impl Deref for InternedStr {
+    type Target = str;
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for InternedStr {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for InternedStr {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for InternedStr {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for InternedStr {
+    fn eq(&self, other: &&'a str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl fmt::Debug for InternedStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for InternedStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{InternedStr, StringInterner};
+    use crate::schema::{parse_schema, Definition, Document};
+    use crate::validation::test_harness::TEST_SCHEMA;
+
+    fn type_names<'d>(doc: &'d Document<'static, InternedStr>)
+        -> Vec<&'d InternedStr>
+    {
+        doc.definitions.iter()
+            .filter_map(|def| match *def {
+                Definition::TypeDefinition(ref typ) => Some(typ.name()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_pointer() {
+        let mut interner = StringInterner::new();
+        let a = interner.intern("Human");
+        let b = interner.intern(&String::from("Human"));
+        assert!(InternedStr::ptr_eq(&a, &b));
+        assert!(!InternedStr::ptr_eq(&a, &interner.intern("Dog")));
+        assert!(!InternedStr::ptr_eq(&a, &InternedStr::from("Human")));
+        assert_eq!(a, InternedStr::from("Human"));
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[test]
+    fn shared_between_documents() {
+        let mut interner = StringInterner::new();
+        let parse = |interner: &mut StringInterner| {
+            parse_schema::<&str>(TEST_SCHEMA).unwrap()
+                .map(|name| interner.intern(name))
+        };
+        let first = parse(&mut interner);
+        let second = parse(&mut interner);
+        let pooled = interner.len();
+        assert_eq!(first, second);
+        for (a, b) in type_names(&first).into_iter()
+            .zip(type_names(&second))
+        {
+            assert!(InternedStr::ptr_eq(a, b));
+        }
+
+        let third = parse(&mut interner);
+        assert_eq!(interner.len(), pooled);
+        let human = type_names(&third).into_iter()
+            .find(|name| **name == "Human")
+            .unwrap()
+            .clone();
+        let count = Arc::strong_count(&human.0);
+        drop(interner);
+        assert_eq!(Arc::strong_count(&human.0), count - 1);
+    }
+}
@@ -0,0 +1,412 @@
+//! Export of documents in the JSON AST format of graphql-js
+//!
+//! The output matches `JSON.stringify(parse(source, {noLocation: true}))`
+//! of graphql-js 16: every node has a `kind`, names are `Name` nodes and
+//! missing optional properties are omitted. Since nodes of this crate have
+//! no spans, there are no `loc` properties.
+//!
+//! The conversion is lossy in one place: `SchemaDefinition` has no
+//! description.
+//!
+use serde_json::{json, Map, Value as JsonValue};
+
+use crate::common::{Directive, Text, Type, Value};
+use crate::persisted;
+use crate::query;
+use crate::schema;
+
+
+/// Converts a query document to a graphql-js AST
+pub fn to_js_ast_json<'a, T>(doc: &query::Document<'a, T>) -> JsonValue
+    where T: Text<'a>,
+{
+    use crate::query::Definition::*;
+
+    let definitions = doc.definitions.iter().map(|def| match *def {
+        Operation(ref op) => operation(op),
+        Fragment(ref frag) => node("FragmentDefinition", vec![
+            ("name", name(&frag.name)),
+            ("typeCondition", type_condition(&frag.type_condition)),
+            ("directives", directives(&frag.directives)),
+            ("selectionSet", selection_set(&frag.selection_set)),
+        ]),
+    }).collect();
+    node("Document", vec![("definitions", JsonValue::Array(definitions))])
+}
+
+/// Converts a schema document to a graphql-js AST
+pub fn schema_to_js_ast_json<'a, T>(doc: &schema::Document<'a, T>)
+    -> JsonValue
+    where T: Text<'a>,
+{
+    use crate::schema::Definition::*;
+
+    let definitions = doc.definitions.iter().map(|def| match *def {
+        SchemaDefinition(ref schema) => {
+            node("SchemaDefinition", vec![
+                ("directives", directives(&schema.directives)),
+                ("operationTypes", operation_types::<T>([
+                    ("query", &schema.query),
+                    ("mutation", &schema.mutation),
+                    ("subscription", &schema.subscription),
+                ])),
+            ])
+        }
+        SchemaExtension(ref ext) => {
+            node("SchemaExtension", vec![
+                ("directives", directives(&ext.directives)),
+                ("operationTypes", operation_types::<T>([
+                    ("query", &ext.query),
+                    ("mutation", &ext.mutation),
+                    ("subscription", &ext.subscription),
+                ])),
+            ])
+        }
+        TypeDefinition(ref def) => type_definition(def),
+        TypeExtension(ref ext) => type_extension(ext),
+        DirectiveDefinition(ref def) => {
+            let locations = def.locations.iter()
+                .map(|loc| name(loc.as_str()))
+                .collect();
+            described("DirectiveDefinition", &def.description, vec![
+                ("name", name(&def.name)),
+                ("arguments", input_values(&def.arguments)),
+                ("repeatable", JsonValue::Bool(def.repeatable)),
+                ("locations", JsonValue::Array(locations)),
+            ])
+        }
+    }).collect();
+    node("Document", vec![("definitions", JsonValue::Array(definitions))])
+}
+
+fn node(kind: &str, properties: Vec<(&str, JsonValue)>) -> JsonValue {
+    let mut map = Map::new();
+    map.insert("kind".into(), JsonValue::String(kind.into()));
+    for (key, value) in properties {
+        map.insert(key.into(), value);
+    }
+    JsonValue::Object(map)
+}
+
+/// A node with an optional description, which is omitted if missing
+fn described(kind: &str, description: &Option<schema::Description>,
+    mut properties: Vec<(&str, JsonValue)>)
+    -> JsonValue
+{
+    if let Some(ref text) = *description {
+        properties.insert(0, ("description", node("StringValue", vec![
+            ("value", JsonValue::String(text.value.clone())),
+            ("block", JsonValue::Bool(text.block)),
+        ])));
+    }
+    node(kind, properties)
+}
+
+fn name<S: AsRef<str>>(value: S) -> JsonValue {
+    node("Name", vec![("value", JsonValue::String(value.as_ref().into()))])
+}
+
+fn named_type<S: AsRef<str>>(value: S) -> JsonValue {
+    node("NamedType", vec![("name", name(value))])
+}
+
+fn named_types<S: AsRef<str>>(values: &[S]) -> JsonValue {
+    JsonValue::Array(values.iter().map(named_type).collect())
+}
+
+fn type_condition<'a, T>(cond: &query::TypeCondition<'a, T>) -> JsonValue
+    where T: Text<'a>,
+{
+    let query::TypeCondition::On(ref type_name) = *cond;
+    named_type(type_name)
+}
+
+fn operation<'a, T>(op: &query::OperationDefinition<'a, T>) -> JsonValue
+    where T: Text<'a>,
+{
+    use crate::query::OperationDefinition::*;
+
+    let (kind, name_, vars, dirs, sel) = match *op {
+        SelectionSet(ref sel) => ("query", &None, &[][..], &[][..], sel),
+        Query(ref q) => ("query", &q.name, &q.variable_definitions[..],
+                         &q.directives[..], &q.selection_set),
+        Mutation(ref m) => ("mutation", &m.name, &m.variable_definitions[..],
+                            &m.directives[..], &m.selection_set),
+        Subscription(ref s) => ("subscription", &s.name,
+                                &s.variable_definitions[..],
+                                &s.directives[..], &s.selection_set),
+    };
+    let mut properties = vec![("operation", json!(kind))];
+    if let Some(ref value) = *name_ {
+        properties.push(("name", name(value)));
+    }
+    let variables = vars.iter().map(|var| {
+        let mut properties = vec![
+            ("variable", variable(&var.name)),
+            ("type", type_(&var.var_type)),
+        ];
+        if let Some(ref default) = var.default_value {
+            properties.push(("defaultValue", value(default)));
+        }
+        properties.push(("directives", json!([])));
+        node("VariableDefinition", properties)
+    }).collect();
+    properties.push(("variableDefinitions", JsonValue::Array(variables)));
+    properties.push(("directives", directives(dirs)));
+    properties.push(("selectionSet", selection_set(sel)));
+    node("OperationDefinition", properties)
+}
+
+fn selection_set<'a, T>(set: &query::SelectionSet<'a, T>) -> JsonValue
+    where T: Text<'a>,
+{
+    use crate::query::Selection::*;
+
+    let selections = set.items.iter().map(|item| match *item {
+        Field(ref field) => {
+            let mut properties = Vec::new();
+            if let Some(ref alias) = field.alias {
+                properties.push(("alias", name(alias)));
+            }
+            properties.push(("name", name(&field.name)));
+            properties.push(("arguments", arguments(&field.arguments)));
+            properties.push(("directives", directives(&field.directives)));
+            if !field.selection_set.items.is_empty() {
+                properties.push(("selectionSet",
+                                 selection_set(&field.selection_set)));
+            }
+            node("Field", properties)
+        }
+        FragmentSpread(ref spread) => node("FragmentSpread", vec![
+            ("name", name(&spread.fragment_name)),
+            ("directives", directives(&spread.directives)),
+        ]),
+        InlineFragment(ref frag) => {
+            let mut properties = Vec::new();
+            if let Some(ref cond) = frag.type_condition {
+                properties.push(("typeCondition", type_condition(cond)));
+            }
+            properties.push(("directives", directives(&frag.directives)));
+            properties.push(("selectionSet",
+                             selection_set(&frag.selection_set)));
+            node("InlineFragment", properties)
+        }
+    }).collect();
+    node("SelectionSet", vec![("selections", JsonValue::Array(selections))])
+}
+
+fn variable<S: AsRef<str>>(value: S) -> JsonValue {
+    node("Variable", vec![("name", name(value))])
+}
+
+fn arguments<'a, T>(args: &[(T::Value, Value<'a, T>)]) -> JsonValue
+    where T: Text<'a>,
+{
+    JsonValue::Array(args.iter().map(|(arg, val)| {
+        node("Argument", vec![("name", name(arg)), ("value", value(val))])
+    }).collect())
+}
+
+fn directives<'a, T>(dirs: &[Directive<'a, T>]) -> JsonValue
+    where T: Text<'a>,
+{
+    JsonValue::Array(dirs.iter().map(|dir| {
+        node("Directive", vec![
+            ("name", name(&dir.name)),
+            ("arguments", arguments(&dir.arguments)),
+        ])
+    }).collect())
+}
+
+fn value<'a, T>(val: &Value<'a, T>) -> JsonValue
+    where T: Text<'a>,
+{
+    match *val {
+        Value::Variable(ref var) => variable(var),
+        // numbers keep the source text, as printed by graphql-js
+        Value::Int(_) => {
+            node("IntValue", vec![("value", json!(persisted::value(val)))])
+        }
+        Value::Float(_) => {
+            node("FloatValue", vec![("value", json!(persisted::value(val)))])
+        }
+        Value::String(ref s) => node("StringValue", vec![
+            ("value", json!(s)),
+            ("block", json!(false)),
+        ]),
+        Value::BlockString(ref s) => node("StringValue", vec![
+            ("value", json!(s)),
+            ("block", json!(true)),
+        ]),
+        Value::Boolean(b) => node("BooleanValue", vec![("value", json!(b))]),
+        Value::Null => node("NullValue", vec![]),
+        Value::Enum(ref e) => {
+            node("EnumValue", vec![("value", json!(e.as_ref()))])
+        }
+        Value::List(ref items) => node("ListValue", vec![
+            ("values", JsonValue::Array(items.iter().map(value).collect())),
+        ]),
+        Value::Object(ref fields) => node("ObjectValue", vec![
+            ("fields", JsonValue::Array(fields.iter().map(|(key, val)| {
+                node("ObjectField", vec![
+                    ("name", name(key)),
+                    ("value", value(val)),
+                ])
+            }).collect())),
+        ]),
+    }
+}
+
+fn type_<'a, T>(typ: &Type<'a, T>) -> JsonValue
+    where T: Text<'a>,
+{
+    match *typ {
+        Type::NamedType(ref type_name) => named_type(type_name),
+        Type::ListType(ref inner) => {
+            node("ListType", vec![("type", type_(inner))])
+        }
+        Type::NonNullType(ref inner) => {
+            node("NonNullType", vec![("type", type_(inner))])
+        }
+    }
+}
+
+fn operation_types<'a, T>(types: [(&str, &Option<T::Value>); 3])
+    -> JsonValue
+    where T: Text<'a>,
+{
+    JsonValue::Array(types.iter()
+        .filter_map(|(operation, typ)| typ.as_ref().map(|typ| {
+            node("OperationTypeDefinition", vec![
+                ("operation", json!(operation)),
+                ("type", named_type(typ)),
+            ])
+        }))
+        .collect())
+}
+
+fn fields<'a, T>(fields: &[schema::Field<'a, T>]) -> JsonValue
+    where T: Text<'a>,
+{
+    JsonValue::Array(fields.iter().map(|field| {
+        described("FieldDefinition", &field.description, vec![
+            ("name", name(&field.name)),
+            ("arguments", input_values(&field.arguments)),
+            ("type", type_(&field.field_type)),
+            ("directives", directives(&field.directives)),
+        ])
+    }).collect())
+}
+
+fn input_values<'a, T>(values: &[schema::InputValue<'a, T>]) -> JsonValue
+    where T: Text<'a>,
+{
+    JsonValue::Array(values.iter().map(|input| {
+        let mut properties = vec![
+            ("name", name(&input.name)),
+            ("type", type_(&input.value_type)),
+        ];
+        if let Some(ref default) = input.default_value {
+            properties.push(("defaultValue", value(default)));
+        }
+        properties.push(("directives", directives(&input.directives)));
+        described("InputValueDefinition", &input.description, properties)
+    }).collect())
+}
+
+fn enum_values<'a, T>(values: &[schema::EnumValue<'a, T>]) -> JsonValue
+    where T: Text<'a>,
+{
+    JsonValue::Array(values.iter().map(|val| {
+        described("EnumValueDefinition", &val.description, vec![
+            ("name", name(&val.name)),
+            ("directives", directives(&val.directives)),
+        ])
+    }).collect())
+}
+
+fn type_definition<'a, T>(def: &schema::TypeDefinition<'a, T>) -> JsonValue
+    where T: Text<'a>,
+{
+    use crate::schema::TypeDefinition::*;
+
+    match *def {
+        Scalar(ref t) => described("ScalarTypeDefinition", &t.description,
+            vec![
+                ("name", name(&t.name)),
+                ("directives", directives(&t.directives)),
+            ]),
+        Object(ref t) => described("ObjectTypeDefinition", &t.description,
+            vec![
+                ("name", name(&t.name)),
+                ("interfaces", named_types(&t.implements_interfaces)),
+                ("directives", directives(&t.directives)),
+                ("fields", fields(&t.fields)),
+            ]),
+        Interface(ref t) => described("InterfaceTypeDefinition",
+            &t.description, vec![
+                ("name", name(&t.name)),
+                ("interfaces", json!([])),
+                ("directives", directives(&t.directives)),
+                ("fields", fields(&t.fields)),
+            ]),
+        Union(ref t) => described("UnionTypeDefinition", &t.description,
+            vec![
+                ("name", name(&t.name)),
+                ("directives", directives(&t.directives)),
+                ("types", named_types(&t.types)),
+            ]),
+        Enum(ref t) => described("EnumTypeDefinition", &t.description,
+            vec![
+                ("name", name(&t.name)),
+                ("directives", directives(&t.directives)),
+                ("values", enum_values(&t.values)),
+            ]),
+        InputObject(ref t) => described("InputObjectTypeDefinition",
+            &t.description, vec![
+                ("name", name(&t.name)),
+                ("directives", directives(&t.directives)),
+                ("fields", input_values(&t.fields)),
+            ]),
+    }
+}
+
+fn type_extension<'a, T>(ext: &schema::TypeExtension<'a, T>) -> JsonValue
+    where T: Text<'a>,
+{
+    use crate::schema::TypeExtension::*;
+
+    match *ext {
+        Scalar(ref t) => node("ScalarTypeExtension", vec![
+            ("name", name(&t.name)),
+            ("directives", directives(&t.directives)),
+        ]),
+        Object(ref t) => node("ObjectTypeExtension", vec![
+            ("name", name(&t.name)),
+            ("interfaces", named_types(&t.implements_interfaces)),
+            ("directives", directives(&t.directives)),
+            ("fields", fields(&t.fields)),
+        ]),
+        Interface(ref t) => node("InterfaceTypeExtension", vec![
+            ("name", name(&t.name)),
+            ("interfaces", json!([])),
+            ("directives", directives(&t.directives)),
+            ("fields", fields(&t.fields)),
+        ]),
+        Union(ref t) => node("UnionTypeExtension", vec![
+            ("name", name(&t.name)),
+            ("directives", directives(&t.directives)),
+            ("types", named_types(&t.types)),
+        ]),
+        Enum(ref t) => node("EnumTypeExtension", vec![
+            ("name", name(&t.name)),
+            ("directives", directives(&t.directives)),
+            ("values", enum_values(&t.values)),
+        ]),
+        InputObject(ref t) => node("InputObjectTypeExtension", vec![
+            ("name", name(&t.name)),
+            ("directives", directives(&t.directives)),
+            ("fields", input_values(&t.fields)),
+        ]),
+    }
+}
@@ -0,0 +1,179 @@
+//! Conversion of values to and from JSON
+//!
+use std::convert::TryFrom;
+
+use serde_json::{Map, Number as JsonNumber, Value as JsonValue};
+use thiserror::Error;
+
+use crate::common::{Number, Text, Value};
+
+
+/// Error converting a value to JSON
+///
+/// Variables have no value until the query is executed, so a value
+/// referring to a variable can't be converted.
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("variable ${0} has no value to convert to JSON")]
+pub struct UnresolvedVariable(String);
+
+impl UnresolvedVariable {
+    /// Returns the name of the variable (without the `$`)
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'a, T: Text<'a>> Value<'a, T> {
+    /// Converts a literal value to JSON
+    ///
+    /// Enum values are converted to strings. Floats which aren't finite
+    /// are converted to `null`, like `serde_json` itself does.
+    pub fn into_json(&self) -> Result<JsonValue, UnresolvedVariable> {
+        let value = match *self {
+            Value::Variable(ref name) => {
+                return Err(UnresolvedVariable(name.as_ref().to_string()));
+            }
+            Value::Int(ref num) => JsonValue::Number(num.value.into()),
+            Value::Float(ref num) => JsonNumber::from_f64(num.as_f64())
+                .map(JsonValue::Number)
+                .unwrap_or(JsonValue::Null),
+            Value::String(ref s) | Value::BlockString(ref s) => {
+                JsonValue::String(s.clone())
+            }
+            Value::Boolean(b) => JsonValue::Bool(b),
+            Value::Null => JsonValue::Null,
+            Value::Enum(ref name) => JsonValue::String(name.as_ref().into()),
+            Value::List(ref items) => JsonValue::Array(items.iter()
+                .map(|item| item.into_json())
+                .collect::<Result<_, _>>()?),
+            Value::Object(ref fields) => JsonValue::Object(fields.iter()
+                .map(|(name, value)| {
+                    Ok((name.as_ref().to_string(), value.into_json()?))
+                })
+                .collect::<Result<Map<_, _>, _>>()?),
+        };
+        Ok(value)
+    }
+}
+
+impl<'a, 'v, T: Text<'a>> TryFrom<&'v Value<'a, T>> for JsonValue {
+    type Error = UnresolvedVariable;
+
+    fn try_from(value: &'v Value<'a, T>) -> Result<JsonValue, Self::Error> {
+        value.into_json()
+    }
+}
+
+impl<'a> Value<'a, String> {
+    /// Converts JSON to a literal value
+    ///
+    /// Integers which fit into `i64` become `Int`, other numbers become
+    /// `Float`. JSON has no enums, so all strings become `String`.
+    pub fn from_json(json: &JsonValue) -> Value<'a, String> {
+        match *json {
+            JsonValue::Null => Value::Null,
+            JsonValue::Bool(b) => Value::Boolean(b),
+            JsonValue::Number(ref num) => match num.as_i64() {
+                Some(num) => Value::Int(Number::from(num)),
+                None => {
+                    Value::Float(num.as_f64().unwrap_or(f64::NAN).into())
+                }
+            },
+            JsonValue::String(ref s) => Value::String(s.clone()),
+            JsonValue::Array(ref items) => {
+                Value::List(items.iter().map(Value::from_json).collect())
+            }
+            JsonValue::Object(ref fields) => Value::Object(fields.iter()
+                .map(|(name, value)| (name.clone(), Value::from_json(value)))
+                .collect()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use serde_json::{json, Value as JsonValue};
+
+    use super::UnresolvedVariable;
+    use crate::common::Number;
+    use crate::query::{parse_query, Definition, OperationDefinition};
+    use crate::query::{Selection, Value};
+
+    fn argument(query: &str) -> Value<'_, String> {
+        let doc = parse_query::<String>(query).unwrap();
+        match doc.definitions.into_iter().next() {
+            Some(Definition::Operation(OperationDefinition::SelectionSet(
+                set))) => match set.items.into_iter().next() {
+                Some(Selection::Field(field)) => {
+                    field.arguments.into_iter().next().unwrap().1
+                }
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn nested_object() {
+        let value = argument(r#"{ a(x: {
+            int: -7, float: 1.5, yes: true, no: false, none: null,
+            list: [1, "two", [3.25], {four: 4}],
+            nested: {deeper: {deepest: "bottom"}}
+        }) }"#);
+        let json = json!({
+            "int": -7, "float": 1.5, "yes": true, "no": false, "none": null,
+            "list": [1, "two", [3.25], {"four": 4}],
+            "nested": {"deeper": {"deepest": "bottom"}},
+        });
+        assert_eq!(value.into_json(), Ok(json.clone()));
+        assert_eq!(Value::from_json(&json), value);
+    }
+
+    #[test]
+    fn enums_and_block_strings() {
+        let value = argument(r#"{ a(x: [RED, """block""", "text"]) }"#);
+        assert_eq!(value.into_json(), Ok(json!(["RED", "block", "text"])));
+    }
+
+    #[test]
+    fn variables() {
+        let value = argument("{ a(x: {list: [1, $var]}) }");
+        let err = value.into_json().unwrap_err();
+        assert_eq!(err.name(), "var");
+        assert_eq!(err, UnresolvedVariable("var".into()));
+        assert_eq!(err.to_string(),
+                   "variable $var has no value to convert to JSON");
+    }
+
+    #[test]
+    fn large_numbers() {
+        assert_eq!(Value::from_json(&json!(i64::MAX)),
+                   Value::Int(Number::from(i64::MAX)));
+        assert_eq!(Value::from_json(&json!(u64::MAX)),
+                   Value::Float((u64::MAX as f64).into()));
+        assert_eq!(Value::from_json(&json!(1e300)), Value::Float(1e300.into()));
+    }
+
+    #[test]
+    fn round_trip() {
+        let json = json!([
+            i64::MAX, i64::MAX - 1, i64::MIN, 0, -1,
+            1e308, -1e308, 0.5, f64::MIN_POSITIVE,
+            "ENUM_LIKE", {"nested": [null, true]},
+        ]);
+        let value = Value::from_json(&json);
+        assert_eq!(JsonValue::try_from(&value), Ok(json));
+        match value {
+            Value::List(ref items) => {
+                assert_eq!(items[0], Value::Int(Number::from(i64::MAX)));
+                assert_eq!(items[5], Value::Float(1e308.into()));
+                assert_eq!(items[9], Value::String("ENUM_LIKE".into()));
+            }
+            _ => unreachable!(),
+        }
+        let err = JsonValue::try_from(&argument("{ a(x: $v) }"));
+        assert_eq!(err, Err(UnresolvedVariable("v".into())));
+    }
+}
@@ -0,0 +1,313 @@
+//! Tokenizing without parsing, e.g. for syntax highlighting
+//!
+use combine::easy::Error;
+use combine::{Positioned, StreamOnce};
+use thiserror::Error;
+
+use crate::position::{advance, Pos};
+use crate::tokenizer::{ends_number, Kind, TokenStream};
+
+
+/// Kind of a lexical token
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// Punctuation, e.g. `{`, `:` or `...`
+    Punctuator,
+    /// Names, including keywords like `query` or `true`
+    Name,
+    Int,
+    Float,
+    String,
+    BlockString,
+    /// A comment, from `#` up to the end of the line
+    Comment,
+}
+
+/// A token read by `Lexer`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    /// Source text of the token
+    pub value: &'a str,
+    /// Positions of the start of the token and right after its end
+    pub span: (Pos, Pos),
+}
+
+/// Error reading a token
+///
+/// This structure is opaque for forward compatibility.
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("lexer error at {}: {}", span.0, message)]
+pub struct LexError {
+    message: String,
+    span: (Pos, Pos),
+}
+
+/// An iterator over the tokens and the comments of a source
+///
+/// Whitespace, commas and byte order marks are skipped, everything else
+/// is either a token or an error, so the source is reconstructed from the
+/// spans and the skipped characters between them. An error doesn't stop
+/// the iteration: the invalid character is skipped, or the whole literal
+/// if the error is in a string or a number.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::lexer::{Lexer, TokenKind};
+///
+/// let kinds = Lexer::new("{ a # comment\n }")
+///     .map(|token| token.unwrap().kind)
+///     .collect::<Vec<_>>();
+/// assert_eq!(kinds, [TokenKind::Punctuator, TokenKind::Name,
+///                    TokenKind::Comment, TokenKind::Punctuator]);
+/// ```
+#[derive(Debug)]
+pub struct Lexer<'a> {
+    source: &'a str,
+    tokens: TokenStream<'a>,
+}
+
+impl LexError {
+    /// Returns the position of the invalid text
+    pub fn position(&self) -> Pos {
+        self.span.0
+    }
+
+    /// Returns the positions of the start of the skipped text and right
+    /// after its end
+    pub fn span(&self) -> (Pos, Pos) {
+        self.span
+    }
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(source: &'a str) -> Lexer<'a> {
+        Lexer {
+            source,
+            tokens: TokenStream::keeping_comments(source),
+        }
+    }
+}
+
+/// Returns the tokens and the comments of the source
+///
+/// A shortcut for `Lexer::new(source)`.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::tokenize;
+///
+/// let names = tokenize("query { user }")
+///     .filter_map(|token| token.ok())
+///     .map(|token| token.value)
+///     .collect::<Vec<_>>();
+/// assert_eq!(names, ["query", "{", "user", "}"]);
+/// ```
+pub fn tokenize(source: &str) -> Lexer<'_> {
+    Lexer::new(source)
+}
+
+/// Returns the length of the text up to the end of the line
+fn line_len(rest: &str) -> usize {
+    rest.find(['\r', '\n']).unwrap_or(rest.len())
+}
+
+/// Returns the length of the invalid text to skip
+fn invalid_len(rest: &str) -> usize {
+    match rest.chars().next() {
+        _ if rest.starts_with("\"\"\"") => rest.len(),
+        Some('"') => line_len(rest),
+        Some(c) if c == '-' || c.is_ascii_digit() => {
+            rest.find(ends_number).unwrap_or(rest.len())
+        }
+        Some(c) => c.len_utf8(),
+        None => 0,
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Result<Token<'a>, LexError>> {
+        let start = self.tokens.position();
+        let rest = &self.source[start.offset..];
+        if rest.is_empty() {
+            return None;
+        }
+        if rest.starts_with('#') {
+            let len = line_len(rest);
+            self.tokens.skip(len);
+            return Some(Ok(Token {
+                kind: TokenKind::Comment,
+                value: &rest[..len],
+                span: (start, advance(start, &rest[..len])),
+            }));
+        }
+        match self.tokens.uncons() {
+            Ok(token) => Some(Ok(Token {
+                kind: match token.kind {
+                    Kind::Punctuator => TokenKind::Punctuator,
+                    Kind::Name => TokenKind::Name,
+                    Kind::IntValue => TokenKind::Int,
+                    Kind::FloatValue => TokenKind::Float,
+                    Kind::StringValue => TokenKind::String,
+                    Kind::BlockString => TokenKind::BlockString,
+                },
+                value: token.value,
+                span: (start, advance(start, token.value)),
+            })),
+            Err(err) => {
+                let len = invalid_len(rest);
+                self.tokens.skip(len);
+                let message = match err {
+                    Error::Unexpected(info) | Error::Message(info) => {
+                        info.to_string()
+                    }
+                    err => err.to_string(),
+                };
+                Some(Err(LexError {
+                    message,
+                    span: (start, advance(start, &rest[..len])),
+                }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{tokenize, Lexer, TokenKind};
+    use super::TokenKind::*;
+    use crate::position::Pos;
+
+    fn tokens(source: &str) -> Vec<(TokenKind, &str)> {
+        Lexer::new(source)
+            .map(|token| token.unwrap())
+            .map(|token| (token.kind, token.value))
+            .collect()
+    }
+
+    /// Checks that the source consists of the spans and ignored characters
+    fn check_lossless(source: &str) {
+        let mut text = std::string::String::new();
+        let mut end = 0;
+        for item in Lexer::new(source) {
+            let span = match item {
+                Ok(token) => token.span,
+                Err(err) => err.span(),
+            };
+            let skipped = &source[end..span.0.offset];
+            assert!(skipped.chars().all(|c| {
+                matches!(c, ' ' | '\t' | '\n' | '\r' | ',' | '\u{feff}')
+            }), "{:?}", skipped);
+            text.push_str(skipped);
+            text.push_str(&source[span.0.offset..span.1.offset]);
+            end = span.1.offset;
+        }
+        assert!(source[end..].trim().is_empty());
+        text.push_str(&source[end..]);
+        assert_eq!(text, source);
+    }
+
+    #[test]
+    fn query() {
+        let source = "query Q($id: ID = \"x\") {\n  \
+            user(id: $id, n: -1.5e3) { ...F @skip(if: true) }\n}";
+        assert_eq!(tokens(source), [
+            (Name, "query"), (Name, "Q"), (Punctuator, "("),
+            (Punctuator, "$"), (Name, "id"), (Punctuator, ":"), (Name, "ID"),
+            (Punctuator, "="), (String, "\"x\""), (Punctuator, ")"),
+            (Punctuator, "{"), (Name, "user"), (Punctuator, "("),
+            (Name, "id"), (Punctuator, ":"), (Punctuator, "$"), (Name, "id"),
+            (Name, "n"), (Punctuator, ":"), (Float, "-1.5e3"),
+            (Punctuator, ")"), (Punctuator, "{"), (Punctuator, "..."),
+            (Name, "F"), (Punctuator, "@"), (Name, "skip"),
+            (Punctuator, "("), (Name, "if"), (Punctuator, ":"),
+            (Name, "true"), (Punctuator, ")"), (Punctuator, "}"),
+            (Punctuator, "}"),
+        ]);
+        check_lossless(source);
+    }
+
+    #[test]
+    fn tokenize_field() {
+        let kinds = tokenize("{ a(b: 1) }")
+            .map(|token| token.unwrap().kind)
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, [Punctuator, Name, Punctuator, Name, Punctuator,
+                           Int, Punctuator, Punctuator]);
+        let last = tokenize("{ a(b: 1) }").last().unwrap().unwrap();
+        assert_eq!(last.span, (Pos { line: 1, column: 11, offset: 10 },
+                               Pos { line: 1, column: 12, offset: 11 }));
+    }
+
+    #[test]
+    fn comments() {
+        let source = "# leading\r\n{ a # trailing\n  b(x: 1)\n}\n# last";
+        assert_eq!(tokens(source), [
+            (Comment, "# leading"), (Punctuator, "{"), (Name, "a"),
+            (Comment, "# trailing"), (Name, "b"), (Punctuator, "("),
+            (Name, "x"), (Punctuator, ":"), (Int, "1"), (Punctuator, ")"),
+            (Punctuator, "}"), (Comment, "# last"),
+        ]);
+        let spans = Lexer::new(source)
+            .map(|token| token.unwrap().span.0)
+            .collect::<Vec<_>>();
+        assert_eq!(spans[3], Pos { line: 2, column: 5, offset: 15 });
+        assert_eq!(spans[4], Pos { line: 3, column: 3, offset: 28 });
+        check_lossless(source);
+    }
+
+    #[test]
+    fn schema() {
+        let source = "\
+\"\"\"
+Description
+\"\"\"
+type Query implements Node & Entity {
+  id: ID! # identifier
+  list: [Int!]! @deprecated(reason: \"no\")
+}
+union U = | A | B
+";
+        assert_eq!(tokens(source), [
+            (BlockString, "\"\"\"\nDescription\n\"\"\""),
+            (Name, "type"), (Name, "Query"), (Name, "implements"),
+            (Name, "Node"), (Punctuator, "&"), (Name, "Entity"),
+            (Punctuator, "{"), (Name, "id"), (Punctuator, ":"),
+            (Name, "ID"), (Punctuator, "!"), (Comment, "# identifier"),
+            (Name, "list"), (Punctuator, ":"), (Punctuator, "["),
+            (Name, "Int"), (Punctuator, "!"), (Punctuator, "]"),
+            (Punctuator, "!"), (Punctuator, "@"), (Name, "deprecated"),
+            (Punctuator, "("), (Name, "reason"), (Punctuator, ":"),
+            (String, "\"no\""), (Punctuator, ")"), (Punctuator, "}"),
+            (Name, "union"), (Name, "U"), (Punctuator, "="),
+            (Punctuator, "|"), (Name, "A"), (Punctuator, "|"), (Name, "B"),
+        ]);
+        check_lossless(source);
+    }
+
+    #[test]
+    fn invalid() {
+        let source = "{ a ~ b(x: 01, s: \"open\n c }";
+        let items = Lexer::new(source)
+            .map(|item| match item {
+                Ok(token) => Ok(token.value),
+                Err(err) => Err((err.position(), err.to_string())),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(items, [
+            Ok("{"), Ok("a"),
+            Err((Pos { line: 1, column: 5, offset: 4 },
+                 "lexer error at 1:5: unexpected character '~'".into())),
+            Ok("b"), Ok("("), Ok("x"), Ok(":"),
+            Err((Pos { line: 1, column: 12, offset: 11 },
+                 "lexer error at 1:12: unsupported integer \"01\"".into())),
+            Ok("s"), Ok(":"),
+            Err((Pos { line: 1, column: 19, offset: 18 },
+                 "lexer error at 1:19: unterminated string value".into())),
+            Ok("c"), Ok("}"),
+        ]);
+        check_lossless(source);
+    }
+}
@@ -74,9 +74,9 @@
 //! }
 //!
 //! \"\"\"
-//!   Example user object
+//! Example user object
 //!
-//!   This is just a demo comment.
+//! This is just a demo comment.
 //! \"\"\"
 //! type User {
 //!   name: String!
@@ -89,21 +89,52 @@
 //! # }
 //! ```
 //!
+//! Serialization
+//! -------------
+//!
+//! With the `serde` feature enabled, every AST node (and `Pos`) implements
+//! `Serialize`, and also `Deserialize` for `String` text. The
+//! representation is the one derived by serde: structs are maps keyed by
+//! field names and enums are externally tagged, i.e. `{"Int": 1}` or
+//! `"Null"` for unit variants. Arguments and object fields are lists of
+//! `[name, value]` pairs and maps respectively.
+//!
 #![warn(missing_debug_implementations)]
 
 #[cfg(test)] #[macro_use] extern crate pretty_assertions;
 
 
+#[macro_use]
 mod common;
 #[macro_use]
 mod format;
+mod parse_options;
 mod position;
 mod tokenizer;
 mod helpers;
+mod interner;
+mod traverse;
+pub mod document;
+pub mod lexer;
+mod sha256;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "json")]
+pub mod js_ast;
+pub mod persisted;
 pub mod query;
+mod reformat;
 pub mod schema;
+pub mod validation;
 
 pub use crate::query::parse_query;
 pub use crate::schema::parse_schema;
+pub use crate::document::parse_document;
+pub use crate::lexer::tokenize;
+pub use crate::reformat::reformat;
 pub use crate::position::Pos;
-pub use crate::format::Style;
+pub use crate::interner::{InternedStr, StringInterner};
+pub use crate::parse_options::ParseOptions;
+pub use crate::format::{Style, FormatOptions, Indent, BlockOrString};
+#[cfg(feature = "json")]
+pub use crate::json::UnresolvedVariable;
@@ -0,0 +1,20 @@
+/// Options of `parse_query_with` and `parse_schema_with`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseOptions {
+    /// Maximum nesting of brackets: `{}`, `[]` and `()`
+    ///
+    /// The parser is recursive, so deeper nesting is rejected with an error
+    /// (see `ParseError::is_depth_limit_exceeded`) before it overflows the
+    /// stack. The default of 50 fits into the 2 MiB stack of spawned
+    /// threads even in debug builds, so raise the limit only together with
+    /// the stack size.
+    pub max_depth: usize,
+}
+
+impl Default for ParseOptions {
+    fn default() -> ParseOptions {
+        ParseOptions {
+            max_depth: 50,
+        }
+    }
+}
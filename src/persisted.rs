@@ -0,0 +1,405 @@
+//! Persisted query ids and manifests compatible with Apollo tooling
+//!
+//! Apollo clients identify a persisted query by the SHA-256 of the query
+//! printed by the `print` function of graphql-js, after sorting its
+//! definitions (operations first, then by name). `print` mirrors that
+//! function byte for byte, so ids computed here match the ones of
+//! `@apollo/generate-persisted-query-manifest` and the client links.
+//!
+//! Like graphql-js, numbers are printed as written in the source (`1.50`
+//! stays `1.50`) and fields of input object literals keep their order.
+//!
+use crate::common::{Directive, Text, Value};
+use crate::query::{Definition, Document, FragmentDefinition, OperationType};
+use crate::query::{OperationDefinition, Selection, SelectionSet};
+use crate::query::{TypeCondition, VariableDefinition};
+use crate::sha256::Sha256;
+
+
+/// Line length after which graphql-js puts field arguments on their own
+/// lines
+const MAX_LINE_LENGTH: usize = 80;
+
+/// Persisted query manifest in the `apollo-persisted-query-manifest`
+/// format
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Manifest {
+    /// Always `apollo-persisted-query-manifest`
+    pub format: String,
+    /// Always `1`
+    pub version: u32,
+    pub operations: Vec<ManifestOperation>,
+}
+
+/// A single operation of a `Manifest`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ManifestOperation {
+    /// SHA-256 of the `body` as lowercase hex
+    pub id: String,
+    /// The document as printed by `print`
+    pub body: String,
+    pub name: String,
+    /// One of `query`, `mutation` or `subscription`
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub operation_type: String,
+}
+
+/// Returns the document printed as by the `print` function of graphql-js
+///
+/// Definitions are printed in the order of the document, use
+/// `sha256_hash` or `build_manifest` to also sort them as Apollo does.
+pub fn print<'a, T: Text<'a>>(doc: &Document<'a, T>) -> String {
+    join(doc.definitions.iter().map(definition), "\n\n")
+}
+
+/// Returns the id of the persisted query as computed by Apollo tooling
+///
+/// That's the SHA-256 (as lowercase hex) of the document printed by
+/// `print` with operations first and then fragments, each sorted by name.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::parse_query;
+/// use graphql_parser::persisted::sha256_hash;
+///
+/// let a = parse_query::<&str>("query Q { a ...F } fragment F on T { b }")
+///     .unwrap();
+/// let b = parse_query::<&str>("fragment F on T{b}, query Q{a,...F}")
+///     .unwrap();
+/// assert_eq!(sha256_hash(&a), sha256_hash(&b));
+/// ```
+pub fn sha256_hash<'a, T: Text<'a>>(doc: &Document<'a, T>) -> String {
+    hex(&sorted_body(doc))
+}
+
+/// Returns the manifest of the operation documents keyed by operation name
+///
+/// Each document should contain a single operation along with the
+/// fragments it uses (see `query::separate_operations`). The type of the
+/// manifest entry is the type of the first operation of the document.
+pub fn build_manifest<'a, T, N>(documents: &[(N, Document<'a, T>)])
+    -> Manifest
+    where T: Text<'a>,
+          N: AsRef<str>,
+{
+    let operations = documents.iter()
+        .map(|(name, doc)| {
+            let body = sorted_body(doc);
+            let operation_type = match doc.operations().next()
+                .map(|op| op.operation_type())
+            {
+                Some(OperationType::Mutation) => "mutation",
+                Some(OperationType::Subscription) => "subscription",
+                Some(OperationType::Query) | None => "query",
+            };
+            ManifestOperation {
+                id: hex(&body),
+                body,
+                name: name.as_ref().to_string(),
+                operation_type: operation_type.to_string(),
+            }
+        })
+        .collect();
+    Manifest {
+        format: "apollo-persisted-query-manifest".into(),
+        version: 1,
+        operations,
+    }
+}
+
+/// Prints the document with definitions sorted as `sortTopLevelDefinitions`
+/// of `@apollo/persisted-query-lists`
+fn sorted_body<'a, T: Text<'a>>(doc: &Document<'a, T>) -> String {
+    let mut definitions = doc.definitions.iter().collect::<Vec<_>>();
+    definitions.sort_by_key(|def| match **def {
+        Definition::Operation(ref op) => (0, op.name().unwrap_or("")),
+        Definition::Fragment(ref frag) => (1, frag.name.as_ref()),
+    });
+    join(definitions.into_iter().map(definition), "\n\n")
+}
+
+fn hex(body: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(body.as_bytes());
+    hasher.finish().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Joins non-empty strings with the separator, as `join` of graphql-js
+pub(crate) fn join<I>(items: I, separator: &str) -> String
+    where I: IntoIterator<Item=String>,
+{
+    items.into_iter()
+        .filter(|item| !item.is_empty())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Wraps a non-empty string, as `wrap` of graphql-js
+pub(crate) fn wrap(start: &str, value: &str, end: &str) -> String {
+    if value.is_empty() {
+        String::new()
+    } else {
+        format!("{}{}{}", start, value, end)
+    }
+}
+
+fn indent(value: &str) -> String {
+    wrap("  ", &value.replace('\n', "\n  "), "")
+}
+
+fn block(items: Vec<String>) -> String {
+    if items.is_empty() {
+        String::new()
+    } else {
+        format!("{{\n{}\n}}", indent(&join(items, "\n")))
+    }
+}
+
+fn definition<'a, T: Text<'a>>(def: &Definition<'a, T>) -> String {
+    match *def {
+        Definition::Operation(ref op) => operation(op),
+        Definition::Fragment(ref frag) => fragment(frag),
+    }
+}
+
+fn operation<'a, T: Text<'a>>(op: &OperationDefinition<'a, T>) -> String {
+    let (kind, name, vars, dirs, sel) = match *op {
+        OperationDefinition::SelectionSet(ref sel) => {
+            ("query", None, &[][..], &[][..], sel)
+        }
+        OperationDefinition::Query(ref q) => {
+            ("query", q.name.as_ref(), &q.variable_definitions[..],
+             &q.directives[..], &q.selection_set)
+        }
+        OperationDefinition::Mutation(ref m) => {
+            ("mutation", m.name.as_ref(), &m.variable_definitions[..],
+             &m.directives[..], &m.selection_set)
+        }
+        OperationDefinition::Subscription(ref s) => {
+            ("subscription", s.name.as_ref(), &s.variable_definitions[..],
+             &s.directives[..], &s.selection_set)
+        }
+    };
+    let var_defs = wrap("(", &join(vars.iter().map(variable), ", "), ")");
+    let name = name.map(|name| name.as_ref()).unwrap_or("");
+    let prefix = join(vec![
+        kind.to_string(),
+        join(vec![name.to_string(), var_defs], ""),
+        directives(dirs),
+    ], " ");
+    if prefix == "query" {
+        selection_set(sel)
+    } else {
+        format!("{} {}", prefix, selection_set(sel))
+    }
+}
+
+fn variable<'a, T: Text<'a>>(var: &VariableDefinition<'a, T>) -> String {
+    let default = var.default_value.as_ref().map(value).unwrap_or_default();
+    format!("${}: {}{}", var.name.as_ref(), var.var_type,
+            wrap(" = ", &default, ""))
+}
+
+fn fragment<'a, T: Text<'a>>(frag: &FragmentDefinition<'a, T>) -> String {
+    let TypeCondition::On(ref type_name) = frag.type_condition;
+    format!("fragment {} on {} {}{}", frag.name.as_ref(), type_name.as_ref(),
+            wrap("", &directives(&frag.directives), " "),
+            selection_set(&frag.selection_set))
+}
+
+fn selection_set<'a, T: Text<'a>>(sel: &SelectionSet<'a, T>) -> String {
+    block(sel.items.iter().map(selection).collect())
+}
+
+fn selection<'a, T: Text<'a>>(sel: &Selection<'a, T>) -> String {
+    match *sel {
+        Selection::Field(ref field) => {
+            let alias = field.alias.as_ref().map(|a| a.as_ref()).unwrap_or("");
+            let prefix = wrap("", alias, ": ") + field.name.as_ref();
+            let args = field.arguments.iter()
+                .map(|(name, val)| argument(name, val))
+                .collect::<Vec<_>>();
+            let mut args_line = prefix.clone() +
+                &wrap("(", &join(args.clone(), ", "), ")");
+            if args_line.encode_utf16().count() > MAX_LINE_LENGTH {
+                args_line = prefix +
+                    &wrap("(\n", &indent(&join(args, "\n")), "\n)");
+            }
+            join(vec![
+                args_line,
+                directives(&field.directives),
+                selection_set(&field.selection_set),
+            ], " ")
+        }
+        Selection::FragmentSpread(ref spread) => {
+            format!("...{}{}", spread.fragment_name.as_ref(),
+                    wrap(" ", &directives(&spread.directives), ""))
+        }
+        Selection::InlineFragment(ref frag) => {
+            let cond = match frag.type_condition {
+                Some(TypeCondition::On(ref name)) => name.as_ref(),
+                None => "",
+            };
+            join(vec![
+                "...".to_string(),
+                wrap("on ", cond, ""),
+                directives(&frag.directives),
+                selection_set(&frag.selection_set),
+            ], " ")
+        }
+    }
+}
+
+fn directives<'a, T: Text<'a>>(dirs: &[Directive<'a, T>]) -> String {
+    join(dirs.iter().map(|dir| {
+        let args = dir.arguments.iter()
+            .map(|(name, val)| argument(name, val));
+        format!("@{}{}", dir.name.as_ref(), wrap("(", &join(args, ", "), ")"))
+    }), " ")
+}
+
+fn argument<'a, T: Text<'a>>(name: &T::Value, val: &Value<'a, T>) -> String {
+    format!("{}: {}", name.as_ref(), value(val))
+}
+
+pub(crate) fn value<'a, T: Text<'a>>(val: &Value<'a, T>) -> String {
+    match *val {
+        Value::Variable(ref name) => format!("${}", name.as_ref()),
+        Value::String(ref s) => string(s),
+        Value::BlockString(ref s) => block_string(s),
+        Value::List(ref items) => {
+            format!("[{}]", join(items.iter().map(value), ", "))
+        }
+        Value::Object(ref fields) => {
+            format!("{{{}}}", join(fields.iter()
+                .map(|(name, val)| argument(name, val)), ", "))
+        }
+        Value::Int(ref num) => {
+            num.raw().map_or_else(|| val.to_string(), String::from)
+        }
+        Value::Float(ref num) => {
+            num.raw().map_or_else(|| val.to_string(), String::from)
+        }
+        Value::Boolean(_)
+        | Value::Null
+        | Value::Enum(_)
+        => val.to_string(),
+    }
+}
+
+/// Quotes the string as `printString` of graphql-js
+pub(crate) fn string(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+    for c in value.chars() {
+        match c {
+            '\u{8}' => result.push_str("\\b"),
+            '\t' => result.push_str("\\t"),
+            '\n' => result.push_str("\\n"),
+            '\u{c}' => result.push_str("\\f"),
+            '\r' => result.push_str("\\r"),
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\u{0}'..='\u{1f}' | '\u{7f}'..='\u{9f}' => {
+                result.push_str(&format!("\\u{:04X}", c as u32));
+            }
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Quotes the string as `printBlockString` of graphql-js
+pub(crate) fn block_string(value: &str) -> String {
+    let is_space = |c: Option<char>| c == Some(' ') || c == Some('\t');
+    let escaped = value.replace("\"\"\"", "\\\"\"\"");
+    let lines = escaped.split("\r\n")
+        .flat_map(|line| line.split(['\n', '\r']))
+        .collect::<Vec<_>>();
+    let is_single_line = lines.len() == 1;
+    let force_leading_newline = lines.len() > 1 && lines[1..].iter()
+        .all(|line| line.is_empty() || is_space(line.chars().next()));
+    let has_trailing_triple_quotes = escaped.ends_with("\\\"\"\"");
+    let has_trailing_quote = value.ends_with('"')
+        && !has_trailing_triple_quotes;
+    let has_trailing_slash = value.ends_with('\\');
+    let force_trailing_newline = has_trailing_quote || has_trailing_slash;
+    let print_as_multiple_lines = !is_single_line
+        || value.encode_utf16().count() > 70
+        || force_trailing_newline
+        || force_leading_newline
+        || has_trailing_triple_quotes;
+    let skip_leading_newline = is_single_line
+        && is_space(value.chars().next());
+    let mut result = String::from("\"\"\"");
+    if print_as_multiple_lines && !skip_leading_newline
+        || force_leading_newline
+    {
+        result.push('\n');
+    }
+    result.push_str(&escaped);
+    if print_as_multiple_lines || force_trailing_newline {
+        result.push('\n');
+    }
+    result.push_str("\"\"\"");
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{block_string, print, string};
+    use crate::query::parse_query;
+
+    fn reprint(query: &str) -> String {
+        print(&parse_query::<&str>(query).unwrap())
+    }
+
+    #[test]
+    fn operations() {
+        assert_eq!(reprint("query { a }"), "{\n  a\n}");
+        assert_eq!(reprint("query($x: [Int!] = [1, 2]) @d { a }"),
+                   "query ($x: [Int!] = [1, 2]) @d {\n  a\n}");
+        assert_eq!(reprint("mutation M { a(x: {y: $v, z: ENUM}) }"),
+                   "mutation M {\n  a(x: {y: $v, z: ENUM})\n}");
+        assert_eq!(reprint("fragment F on T @d(a: null) { ... @skip(if: true) \
+                            { b: c } ... on U { d } ...G }"),
+                   "fragment F on T @d(a: null) {\n  \
+                    ... @skip(if: true) {\n    b: c\n  }\n  \
+                    ... on U {\n    d\n  }\n  ...G\n}");
+    }
+
+    #[test]
+    fn literals_as_written() {
+        assert_eq!(reprint("{ a(x: 1.50, y: -1E3, z: -0, w: {b: 0.0, a: 1}) }"),
+                   "{\n  a(x: 1.50, y: -1E3, z: -0, w: {b: 0.0, a: 1})\n}");
+        assert_eq!(reprint("query($x: In = {z: [1e-2], y: 0}) { a }"),
+                   "query ($x: In = {z: [1e-2], y: 0}) {\n  a\n}");
+    }
+
+    #[test]
+    fn long_arguments() {
+        assert_eq!(reprint("{ field(first: 10, \
+                            after: \"abcdefghijklmnopqrstuvwxyz\", \
+                            filter: {name: \"abcdefghijk\"}) { id } }"),
+                   "{\n  field(\n    first: 10\n    after: \
+                    \"abcdefghijklmnopqrstuvwxyz\"\n    filter: {name: \
+                    \"abcdefghijk\"}\n  ) {\n    id\n  }\n}");
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(string("a\"b\\c\n\u{7}\u{b}\u{9f}é"),
+                   r#""a\"b\\c\n\u0007\u000B\u009Fé""#);
+        assert_eq!(block_string("text"), r#""""text""""#);
+        assert_eq!(block_string(" indented"), r#"""" indented""""#);
+        assert_eq!(block_string("a\nb"), "\"\"\"\na\nb\n\"\"\"");
+        assert_eq!(block_string("a\n  b"), "\"\"\"\na\n  b\n\"\"\"");
+        assert_eq!(block_string("ends with \""),
+                   "\"\"\"\nends with \"\n\"\"\"");
+        assert_eq!(block_string("has \"\"\" quotes"),
+                   "\"\"\"has \\\"\"\" quotes\"\"\"");
+    }
+}
@@ -1,12 +1,91 @@
+use std::cmp::Ordering;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
 /// Original position of element in source code
-#[derive(PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Default, Hash)]
+///
+/// Positions are compared and hashed by line and column only: for
+/// positions in the same source the offset follows from them, and
+/// positions constructed by hand usually don't know the offset.
+#[derive(Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pos {
     /// One-based line number
     pub line: usize,
     /// One-based column number
     pub column: usize,
+    /// Zero-based offset in bytes from the start of the source
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub offset: usize,
+}
+
+impl Pos {
+    /// Creates a position with an unknown (zero) offset
+    pub fn new(line: usize, column: usize) -> Pos {
+        Pos { line, column, offset: 0 }
+    }
+
+    /// Returns the position used for nodes which are not parsed
+    ///
+    /// Same as `Pos::default()`.
+    pub const fn zero() -> Pos {
+        Pos { line: 0, column: 0, offset: 0 }
+    }
+
+    /// Returns the one-based column in UTF-16 code units, as used by the
+    /// language server protocol
+    ///
+    /// `column` counts characters, so it differs after astral characters
+    /// (e.g. emoji) which take two code units. The position must come
+    /// from parsing `source`, as the column is computed from `offset`.
+    pub fn utf16_column(&self, source: &str) -> usize {
+        let before = &source[..self.offset];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        before[line_start..].encode_utf16().count() + 1
+    }
+}
+
+/// Returns the position after `text` written starting at `start`
+pub(crate) fn advance(start: Pos, text: &str) -> Pos {
+    match text.rfind('\n') {
+        Some(index) => Pos {
+            line: start.line + text.matches('\n').count(),
+            column: text[index + 1..].chars().count() + 1,
+            offset: start.offset + text.len(),
+        },
+        None => Pos {
+            line: start.line,
+            column: start.column + text.chars().count(),
+            offset: start.offset + text.len(),
+        },
+    }
+}
+
+impl PartialEq for Pos {
+    fn eq(&self, other: &Pos) -> bool {
+        self.line == other.line && self.column == other.column
+    }
+}
+
+impl Eq for Pos {}
+
+impl PartialOrd for Pos {
+    fn partial_cmp(&self, other: &Pos) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pos {
+    fn cmp(&self, other: &Pos) -> Ordering {
+        (self.line, self.column).cmp(&(other.line, other.column))
+    }
+}
+
+impl Hash for Pos {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.line.hash(state);
+        self.column.hash(state);
+    }
 }
 
 impl fmt::Debug for Pos {
@@ -20,3 +99,28 @@ impl fmt::Display for Pos {
         write!(f, "{}:{}", self.line, self.column)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::query::{parse_query, Definition, OperationDefinition};
+    use crate::query::Selection;
+
+    #[test]
+    fn utf16_column() {
+        let source = "{\n  a(x: \"\u{1F600} \u{e9}\") b\n}";
+        let doc = parse_query::<&str>(source).unwrap();
+        let items = match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::SelectionSet(ref set))
+            => &set.items,
+            _ => unreachable!(),
+        };
+        let (a, b) = match (&items[0], &items[1]) {
+            (Selection::Field(a), Selection::Field(b)) => (a, b),
+            _ => unreachable!(),
+        };
+        assert_eq!(a.position.column, 3);
+        assert_eq!(a.position.utf16_column(source), 3);
+        assert_eq!(b.position.column, 15);
+        assert_eq!(b.position.utf16_column(source), 16);
+    }
+}
@@ -5,15 +5,154 @@
 //!
 //! [graphql grammar]: http://facebook.github.io/graphql/October2016/#sec-Appendix-Grammar-Summary
 //!
+use std::collections::HashMap;
+
 use crate::position::Pos;
-pub use crate::common::{Directive, Number, Value, Text, Type};
+pub use crate::common::{Directive, Float, Number, ObjectFields, Value, Text};
+pub use crate::common::Type;
+pub use crate::common::{get_named_type, is_list, is_non_null, unwrap_non_null};
 
 /// Root of query data
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct Document<'a, T: Text<'a>> {
     pub definitions: Vec<Definition<'a, T>>,
 }
 
+impl<'a, T: Text<'a>> Default for Document<'a, T> {
+    fn default() -> Self {
+        Document { definitions: Vec::new() }
+    }
+}
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    /// Iterates over all fields of operations and fragments
+    ///
+    /// Fields are returned in document order, each one followed by the
+    /// fields of its selection set. Fields of inline fragments are
+    /// included, fragment spreads are not followed.
+    pub fn all_fields(&self) -> impl Iterator<Item=&Field<'a, T>> {
+        AllFields {
+            definitions: self.definitions.iter(),
+            stack: Vec::new(),
+        }
+    }
+
+    /// Iterates over fragment definitions in document order
+    ///
+    /// ```rust
+    /// # extern crate graphql_parser;
+    /// # use graphql_parser::query::parse_query;
+    /// let doc = parse_query::<&str>("
+    ///     fragment A on T { x } { ...A } fragment B on T { y }
+    /// ").unwrap();
+    /// let names = doc.fragments().map(|f| f.name).collect::<Vec<_>>();
+    /// assert_eq!(names, ["A", "B"]);
+    /// ```
+    pub fn fragments(&self)
+        -> impl Iterator<Item=&FragmentDefinition<'a, T>>
+    {
+        self.definitions.iter().filter_map(|def| match *def {
+            Definition::Fragment(ref frag) => Some(frag),
+            Definition::Operation(_) => None,
+        })
+    }
+
+    /// Returns the (first) fragment definition with the name
+    ///
+    /// ```rust
+    /// # extern crate graphql_parser;
+    /// # use graphql_parser::query::parse_query;
+    /// let doc = parse_query::<&str>("
+    ///     { ...A } fragment A on T { x }
+    /// ").unwrap();
+    /// assert_eq!(doc.fragment("A").unwrap().to_string(),
+    ///            "fragment A on T {\n  x\n}\n");
+    /// assert!(doc.fragment("B").is_none());
+    /// ```
+    pub fn fragment(&self, name: &str) -> Option<&FragmentDefinition<'a, T>> {
+        self.fragments().find(|frag| frag.name.as_ref() == name)
+    }
+
+    /// Returns fragment definitions by name, for repeated lookups
+    ///
+    /// If multiple fragments have the same name, the first one is used.
+    ///
+    /// ```rust
+    /// # extern crate graphql_parser;
+    /// # use graphql_parser::query::parse_query;
+    /// let doc = parse_query::<String>("
+    ///     fragment A on T { x } fragment B on T { y }
+    /// ").unwrap();
+    /// let fragments = doc.fragment_map();
+    /// assert_eq!(fragments.len(), 2);
+    /// assert_eq!(fragments["B"].name, "B");
+    /// ```
+    pub fn fragment_map(&self) -> FragmentMap<'_, 'a, T> {
+        let mut map = HashMap::new();
+        for frag in self.fragments() {
+            map.entry(frag.name.as_ref()).or_insert(frag);
+        }
+        map
+    }
+
+    /// Iterates over operation definitions in document order
+    ///
+    /// ```rust
+    /// # extern crate graphql_parser;
+    /// # use graphql_parser::query::parse_query;
+    /// let doc = parse_query::<&str>("
+    ///     query A { x } fragment F on T { y } mutation B { z }
+    /// ").unwrap();
+    /// let names = doc.operations().map(|op| op.name()).collect::<Vec<_>>();
+    /// assert_eq!(names, [Some("A"), Some("B")]);
+    /// ```
+    pub fn operations(&self)
+        -> impl Iterator<Item=&OperationDefinition<'a, T>>
+    {
+        self.definitions.iter().filter_map(|def| match *def {
+            Definition::Operation(ref op) => Some(op),
+            Definition::Fragment(_) => None,
+        })
+    }
+
+    /// Returns the operation with the name, or without a name the only
+    /// operation of the document
+    ///
+    /// See `get_operation` for a version reporting why the operation
+    /// couldn't be selected.
+    ///
+    /// ```rust
+    /// # extern crate graphql_parser;
+    /// # use graphql_parser::query::parse_query;
+    /// let doc = parse_query::<&str>("query A { x } query B { y }").unwrap();
+    /// assert_eq!(doc.operation(Some("B")).unwrap().name(), Some("B"));
+    /// assert!(doc.operation(Some("C")).is_none());
+    /// assert!(doc.operation(None).is_none());
+    /// ```
+    pub fn operation(&self, name: Option<&str>)
+        -> Option<&OperationDefinition<'a, T>>
+    {
+        crate::query::get_operation(self, name).ok()
+    }
+
+    /// Returns the number of operation definitions
+    ///
+    /// ```rust
+    /// # extern crate graphql_parser;
+    /// # use graphql_parser::query::parse_query;
+    /// let doc = parse_query::<&str>("{ x } fragment F on T { y }").unwrap();
+    /// assert_eq!(doc.operation_count(), 1);
+    /// ```
+    pub fn operation_count(&self) -> usize {
+        self.operations().count()
+    }
+}
+
 impl<'a> Document<'a, String> {
     pub fn into_static(self) -> Document<'static, String> {
         // To support both reference and owned values in the AST,
@@ -33,22 +172,62 @@ impl<'a> Document<'a, String> {
     }
 }
 
+/// Fragment definitions by name, see `Document::fragment_map`
+pub type FragmentMap<'d, 'a, T> =
+    HashMap<&'d str, &'d FragmentDefinition<'a, T>>;
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub enum Definition<'a, T: Text<'a>> {
     Operation(OperationDefinition<'a, T>),
     Fragment(FragmentDefinition<'a, T>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct FragmentDefinition<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: T::Value,
+    #[cfg(feature = "fragment-arguments")]
+    pub variable_definitions: Vec<VariableDefinition<'a, T>>,
     pub type_condition: TypeCondition<'a, T>,
     pub directives: Vec<Directive<'a, T>>,
     pub selection_set: SelectionSet<'a, T>,
 }
 
+impl<'a, T> FragmentDefinition<'a, T>
+    where T: Text<'a>
+{
+    pub fn new(name: T::Value, type_condition: T::Value,
+        selection_set: SelectionSet<'a, T>)
+        -> Self
+    {
+        Self {
+            position: Pos::default(),
+            name,
+            #[cfg(feature = "fragment-arguments")]
+            variable_definitions: vec![],
+            type_condition: TypeCondition::On(type_condition),
+            directives: vec![],
+            selection_set,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub enum OperationDefinition<'a, T: Text<'a>> {
     SelectionSet(SelectionSet<'a, T>),
     Query(Query<'a, T>),
@@ -56,7 +235,75 @@ pub enum OperationDefinition<'a, T: Text<'a>> {
     Subscription(Subscription<'a, T>),
 }
 
+impl<'a, T> OperationDefinition<'a, T>
+    where T: Text<'a>
+{
+    /// Returns an anonymous `query` operation without variables
+    pub fn query(selection_set: SelectionSet<'a, T>) -> Self {
+        OperationDefinition::Query(Query {
+            position: Pos::default(),
+            name: None,
+            variable_definitions: vec![],
+            directives: vec![],
+            selection_set,
+        })
+    }
+}
+
+impl<'a, T> OperationDefinition<'a, T>
+    where T: Text<'a>,
+          T::Value: AsRef<str>,
+{
+    /// Returns the kind of the operation, the shorthand `{ ... }` form is
+    /// a query
+    pub fn operation_type(&self) -> OperationType {
+        match *self {
+            OperationDefinition::SelectionSet(_) => OperationType::Query,
+            OperationDefinition::Query(_) => OperationType::Query,
+            OperationDefinition::Mutation(_) => OperationType::Mutation,
+            OperationDefinition::Subscription(_) => {
+                OperationType::Subscription
+            }
+        }
+    }
+
+    /// Returns the name of the operation, if it has one
+    pub fn name(&self) -> Option<&str> {
+        let name = match *self {
+            OperationDefinition::SelectionSet(_) => None,
+            OperationDefinition::Query(ref q) => q.name.as_ref(),
+            OperationDefinition::Mutation(ref m) => m.name.as_ref(),
+            OperationDefinition::Subscription(ref s) => s.name.as_ref(),
+        };
+        name.map(|name| name.as_ref())
+    }
+
+    /// Returns the top-level selection set of the operation
+    pub fn selection_set(&self) -> &SelectionSet<'a, T> {
+        match *self {
+            OperationDefinition::SelectionSet(ref set) => set,
+            OperationDefinition::Query(ref q) => &q.selection_set,
+            OperationDefinition::Mutation(ref m) => &m.selection_set,
+            OperationDefinition::Subscription(ref s) => &s.selection_set,
+        }
+    }
+}
+
+/// Kind of an operation, see `OperationDefinition::operation_type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OperationType {
+    Query,
+    Mutation,
+    Subscription,
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct Query<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: Option<T::Value>,
@@ -66,6 +313,11 @@ pub struct Query<'a, T: Text<'a>> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct Mutation<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: Option<T::Value>,
@@ -75,6 +327,11 @@ pub struct Mutation<'a, T: Text<'a>> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct Subscription<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: Option<T::Value>,
@@ -84,12 +341,39 @@ pub struct Subscription<'a, T: Text<'a>> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct SelectionSet<'a, T: Text<'a>> {
     pub span: (Pos, Pos),
     pub items: Vec<Selection<'a, T>>,
 }
 
+impl<'a, T> SelectionSet<'a, T>
+    where T: Text<'a>
+{
+    pub fn of(items: Vec<Selection<'a, T>>) -> Self {
+        Self {
+            span: (Pos::default(), Pos::default()),
+            items,
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Default for SelectionSet<'a, T> {
+    fn default() -> Self {
+        SelectionSet::of(vec![])
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct VariableDefinition<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: T::Value,
@@ -97,14 +381,55 @@ pub struct VariableDefinition<'a, T: Text<'a>> {
     pub default_value: Option<Value<'a, T>>,
 }
 
+impl<'a, T> VariableDefinition<'a, T>
+    where T: Text<'a>
+{
+    pub fn new(name: T::Value, var_type: Type<'a, T>) -> Self {
+        Self {
+            position: Pos::default(),
+            name,
+            var_type,
+            default_value: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub enum Selection<'a, T: Text<'a>> {
     Field(Field<'a, T>),
     FragmentSpread(FragmentSpread<'a, T>),
     InlineFragment(InlineFragment<'a, T>),
 }
 
+impl<'a, T: Text<'a>> From<Field<'a, T>> for Selection<'a, T> {
+    fn from(field: Field<'a, T>) -> Self {
+        Selection::Field(field)
+    }
+}
+
+impl<'a, T: Text<'a>> From<FragmentSpread<'a, T>> for Selection<'a, T> {
+    fn from(spread: FragmentSpread<'a, T>) -> Self {
+        Selection::FragmentSpread(spread)
+    }
+}
+
+impl<'a, T: Text<'a>> From<InlineFragment<'a, T>> for Selection<'a, T> {
+    fn from(fragment: InlineFragment<'a, T>) -> Self {
+        Selection::InlineFragment(fragment)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct Field<'a, T: Text<'a>> {
     pub position: Pos,
     pub alias: Option<T::Value>,
@@ -114,22 +439,297 @@ pub struct Field<'a, T: Text<'a>> {
     pub selection_set: SelectionSet<'a, T>,
 }
 
+impl<'a, T> Field<'a, T>
+    where T: Text<'a>
+{
+    pub fn new(name: T::Value) -> Self {
+        Self {
+            position: Pos::default(),
+            alias: None,
+            name,
+            arguments: vec![],
+            directives: vec![],
+            selection_set: SelectionSet::default(),
+        }
+    }
+
+    pub fn alias(mut self, alias: T::Value) -> Self {
+        self.alias = Some(alias);
+        self
+    }
+
+    pub fn argument(mut self, name: T::Value, value: Value<'a, T>) -> Self {
+        self.arguments.push((name, value));
+        self
+    }
+
+    pub fn directive(mut self, directive: Directive<'a, T>) -> Self {
+        self.directives.push(directive);
+        self
+    }
+
+    /// Adds a field or fragment to the selection set
+    pub fn selection<S: Into<Selection<'a, T>>>(mut self, selection: S)
+        -> Self
+    {
+        self.selection_set.items.push(selection.into());
+        self
+    }
+
+    /// Returns `true` if the field has the `@stream` directive
+    ///
+    /// `@stream(if: false)` doesn't stream the field, a variable in the
+    /// `if` argument is assumed to be `true`.
+    pub fn is_streamed(&self) -> bool {
+        self.stream().is_some_and(|dir| dir.get_bool("if") != Some(false))
+    }
+
+    /// Returns the `label` argument of the `@stream` directive
+    pub fn stream_label(&self) -> Option<&str> {
+        match *self.stream()?.get_argument("label")? {
+            Value::String(ref label) | Value::BlockString(ref label) => {
+                Some(label)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the `initialCount` argument of the `@stream` directive,
+    /// which is `0` if omitted
+    pub fn stream_initial_count(&self) -> Option<i64> {
+        match self.stream()?.get_argument("initialCount") {
+            Some(Value::Int(ref count)) => count.as_i64(),
+            Some(_) => None,
+            None => Some(0),
+        }
+    }
+
+    fn stream(&self) -> Option<&Directive<'a, T>> {
+        self.directives.iter().find(|dir| dir.name.as_ref() == "stream")
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct FragmentSpread<'a, T: Text<'a>> {
     pub position: Pos,
     pub fragment_name: T::Value,
+    #[cfg(feature = "fragment-arguments")]
+    pub arguments: Vec<(T::Value, Value<'a, T>)>,
     pub directives: Vec<Directive<'a, T>>,
 }
 
+impl<'a, T> FragmentSpread<'a, T>
+    where T: Text<'a>
+{
+    pub fn new(fragment_name: T::Value) -> Self {
+        Self {
+            position: Pos::default(),
+            fragment_name,
+            #[cfg(feature = "fragment-arguments")]
+            arguments: vec![],
+            directives: vec![],
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub enum TypeCondition<'a, T: Text<'a>> {
     On(T::Value),
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct InlineFragment<'a, T: Text<'a>> {
     pub position: Pos,
     pub type_condition: Option<TypeCondition<'a, T>>,
     pub directives: Vec<Directive<'a, T>>,
     pub selection_set: SelectionSet<'a, T>,
 }
+
+impl<'a, T> InlineFragment<'a, T>
+    where T: Text<'a>
+{
+    pub fn new(type_condition: Option<T::Value>,
+        selection_set: SelectionSet<'a, T>)
+        -> Self
+    {
+        Self {
+            position: Pos::default(),
+            type_condition: type_condition.map(TypeCondition::On),
+            directives: vec![],
+            selection_set,
+        }
+    }
+
+    /// Returns `true` if the fragment has the `@defer` directive
+    ///
+    /// `@defer(if: false)` doesn't defer the fragment, a variable in the
+    /// `if` argument is assumed to be `true`.
+    pub fn is_deferred(&self) -> bool {
+        self.defer().is_some_and(|dir| dir.get_bool("if") != Some(false))
+    }
+
+    /// Returns the `label` argument of the `@defer` directive
+    pub fn defer_label(&self) -> Option<&str> {
+        match *self.defer()?.get_argument("label")? {
+            Value::String(ref label) | Value::BlockString(ref label) => {
+                Some(label)
+            }
+            _ => None,
+        }
+    }
+
+    fn defer(&self) -> Option<&Directive<'a, T>> {
+        self.directives.iter().find(|dir| dir.name.as_ref() == "defer")
+    }
+}
+
+struct AllFields<'b, 'a: 'b, T: Text<'a>> {
+    definitions: std::slice::Iter<'b, Definition<'a, T>>,
+    stack: Vec<std::slice::Iter<'b, Selection<'a, T>>>,
+}
+
+impl<'b, 'a: 'b, T: Text<'a>> Iterator for AllFields<'b, 'a, T> {
+    type Item = &'b Field<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let items = match self.stack.last_mut() {
+                Some(items) => items,
+                None => {
+                    let set = match *self.definitions.next()? {
+                        Definition::Operation(ref op) => match *op {
+                            OperationDefinition::SelectionSet(ref set) => set,
+                            OperationDefinition::Query(ref q) => {
+                                &q.selection_set
+                            }
+                            OperationDefinition::Mutation(ref m) => {
+                                &m.selection_set
+                            }
+                            OperationDefinition::Subscription(ref s) => {
+                                &s.selection_set
+                            }
+                        },
+                        Definition::Fragment(ref frag) => &frag.selection_set,
+                    };
+                    self.stack.push(set.items.iter());
+                    continue;
+                }
+            };
+            match items.next() {
+                Some(Selection::Field(field)) => {
+                    self.stack.push(field.selection_set.items.iter());
+                    return Some(field);
+                }
+                Some(Selection::InlineFragment(frag)) => {
+                    self.stack.push(frag.selection_set.items.iter());
+                }
+                Some(Selection::FragmentSpread(_)) => {}
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::query::parse_query;
+    use super::{Definition, OperationDefinition, OperationType, Selection};
+
+    #[test]
+    fn all_fields() {
+        let doc = parse_query::<&str>("
+            query { user { id friends { name } ... on Admin { level } } }
+            mutation { logout }
+            fragment F on User { email ...G }
+        ").unwrap();
+        let names = doc.all_fields()
+            .map(|field| field.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["user", "id", "friends", "name", "level",
+                           "logout", "email"]);
+    }
+
+    fn operation<'a>(source: &'a str) -> OperationDefinition<'a, &'a str> {
+        match parse_query::<&str>(source).unwrap().definitions.remove(0) {
+            Definition::Operation(op) => op,
+            Definition::Fragment(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn defer() {
+        let op = operation("{
+            ... @defer(label: \"MyLabel\") { name }
+            ... @defer(if: false) { id }
+            ... on User { email }
+        }");
+        let frags = op.selection_set().items.iter()
+            .map(|item| match *item {
+                Selection::InlineFragment(ref frag) => frag,
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert!(frags[0].is_deferred());
+        assert_eq!(frags[0].defer_label(), Some("MyLabel"));
+        assert!(!frags[1].is_deferred());
+        assert_eq!(frags[1].defer_label(), None);
+        assert!(!frags[2].is_deferred());
+    }
+
+    #[test]
+    fn stream() {
+        let op = operation("{
+            pets @stream(initialCount: 2, label: \"pets\") { name }
+            friends @stream { name }
+            relatives @stream(if: false) { name }
+            name
+        }");
+        let fields = op.selection_set().items.iter()
+            .map(|item| match *item {
+                Selection::Field(ref field) => field,
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert!(fields[0].is_streamed());
+        assert_eq!(fields[0].stream_label(), Some("pets"));
+        assert_eq!(fields[0].stream_initial_count(), Some(2));
+        assert!(fields[1].is_streamed());
+        assert_eq!(fields[1].stream_initial_count(), Some(0));
+        assert!(!fields[2].is_streamed());
+        assert!(!fields[3].is_streamed());
+        assert_eq!(fields[3].stream_initial_count(), None);
+    }
+
+    #[test]
+    fn shorthand_operation() {
+        let op = operation("{ a }");
+        assert_eq!(op.operation_type(), OperationType::Query);
+        assert_eq!(op.name(), None);
+        assert_eq!(op.selection_set().items.len(), 1);
+    }
+
+    #[test]
+    fn named_mutation() {
+        let op = operation("mutation Logout { logout reset }");
+        assert_eq!(op.operation_type(), OperationType::Mutation);
+        assert_eq!(op.name(), Some("Logout"));
+        assert_eq!(op.selection_set().items.len(), 2);
+    }
+}
@@ -0,0 +1,487 @@
+//! Fluent builders for constructing query documents in code
+//!
+//! All positions of the built nodes are `Pos::default()` and all the
+//! collections not mentioned are empty:
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::query::Value;
+//! use graphql_parser::query::builder::{field, query};
+//!
+//! let doc = query()
+//!     .field(field("user")
+//!         .argument("id", Value::Int(4.into()))
+//!         .select(|s| s.field("name")))
+//!     .build();
+//! assert_eq!(doc.to_string(), "{\n  user(id: 4) {\n    name\n  }\n}\n");
+//! ```
+//!
+use crate::position::Pos;
+use crate::query::ast::*;
+
+
+/// Builds a `Field`, see `field()`
+#[derive(Debug, Clone)]
+pub struct FieldBuilder<'a> {
+    field: Field<'a, String>,
+}
+
+/// Builds a `SelectionSet`, see `selection_set()`
+#[derive(Debug, Clone, Default)]
+pub struct SelectionSetBuilder<'a> {
+    items: Vec<Selection<'a, String>>,
+}
+
+/// Builds a `Directive`, see `directive()`
+#[derive(Debug, Clone)]
+pub struct DirectiveBuilder<'a> {
+    directive: Directive<'a, String>,
+}
+
+/// Builds a document with a single query, see `query()`
+#[derive(Debug, Clone, Default)]
+pub struct QueryBuilder<'a> {
+    name: Option<String>,
+    variable_definitions: Vec<VariableDefinition<'a, String>>,
+    directives: Vec<Directive<'a, String>>,
+    selection_set: SelectionSetBuilder<'a>,
+}
+
+/// Builds an `OperationDefinition` of any kind, see `query_op()`
+#[derive(Debug, Clone)]
+pub struct OperationBuilder<'a> {
+    operation_type: OperationType,
+    name: Option<String>,
+    variable_definitions: Vec<VariableDefinition<'a, String>>,
+    directives: Vec<Directive<'a, String>>,
+    selection_set: SelectionSetBuilder<'a>,
+}
+
+/// Starts building a field with the given name
+pub fn field<'a>(name: &str) -> FieldBuilder<'a> {
+    FieldBuilder {
+        field: Field::new(name.into()),
+    }
+}
+
+/// Starts building a selection set
+///
+/// Selection sets of fields are usually built with `FieldBuilder::select`,
+/// this is for building one separately, e.g. to reuse it.
+pub fn selection_set<'a>() -> SelectionSetBuilder<'a> {
+    SelectionSetBuilder::default()
+}
+
+/// Starts building a directive with the given name (without the `@`)
+pub fn directive<'a>(name: &str) -> DirectiveBuilder<'a> {
+    DirectiveBuilder {
+        directive: Directive::new(name.into()),
+    }
+}
+
+/// Starts building a query
+pub fn query<'a>() -> QueryBuilder<'a> {
+    QueryBuilder::default()
+}
+
+/// Starts building a `query` operation
+///
+/// Unlike `query()` this builds only the operation, which is always written
+/// in the full `query { ... }` form.
+pub fn query_op<'a>() -> OperationBuilder<'a> {
+    OperationBuilder::new(OperationType::Query)
+}
+
+/// Starts building a `mutation` operation
+pub fn mutation_op<'a>() -> OperationBuilder<'a> {
+    OperationBuilder::new(OperationType::Mutation)
+}
+
+/// Starts building a `subscription` operation
+pub fn subscription_op<'a>() -> OperationBuilder<'a> {
+    OperationBuilder::new(OperationType::Subscription)
+}
+
+impl<'a> FieldBuilder<'a> {
+    pub fn alias(mut self, alias: &str) -> Self {
+        self.field.alias = Some(alias.into());
+        self
+    }
+
+    pub fn argument(mut self, name: &str, value: Value<'a, String>) -> Self {
+        self.field.arguments.push((name.into(), value));
+        self
+    }
+
+    pub fn directive<D>(mut self, directive: D) -> Self
+        where D: Into<Directive<'a, String>>,
+    {
+        self.field.directives.push(directive.into());
+        self
+    }
+
+    /// Adds subfields to the selection set of the field
+    pub fn select<F>(mut self, f: F) -> Self
+        where F: FnOnce(SelectionSetBuilder<'a>) -> SelectionSetBuilder<'a>,
+    {
+        let items = f(SelectionSetBuilder::default()).items;
+        self.field.selection_set.items.extend(items);
+        self
+    }
+
+    /// Replaces the selection set of the field with a built one
+    pub fn selection_set(mut self, selection_set: SelectionSet<'a, String>)
+        -> Self
+    {
+        self.field.selection_set = selection_set;
+        self
+    }
+
+    pub fn build(self) -> Field<'a, String> {
+        self.field
+    }
+}
+
+impl<'a> From<&str> for FieldBuilder<'a> {
+    fn from(name: &str) -> FieldBuilder<'a> {
+        field(name)
+    }
+}
+
+impl<'a> From<Field<'a, String>> for FieldBuilder<'a> {
+    fn from(field: Field<'a, String>) -> FieldBuilder<'a> {
+        FieldBuilder { field }
+    }
+}
+
+impl<'a> From<FieldBuilder<'a>> for Field<'a, String> {
+    fn from(builder: FieldBuilder<'a>) -> Field<'a, String> {
+        builder.build()
+    }
+}
+
+impl<'a> SelectionSetBuilder<'a> {
+    /// Adds a field, either by name or a (built) field
+    pub fn field<F: Into<FieldBuilder<'a>>>(mut self, field: F) -> Self {
+        self.items.push(Selection::Field(field.into().build()));
+        self
+    }
+
+    /// Adds a `...Name` fragment spread
+    pub fn fragment_spread(mut self, name: &str) -> Self {
+        self.items.push(Selection::FragmentSpread(
+            FragmentSpread::new(name.into())));
+        self
+    }
+
+    /// Adds a selection node, e.g. a `FragmentSpread` or an
+    /// `InlineFragment` with directives
+    pub fn selection<S>(mut self, selection: S) -> Self
+        where S: Into<Selection<'a, String>>,
+    {
+        self.items.push(selection.into());
+        self
+    }
+
+    /// Adds a `... on Type { }` inline fragment
+    pub fn inline_fragment<F>(mut self, type_condition: Option<&str>, f: F)
+        -> Self
+        where F: FnOnce(SelectionSetBuilder<'a>) -> SelectionSetBuilder<'a>,
+    {
+        self.items.push(Selection::InlineFragment(InlineFragment::new(
+            type_condition.map(|name| name.into()),
+            f(SelectionSetBuilder::default()).build())));
+        self
+    }
+
+    pub fn build(self) -> SelectionSet<'a, String> {
+        SelectionSet::of(self.items)
+    }
+}
+
+impl<'a> DirectiveBuilder<'a> {
+    pub fn argument(mut self, name: &str, value: Value<'a, String>) -> Self {
+        self.directive.arguments.push((name.into(), value));
+        self
+    }
+
+    pub fn build(self) -> Directive<'a, String> {
+        self.directive
+    }
+}
+
+impl<'a> From<DirectiveBuilder<'a>> for Directive<'a, String> {
+    fn from(builder: DirectiveBuilder<'a>) -> Directive<'a, String> {
+        builder.build()
+    }
+}
+
+impl<'a> QueryBuilder<'a> {
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Adds a `$name: Type` variable definition
+    pub fn variable(mut self, name: &str, var_type: Type<'a, String>) -> Self {
+        self.variable_definitions.push(
+            VariableDefinition::new(name.into(), var_type));
+        self
+    }
+
+    pub fn directive<D>(mut self, directive: D) -> Self
+        where D: Into<Directive<'a, String>>,
+    {
+        self.directives.push(directive.into());
+        self
+    }
+
+    /// Adds a root field, either by name or a (built) field
+    pub fn field<F: Into<FieldBuilder<'a>>>(mut self, field: F) -> Self {
+        self.selection_set = self.selection_set.field(field);
+        self
+    }
+
+    /// Returns a document consisting of the single query
+    ///
+    /// The query is written in the `{ ... }` shorthand form if it has no
+    /// name, variables and directives.
+    pub fn build(self) -> Document<'a, String> {
+        let selection_set = self.selection_set.build();
+        let operation = if self.name.is_none()
+            && self.variable_definitions.is_empty()
+            && self.directives.is_empty()
+        {
+            OperationDefinition::SelectionSet(selection_set)
+        } else {
+            OperationDefinition::Query(Query {
+                position: Pos::default(),
+                name: self.name,
+                variable_definitions: self.variable_definitions,
+                directives: self.directives,
+                selection_set,
+            })
+        };
+        Document {
+            definitions: vec![Definition::Operation(operation)],
+        }
+    }
+}
+
+impl<'a> OperationBuilder<'a> {
+    pub fn new(operation_type: OperationType) -> Self {
+        OperationBuilder {
+            operation_type,
+            name: None,
+            variable_definitions: Vec::new(),
+            directives: Vec::new(),
+            selection_set: SelectionSetBuilder::default(),
+        }
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn operation_type(mut self, operation_type: OperationType) -> Self {
+        self.operation_type = operation_type;
+        self
+    }
+
+    pub fn variable(mut self, variable: VariableDefinition<'a, String>)
+        -> Self
+    {
+        self.variable_definitions.push(variable);
+        self
+    }
+
+    pub fn directive<D>(mut self, directive: D) -> Self
+        where D: Into<Directive<'a, String>>,
+    {
+        self.directives.push(directive.into());
+        self
+    }
+
+    /// Adds a root field, either by name or a (built) field
+    pub fn field<F: Into<FieldBuilder<'a>>>(mut self, field: F) -> Self {
+        self.selection_set = self.selection_set.field(field);
+        self
+    }
+
+    /// Adds a root selection node
+    pub fn selection<S>(mut self, selection: S) -> Self
+        where S: Into<Selection<'a, String>>,
+    {
+        self.selection_set = self.selection_set.selection(selection);
+        self
+    }
+
+    pub fn build(self) -> OperationDefinition<'a, String> {
+        let position = Pos::default();
+        let name = self.name;
+        let variable_definitions = self.variable_definitions;
+        let directives = self.directives;
+        let selection_set = self.selection_set.build();
+        match self.operation_type {
+            OperationType::Query => OperationDefinition::Query(Query {
+                position, name, variable_definitions, directives,
+                selection_set,
+            }),
+            OperationType::Mutation => {
+                OperationDefinition::Mutation(Mutation {
+                    position, name, variable_definitions, directives,
+                    selection_set,
+                })
+            }
+            OperationType::Subscription => {
+                OperationDefinition::Subscription(Subscription {
+                    position, name, variable_definitions, directives,
+                    selection_set,
+                })
+            }
+        }
+    }
+}
+
+impl<'a> From<OperationBuilder<'a>> for Definition<'a, String> {
+    fn from(builder: OperationBuilder<'a>) -> Definition<'a, String> {
+        Definition::Operation(builder.build())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{directive, field, query, selection_set};
+    use super::{mutation_op, query_op, subscription_op};
+    use crate::query::{FragmentSpread, InlineFragment, Directive};
+    use crate::query::{Document, OperationType, VariableDefinition};
+    use crate::query::{parse_query, Type, Value};
+
+    #[test]
+    fn shorthand_query() {
+        let doc = query()
+            .field(field("user")
+                .argument("id", Value::Int(4.into()))
+                .select(|s| s.field("name")))
+            .build();
+        assert_eq!(doc.to_string(), "{\n  user(id: 4) {\n    name\n  }\n}\n");
+    }
+
+    #[test]
+    fn full_query() {
+        let doc = query()
+            .name("Hero")
+            .variable("withFriends", Type::NamedType("Boolean".into()))
+            .field(field("hero")
+                .alias("h")
+                .select(|s| s
+                    .field("name")
+                    .field(field("friends")
+                        .directive(directive("include")
+                            .argument("if", Value::Variable(
+                                "withFriends".into())))
+                        .select(|s| s.field("name")))
+                    .fragment_spread("Details")
+                    .inline_fragment(Some("Droid"), |s| {
+                        s.field("primaryFunction")
+                    })))
+            .build();
+        let expected = parse_query::<String>("
+            query Hero($withFriends: Boolean) {
+                h: hero {
+                    name
+                    friends @include(if: $withFriends) { name }
+                    ...Details
+                    ... on Droid { primaryFunction }
+                }
+            }
+        ").unwrap();
+        assert_eq!(doc.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn prebuilt_selection_set() {
+        let fields = selection_set()
+            .field("id")
+            .field(field("name").alias("n"))
+            .build();
+        let user = field("user").selection_set(fields.clone()).build();
+        assert_eq!(user.to_string(), "user {\n  id\n  n: name\n}\n");
+        let doc = query().field(field("me").selection_set(fields)).build();
+        assert_eq!(doc.to_string(),
+                   "{\n  me {\n    id\n    n: name\n  }\n}\n");
+    }
+
+    #[test]
+    fn nested_selection_sets() {
+        let mut spread = FragmentSpread::new("Details".to_string());
+        spread.directives.push(Directive::new("defer".into()));
+        let mut droid = InlineFragment::new(Some("Droid".into()),
+            selection_set().field("primaryFunction").build());
+        droid.directives.push(Directive::new("live".into()));
+        let set = selection_set()
+            .field(field("hero").select(|s| s
+                .field(field("friends").select(|s| s.field("name")))
+                .selection(spread)
+                .selection(droid)))
+            .build();
+        assert_eq!(set.to_string(), "\
+{
+  hero {
+    friends {
+      name
+    }
+    ...Details @defer
+    ... on Droid @live {
+      primaryFunction
+    }
+  }
+}
+");
+    }
+
+    #[test]
+    fn operations() {
+        let mut id = VariableDefinition::new("id".into(),
+            Type::non_null(Type::named("ID")));
+        id.default_value = Some(Value::String("me".into()));
+        let doc = Document {
+            definitions: vec![
+                query_op().field("viewer").into(),
+                mutation_op()
+                    .name("Rename")
+                    .variable(id)
+                    .directive(directive("audit"))
+                    .field(field("rename")
+                        .argument("id", Value::Variable("id".into())))
+                    .into(),
+                subscription_op()
+                    .name("Events")
+                    .selection(FragmentSpread::new("EventFields".into()))
+                    .into(),
+                subscription_op()
+                    .operation_type(OperationType::Query)
+                    .field("ping")
+                    .into(),
+            ],
+        };
+        assert_eq!(doc.to_string(), "\
+query {
+  viewer
+}
+
+mutation Rename($id: ID! = \"me\") @audit {
+  rename(id: $id)
+}
+
+subscription Events {
+  ...EventFields
+}
+
+query {
+  ping
+}
+");
+    }
+}
@@ -0,0 +1,236 @@
+//! Evaluation of the built-in `@skip`, `@include` and `@stream` directives
+//!
+use std::collections::HashMap;
+
+use crate::query::ast::*;
+use crate::query::variables::variable_definitions;
+
+
+/// Returns the document without the selections excluded by `@skip` and
+/// `@include`
+///
+/// The `if` argument is either a boolean literal or a variable, which is
+/// looked up in `vars` and then in the defaults of variable definitions.
+/// Directives whose `if` doesn't resolve to a boolean don't exclude
+/// anything. The `@skip` and `@include` directives are removed from the
+/// selections which are kept.
+pub fn apply_skip_include<'a, T>(doc: &Document<'a, T>,
+    vars: &HashMap<String, Value<'a, T>>)
+    -> Document<'a, T>
+    where T: Text<'a> + Clone,
+{
+    let mut defaults = HashMap::new();
+    for def in &doc.definitions {
+        if let Definition::Operation(ref op) = *def {
+            for var in variable_definitions(op) {
+                if let Some(ref value) = var.default_value {
+                    defaults.entry(var.name.as_ref()).or_insert(value);
+                }
+            }
+        }
+    }
+    let conditions = Conditions { vars, defaults };
+    let mut result = doc.clone();
+    for def in &mut result.definitions {
+        let selection_set = match *def {
+            Definition::Operation(OperationDefinition::SelectionSet(
+                ref mut set)) => set,
+            Definition::Operation(OperationDefinition::Query(ref mut q)) => {
+                &mut q.selection_set
+            }
+            Definition::Operation(OperationDefinition::Mutation(
+                ref mut m)) => &mut m.selection_set,
+            Definition::Operation(OperationDefinition::Subscription(
+                ref mut s)) => &mut s.selection_set,
+            Definition::Fragment(ref mut frag) => &mut frag.selection_set,
+        };
+        conditions.apply_selection_set(selection_set);
+    }
+    result
+}
+
+/// Evaluates `@skip` and `@include` with literal `if` arguments
+///
+/// Returns `Some(false)` if the directives always exclude the selection,
+/// `Some(true)` if they always keep it and `None` if that depends on the
+/// variables.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::query::{evaluate_directives, parse_query};
+///
+/// let doc = parse_query::<&str>("{ a @skip(if: true) }").unwrap();
+/// let field = doc.all_fields().next().unwrap();
+/// assert_eq!(evaluate_directives(&field.directives), Some(false));
+/// ```
+pub fn evaluate_directives<'a, T>(directives: &[Directive<'a, T>])
+    -> Option<bool>
+    where T: Text<'a>,
+{
+    let mut result = Some(true);
+    for dir in directives {
+        let exclude_if = match dir.name.as_ref() {
+            "skip" => true,
+            "include" => false,
+            _ => continue,
+        };
+        match dir.get_argument("if") {
+            Some(&Value::Boolean(value)) if value == exclude_if => {
+                return Some(false);
+            }
+            Some(&Value::Boolean(_)) => {}
+            _ => result = None,
+        }
+    }
+    result
+}
+
+/// Returns `true` if the field is streamed with `@stream`
+///
+/// This is the same as `Field::is_streamed`, for use where a function is
+/// more convenient, e.g. in `filter`.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::query::{is_streamed_field, parse_query};
+///
+/// let doc = parse_query::<&str>("{
+///     pets @stream(initialCount: 0) { name }
+///     name
+/// }").unwrap();
+/// let streamed = doc.all_fields()
+///     .filter(|field| is_streamed_field(field))
+///     .map(|field| field.name)
+///     .collect::<Vec<_>>();
+/// assert_eq!(streamed, ["pets"]);
+/// ```
+pub fn is_streamed_field<'a, T: Text<'a>>(field: &Field<'a, T>) -> bool {
+    field.is_streamed()
+}
+
+struct Conditions<'v, 'a, T: Text<'a>> {
+    vars: &'v HashMap<String, Value<'a, T>>,
+    defaults: HashMap<&'v str, &'v Value<'a, T>>,
+}
+
+impl<'v, 'a, T: Text<'a>> Conditions<'v, 'a, T> {
+    fn apply_selection_set(&self, selection_set: &mut SelectionSet<'a, T>) {
+        selection_set.items.retain_mut(|item| {
+            let (directives, children) = match *item {
+                Selection::Field(ref mut field) => {
+                    (&mut field.directives, Some(&mut field.selection_set))
+                }
+                Selection::FragmentSpread(ref mut spread) => {
+                    (&mut spread.directives, None)
+                }
+                Selection::InlineFragment(ref mut frag) => {
+                    (&mut frag.directives, Some(&mut frag.selection_set))
+                }
+            };
+            if !self.is_included(directives) {
+                return false;
+            }
+            directives.retain(|dir| {
+                dir.name.as_ref() != "skip" && dir.name.as_ref() != "include"
+            });
+            if let Some(children) = children {
+                self.apply_selection_set(children);
+            }
+            true
+        });
+    }
+
+    fn is_included(&self, directives: &[Directive<'a, T>]) -> bool {
+        !directives.iter().any(|dir| {
+            matches!((dir.name.as_ref(), self.condition(dir)),
+                     ("skip", Some(true)) | ("include", Some(false)))
+        })
+    }
+
+    fn condition(&self, directive: &Directive<'a, T>) -> Option<bool> {
+        let value = match directive.get_argument("if")? {
+            Value::Variable(ref name) => {
+                let name = name.as_ref();
+                self.vars.get(name)
+                    .or_else(|| self.defaults.get(name).copied())?
+            }
+            value => value,
+        };
+        match *value {
+            Value::Boolean(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::{apply_skip_include, evaluate_directives};
+    use crate::query::{parse_query, Value};
+
+    fn apply(query: &str, vars: &[(&str, bool)]) -> String {
+        let doc = parse_query::<String>(query).unwrap();
+        let vars = vars.iter()
+            .map(|&(name, value)| (name.to_string(), Value::Boolean(value)))
+            .collect::<HashMap<_, _>>();
+        apply_skip_include(&doc, &vars).to_string()
+    }
+
+    fn reprint(query: &str) -> String {
+        parse_query::<String>(query).unwrap().to_string()
+    }
+
+    #[test]
+    fn literal_conditions() {
+        assert_eq!(apply("{
+            a @skip(if: true)
+            b @skip(if: false)
+            c @include(if: true) @live
+            d @include(if: false)
+            e { f @skip(if: true) g }
+            ...F @include(if: false)
+            ... on T @skip(if: false) { h }
+        }", &[]), reprint("{ b c @live e { g } ... on T { h } }"));
+    }
+
+    #[test]
+    fn variable_conditions() {
+        let query = "query ($flag: Boolean!, $other: Boolean = true) {
+            a @include(if: $flag)
+            b @skip(if: $other)
+            c @include(if: $unknown)
+            ...F @skip(if: $flag)
+        }
+        fragment F on T { d @include(if: $flag) e }";
+        assert_eq!(apply(query, &[("flag", false)]),
+            reprint("query ($flag: Boolean!, $other: Boolean = true) {
+                c
+                ...F
+            }
+            fragment F on T { e }"));
+        assert_eq!(apply(query, &[("flag", true), ("other", false)]),
+            reprint("query ($flag: Boolean!, $other: Boolean = true) {
+                a b c
+            }
+            fragment F on T { d e }"));
+    }
+
+    #[test]
+    fn static_evaluation() {
+        let doc = parse_query::<&str>("query ($v: Boolean!) {
+            a b @skip(if: false) @live c @include(if: true)
+            d @skip(if: true) e @include(if: false) f @skip(if: $v)
+            g @skip(if: $v) @include(if: false)
+        }").unwrap();
+        let results = doc.all_fields()
+            .map(|f| evaluate_directives(&f.directives))
+            .collect::<Vec<_>>();
+        assert_eq!(results, [
+            Some(true), Some(true), Some(true),
+            Some(false), Some(false), None,
+            Some(false),
+        ]);
+    }
+}
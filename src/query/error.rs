@@ -1,7 +1,7 @@
 use combine::easy::Errors;
 use thiserror::Error;
 
-use crate::tokenizer::Token;
+use crate::tokenizer::{is_depth_limit_exceeded, int_out_of_range, Token};
 use crate::position::Pos;
 
 pub type InternalError<'a> = Errors<Token<'a>, Token<'a>, Pos>;
@@ -12,11 +12,45 @@ pub type InternalError<'a> = Errors<Token<'a>, Token<'a>, Pos>;
 /// This structure is opaque for forward compatibility. We are exploring a
 /// way to improve both error message and API.
 #[derive(Error, Debug)]
-#[error("query parse error: {}", _0)]
-pub struct ParseError(String);
+#[error("query parse error: {}", message)]
+pub struct ParseError {
+    message: String,
+    depth_limit_exceeded: bool,
+    int_out_of_range: Option<(String, Pos)>,
+}
+
+impl ParseError {
+    /// Returns `true` if the document is nested deeper than
+    /// `ParseOptions::max_depth`
+    pub fn is_depth_limit_exceeded(&self) -> bool {
+        self.depth_limit_exceeded
+    }
+
+    /// Returns the literal and the position of the integer which doesn't
+    /// fit `i64`, if that's what failed the parsing
+    pub fn int_out_of_range(&self) -> Option<(&str, Pos)> {
+        self.int_out_of_range.as_ref()
+            .map(|(literal, pos)| (&literal[..], *pos))
+    }
+}
 
 impl<'a> From<InternalError<'a>> for ParseError {
     fn from(e: InternalError<'a>) -> ParseError {
-        ParseError(format!("{}", e))
+        ParseError {
+            message: format!("{}", e),
+            depth_limit_exceeded: is_depth_limit_exceeded(&e),
+            int_out_of_range: int_out_of_range(&e),
+        }
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl From<std::str::Utf8Error> for ParseError {
+    fn from(e: std::str::Utf8Error) -> ParseError {
+        ParseError {
+            message: format!("source is not valid UTF-8: {}", e),
+            depth_limit_exceeded: false,
+            int_out_of_range: None,
+        }
     }
 }
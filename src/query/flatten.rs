@@ -0,0 +1,114 @@
+//! Removal of redundant inline fragments
+//!
+use crate::query::ast::*;
+
+
+/// Replaces inline fragments which don't narrow the type with their
+/// selections
+///
+/// An inline fragment is flattened into the parent selection set if it
+/// has no directives and either has no type condition or its condition is
+/// the type of an enclosing inline fragment. The schema isn't known here,
+/// so fragments with a condition on the type of an enclosing field are
+/// kept. Nested fragments are flattened recursively.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::query::{parse_query, flatten_inline_fragments};
+/// use graphql_parser::query::{Definition, OperationDefinition};
+///
+/// let mut doc = parse_query::<&str>("{ ... { a ... { b } } }").unwrap();
+/// if let Definition::Operation(OperationDefinition::SelectionSet(ref mut set))
+///     = doc.definitions[0]
+/// {
+///     flatten_inline_fragments(set);
+/// }
+/// assert_eq!(doc.to_string(), "{\n  a\n  b\n}\n");
+/// ```
+pub fn flatten_inline_fragments<'a, T>(selection_set: &mut SelectionSet<'a, T>)
+    where T: Text<'a>,
+{
+    flatten(selection_set, None);
+}
+
+fn flatten<'a, T>(selection_set: &mut SelectionSet<'a, T>,
+    parent_type: Option<&str>)
+    where T: Text<'a>,
+{
+    let mut items = Vec::with_capacity(selection_set.items.len());
+    for item in selection_set.items.drain(..) {
+        match item {
+            Selection::Field(mut field) => {
+                flatten(&mut field.selection_set, None);
+                items.push(Selection::Field(field));
+            }
+            Selection::FragmentSpread(spread) => {
+                items.push(Selection::FragmentSpread(spread));
+            }
+            Selection::InlineFragment(mut frag) => {
+                let condition = match frag.type_condition {
+                    Some(TypeCondition::On(ref name)) => Some(name.as_ref()),
+                    None => None,
+                };
+                let redundant = frag.directives.is_empty()
+                    && (condition.is_none() || condition == parent_type);
+                flatten(&mut frag.selection_set, condition.or(parent_type));
+                if redundant {
+                    items.append(&mut frag.selection_set.items);
+                } else {
+                    items.push(Selection::InlineFragment(frag));
+                }
+            }
+        }
+    }
+    selection_set.items = items;
+}
+
+#[cfg(test)]
+mod test {
+    use super::flatten_inline_fragments;
+    use crate::query::{parse_query, Definition, OperationDefinition};
+
+    fn flatten(query: &str) -> String {
+        let mut doc = parse_query::<&str>(query).unwrap();
+        for def in &mut doc.definitions {
+            let selection_set = match *def {
+                Definition::Operation(OperationDefinition::SelectionSet(
+                    ref mut set)) => set,
+                Definition::Operation(OperationDefinition::Query(
+                    ref mut q)) => &mut q.selection_set,
+                Definition::Operation(_) => unreachable!(),
+                Definition::Fragment(ref mut frag) => &mut frag.selection_set,
+            };
+            flatten_inline_fragments(selection_set);
+        }
+        doc.to_minified_string()
+    }
+
+    #[test]
+    fn untyped() {
+        assert_eq!(flatten("{ ... { a } }"), "{a}");
+        assert_eq!(flatten("fragment F on Dog { ... { a } }"),
+                   "fragment F on Dog{a}");
+        assert_eq!(flatten("{ x { ... { ... { a } b } } }"), "{x{a b}}");
+    }
+
+    #[test]
+    fn typed() {
+        assert_eq!(flatten("{ ... on Dog { a } }"), "{...on Dog{a}}");
+        assert_eq!(flatten("{ ... on Dog { ... on Dog { a } ... { b } } }"),
+                   "{...on Dog{a b}}");
+        assert_eq!(flatten("{ ... on Pet { ... on Dog { a } } }"),
+                   "{...on Pet{...on Dog{a}}}");
+        assert_eq!(flatten("{ ... on Dog { x { ... on Dog { a } } } }"),
+                   "{...on Dog{x{...on Dog{a}}}}");
+    }
+
+    #[test]
+    fn directives() {
+        assert_eq!(flatten("{ ... @include(if: $x) { ... { a } } }"),
+                   "{...@include(if:$x){a}}");
+        assert_eq!(flatten("{ ... on Dog { ... on Dog @skip(if: x) { a } } }"),
+                   "{...on Dog{...on Dog@skip(if:x){a}}}");
+    }
+}
@@ -1,6 +1,7 @@
 use std::fmt;
 
 use crate::format::{Displayable, Formatter, Style, format_directives};
+use crate::format::{FormatOptions, sorted, to_string};
 
 use crate::query::ast::*;
 
@@ -10,24 +11,49 @@ impl<'a, T: Text<'a>> Document<'a, T>
 {
     /// Format a document according to style
     pub fn format(&self, style: &Style) -> String {
-        let mut formatter = Formatter::new(style);
-        self.display(&mut formatter);
-        formatter.into_string()
+        to_string(self, style)
+    }
+
+    /// Format a document on a single line with as little whitespace as
+    /// possible
+    pub fn to_minified_string(&self) -> String {
+        let mut style = Style::default();
+        style.minify(true);
+        to_string(self, &style)
+    }
+
+    /// Format a document according to formatting options
+    pub fn format_with(&self, options: &FormatOptions) -> String {
+        to_string(self, &Style::from(options))
     }
-}
 
-fn to_string<T: Displayable>(v: &T) -> String {
-    let style = Style::default();
-    let mut formatter = Formatter::new(&style);
-    v.display(&mut formatter);
-    formatter.into_string()
+    /// Format a document with arguments written one per line when a field
+    /// or directive has more than three of them
+    pub fn to_string_pretty(&self) -> String {
+        self.format_with(&FormatOptions {
+            args_per_line: Some(3),
+            trailing_commas: true,
+            ..FormatOptions::default()
+        })
+    }
 }
 
 impl<'a, T: Text<'a>> Displayable for Document<'a, T> 
     where T: Text<'a>,
 {
     fn display(&self, f: &mut Formatter) {
-        for item in &self.definitions {
+        let definitions = sorted(&self.definitions, f.sort_definitions(),
+            |def| match *def {
+                Definition::Operation(ref op) => match *op {
+                    OperationDefinition::SelectionSet(_) => None,
+                    OperationDefinition::Query(ref q) => q.name.as_ref(),
+                    OperationDefinition::Mutation(ref m) => m.name.as_ref(),
+                    OperationDefinition::Subscription(ref s)
+                        => s.name.as_ref(),
+                }.map(|name| name.as_ref()),
+                Definition::Fragment(ref frag) => Some(frag.name.as_ref()),
+            });
+        for item in definitions {
             item.display(f);
         }
     }
@@ -65,14 +91,14 @@ impl<'a, T: Text<'a>> Displayable for FragmentDefinition<'a, T>
         f.indent();
         f.write("fragment ");
         f.write(self.name.as_ref());
+        #[cfg(feature = "fragment-arguments")]
+        format_variable_definitions(&self.variable_definitions, f);
         f.write(" ");
         self.type_condition.display(f);
         format_directives(&self.directives, f);
         f.write(" ");
         f.start_block();
-        for item in &self.selection_set.items {
-            item.display(f);
-        }
+        format_selections(&self.selection_set.items, f);
         f.end_block();
     }
 }
@@ -84,9 +110,7 @@ impl<'a, T: Text<'a>> Displayable for SelectionSet<'a, T>
         f.margin();
         f.indent();
         f.start_block();
-        for item in &self.items {
-            item.display(f);
-        }
+        format_selections(&self.items, f);
         f.end_block();
     }
 }
@@ -103,10 +127,49 @@ impl<'a, T: Text<'a>> Displayable for Selection<'a, T>
     }
 }
 
-fn format_arguments<'a, T: Text<'a>>(arguments: &[(T::Value, Value<'a, T>)], f: &mut Formatter) 
+fn format_selections<'a, T>(items: &[Selection<'a, T>], f: &mut Formatter)
+    where T: Text<'a>,
+{
+    // fields sorted by response key go first, fragments keep their order
+    let items = sorted(items, f.sort_fields(), |item| match *item {
+        Selection::Field(ref fld) => {
+            (false, fld.alias.as_ref().unwrap_or(&fld.name).as_ref())
+        }
+        Selection::FragmentSpread(_) | Selection::InlineFragment(_) => {
+            (true, "")
+        }
+    });
+    for item in items {
+        item.display(f);
+    }
+}
+
+fn format_arguments<'a, T>(arguments: &[(T::Value, Value<'a, T>)], f: &mut Formatter)
     where T: Text<'a>,
 {
-    if !arguments.is_empty() {
+    if arguments.is_empty() {
+        return;
+    }
+    let arguments = sorted(arguments, f.sort_arguments(),
+                           |arg| arg.0.as_ref());
+    if f.args_per_line().is_some_and(|max| arguments.len() > max) {
+        f.write("(");
+        f.endline();
+        f.push_indent();
+        for (i, arg) in arguments.iter().enumerate() {
+            f.indent();
+            f.write(arg.0.as_ref());
+            f.write(": ");
+            arg.1.display(f);
+            if i + 1 < arguments.len() || f.trailing_commas() {
+                f.write(",");
+            }
+            f.endline();
+        }
+        f.pop_indent();
+        f.indent();
+        f.write(")");
+    } else {
         f.write("(");
         f.write(arguments[0].0.as_ref());
         f.write(": ");
@@ -136,9 +199,7 @@ impl<'a, T: Text<'a>> Displayable for Field<'a, T>
         if !self.selection_set.items.is_empty() {
             f.write(" ");
             f.start_block();
-            for item in &self.selection_set.items {
-                item.display(f);
-            }
+            format_selections(&self.selection_set.items, f);
             f.end_block();
         } else {
             f.endline();
@@ -146,6 +207,21 @@ impl<'a, T: Text<'a>> Displayable for Field<'a, T>
     }
 }
 
+fn format_variable_definitions<'a, T>(vars: &[VariableDefinition<'a, T>],
+    f: &mut Formatter)
+    where T: Text<'a>,
+{
+    if !vars.is_empty() {
+        f.write("(");
+        vars[0].display(f);
+        for var in &vars[1..] {
+            f.write(", ");
+            var.display(f);
+        }
+        f.write(")");
+    }
+}
+
 impl<'a, T: Text<'a>> Displayable for Query<'a, T> 
     where T: Text<'a>,
 {
@@ -157,21 +233,11 @@ impl<'a, T: Text<'a>> Displayable for Query<'a, T>
             f.write(" ");
             f.write(name.as_ref());
         }
-        if !self.variable_definitions.is_empty() {
-            f.write("(");
-            self.variable_definitions[0].display(f);
-            for var in &self.variable_definitions[1..] {
-                f.write(", ");
-                var.display(f);
-            }
-            f.write(")");
-        }
+        format_variable_definitions(&self.variable_definitions, f);
         format_directives(&self.directives, f);
         f.write(" ");
         f.start_block();
-        for item in &self.selection_set.items {
-            item.display(f);
-        }
+        format_selections(&self.selection_set.items, f);
         f.end_block();
     }
 }
@@ -186,20 +252,12 @@ impl<'a, T: Text<'a>> Displayable for Mutation<'a, T>
         if let Some(ref name) = self.name {
             f.write(" ");
             f.write(name.as_ref());
-            if !self.variable_definitions.is_empty() {
-                f.write("(");
-                for var in &self.variable_definitions {
-                    var.display(f);
-                }
-                f.write(")");
-            }
         }
+        format_variable_definitions(&self.variable_definitions, f);
         format_directives(&self.directives, f);
         f.write(" ");
         f.start_block();
-        for item in &self.selection_set.items {
-            item.display(f);
-        }
+        format_selections(&self.selection_set.items, f);
         f.end_block();
     }
 }
@@ -214,20 +272,12 @@ impl<'a, T: Text<'a>> Displayable for Subscription<'a, T>
         if let Some(ref name) = self.name {
             f.write(" ");
             f.write(name.as_ref());
-            if !self.variable_definitions.is_empty() {
-                f.write("(");
-                for var in &self.variable_definitions {
-                    var.display(f);
-                }
-                f.write(")");
-            }
         }
+        format_variable_definitions(&self.variable_definitions, f);
         format_directives(&self.directives, f);
         f.write(" ");
         f.start_block();
-        for item in &self.selection_set.items {
-            item.display(f);
-        }
+        format_selections(&self.selection_set.items, f);
         f.end_block();
     }
 }
@@ -266,15 +316,42 @@ impl<'a, T: Text<'a>> Displayable for Type<'a, T>
     }
 }
 
+/// Formats a float so that it's read back as the same float
+///
+/// The exponent form is used when it saves at least five characters, so
+/// `1e10` isn't expanded to a long run of zeros. Infinities are written
+/// as literals which overflow back to them, and NaN, which has no literal,
+/// as `null`.
+fn float_literal(val: f64) -> String {
+    if val.is_nan() {
+        return "null".into();
+    }
+    if val.is_infinite() {
+        return if val > 0.0 { "1e999" } else { "-1e999" }.into();
+    }
+    let mut decimal = format!("{}", val);
+    // keep the number a float when it's read back
+    if !decimal.contains('.') {
+        decimal.push_str(".0");
+    }
+    let exponent = format!("{:e}", val);
+    if exponent.len() + 5 <= decimal.len() {
+        exponent
+    } else {
+        decimal
+    }
+}
+
 impl<'a, T: Text<'a>> Displayable for Value<'a, T> 
     where T: Text<'a>,
 {
     fn display(&self, f: &mut Formatter) {
         match *self {
             Value::Variable(ref name) => { f.write("$"); f.write(name.as_ref()); },
-            Value::Int(ref num) => f.write(&format!("{}", num.0)),
-            Value::Float(val) => f.write(&format!("{}", val)),
+            Value::Int(ref num) => f.write(&format!("{}", num.value)),
+            Value::Float(ref val) => f.write(&float_literal(val.as_f64())),
             Value::String(ref val) => f.write_quoted(val),
+            Value::BlockString(ref val) => f.write_block_quoted(val),
             Value::Boolean(true) => f.write("true"),
             Value::Boolean(false) => f.write("false"),
             Value::Null => f.write("null"),
@@ -293,7 +370,7 @@ impl<'a, T: Text<'a>> Displayable for Value<'a, T>
             Value::Object(ref items) => {
                 f.write("{");
                 let mut first = true;
-                for (name, value) in items.iter() {
+                for (name, value) in items.sorted() {
                     if first {
                         first = false;
                     } else {
@@ -322,9 +399,7 @@ impl<'a, T: Text<'a>> Displayable for InlineFragment<'a, T>
         format_directives(&self.directives, f);
         f.write(" ");
         f.start_block();
-        for item in &self.selection_set.items {
-            item.display(f);
-        }
+        format_selections(&self.selection_set.items, f);
         f.end_block();
     }
 }
@@ -349,6 +424,8 @@ impl<'a, T: Text<'a>> Displayable for FragmentSpread<'a, T>
         f.indent();
         f.write("...");
         f.write(self.fragment_name.as_ref());
+        #[cfg(feature = "fragment-arguments")]
+        format_arguments(&self.arguments, f);
         format_directives(&self.directives, f);
         f.endline();
     }
@@ -0,0 +1,151 @@
+//! Utilities for working with fragment definitions
+//!
+use std::collections::{HashMap, HashSet};
+
+use crate::query::ast::*;
+
+
+/// Returns fragment cycles of the document
+///
+/// Each cycle is a list of fragment names in the order they spread each
+/// other, the last one spreading the first one. A fragment spreading
+/// itself is a cycle of one name. Spreads of unknown fragments are ignored.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::query::{parse_query, detect_fragment_cycles};
+///
+/// let doc = parse_query::<&str>("
+///     fragment A on T { ...B }
+///     fragment B on T { x ...A }
+/// ").unwrap();
+/// assert_eq!(detect_fragment_cycles(&doc), vec![vec![&"A", &"B"]]);
+/// ```
+pub fn detect_fragment_cycles<'d, 'a, T>(doc: &'d Document<'a, T>)
+    -> Vec<Vec<&'d T::Value>>
+    where T: Text<'a>,
+{
+    let mut spreads = HashMap::new();
+    let mut order = Vec::new();
+    for def in &doc.definitions {
+        if let Definition::Fragment(ref frag) = *def {
+            let mut names = Vec::new();
+            collect_spreads(&frag.selection_set, &mut names);
+            if spreads.insert(frag.name.as_ref(), names).is_none() {
+                order.push(&frag.name);
+            }
+        }
+    }
+    let mut search = CycleSearch::<T> {
+        spreads,
+        visited: HashSet::new(),
+        path: Vec::new(),
+        cycles: Vec::new(),
+    };
+    for name in order {
+        search.visit(name);
+    }
+    search.cycles
+}
+
+pub(crate) fn collect_spreads<'d, 'a, T>(selection_set: &'d SelectionSet<'a, T>,
+    names: &mut Vec<&'d T::Value>)
+    where T: Text<'a>,
+{
+    for item in &selection_set.items {
+        match *item {
+            Selection::Field(ref field) => {
+                collect_spreads(&field.selection_set, names);
+            }
+            Selection::FragmentSpread(ref spread) => {
+                if !names.contains(&&spread.fragment_name) {
+                    names.push(&spread.fragment_name);
+                }
+            }
+            Selection::InlineFragment(ref frag) => {
+                collect_spreads(&frag.selection_set, names);
+            }
+        }
+    }
+}
+
+struct CycleSearch<'d, 'a, T: Text<'a>> {
+    spreads: HashMap<&'d str, Vec<&'d T::Value>>,
+    visited: HashSet<&'d str>,
+    /// Fragments being visited, each one spreads the next one
+    path: Vec<&'d T::Value>,
+    cycles: Vec<Vec<&'d T::Value>>,
+}
+
+impl<'d, 'a, T: Text<'a>> CycleSearch<'d, 'a, T> {
+    fn visit(&mut self, name: &'d T::Value) {
+        if let Some(pos) = self.path.iter()
+            .position(|item| item.as_ref() == name.as_ref())
+        {
+            self.cycles.push(self.path[pos..].to_vec());
+            return;
+        }
+        if !self.visited.insert(name.as_ref()) {
+            return;
+        }
+        let spreads = match self.spreads.get(name.as_ref()) {
+            Some(spreads) => spreads.clone(),
+            None => return,
+        };
+        self.path.push(name);
+        for spread in spreads {
+            self.visit(spread);
+        }
+        self.path.pop();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::detect_fragment_cycles;
+    use crate::query::parse_query;
+
+    fn cycles(query: &str) -> Vec<Vec<&str>> {
+        let doc = parse_query::<&str>(query).unwrap();
+        detect_fragment_cycles(&doc).into_iter()
+            .map(|cycle| cycle.into_iter().copied().collect())
+            .collect()
+    }
+
+    #[test]
+    fn two_fragments() {
+        assert_eq!(cycles("
+            fragment A on T { ...B }
+            fragment B on T { ...A }
+        "), vec![vec!["A", "B"]]);
+    }
+
+    #[test]
+    fn three_fragments() {
+        assert_eq!(cycles("
+            query { ...A }
+            fragment A on T { x { ...B } }
+            fragment B on T { ... on U { ...C } }
+            fragment C on T { y ...A ...D }
+            fragment D on T { z }
+        "), vec![vec!["A", "B", "C"]]);
+    }
+
+    #[test]
+    fn self_spread() {
+        assert_eq!(cycles("
+            fragment A on T { x ...A ...A }
+            fragment B on T { ...A }
+        "), vec![vec!["A"]]);
+    }
+
+    #[test]
+    fn acyclic() {
+        assert_eq!(cycles("
+            query { ...A ...B }
+            fragment A on T { ...B ...C ...Unknown }
+            fragment B on T { ...C }
+            fragment C on T { x }
+        "), Vec::<Vec<&str>>::new());
+    }
+}
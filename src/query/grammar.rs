@@ -3,6 +3,7 @@ use combine::combinator::{many1, eof, optional, position};
 
 use crate::common::{Directive};
 use crate::common::{directives, arguments, default_value, parse_type};
+use crate::parse_options::ParseOptions;
 use crate::tokenizer::{TokenStream};
 use crate::helpers::{punct, ident, name};
 use crate::query::error::{ParseError};
@@ -52,17 +53,41 @@ pub fn selection<'a, S>(input: &mut TokenStream<'a>)
                                  selection_set, directives }
             })
             .map(Selection::InlineFragment)
-        .or((position(),
-             name::<'a, S>(),
-             parser(directives),
-            ).map(|(position, fragment_name, directives)| {
-                FragmentSpread { position, fragment_name, directives }
-            })
-            .map(Selection::FragmentSpread))
+        .or(parser(fragment_spread).map(Selection::FragmentSpread))
     ))
     .parse_stream(input)
 }
 
+/// Parses a fragment spread after the `...`
+fn fragment_spread<'a, S>(input: &mut TokenStream<'a>)
+    -> ParseResult<FragmentSpread<'a, S>, TokenStream<'a>>
+    where S: Text<'a>
+{
+    #[cfg(feature = "fragment-arguments")]
+    {
+        (
+            position(),
+            name::<'a, S>(),
+            parser(arguments),
+            parser(directives),
+        ).map(|(position, fragment_name, arguments, directives)| {
+            FragmentSpread { position, fragment_name, arguments, directives }
+        })
+        .parse_stream(input)
+    }
+    #[cfg(not(feature = "fragment-arguments"))]
+    {
+        (
+            position(),
+            name::<'a, S>(),
+            parser(directives),
+        ).map(|(position, fragment_name, directives)| {
+            FragmentSpread { position, fragment_name, directives }
+        })
+        .parse_stream(input)
+    }
+}
+
 pub fn selection_set<'a, S>(input: &mut TokenStream<'a>)
     -> ParseResult<SelectionSet<'a, S>, TokenStream<'a>>
     where S: Text<'a>,
@@ -75,7 +100,7 @@ pub fn selection_set<'a, S>(input: &mut TokenStream<'a>)
     .parse_stream(input)
 }
 
-pub fn query<'a, T: Text<'a>>(input: &mut TokenStream<'a>)
+pub fn query<'a, T>(input: &mut TokenStream<'a>)
     -> ParseResult<Query<'a, T>, TokenStream<'a>>
     where T: Text<'a>,
 {
@@ -98,12 +123,12 @@ type OperationCommon<'a, T: Text<'a>> = (
     SelectionSet<'a, T>,
 );
 
-pub fn operation_common<'a, T: Text<'a>>(input: &mut TokenStream<'a>)
-    -> ParseResult<OperationCommon<'a, T>, TokenStream<'a>>
+/// Parses optional variable definitions in parentheses
+fn variable_definitions<'a, T>(input: &mut TokenStream<'a>)
+    -> ParseResult<Vec<VariableDefinition<'a, T>>, TokenStream<'a>>
     where T: Text<'a>,
 {
-    optional(name::<'a, T>())
-    .and(optional(
+    optional(
         punct("(")
         .with(many1(
             (
@@ -119,14 +144,23 @@ pub fn operation_common<'a, T: Text<'a>>(input: &mut TokenStream<'a>)
                 }
             })))
         .skip(punct(")")))
-        .map(|vars| vars.unwrap_or_else(Vec::new)))
+        .map(|vars| vars.unwrap_or_else(Vec::new))
+    .parse_stream(input)
+}
+
+pub fn operation_common<'a, T>(input: &mut TokenStream<'a>)
+    -> ParseResult<OperationCommon<'a, T>, TokenStream<'a>>
+    where T: Text<'a>,
+{
+    optional(name::<'a, T>())
+    .and(parser(variable_definitions))
     .and(parser(directives))
     .and(parser(selection_set))
     .map(|(((a, b), c), d)| (a, b, c, d))
     .parse_stream(input)
 }
 
-pub fn mutation<'a, T: Text<'a>>(input: &mut TokenStream<'a>)
+pub fn mutation<'a, T>(input: &mut TokenStream<'a>)
     -> ParseResult<Mutation<'a, T>, TokenStream<'a>>
     where T: Text<'a>,
 {
@@ -140,7 +174,7 @@ pub fn mutation<'a, T: Text<'a>>(input: &mut TokenStream<'a>)
     .parse_stream(input)
 }
 
-pub fn subscription<'a, T: Text<'a>>(input: &mut TokenStream<'a>)
+pub fn subscription<'a, T>(input: &mut TokenStream<'a>)
     -> ParseResult<Subscription<'a, T>, TokenStream<'a>>
     where T: Text<'a>,
 {
@@ -165,22 +199,43 @@ pub fn operation_definition<'a, S>(input: &mut TokenStream<'a>)
     .parse_stream(input)
 }
 
-pub fn fragment_definition<'a, T: Text<'a>>(input: &mut TokenStream<'a>)
+pub fn fragment_definition<'a, T>(input: &mut TokenStream<'a>)
     -> ParseResult<FragmentDefinition<'a, T>, TokenStream<'a>>
     where T: Text<'a>,
 {
-    (
-        position().skip(ident("fragment")),
-        name::<'a, T>(),
-        ident("on").with(name::<'a, T>()).map(TypeCondition::On),
-        parser(directives),
-        parser(selection_set)
-    ).map(|(position, name, type_condition, directives, selection_set)| {
-        FragmentDefinition {
-            position, name, type_condition, directives, selection_set,
-        }
-    })
-    .parse_stream(input)
+    #[cfg(feature = "fragment-arguments")]
+    {
+        (
+            position().skip(ident("fragment")),
+            name::<'a, T>(),
+            parser(variable_definitions),
+            ident("on").with(name::<'a, T>()).map(TypeCondition::On),
+            parser(directives),
+            parser(selection_set)
+        ).map(|(position, name, variable_definitions, type_condition,
+                directives, selection_set)| {
+            FragmentDefinition {
+                position, name, variable_definitions, type_condition,
+                directives, selection_set,
+            }
+        })
+        .parse_stream(input)
+    }
+    #[cfg(not(feature = "fragment-arguments"))]
+    {
+        (
+            position().skip(ident("fragment")),
+            name::<'a, T>(),
+            ident("on").with(name::<'a, T>()).map(TypeCondition::On),
+            parser(directives),
+            parser(selection_set)
+        ).map(|(position, name, type_condition, directives, selection_set)| {
+            FragmentDefinition {
+                position, name, type_condition, directives, selection_set,
+            }
+        })
+        .parse_stream(input)
+    }
 }
 
 pub fn definition<'a, S>(input: &mut TokenStream<'a>)
@@ -196,7 +251,15 @@ pub fn definition<'a, S>(input: &mut TokenStream<'a>)
 pub fn parse_query<'a, S>(s: &'a str) -> Result<Document<'a, S>, ParseError> 
     where S: Text<'a>,
 {
-    let mut tokens = TokenStream::new(s);
+    parse_query_with(s, &ParseOptions::default())
+}
+
+/// Parses a piece of query language with the given options
+pub fn parse_query_with<'a, S>(s: &'a str, options: &ParseOptions)
+    -> Result<Document<'a, S>, ParseError>
+    where S: Text<'a>,
+{
+    let mut tokens = TokenStream::with_recursion_limit(s, options.max_depth);
     let (doc, _) = many1(parser(definition))
         .map(|d| Document { definitions: d })
         .skip(eof())
@@ -206,6 +269,21 @@ pub fn parse_query<'a, S>(s: &'a str) -> Result<Document<'a, S>, ParseError>
     Ok(doc)
 }
 
+/// Parses a query from a `Bytes` buffer
+///
+/// Names in the resulting document are slices of the buffer, so they are
+/// not copied. Returns an error if the buffer isn't valid UTF-8.
+#[cfg(feature = "bytes")]
+pub fn parse_query_bytes(source: bytes::Bytes)
+    -> Result<Document<'static, bytestring::ByteString>, ParseError>
+{
+    use std::convert::TryFrom;
+
+    let source = bytestring::ByteString::try_from(source)?;
+    let doc = parse_query::<&str>(&source)?;
+    Ok(doc.map(|name| source.slice_ref(name)))
+}
+
 /// Parses a single ExecutableDefinition and returns an AST as well as the
 /// remainder of the input which is unparsed
 pub fn consume_definition<'a, S>(s: &'a str) -> Result<(Definition<'a, S>, &'a str), ParseError> where S: Text<'a> {
@@ -219,10 +297,42 @@ pub fn consume_definition<'a, S>(s: &'a str) -> Result<(Definition<'a, S>, &'a s
 mod test {
     use crate::position::Pos;
     use crate::query::grammar::*;
-    use super::{parse_query, consume_definition};
+    use crate::parse_options::ParseOptions;
+    use super::{parse_query, parse_query_with, consume_definition};
 
-    fn ast(s: &str) -> Document<String> {
-        parse_query::<String>(&s).unwrap().to_owned()
+    fn ast(s: &str) -> Document<'_, String> {
+        parse_query::<String>(s).unwrap().to_owned()
+    }
+
+    #[cfg(feature = "fragment-arguments")]
+    #[test]
+    fn fragment_arguments() {
+        let doc = ast("{ ...F(x: 42) } fragment F($x: Int) on T { a(b: $x) }");
+        let spread = match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::SelectionSet(
+                ref set)) => match set.items[0] {
+                Selection::FragmentSpread(ref spread) => spread,
+                _ => panic!("expected fragment spread"),
+            },
+            _ => panic!("expected selection set"),
+        };
+        assert_eq!(spread.arguments, [("x".into(), Value::Int(42.into()))]);
+        match doc.definitions[1] {
+            Definition::Fragment(ref frag) => {
+                let var = &frag.variable_definitions[0];
+                assert_eq!(var.name, "x");
+                assert_eq!(var.position, Pos::new(1, 28));
+            }
+            _ => panic!("expected fragment"),
+        }
+    }
+
+    #[cfg(not(feature = "fragment-arguments"))]
+    #[test]
+    fn fragment_arguments_disabled() {
+        assert!(parse_query::<&str>("{ ...F(x: 42) }").is_err());
+        assert!(parse_query::<&str>("fragment F($x: Int) on T { a }")
+                .is_err());
     }
 
     #[test]
@@ -231,18 +341,18 @@ mod test {
             definitions: vec![
                 Definition::Operation(OperationDefinition::SelectionSet(
                     SelectionSet {
-                        span: (Pos { line: 1, column: 1 },
-                               Pos { line: 1, column: 5 }),
+                        span: (Pos::new(1, 1),
+                               Pos::new(1, 5)),
                         items: vec![
                             Selection::Field(Field {
-                                position: Pos { line: 1, column: 3 },
+                                position: Pos::new(1, 3),
                                 alias: None,
                                 name: "a".into(),
                                 arguments: Vec::new(),
                                 directives: Vec::new(),
                                 selection_set: SelectionSet {
-                                    span: (Pos { line: 1, column: 3 },
-                                           Pos { line: 1, column: 3 }),
+                                    span: (Pos::new(1, 3),
+                                           Pos::new(1, 3)),
                                     items: Vec::new()
                                 },
                             }),
@@ -260,11 +370,11 @@ mod test {
                 definitions: vec![
                     Definition::Operation(OperationDefinition::SelectionSet(
                         SelectionSet {
-                            span: (Pos { line: 1, column: 1 },
-                                   Pos { line: 1, column: 33 }),
+                            span: (Pos::new(1, 1),
+                                   Pos::new(1, 33)),
                             items: vec![
                                 Selection::Field(Field {
-                                    position: Pos { line: 1, column: 3 },
+                                    position: Pos::new(1, 3),
                                     alias: None,
                                     name: "a".into(),
                                     arguments: vec![
@@ -277,8 +387,8 @@ mod test {
                                     ],
                                     directives: Vec::new(),
                                     selection_set: SelectionSet {
-                                        span: (Pos { line: 1, column: 3 },
-                                               Pos { line: 1, column: 3 }),
+                                        span: (Pos::new(1, 3),
+                                               Pos::new(1, 3)),
                                         items: Vec::new()
                                     },
                                 }),
@@ -295,7 +405,20 @@ mod test {
     }
 
     #[test]
-    #[should_panic(expected="number too large")]
+    fn named_subscription() {
+        let doc = ast("subscription Events($id: ID) { events(id: $id) }");
+        match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::Subscription(ref s)) => {
+                assert_eq!(s.name.as_ref().map(|n| &n[..]), Some("Events"));
+                assert_eq!(s.variable_definitions.len(), 1);
+                assert_eq!(s.selection_set.items.len(), 1);
+            }
+            ref def => panic!("expected a subscription, got {:?}", def),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected="integer out of range")]
     fn large_integer() {
         ast("{ a(x: 10000000000000000000000000000 }");
     }
@@ -329,11 +452,102 @@ mod test {
         let err = format!("{}", err);
         assert_eq!(err, "query parse error: Parse error at 1:1\nUnexpected `where[Name]`\nExpected `{`, `query`, `mutation`, `subscription` or `fragment`\n");
     }
-  
+
+    #[test]
     fn recursion_too_deep() {
         let query = format!("{}(b: {}{}){}", "{ a".repeat(30), "[".repeat(25), "]".repeat(25),  "}".repeat(30));
         let result = parse_query::<&str>(&query);
         let err = format!("{}", result.unwrap_err());
         assert_eq!(&err, "query parse error: Parse error at 1:114\nExpected `]`\nRecursion limit exceeded\n")
     }
+
+    #[test]
+    fn huge_nesting_does_not_overflow_stack() {
+        let depth = 200_000;
+        let queries = [
+            "{ a ".repeat(depth),
+            format!("{{ a(b: {}) }}", "[".repeat(depth)),
+            format!("{{ a(b: {}) }}", "{c: ".repeat(depth)),
+            format!("query($v: {}Int) {{ a }}", "[".repeat(depth)),
+        ];
+        for query in &queries {
+            let err = parse_query::<&str>(query).unwrap_err().to_string();
+            assert!(err.contains("Recursion limit exceeded"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn nesting_below_limit() {
+        let query = format!("{}{}", "{ a ".repeat(49), "}".repeat(49));
+        assert!(parse_query::<&str>(&query).is_ok());
+    }
+
+    #[test]
+    fn byte_offsets() {
+        let source = "query {\n  # caf\u{e9}\n  a(s: \"\u{fc}\") {\n    b\n  }\n}";
+        let doc = parse_query::<&str>(source).unwrap();
+        let a = match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::Query(ref q)) => {
+                match q.selection_set.items[0] {
+                    Selection::Field(ref field) => field,
+                    _ => unreachable!(),
+                }
+            }
+            _ => unreachable!(),
+        };
+        let b = match a.selection_set.items[0] {
+            Selection::Field(ref field) => field,
+            _ => unreachable!(),
+        };
+        assert_eq!((a.position.line, a.position.column), (3, 3));
+        assert_eq!(a.position.offset, source.find("a(").unwrap());
+        assert_eq!((b.position.line, b.position.column), (4, 5));
+        assert_eq!(b.position.offset, source.find('b').unwrap());
+        assert_eq!(a.selection_set.span.1.offset,
+                   source.rfind("  }").unwrap() + 2);
+    }
+
+    #[test]
+    fn max_depth() {
+        let options = ParseOptions { max_depth: 3 };
+        let query = |depth: usize| {
+            format!("{}{}", "{ a ".repeat(depth), "}".repeat(depth))
+        };
+        assert!(parse_query_with::<&str>(&query(3), &options).is_ok());
+        let err = parse_query_with::<&str>(&query(4), &options).unwrap_err();
+        assert!(err.is_depth_limit_exceeded());
+        let err = parse_query_with::<&str>("{ a(b: [[[[1]]]]) }", &options)
+            .unwrap_err();
+        assert!(err.is_depth_limit_exceeded());
+        let err = parse_query::<&str>(&query(10_000)).unwrap_err();
+        assert!(err.is_depth_limit_exceeded());
+        let err = parse_query::<&str>("{ a(b: ) }").unwrap_err();
+        assert!(!err.is_depth_limit_exceeded());
+    }
+
+    #[test]
+    fn borrowed_cow() {
+        use std::borrow::Cow;
+
+        let doc = parse_query::<Cow<str>>(
+            "query Q($v: ID) { user(id: $v) @live { name } }").unwrap();
+        let q = match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::Query(ref q)) => q,
+            _ => unreachable!(),
+        };
+        let user = match q.selection_set.items[0] {
+            Selection::Field(ref field) => field,
+            _ => unreachable!(),
+        };
+        let is_borrowed = |value: &Cow<str>| match *value {
+            Cow::Borrowed(_) => true,
+            Cow::Owned(_) => false,
+        };
+        assert!(is_borrowed(q.name.as_ref().unwrap()));
+        assert!(is_borrowed(&q.variable_definitions[0].name));
+        assert!(is_borrowed(&user.name));
+        assert!(is_borrowed(&user.arguments[0].0));
+        assert!(is_borrowed(&user.directives[0].name));
+        assert!(doc.all_fields().all(|field| is_borrowed(&field.name)));
+    }
 }
@@ -0,0 +1,329 @@
+//! Conversion of query AST between text types
+//!
+use crate::common::{map_arguments, map_directives};
+use crate::query::ast::*;
+
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> Document<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        Document {
+            definitions: self.definitions.into_iter()
+                .map(|def| def.map_text(f))
+                .collect(),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Definition<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> Definition<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        match self {
+            Definition::Operation(op) => Definition::Operation(op.map_text(f)),
+            Definition::Fragment(frag) => {
+                Definition::Fragment(frag.map_text(f))
+            }
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> FragmentDefinition<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F)
+        -> FragmentDefinition<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        FragmentDefinition {
+            position: self.position,
+            name: f(self.name),
+            #[cfg(feature = "fragment-arguments")]
+            variable_definitions: map_variable_definitions(
+                self.variable_definitions, f),
+            type_condition: self.type_condition.map_text(f),
+            directives: map_directives(self.directives, f),
+            selection_set: self.selection_set.map_text(f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> OperationDefinition<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F)
+        -> OperationDefinition<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        match self {
+            OperationDefinition::SelectionSet(set) => {
+                OperationDefinition::SelectionSet(set.map_text(f))
+            }
+            OperationDefinition::Query(q) => {
+                OperationDefinition::Query(q.map_text(f))
+            }
+            OperationDefinition::Mutation(m) => {
+                OperationDefinition::Mutation(m.map_text(f))
+            }
+            OperationDefinition::Subscription(s) => {
+                OperationDefinition::Subscription(s.map_text(f))
+            }
+        }
+    }
+}
+
+fn map_variable_definitions<'a, 'b, T, U, F>(
+    definitions: Vec<VariableDefinition<'a, T>>, f: &mut F)
+    -> Vec<VariableDefinition<'b, U>>
+    where T: Text<'a>,
+          U: Text<'b>,
+          F: FnMut(T::Value) -> U::Value,
+{
+    definitions.into_iter().map(|def| def.map_text(f)).collect()
+}
+
+macro_rules! impl_operation_map_text {
+    ($( $typ: ident ),*) => {
+        $(
+            impl<'a, T: Text<'a>> $typ<'a, T> {
+                pub(crate) fn map_text<'b, U, F>(self, f: &mut F)
+                    -> $typ<'b, U>
+                    where U: Text<'b>,
+                          F: FnMut(T::Value) -> U::Value,
+                {
+                    $typ {
+                        position: self.position,
+                        name: self.name.map(&mut *f),
+                        variable_definitions: map_variable_definitions(
+                            self.variable_definitions, f),
+                        directives: map_directives(self.directives, f),
+                        selection_set: self.selection_set.map_text(f),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_operation_map_text!(Query, Mutation, Subscription);
+
+impl<'a, T: Text<'a>> SelectionSet<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> SelectionSet<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        SelectionSet {
+            span: self.span,
+            items: self.items.into_iter()
+                .map(|item| item.map_text(f))
+                .collect(),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> VariableDefinition<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F)
+        -> VariableDefinition<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        VariableDefinition {
+            position: self.position,
+            name: f(self.name),
+            var_type: self.var_type.map_text(f),
+            default_value: self.default_value.map(|value| value.map_text(f)),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Selection<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> Selection<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        match self {
+            Selection::Field(field) => Selection::Field(field.map_text(f)),
+            Selection::FragmentSpread(spread) => {
+                Selection::FragmentSpread(spread.map_text(f))
+            }
+            Selection::InlineFragment(frag) => {
+                Selection::InlineFragment(frag.map_text(f))
+            }
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Field<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> Field<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        Field {
+            position: self.position,
+            alias: self.alias.map(&mut *f),
+            name: f(self.name),
+            arguments: map_arguments(self.arguments, f),
+            directives: map_directives(self.directives, f),
+            selection_set: self.selection_set.map_text(f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> FragmentSpread<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> FragmentSpread<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        FragmentSpread {
+            position: self.position,
+            fragment_name: f(self.fragment_name),
+            #[cfg(feature = "fragment-arguments")]
+            arguments: map_arguments(self.arguments, f),
+            directives: map_directives(self.directives, f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> TypeCondition<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> TypeCondition<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        match self {
+            TypeCondition::On(name) => TypeCondition::On(f(name)),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> InlineFragment<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> InlineFragment<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        InlineFragment {
+            position: self.position,
+            type_condition: self.type_condition.map(|cond| cond.map_text(f)),
+            directives: map_directives(self.directives, f),
+            selection_set: self.selection_set.map_text(f),
+        }
+    }
+}
+
+impl_map_text!(Document, Definition, FragmentDefinition,
+    OperationDefinition, Query, Mutation, Subscription, SelectionSet,
+    VariableDefinition, Selection, Field, FragmentSpread, TypeCondition,
+    InlineFragment);
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+    use std::sync::Arc;
+
+    use crate::query::{parse_query, Definition, Document};
+    use crate::query::{OperationDefinition, Selection};
+
+    fn parse_owned(source: &str) -> Document<'static, String> {
+        let source = source.to_string();
+        let doc = parse_query::<&str>(&source).unwrap();
+        doc.into_owned()
+    }
+
+    #[test]
+    fn outlives_source() {
+        fn assert_send_static<T: Send + 'static>(_: &T) {}
+
+        let doc = parse_owned("query Q($id: ID = \"x\") {
+            user(id: $id, filter: {kind: ADMIN, tags: [\"a\"]}) @skip(if: $no) {
+                ...UserFields
+                ... on Admin { level }
+            }
+        }
+        fragment UserFields on User { name }");
+        assert_send_static(&doc);
+        assert_eq!(doc.to_string(), "\
+query Q($id: ID = \"x\") {
+  user(id: $id, filter: {kind: ADMIN, tags: [\"a\"]}) @skip(if: $no) {
+    ...UserFields
+    ... on Admin {
+      level
+    }
+  }
+}
+
+fragment UserFields on User {
+  name
+}
+");
+    }
+
+    #[test]
+    fn map_between_text_types() {
+        let source = "query Q($id: ID!) @live {
+            node(id: $id) { ... on User { name(format: {case: UPPER}) } }
+        }";
+        let doc = parse_query::<&str>(source).unwrap();
+        let expected = doc.to_string();
+        let mut names = Vec::new();
+        let cow: Document<Cow<str>> = doc.map(|name: &str| {
+            names.push(name);
+            Cow::Borrowed(name)
+        });
+        assert_eq!(cow.to_string(), expected);
+        assert_eq!(names, ["Q", "id", "ID", "live", "node", "id", "id",
+                           "User", "name", "format", "case", "UPPER"]);
+        let owned: Document<String> = cow.map(Cow::into_owned);
+        assert_eq!(owned.to_string(), expected);
+    }
+
+    #[test]
+    fn arc_str() {
+        let doc = parse_query::<Arc<str>>("{ user { name } }").unwrap();
+        let name: &Arc<str> = match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::SelectionSet(
+                ref set)) => match set.items[0] {
+                Selection::Field(ref field) => &field.name,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        assert_eq!(&**name, "user");
+        let names = doc.all_fields()
+            .map(|field| field.name.clone())
+            .collect::<Vec<Arc<str>>>();
+        assert_eq!(names, [Arc::from("user"), Arc::from("name")]);
+
+        let interned: Document<Arc<str>> = parse_owned("{ user }")
+            .map(|name: String| Arc::from(name));
+        assert_eq!(interned.to_string(), "{\n  user\n}\n");
+    }
+
+    #[test]
+    #[cfg(feature = "bytes")]
+    fn bytes() {
+        use bytes::Bytes;
+
+        use crate::query::parse_query_bytes;
+
+        const QUERY: &str = "query Q($id: ID) { user(id: $id) }";
+        let source = Bytes::from_static(QUERY.as_bytes());
+        let range = source.as_ptr_range();
+        let doc = parse_query_bytes(source.clone()).unwrap();
+        let names = doc.all_fields()
+            .map(|field| field.name.clone())
+            .chain(doc.operations().filter_map(|op| match *op {
+                OperationDefinition::Query(ref q) => q.name.clone(),
+                _ => None,
+            }))
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["user", "Q"]);
+        assert!(names.iter()
+            .all(|name| range.contains(&name.as_bytes().as_ptr())));
+        assert_eq!(doc.to_string(),
+                   parse_query::<&str>(QUERY).unwrap().to_string());
+
+        let err = parse_query_bytes(Bytes::from_static(b"{ \xff }"))
+            .unwrap_err();
+        assert!(err.to_string().contains("not valid UTF-8"));
+    }
+}
@@ -0,0 +1,204 @@
+//! Merging of duplicate fields in selection sets
+//!
+use crate::query::ast::*;
+
+
+/// Merges sibling fields which select the same data
+///
+/// Fields with the same response key (alias or name), name, arguments and
+/// directives are replaced by the first of them, with the selection sets
+/// of all of them concatenated. Arguments are compared regardless of their
+/// order. Selection sets of fields and inline fragments are merged
+/// recursively; fragment spreads and inline fragments are never merged.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::query::{parse_query, merge_selections};
+/// use graphql_parser::query::{Definition, OperationDefinition};
+///
+/// let mut doc = parse_query::<&str>("{ a { x } a { y } }").unwrap();
+/// if let Definition::Operation(OperationDefinition::SelectionSet(ref mut set))
+///     = doc.definitions[0]
+/// {
+///     merge_selections(set);
+/// }
+/// assert_eq!(doc.to_string(), "{\n  a {\n    x\n    y\n  }\n}\n");
+/// ```
+pub fn merge_selections<'a, T>(selection_set: &mut SelectionSet<'a, T>)
+    where T: Text<'a>,
+{
+    let mut items: Vec<Selection<'a, T>> = Vec::new();
+    for item in selection_set.items.drain(..) {
+        if let Selection::Field(mut field) = item {
+            let target = items.iter_mut().find_map(|item| match *item {
+                Selection::Field(ref mut target)
+                    if same_field(target, &field) => Some(target),
+                _ => None,
+            });
+            match target {
+                Some(target) => {
+                    target.selection_set.items
+                        .append(&mut field.selection_set.items);
+                }
+                None => items.push(Selection::Field(field)),
+            }
+        } else {
+            items.push(item);
+        }
+    }
+    for item in &mut items {
+        match *item {
+            Selection::Field(ref mut field) => {
+                merge_selections(&mut field.selection_set);
+            }
+            Selection::InlineFragment(ref mut frag) => {
+                merge_selections(&mut frag.selection_set);
+            }
+            Selection::FragmentSpread(_) => {}
+        }
+    }
+    selection_set.items = items;
+}
+
+fn same_field<'a, T>(a: &Field<'a, T>, b: &Field<'a, T>) -> bool
+    where T: Text<'a>,
+{
+    a.alias == b.alias
+        && a.name == b.name
+        && arguments_eq(&a.arguments, &b.arguments)
+        && a.directives.len() == b.directives.len()
+        && a.directives.iter().zip(&b.directives).all(|(a, b)| {
+            a.name == b.name && arguments_eq(&a.arguments, &b.arguments)
+        })
+}
+
+/// Compares argument lists regardless of the order of arguments
+pub(crate) fn arguments_eq<'a, T>(a: &[(T::Value, Value<'a, T>)],
+    b: &[(T::Value, Value<'a, T>)])
+    -> bool
+    where T: Text<'a>,
+{
+    a.len() == b.len() && a.iter().all(|(name, value)| {
+        b.iter().any(|(other, other_value)| {
+            name == other && value_eq(value, other_value)
+        })
+    })
+}
+
+/// Compares values structurally, a block string is equal to a string
+/// with the same value
+pub(crate) fn value_eq<'a, T>(a: &Value<'a, T>, b: &Value<'a, T>) -> bool
+    where T: Text<'a>,
+{
+    match (a, b) {
+        (Value::Variable(a), Value::Variable(b)) => a == b,
+        (Value::Int(a), Value::Int(b)) => a == b,
+        (Value::Float(a), Value::Float(b)) => a == b,
+        (Value::String(a), Value::String(b))
+        | (Value::String(a), Value::BlockString(b))
+        | (Value::BlockString(a), Value::String(b))
+        | (Value::BlockString(a), Value::BlockString(b)) => a == b,
+        (Value::Boolean(a), Value::Boolean(b)) => a == b,
+        (Value::Null, Value::Null) => true,
+        (Value::Enum(a), Value::Enum(b)) => a == b,
+        (Value::List(a), Value::List(b)) => {
+            a.len() == b.len()
+                && a.iter().zip(b).all(|(a, b)| value_eq(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter().all(|(name, va)| {
+                    b.get(name.as_ref()).is_some_and(|vb| value_eq(va, vb))
+                })
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::merge_selections;
+    use crate::query::{parse_query, Definition, OperationDefinition};
+
+    fn merge(query: &str) -> String {
+        let mut doc = parse_query::<&str>(query).unwrap();
+        for def in &mut doc.definitions {
+            match *def {
+                Definition::Operation(OperationDefinition::Query(ref mut q))
+                    => merge_selections(&mut q.selection_set),
+                Definition::Operation(
+                    OperationDefinition::SelectionSet(ref mut set))
+                    => merge_selections(set),
+                _ => unreachable!(),
+            }
+        }
+        doc.to_string()
+    }
+
+    #[test]
+    fn merges_equal_fields() {
+        assert_eq!(merge("{
+            a { x } b a { y }
+            user(id: 1, name: \"x\") { id }
+            ... on User { user(name: \"x\", id: 1) { name } }
+            user(name: \"\"\"x\"\"\", id: 1) { email a { z } a { z } }
+        }"), "\
+{
+  a {
+    x
+    y
+  }
+  b
+  user(id: 1, name: \"x\") {
+    id
+    email
+    a {
+      z
+    }
+  }
+  ... on User {
+    user(name: \"x\", id: 1) {
+      name
+    }
+  }
+}
+");
+    }
+
+    #[test]
+    fn keeps_different_fields() {
+        assert_eq!(merge("query {
+            a(x: 1) { x } a(x: 2) { y }
+            b: a { x } a { y }
+            c @skip(if: true) { x } c { y }
+            d(obj: {k: [1]}) { x } d(obj: {k: [1, 2]}) { y }
+        }"), "\
+query {
+  a(x: 1) {
+    x
+  }
+  a(x: 2) {
+    y
+  }
+  b: a {
+    x
+  }
+  a {
+    y
+  }
+  c @skip(if: true) {
+    x
+  }
+  c {
+    y
+  }
+  d(obj: {k: [1]}) {
+    x
+  }
+  d(obj: {k: [1, 2]}) {
+    y
+  }
+}
+");
+    }
+}
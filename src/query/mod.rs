@@ -1,11 +1,48 @@
 //! Query language AST and parsing utilities
 //!
 mod ast;
+mod directives;
 mod error;
+mod flatten;
 mod format;
-mod grammar;
+mod fragments;
+pub(crate) mod grammar;
+mod map;
+mod merge;
+mod normalize;
+mod operations;
+mod path;
+mod positions;
+mod span;
+mod stream;
+mod usage;
+mod variables;
+mod visitor;
+pub mod builder;
 
 
-pub use self::grammar::{parse_query, consume_definition};
+pub use self::grammar::{parse_query, parse_query_with, consume_definition};
+#[cfg(feature = "bytes")]
+pub use self::grammar::parse_query_bytes;
+pub use crate::parse_options::ParseOptions;
 pub use self::error::ParseError;
 pub use self::ast::*;
+pub use self::visitor::{visit, ParallelVisitor, QueryAstNode, QueryVisitor};
+pub use self::visitor::{collect_directives, DirectiveCollector};
+pub use self::merge::merge_selections;
+pub use self::flatten::flatten_inline_fragments;
+pub use self::directives::{apply_skip_include, evaluate_directives};
+pub use self::directives::is_streamed_field;
+pub use self::fragments::detect_fragment_cycles;
+pub use self::operations::{get_operation, separate_operations};
+pub use self::operations::GetOperationError;
+pub use self::normalize::{normalize_query, normalize_query_with};
+pub use self::normalize::canonicalize;
+pub use self::normalize::{canonical_hash, canonical_hash_with};
+pub use self::normalize::{CanonicalHasher, NormalizeOptions};
+pub use self::positions::ast_eq_ignore_pos;
+pub use self::span::Spanned;
+pub use self::stream::{parse_query_stream, QueryStream, StreamItem};
+pub use self::usage::{used_fragments, used_variables};
+pub use self::variables::{apply_variables, MissingVariable};
+pub use self::variables::{extract_variables, variable_default_values};
@@ -0,0 +1,525 @@
+//! Canonical form of query documents for deduplication and caching
+//!
+//! `normalize_query` rewrites a document so that queries which differ only
+//! in ways irrelevant to their execution become equal. The following
+//! transformations are applied:
+//!
+//! * arguments of fields and directives are sorted by name
+//! * variable definitions are sorted by name
+//! * block strings become regular strings with the same value
+//! * `-0.0` becomes `0.0` (other float literals, like `1.0` and `1.00`,
+//!   are already parsed into the same value)
+//! * queries without name, variables and directives are written in the
+//!   `{ ... }` shorthand form
+//! * operation names are removed if `NormalizeOptions::remove_names` is set
+//!
+//! Fields, fragments, definitions, directives and list items keep their
+//! order and variables and aliases keep their names, as changing any of
+//! them can change the result of the query. Input object fields are
+//! always sorted by name.
+//!
+//! Whitespace, commas and comments are not part of the AST, so they never
+//! affect the canonical form.
+//!
+//! `canonicalize` goes further, producing the same document for queries
+//! which return the same data, although possibly in a different order:
+//!
+//! * all the transformations of `normalize_query` are applied
+//! * directives are sorted by name
+//! * fields with the same response key, arguments and directives are
+//!   merged into a single field, identical fragment spreads are removed
+//! * fields are sorted by response key and placed before fragment spreads
+//!   (sorted by name) and inline fragments (sorted by type condition)
+//!
+//! Fields with different aliases are never merged.
+//!
+use std::fmt;
+
+use crate::format::Style;
+use crate::query::ast::*;
+use crate::sha256::Sha256;
+
+
+/// Options of `normalize_query_with`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NormalizeOptions {
+    /// Remove names of operations
+    ///
+    /// Names don't change the result of an operation, but they are used
+    /// to pick one of the operations of a document, so this is only
+    /// useful for documents with a single operation.
+    pub remove_names: bool,
+}
+
+/// A hash function with a 32-byte digest, see `canonical_hash_with`
+pub trait CanonicalHasher {
+    fn update(&mut self, data: &[u8]);
+    fn finish(self) -> [u8; 32];
+}
+
+impl CanonicalHasher for Sha256 {
+    fn update(&mut self, data: &[u8]) {
+        Sha256::update(self, data)
+    }
+    fn finish(self) -> [u8; 32] {
+        Sha256::finish(self)
+    }
+}
+
+struct HashWriter<H>(H);
+
+impl<H: CanonicalHasher> fmt::Write for HashWriter<H> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.update(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// Returns the canonical form of the document
+///
+/// See the module documentation for the list of transformations.
+pub fn normalize_query<'a, T>(doc: &Document<'a, T>) -> Document<'a, T>
+    where T: Text<'a> + Clone,
+{
+    normalize_query_with(doc, &NormalizeOptions::default())
+}
+
+/// Returns the canonical form of the document according to the options
+pub fn normalize_query_with<'a, T>(doc: &Document<'a, T>,
+    options: &NormalizeOptions)
+    -> Document<'a, T>
+    where T: Text<'a> + Clone,
+{
+    let mut doc = doc.clone();
+    for def in &mut doc.definitions {
+        match *def {
+            Definition::Operation(ref mut op) => {
+                normalize_operation(op, options);
+            }
+            Definition::Fragment(ref mut frag) => {
+                normalize_directives(&mut frag.directives);
+                normalize_selection_set(&mut frag.selection_set);
+            }
+        }
+    }
+    doc
+}
+
+/// Returns a SHA-256 hash of the canonical form of the document
+///
+/// Documents that are equal after `normalize_query` have the same hash.
+pub fn canonical_hash<'a, T>(doc: &Document<'a, T>) -> [u8; 32]
+    where T: Text<'a> + Clone,
+{
+    canonical_hash_with(doc, Sha256::new())
+}
+
+/// Returns a hash of the canonical form of the document computed by the
+/// hasher
+///
+/// The hasher is fed with the minified canonical form of the document.
+pub fn canonical_hash_with<'a, T, H>(doc: &Document<'a, T>, hasher: H)
+    -> [u8; 32]
+    where T: Text<'a> + Clone,
+          H: CanonicalHasher,
+{
+    let mut style = Style::default();
+    style.minify(true);
+    let mut writer = HashWriter(hasher);
+    normalize_query(doc).format_to(&mut writer, &style)
+        .expect("hashing never fails");
+    writer.0.finish()
+}
+
+/// Returns the canonical form of the document, with fields sorted and
+/// merged
+///
+/// See the module documentation for the list of transformations.
+pub fn canonicalize<'a, T>(doc: &Document<'a, T>) -> Document<'a, T>
+    where T: Text<'a> + Clone,
+{
+    let mut doc = normalize_query(doc);
+    for def in &mut doc.definitions {
+        let (directives, selection_set) = match *def {
+            Definition::Operation(ref mut op) => match *op {
+                OperationDefinition::SelectionSet(ref mut set) => {
+                    canonicalize_selection_set(set);
+                    continue;
+                }
+                OperationDefinition::Query(ref mut q) => {
+                    (&mut q.directives, &mut q.selection_set)
+                }
+                OperationDefinition::Mutation(ref mut m) => {
+                    (&mut m.directives, &mut m.selection_set)
+                }
+                OperationDefinition::Subscription(ref mut s) => {
+                    (&mut s.directives, &mut s.selection_set)
+                }
+            },
+            Definition::Fragment(ref mut frag) => {
+                (&mut frag.directives, &mut frag.selection_set)
+            }
+        };
+        sort_directives(directives);
+        canonicalize_selection_set(selection_set);
+    }
+    doc
+}
+
+fn sort_directives<'a, T>(directives: &mut [Directive<'a, T>])
+    where T: Text<'a>,
+{
+    directives.sort_by(|a, b| a.name.cmp(&b.name));
+}
+
+/// Everything of a field except the selection set, fields with equal
+/// headers can be merged
+fn field_header<'a, T>(field: &Field<'a, T>) -> String
+    where T: Text<'a>,
+{
+    let mut header = String::new();
+    if let Some(ref alias) = field.alias {
+        header.push_str(alias.as_ref());
+        header.push(':');
+    }
+    header.push_str(field.name.as_ref());
+    for (name, value) in &field.arguments {
+        header.push_str(&format!(" {}: {}", name.as_ref(), value));
+    }
+    for dir in &field.directives {
+        header.push_str(&format!(" {}", dir));
+    }
+    header
+}
+
+fn canonicalize_selection_set<'a, T>(
+    selection_set: &mut SelectionSet<'a, T>)
+    where T: Text<'a>,
+{
+    let mut items: Vec<Selection<'a, T>> = Vec::new();
+    let mut headers = Vec::new();
+    for mut item in selection_set.items.drain(..) {
+        let header = match item {
+            Selection::Field(ref mut field) => {
+                sort_directives(&mut field.directives);
+                field_header(field)
+            }
+            Selection::FragmentSpread(ref mut spread) => {
+                sort_directives(&mut spread.directives);
+                spread.to_string()
+            }
+            Selection::InlineFragment(ref mut frag) => {
+                sort_directives(&mut frag.directives);
+                items.push(item);
+                headers.push(String::new());
+                continue;
+            }
+        };
+        match headers.iter().position(|h| *h == header) {
+            Some(index) => {
+                if let (Selection::Field(ref mut target),
+                        Selection::Field(ref mut field)) =
+                    (&mut items[index], item)
+                {
+                    target.selection_set.items
+                        .append(&mut field.selection_set.items);
+                }
+            }
+            None => {
+                items.push(item);
+                headers.push(header);
+            }
+        }
+    }
+    for item in &mut items {
+        match *item {
+            Selection::Field(ref mut field) => {
+                canonicalize_selection_set(&mut field.selection_set);
+            }
+            Selection::InlineFragment(ref mut frag) => {
+                canonicalize_selection_set(&mut frag.selection_set);
+            }
+            Selection::FragmentSpread(_) => {}
+        }
+    }
+    items.sort_by_cached_key(|item| match *item {
+        Selection::Field(ref field) => (0, field_header(field)),
+        Selection::FragmentSpread(ref spread) => {
+            (1, spread.fragment_name.as_ref().to_string())
+        }
+        Selection::InlineFragment(ref frag) => {
+            let cond = match frag.type_condition {
+                Some(TypeCondition::On(ref name)) => name.as_ref(),
+                None => "",
+            };
+            (2, cond.to_string())
+        }
+    });
+    selection_set.items = items;
+}
+
+fn normalize_operation<'a, T>(op: &mut OperationDefinition<'a, T>,
+    options: &NormalizeOptions)
+    where T: Text<'a> + Clone,
+{
+    let shorthand = match *op {
+        OperationDefinition::SelectionSet(ref mut set) => {
+            normalize_selection_set(set);
+            return;
+        }
+        OperationDefinition::Query(ref mut q) => {
+            normalize_header(&mut q.name, &mut q.variable_definitions,
+                             &mut q.directives, options);
+            normalize_selection_set(&mut q.selection_set);
+            q.name.is_none() && q.variable_definitions.is_empty()
+                && q.directives.is_empty()
+        }
+        OperationDefinition::Mutation(ref mut m) => {
+            normalize_header(&mut m.name, &mut m.variable_definitions,
+                             &mut m.directives, options);
+            normalize_selection_set(&mut m.selection_set);
+            false
+        }
+        OperationDefinition::Subscription(ref mut s) => {
+            normalize_header(&mut s.name, &mut s.variable_definitions,
+                             &mut s.directives, options);
+            normalize_selection_set(&mut s.selection_set);
+            false
+        }
+    };
+    if shorthand {
+        if let OperationDefinition::Query(ref mut q) = *op {
+            let span = q.selection_set.span;
+            let items = std::mem::take(&mut q.selection_set.items);
+            *op = OperationDefinition::SelectionSet(SelectionSet {
+                span,
+                items,
+            });
+        }
+    }
+}
+
+fn normalize_header<'a, T>(name: &mut Option<T::Value>,
+    variable_definitions: &mut [VariableDefinition<'a, T>],
+    directives: &mut [Directive<'a, T>],
+    options: &NormalizeOptions)
+    where T: Text<'a>,
+{
+    if options.remove_names {
+        *name = None;
+    }
+    variable_definitions.sort_by(|a, b| a.name.cmp(&b.name));
+    for var in variable_definitions {
+        if let Some(ref mut value) = var.default_value {
+            normalize_value(value);
+        }
+    }
+    normalize_directives(directives);
+}
+
+fn normalize_selection_set<'a, T>(selection_set: &mut SelectionSet<'a, T>)
+    where T: Text<'a>,
+{
+    for item in &mut selection_set.items {
+        match *item {
+            Selection::Field(ref mut field) => {
+                normalize_arguments(&mut field.arguments);
+                normalize_directives(&mut field.directives);
+                normalize_selection_set(&mut field.selection_set);
+            }
+            Selection::FragmentSpread(ref mut spread) => {
+                normalize_directives(&mut spread.directives);
+            }
+            Selection::InlineFragment(ref mut frag) => {
+                normalize_directives(&mut frag.directives);
+                normalize_selection_set(&mut frag.selection_set);
+            }
+        }
+    }
+}
+
+fn normalize_directives<'a, T>(directives: &mut [Directive<'a, T>])
+    where T: Text<'a>,
+{
+    for dir in directives {
+        normalize_arguments(&mut dir.arguments);
+    }
+}
+
+fn normalize_arguments<'a, T>(arguments: &mut [(T::Value, Value<'a, T>)])
+    where T: Text<'a>,
+{
+    arguments.sort_by(|a, b| a.0.cmp(&b.0));
+    for (_, value) in arguments {
+        normalize_value(value);
+    }
+}
+
+fn normalize_value<'a, T>(value: &mut Value<'a, T>)
+    where T: Text<'a>,
+{
+    match *value {
+        Value::BlockString(ref mut s) => {
+            *value = Value::String(std::mem::take(s));
+        }
+        Value::Float(ref mut f) if f.as_f64() == 0.0 => *f = 0.0.into(),
+        Value::List(ref mut items) => {
+            for item in items {
+                normalize_value(item);
+            }
+        }
+        Value::Object(ref mut fields) => {
+            for value in fields.values_mut() {
+                normalize_value(value);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{canonical_hash, normalize_query, normalize_query_with};
+    use super::{canonicalize, NormalizeOptions};
+    use crate::query::parse_query;
+
+    fn normalize(query: &str) -> String {
+        normalize_query(&parse_query::<&str>(query).unwrap()).to_string()
+    }
+
+    fn canonical(query: &str) -> String {
+        canonicalize(&parse_query::<&str>(query).unwrap()).to_string()
+    }
+
+    fn hash(query: &str) -> [u8; 32] {
+        canonical_hash(&parse_query::<&str>(query).unwrap())
+    }
+
+    #[test]
+    fn sorts_arguments_and_variables() {
+        assert_eq!(normalize("
+            query Q($b: Int, $a: [String] = [\"y\", \"x\"]) @dir(z: 1, y: 2) {
+                user(name: $a, id: $b) @include(if: true) {
+                    friends(last: 1, first: -0.0) { ...F @skip(y: 1, x: 2) }
+                }
+            }
+            fragment F on User { name(style: \"\"\"x\"\"\", lang: EN) }
+        "), "\
+query Q($a: [String] = [\"y\", \"x\"], $b: Int) @dir(y: 2, z: 1) {
+  user(id: $b, name: $a) @include(if: true) {
+    friends(first: 0.0, last: 1) {
+      ...F @skip(x: 2, y: 1)
+    }
+  }
+}
+
+fragment F on User {
+  name(lang: EN, style: \"x\")
+}
+");
+    }
+
+    #[test]
+    fn shorthand_and_names() {
+        assert_eq!(normalize("query { a }"), "{\n  a\n}\n");
+        let doc = parse_query::<&str>("query Q { a } mutation M { b }")
+            .unwrap();
+        let options = NormalizeOptions { remove_names: true };
+        assert_eq!(normalize_query_with(&doc, &options).to_string(),
+                   "{\n  a\n}\n\nmutation {\n  b\n}\n");
+    }
+
+    #[test]
+    fn equivalent_queries_hash_the_same() {
+        let hashed = hash("query Q($id: ID, $n: Float = 1.0) {
+            user(id: $id, size: $n) { name, friends(first: 10) { name } }
+        }");
+        assert_eq!(hash("
+            # written differently
+            query Q($n:Float=1.00 $id:ID){user(size:$n,id:$id){
+                name friends(first:10){name}}}
+        "), hashed);
+    }
+
+    #[test]
+    fn different_queries_hash_differently() {
+        let hashed = hash("{ user(id: 1) { name } }");
+        assert_eq!(hash("query { user(id: 1) { name } }"), hashed);
+        assert_ne!(hash("{ user(id: 2) { name } }"), hashed);
+        assert_ne!(hash("{ user(id: \"1\") { name } }"), hashed);
+        assert_ne!(hash("{ user(id: 1.0) { name } }"), hashed);
+        assert_ne!(hash("{ user(id: 1) { id } }"), hashed);
+        assert_ne!(hash("{ user(id: 1) { n: name } }"), hashed);
+    }
+
+    #[test]
+    fn canonicalize_reordered() {
+        let expected = canonical("
+            query Q($id: ID, $first: Int) @live @cached(ttl: 10) {
+                user(id: $id) {
+                    ...Details
+                    name
+                    friends(first: $first, after: null) { name id }
+                    ... on Admin { level }
+                }
+            }
+        ");
+        assert_eq!(canonical("
+            query Q($first: Int, $id: ID) @cached(ttl: 10) @live {
+                user(id: $id) {
+                    friends(after: null, first: $first) { id name }
+                    ... on Admin { level }
+                    name
+                    ...Details
+                }
+            }
+        "), expected);
+        assert_eq!(expected, "\
+query Q($first: Int, $id: ID) @cached(ttl: 10) @live {
+  user(id: $id) {
+    friends(after: null, first: $first) {
+      id
+      name
+    }
+    name
+    ...Details
+    ... on Admin {
+      level
+    }
+  }
+}
+");
+    }
+
+    #[test]
+    fn canonicalize_merges_fields() {
+        assert_eq!(canonical("{
+            user { name friends { name } }
+            user { id friends { id } ...F ...F }
+        }"), canonical("{ user { id name ...F friends { id name } } }"));
+        // different aliases, arguments or directives prevent merging
+        assert_eq!(canonical("{
+            u: user { name }
+            user { id }
+            user(id: 1) { id }
+            user @skip(if: true) { name }
+            u: user { id }
+        }"), "\
+{
+  u: user {
+    id
+    name
+  }
+  user {
+    id
+  }
+  user @skip(if: true) {
+    name
+  }
+  user(id: 1) {
+    id
+  }
+}
+");
+    }
+}
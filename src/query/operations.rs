@@ -0,0 +1,166 @@
+//! Selecting operations of multi-operation documents
+//!
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use thiserror::Error;
+
+use crate::query::ast::*;
+use crate::query::fragments::collect_spreads;
+
+
+/// Error selecting an operation, see `get_operation`
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("{message}")]
+pub struct GetOperationError {
+    message: String,
+    ambiguous: bool,
+}
+
+impl GetOperationError {
+    /// Returns `true` if no name was given and the document has multiple
+    /// operations
+    pub fn is_ambiguous(&self) -> bool {
+        self.ambiguous
+    }
+}
+
+/// Returns the operation to execute, as the `GetOperation` algorithm of
+/// the spec
+///
+/// With a name, returns the operation of that name. Without a name,
+/// returns the only operation of the document.
+pub fn get_operation<'d, 'a, T>(doc: &'d Document<'a, T>, name: Option<&str>)
+    -> Result<&'d OperationDefinition<'a, T>, GetOperationError>
+    where T: Text<'a>,
+{
+    let mut operations = doc.operations();
+    match name {
+        Some(name) => operations
+            .find(|op| op.name() == Some(name))
+            .ok_or_else(|| GetOperationError {
+                message: format!("Unknown operation named \"{}\".", name),
+                ambiguous: false,
+            }),
+        None => match (operations.next(), operations.next()) {
+            (Some(op), None) => Ok(op),
+            (None, _) => Err(GetOperationError {
+                message: "Must provide an operation.".into(),
+                ambiguous: false,
+            }),
+            (Some(_), Some(_)) => Err(GetOperationError {
+                message: "Must provide operation name \
+                    if query contains multiple operations.".into(),
+                ambiguous: true,
+            }),
+        },
+    }
+}
+
+/// Splits the document into a document per operation
+///
+/// Each document contains the operation and the fragments it spreads,
+/// directly or through other fragments, in the original order. Documents
+/// are keyed by operation name, the anonymous operation has an empty name.
+pub fn separate_operations<'a, T>(doc: &Document<'a, T>)
+    -> BTreeMap<String, Document<'a, T>>
+    where T: Text<'a> + Clone,
+{
+    let mut spreads = HashMap::new();
+    for frag in doc.fragments() {
+        let mut names = Vec::new();
+        collect_spreads(&frag.selection_set, &mut names);
+        spreads.entry(frag.name.as_ref()).or_insert(names);
+    }
+    let mut result = BTreeMap::new();
+    for op in doc.operations() {
+        let mut used = HashSet::new();
+        let mut queue = Vec::new();
+        collect_spreads(op.selection_set(), &mut queue);
+        while let Some(name) = queue.pop() {
+            if used.insert(name.as_ref()) {
+                if let Some(names) = spreads.get(name.as_ref()) {
+                    queue.extend(names);
+                }
+            }
+        }
+        let definitions = doc.definitions.iter()
+            .filter(|other| match **other {
+                Definition::Operation(ref other) => std::ptr::eq(other, op),
+                Definition::Fragment(ref frag) => {
+                    used.contains(frag.name.as_ref())
+                }
+            })
+            .cloned()
+            .collect();
+        result.insert(op.name().unwrap_or("").to_string(),
+                      Document { definitions });
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{get_operation, separate_operations};
+    use crate::query::parse_query;
+
+    const DOCUMENT: &str = "
+        query Users { users { ...User } }
+        fragment Unused on T { x }
+        fragment User on User { id ...Name }
+        mutation Rename { rename { ...Name } }
+        fragment Name on User { name }
+        { viewer { ...Viewer } }
+        fragment Viewer on User { ...User settings }
+    ";
+
+    #[test]
+    fn separate() {
+        let doc = parse_query::<&str>(DOCUMENT).unwrap();
+        let separated = separate_operations(&doc).into_iter()
+            .map(|(name, doc)| (name, doc.to_string()))
+            .collect::<Vec<_>>();
+        let expected = [
+            ("", "
+                fragment User on User { id ...Name }
+                fragment Name on User { name }
+                { viewer { ...Viewer } }
+                fragment Viewer on User { ...User settings }
+            "),
+            ("Rename", "
+                mutation Rename { rename { ...Name } }
+                fragment Name on User { name }
+            "),
+            ("Users", "
+                query Users { users { ...User } }
+                fragment User on User { id ...Name }
+                fragment Name on User { name }
+            "),
+        ].iter()
+            .map(|&(name, doc)| {
+                (name.to_string(),
+                 parse_query::<&str>(doc).unwrap().to_string())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(separated, expected);
+    }
+
+    #[test]
+    fn get() {
+        let doc = parse_query::<&str>(DOCUMENT).unwrap();
+        let op = get_operation(&doc, Some("Rename")).unwrap();
+        assert_eq!(op.name(), Some("Rename"));
+        let err = get_operation(&doc, Some("Other")).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown operation named \"Other\".");
+        assert!(!err.is_ambiguous());
+        let err = get_operation(&doc, None).unwrap_err();
+        assert_eq!(err.to_string(), "Must provide operation name \
+            if query contains multiple operations.");
+        assert!(err.is_ambiguous());
+
+        let doc = parse_query::<&str>("query Q { a }").unwrap();
+        assert_eq!(get_operation(&doc, None).unwrap().name(), Some("Q"));
+        let doc = parse_query::<&str>("fragment F on T { a }").unwrap();
+        assert_eq!(get_operation(&doc, None).unwrap_err().to_string(),
+                   "Must provide an operation.");
+    }
+}
@@ -0,0 +1,237 @@
+//! Addressing query AST nodes by paths
+//!
+//! A path is a list of keys separated by dots, e.g.
+//! `definitions[0].selectionSet.selections[2].arguments[0].value`. Keys are
+//! named as the properties of the graphql-js AST, which keeps the paths
+//! stable across the versions of this crate and familiar to editor
+//! tooling.
+//!
+use std::fmt;
+use std::ptr;
+
+use crate::query::ast::*;
+use crate::query::visitor::QueryAstNode;
+
+
+/// A key of a child node in its parent, e.g. `selections[2]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PathKey {
+    name: &'static str,
+    index: Option<usize>,
+}
+
+impl PathKey {
+    /// Returns the key of a single child, e.g. `selectionSet`
+    pub(crate) fn field(name: &'static str) -> PathKey {
+        PathKey { name, index: None }
+    }
+
+    /// Returns the key of an item of a list of children, e.g. `values[1]`
+    pub(crate) fn item(name: &'static str, index: usize) -> PathKey {
+        PathKey { name, index: Some(index) }
+    }
+
+    fn matches(&self, key: &str) -> bool {
+        match self.index {
+            None => self.name == key,
+            Some(index) => {
+                key.strip_prefix(self.name)
+                    .and_then(|rest| rest.strip_prefix('['))
+                    .and_then(|rest| rest.strip_suffix(']'))
+                    .and_then(|rest| rest.parse::<usize>().ok())
+                    == Some(index)
+            }
+        }
+    }
+}
+
+impl fmt::Display for PathKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.index {
+            None => f.write_str(self.name),
+            Some(index) => write!(f, "{}[{}]", self.name, index),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    /// Returns the node at the path, `None` if there is no such node
+    ///
+    /// The empty path is the document itself.
+    ///
+    /// ```rust
+    /// # extern crate graphql_parser;
+    /// use graphql_parser::query::{parse_query, QueryAstNode};
+    ///
+    /// let doc = parse_query::<&str>("{ user { id name } }").unwrap();
+    /// let path = "definitions[0].selectionSet.selections[0]\
+    ///             .selectionSet.selections[1]";
+    /// match doc.node_at_path(path) {
+    ///     Some(QueryAstNode::Field(field)) => assert_eq!(field.name, "name"),
+    ///     _ => unreachable!(),
+    /// }
+    /// assert_eq!(doc.path_of(doc.node_at_path(path).unwrap()).unwrap(),
+    ///            path);
+    /// ```
+    pub fn node_at_path(&'a self, path: &str) -> Option<QueryAstNode<'a, T>> {
+        let mut node = QueryAstNode::Document(self);
+        if path.is_empty() {
+            return Some(node);
+        }
+        for key in path.split('.') {
+            let mut found = None;
+            node.for_each_keyed_child(&mut |child_key, child| {
+                if found.is_none() && child_key.matches(key) {
+                    found = Some(child);
+                }
+            });
+            node = found?;
+        }
+        Some(node)
+    }
+
+    /// Returns the path of the node, `None` if the node doesn't belong to
+    /// the document
+    ///
+    /// Nodes are compared by address, so the node must be taken from this
+    /// document rather than from an equal one.
+    pub fn path_of(&'a self, node: QueryAstNode<'a, T>) -> Option<String> {
+        let mut keys = Vec::new();
+        if find(QueryAstNode::Document(self), node, &mut keys) {
+            let keys = keys.iter().map(|key| key.to_string());
+            Some(keys.collect::<Vec<_>>().join("."))
+        } else {
+            None
+        }
+    }
+}
+
+/// Looks for the target among the node and its descendants, `keys` is
+/// left with the path of the target if it's found
+fn find<'a, T: Text<'a>>(node: QueryAstNode<'a, T>,
+    target: QueryAstNode<'a, T>, keys: &mut Vec<PathKey>)
+    -> bool
+{
+    if same_node(node, target) {
+        return true;
+    }
+    let mut found = false;
+    node.for_each_keyed_child(&mut |key, child| {
+        if !found {
+            keys.push(key);
+            found = find(child, target, keys);
+            if !found {
+                keys.pop();
+            }
+        }
+    });
+    found
+}
+
+fn same_node<'a, T: Text<'a>>(a: QueryAstNode<'a, T>,
+    b: QueryAstNode<'a, T>)
+    -> bool
+{
+    use self::QueryAstNode as N;
+    match (a, b) {
+        (N::Document(a), N::Document(b)) => ptr::eq(a, b),
+        (N::OperationDefinition(a), N::OperationDefinition(b)) => {
+            ptr::eq(a, b)
+        }
+        (N::FragmentDefinition(a), N::FragmentDefinition(b)) => {
+            ptr::eq(a, b)
+        }
+        (N::VariableDefinition(a), N::VariableDefinition(b)) => {
+            ptr::eq(a, b)
+        }
+        (N::SelectionSet(a), N::SelectionSet(b)) => ptr::eq(a, b),
+        (N::Field(a), N::Field(b)) => ptr::eq(a, b),
+        (N::FragmentSpread(a), N::FragmentSpread(b)) => ptr::eq(a, b),
+        (N::InlineFragment(a), N::InlineFragment(b)) => ptr::eq(a, b),
+        (N::Directive(a), N::Directive(b)) => ptr::eq(a, b),
+        (N::Argument(a, _), N::Argument(b, _))
+        | (N::ObjectField(a, _), N::ObjectField(b, _)) => ptr::eq(a, b),
+        (N::Value(a), N::Value(b)) => ptr::eq(a, b),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::same_node;
+    use crate::query::{parse_query, QueryAstNode};
+
+    const QUERY: &str = "
+        query Q($id: ID = 1) @live {
+            user(id: $id) {
+                name
+                friends(first: 10, filter: {tags: [\"a\", \"b\"]}) { id }
+            }
+        }
+        fragment F on User { email }
+    ";
+
+    #[test]
+    fn nested_field() {
+        let doc = parse_query::<&str>(QUERY).unwrap();
+        let path = "definitions[0].selectionSet.selections[0]\
+                    .selectionSet.selections[1]";
+        let field = match doc.node_at_path(path) {
+            Some(QueryAstNode::Field(field)) => field,
+            _ => unreachable!(),
+        };
+        assert_eq!(field.name, "friends");
+        assert_eq!(doc.path_of(QueryAstNode::Field(field)).unwrap(), path);
+    }
+
+    #[test]
+    fn values() {
+        let doc = parse_query::<&str>(QUERY).unwrap();
+        let path = "definitions[0].selectionSet.selections[0]\
+                    .selectionSet.selections[1].arguments[1].value\
+                    .fields[0].value.values[1]";
+        let node = doc.node_at_path(path).unwrap();
+        assert!(matches!(node,
+                         QueryAstNode::Value(v) if v.to_string() == "\"b\""));
+        assert_eq!(doc.path_of(node).unwrap(), path);
+        let default = doc.node_at_path(
+            "definitions[0].variableDefinitions[0].defaultValue").unwrap();
+        assert!(matches!(default,
+                         QueryAstNode::Value(v) if v.to_string() == "1"));
+    }
+
+    #[test]
+    fn every_node() {
+        use crate::query::{visit, QueryVisitor};
+
+        struct Nodes<'a>(Vec<QueryAstNode<'a, &'a str>>);
+        impl<'a> QueryVisitor<'a, &'a str> for Nodes<'a> {
+            fn enter(&mut self, node: QueryAstNode<'a, &'a str>) {
+                self.0.push(node);
+            }
+        }
+        let doc = parse_query::<&str>(QUERY).unwrap();
+        let mut nodes = Nodes(Vec::new());
+        visit(&doc, &mut nodes);
+        for node in nodes.0 {
+            let path = doc.path_of(node).unwrap();
+            let found = doc.node_at_path(&path).unwrap();
+            assert!(same_node(found, node), "{}", path);
+        }
+    }
+
+    #[test]
+    fn missing() {
+        let doc = parse_query::<&str>(QUERY).unwrap();
+        assert!(doc.node_at_path("definitions[2]").is_none());
+        assert!(doc.node_at_path("definitions[1].selections[0]").is_none());
+        assert!(doc.node_at_path("definitions[x]").is_none());
+        assert!(doc.node_at_path("operations[0]").is_none());
+        assert!(matches!(doc.node_at_path(""),
+                         Some(QueryAstNode::Document(_))));
+        let other = parse_query::<&str>(QUERY).unwrap();
+        let field = other.node_at_path("definitions[1].selectionSet\
+                                        .selections[0]").unwrap();
+        assert!(doc.path_of(field).is_none());
+    }
+}
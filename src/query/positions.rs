@@ -0,0 +1,143 @@
+//! Comparison of query documents regardless of positions
+//!
+use crate::common::Directive;
+use crate::position::Pos;
+use crate::query::ast::*;
+
+
+/// Returns `true` if documents are equal except for positions and spans
+///
+/// Useful to compare documents parsed from differently formatted sources,
+/// e.g. to check that printing and parsing again keeps the document.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::query::{parse_query, ast_eq_ignore_pos};
+///
+/// let a = parse_query::<&str>("{ user { id } }").unwrap();
+/// let b = parse_query::<&str>("{\n  user {\n    id\n  }\n}").unwrap();
+/// assert_ne!(a, b);
+/// assert!(ast_eq_ignore_pos(&a, &b));
+/// ```
+pub fn ast_eq_ignore_pos<'a, T>(a: &Document<'a, T>, b: &Document<'a, T>)
+    -> bool
+    where T: Text<'a> + Clone + PartialEq,
+{
+    let mut a = a.clone();
+    let mut b = b.clone();
+    clear_document(&mut a);
+    clear_document(&mut b);
+    a == b
+}
+
+fn clear_document<'a, T: Text<'a>>(doc: &mut Document<'a, T>) {
+    for def in &mut doc.definitions {
+        match *def {
+            Definition::Operation(ref mut op) => clear_operation(op),
+            Definition::Fragment(ref mut frag) => {
+                frag.position = Pos::zero();
+                #[cfg(feature = "fragment-arguments")]
+                for var in &mut frag.variable_definitions {
+                    var.position = Pos::zero();
+                }
+                clear_directives(&mut frag.directives);
+                clear_selection_set(&mut frag.selection_set);
+            }
+        }
+    }
+}
+
+fn clear_operation<'a, T: Text<'a>>(op: &mut OperationDefinition<'a, T>) {
+    let (position, vars, directives, selection_set) = match *op {
+        OperationDefinition::SelectionSet(ref mut set) => {
+            return clear_selection_set(set);
+        }
+        OperationDefinition::Query(ref mut q) => (&mut q.position,
+            &mut q.variable_definitions, &mut q.directives,
+            &mut q.selection_set),
+        OperationDefinition::Mutation(ref mut m) => (&mut m.position,
+            &mut m.variable_definitions, &mut m.directives,
+            &mut m.selection_set),
+        OperationDefinition::Subscription(ref mut s) => (&mut s.position,
+            &mut s.variable_definitions, &mut s.directives,
+            &mut s.selection_set),
+    };
+    *position = Pos::zero();
+    for var in vars {
+        var.position = Pos::zero();
+    }
+    clear_directives(directives);
+    clear_selection_set(selection_set);
+}
+
+fn clear_selection_set<'a, T: Text<'a>>(set: &mut SelectionSet<'a, T>) {
+    set.span = (Pos::zero(), Pos::zero());
+    for item in &mut set.items {
+        match *item {
+            Selection::Field(ref mut field) => {
+                field.position = Pos::zero();
+                clear_directives(&mut field.directives);
+                clear_selection_set(&mut field.selection_set);
+            }
+            Selection::FragmentSpread(ref mut spread) => {
+                spread.position = Pos::zero();
+                clear_directives(&mut spread.directives);
+            }
+            Selection::InlineFragment(ref mut frag) => {
+                frag.position = Pos::zero();
+                clear_directives(&mut frag.directives);
+                clear_selection_set(&mut frag.selection_set);
+            }
+        }
+    }
+}
+
+fn clear_directives<'a, T: Text<'a>>(directives: &mut [Directive<'a, T>]) {
+    for dir in directives {
+        dir.position = Pos::zero();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ast_eq_ignore_pos;
+    use crate::query::parse_query;
+
+    #[test]
+    fn differently_indented() {
+        let a = parse_query::<&str>("\
+query Q($id: ID = 1) @live {
+  user(id: $id) { ...F ... on User @skip(if: false) { name } }
+}
+fragment F on User { id }
+").unwrap();
+        let b = parse_query::<&str>("\
+    query Q(
+        $id: ID = 1
+    ) @live {
+        user(id: $id) {
+            ...F
+            ... on User @skip(if: false) {
+                name
+            }
+        }
+    }
+
+    fragment F on User {
+        id
+    }
+").unwrap();
+        assert_ne!(a, b);
+        assert!(ast_eq_ignore_pos(&a, &b));
+        assert!(ast_eq_ignore_pos(&b, &a));
+    }
+
+    #[test]
+    fn different_structure() {
+        let a = parse_query::<&str>("{ a(x: 1) }").unwrap();
+        let b = parse_query::<&str>("{\n  a(x: 2)\n}").unwrap();
+        assert!(!ast_eq_ignore_pos(&a, &b));
+        let b = parse_query::<&str>("{\n  a(x: 1) @d\n}").unwrap();
+        assert!(!ast_eq_ignore_pos(&a, &b));
+    }
+}
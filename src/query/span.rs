@@ -0,0 +1,210 @@
+//! Source ranges of query AST nodes
+//!
+use crate::position::{advance, Pos};
+use crate::query::ast::*;
+
+
+/// A node of the query AST with a range in the source text
+///
+/// The start of the range is the position stored in the node (for
+/// fragment spreads and inline fragments that's the position right after
+/// the `...`). Nodes which store just a start position get the end
+/// computed from their children or, when there are no children with
+/// positions, from the length of their formatted text, so for a source
+/// with unusual whitespace inside of the node the end is approximate.
+pub trait Spanned {
+    /// Returns the start of the node and the position right after its
+    /// last character
+    fn span(&self) -> (Pos, Pos);
+}
+
+fn directives_text<'a, T>(text: &mut String, directives: &[Directive<'a, T>])
+    where T: Text<'a>,
+{
+    for dir in directives {
+        text.push(' ');
+        text.push_str(&dir.to_string());
+    }
+}
+
+impl<'a, T: Text<'a>> Spanned for Document<'a, T> {
+    fn span(&self) -> (Pos, Pos) {
+        match (self.definitions.first(), self.definitions.last()) {
+            (Some(first), Some(last)) => (first.span().0, last.span().1),
+            _ => (Pos::new(1, 1), Pos::new(1, 1)),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Spanned for Definition<'a, T> {
+    fn span(&self) -> (Pos, Pos) {
+        match *self {
+            Definition::Operation(ref op) => op.span(),
+            Definition::Fragment(ref frag) => frag.span(),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Spanned for OperationDefinition<'a, T> {
+    fn span(&self) -> (Pos, Pos) {
+        match *self {
+            OperationDefinition::SelectionSet(ref set) => set.span(),
+            OperationDefinition::Query(ref q) => q.span(),
+            OperationDefinition::Mutation(ref m) => m.span(),
+            OperationDefinition::Subscription(ref s) => s.span(),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Spanned for Query<'a, T> {
+    fn span(&self) -> (Pos, Pos) {
+        (self.position, self.selection_set.span().1)
+    }
+}
+
+impl<'a, T: Text<'a>> Spanned for Mutation<'a, T> {
+    fn span(&self) -> (Pos, Pos) {
+        (self.position, self.selection_set.span().1)
+    }
+}
+
+impl<'a, T: Text<'a>> Spanned for Subscription<'a, T> {
+    fn span(&self) -> (Pos, Pos) {
+        (self.position, self.selection_set.span().1)
+    }
+}
+
+impl<'a, T: Text<'a>> Spanned for FragmentDefinition<'a, T> {
+    fn span(&self) -> (Pos, Pos) {
+        (self.position, self.selection_set.span().1)
+    }
+}
+
+impl<'a, T: Text<'a>> Spanned for VariableDefinition<'a, T> {
+    fn span(&self) -> (Pos, Pos) {
+        (self.position, advance(self.position, &self.to_string()))
+    }
+}
+
+impl<'a, T: Text<'a>> Spanned for SelectionSet<'a, T> {
+    /// The range from `{` to `}` inclusive
+    ///
+    /// Fields without a selection set have an empty one, which has an
+    /// empty range at the start of the field.
+    fn span(&self) -> (Pos, Pos) {
+        if self.items.is_empty() {
+            return self.span;
+        }
+        let (start, end) = self.span;
+        (start, Pos {
+            line: end.line,
+            column: end.column + 1,
+            offset: end.offset + 1,
+        })
+    }
+}
+
+impl<'a, T: Text<'a>> Spanned for Selection<'a, T> {
+    fn span(&self) -> (Pos, Pos) {
+        match *self {
+            Selection::Field(ref field) => field.span(),
+            Selection::FragmentSpread(ref spread) => spread.span(),
+            Selection::InlineFragment(ref frag) => frag.span(),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Spanned for Field<'a, T> {
+    fn span(&self) -> (Pos, Pos) {
+        if !self.selection_set.items.is_empty() {
+            return (self.position, self.selection_set.span().1);
+        }
+        let mut text = String::new();
+        if let Some(ref alias) = self.alias {
+            text.push_str(alias.as_ref());
+            text.push_str(": ");
+        }
+        text.push_str(self.name.as_ref());
+        if !self.arguments.is_empty() {
+            let arguments = self.arguments.iter()
+                .map(|(name, value)| format!("{}: {}", name.as_ref(), value))
+                .collect::<Vec<_>>();
+            text.push('(');
+            text.push_str(&arguments.join(", "));
+            text.push(')');
+        }
+        directives_text(&mut text, &self.directives);
+        (self.position, advance(self.position, &text))
+    }
+}
+
+impl<'a, T: Text<'a>> Spanned for FragmentSpread<'a, T> {
+    fn span(&self) -> (Pos, Pos) {
+        let mut text = self.fragment_name.as_ref().to_string();
+        directives_text(&mut text, &self.directives);
+        (self.position, advance(self.position, &text))
+    }
+}
+
+impl<'a, T: Text<'a>> Spanned for InlineFragment<'a, T> {
+    fn span(&self) -> (Pos, Pos) {
+        (self.position, self.selection_set.span().1)
+    }
+}
+
+impl<'a, T: Text<'a>> Spanned for Directive<'a, T> {
+    fn span(&self) -> (Pos, Pos) {
+        (self.position, advance(self.position, &self.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Spanned;
+    use crate::position::Pos;
+    use crate::query::{parse_query, Definition, OperationDefinition};
+    use crate::query::{Document, Selection};
+
+    fn pos(line: usize, column: usize) -> Pos {
+        Pos::new(line, column)
+    }
+
+    fn items<'a, 'b>(doc: &'b Document<'a, &'a str>)
+        -> &'b [Selection<'a, &'a str>]
+    {
+        match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::Query(ref q)) => {
+                &q.selection_set.items
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn field_span() {
+        let doc = parse_query::<&str>("query {
+  user: node(id: 1, tags: [\"a\"]) @skip(if: false)
+  node { id }
+  name
+}").unwrap();
+        let items = items(&doc);
+        assert_eq!(items[0].span(), (pos(2, 3), pos(2, 50)));
+        assert_eq!(items[1].span(), (pos(3, 3), pos(3, 14)));
+        assert_eq!(items[2].span(), (pos(4, 3), pos(4, 7)));
+        assert_eq!(doc.span(), (pos(1, 1), pos(5, 2)));
+    }
+
+    #[test]
+    fn fragment_spread_span() {
+        let doc = parse_query::<&str>("query {
+  ...Friends @include(if: $all)
+  ... on User { id }
+}").unwrap();
+        let items = items(&doc);
+        assert_eq!(items[0].span(), (pos(2, 6), pos(2, 32)));
+        assert_eq!(items[1].span(), (pos(3, 7), pos(3, 21)));
+        if let Selection::FragmentSpread(ref spread) = items[0] {
+            assert_eq!(spread.directives[0].span(), (pos(2, 14), pos(2, 32)));
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! Parsing of sources which concatenate many independent definitions
+//!
+use std::marker::PhantomData;
+
+use combine::{parser, Parser, Positioned, StreamOnce};
+use combine::stream::Resetable;
+
+use crate::position::{advance, Pos};
+use crate::query::ast::*;
+use crate::query::error::ParseError;
+use crate::query::grammar::definition;
+use crate::tokenizer::{Kind, TokenStream};
+
+
+/// A definition read by `parse_query_stream`
+#[derive(Debug)]
+pub struct StreamItem<'a, S: Text<'a>> {
+    /// Position of the first token of the definition
+    pub start: Pos,
+    /// Position right after the last token of the definition
+    pub end: Pos,
+    /// Source text of the definition
+    pub text: &'a str,
+    /// A document with the single definition, or the error parsing it
+    pub result: Result<Document<'a, S>, ParseError>,
+}
+
+/// An iterator over the definitions of a source, see `parse_query_stream`
+#[derive(Debug)]
+pub struct QueryStream<'a, S> {
+    source: &'a str,
+    tokens: TokenStream<'a>,
+    phantom: PhantomData<S>,
+}
+
+/// Parses definitions of the source one by one
+///
+/// A definition which fails to parse doesn't stop the iteration. The
+/// invalid definition is assumed to end at the `}` closing its first `{`,
+/// or, if its brackets are unbalanced, right before the next line starting
+/// with `{`, `query`, `mutation`, `subscription` or `fragment`. Parsing
+/// continues after it.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::query::parse_query_stream;
+///
+/// let results = parse_query_stream::<&str>("{ a }\n{ b( }\n{ c }")
+///     .map(|item| item.result.is_ok())
+///     .collect::<Vec<_>>();
+/// assert_eq!(results, [true, false, true]);
+/// ```
+pub fn parse_query_stream<'a, S>(s: &'a str) -> QueryStream<'a, S>
+    where S: Text<'a>,
+{
+    QueryStream {
+        source: s,
+        tokens: TokenStream::new(s),
+        phantom: PhantomData,
+    }
+}
+
+/// Checks whether a token at the start of a line likely starts the next
+/// definition
+fn starts_definition(kind: Kind, value: &str) -> bool {
+    match kind {
+        Kind::Punctuator => value == "{",
+        Kind::Name => matches!(value,
+            "query" | "mutation" | "subscription" | "fragment"),
+        _ => false,
+    }
+}
+
+impl<'a, S: Text<'a>> QueryStream<'a, S> {
+    /// Skips tokens up to the `}` which closes all the brackets opened
+    /// before it, returns the position after that token
+    ///
+    /// With `recover` set, also stops at the tokens which likely start
+    /// the next definition and skips lines with unreadable characters.
+    fn skip_definition(&mut self, recover: bool) -> Pos {
+        let mut end = self.tokens.position();
+        let mut depth = 0usize;
+        loop {
+            let checkpoint = self.tokens.checkpoint();
+            let position = self.tokens.position();
+            if position.offset == self.source.len() {
+                break;
+            }
+            let token = match self.tokens.uncons() {
+                Ok(token) => token,
+                Err(_) if recover => {
+                    let line = self.source[position.offset..].lines()
+                        .next().unwrap_or("");
+                    end = advance(position, line.trim_end());
+                    self.tokens.skip_line();
+                    continue;
+                }
+                Err(_) => break,
+            };
+            if recover && depth > 0 && position.column == 1
+                && starts_definition(token.kind, token.value)
+            {
+                self.tokens.reset(checkpoint);
+                break;
+            }
+            end = advance(position, token.value);
+            if token.kind == Kind::Punctuator {
+                match token.value {
+                    "{" | "(" | "[" => depth += 1,
+                    "}" | ")" | "]" => {
+                        depth = depth.saturating_sub(1);
+                        if depth == 0 && token.value == "}" {
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        end
+    }
+}
+
+impl<'a, S: Text<'a>> Iterator for QueryStream<'a, S> {
+    type Item = StreamItem<'a, S>;
+
+    fn next(&mut self) -> Option<StreamItem<'a, S>> {
+        let start = self.tokens.position();
+        if start.offset == self.source.len() {
+            return None;
+        }
+        let checkpoint = self.tokens.checkpoint();
+        let result = parser(definition)
+            .parse_stream(&mut self.tokens)
+            .map(|(def, _)| Document { definitions: vec![def] })
+            .map_err(|e| ParseError::from(e.into_inner().error));
+        // a valid definition ends at the `}` closing all the brackets, so
+        // skipping it again finds its end
+        self.tokens.reset(checkpoint);
+        let end = self.skip_definition(result.is_err());
+        Some(StreamItem {
+            start,
+            end,
+            text: &self.source[start.offset..end.offset],
+            result,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_query_stream;
+    use crate::position::Pos;
+
+    #[test]
+    fn invalid_middle_operation() {
+        let source = "\
+query A { user { id } }
+
+query B { user(id: ) { id }
+
+# comment
+mutation C { logout }
+";
+        let items = parse_query_stream::<&str>(source).collect::<Vec<_>>();
+        assert_eq!(items.len(), 3);
+        assert!(items[0].result.is_ok());
+        assert!(items[1].result.is_err());
+        assert!(items[2].result.is_ok());
+        let spans = items.iter()
+            .map(|item| (item.start, item.end, item.text))
+            .collect::<Vec<_>>();
+        assert_eq!(spans, [
+            (Pos { line: 1, column: 1, offset: 0 },
+             Pos { line: 1, column: 24, offset: 23 },
+             "query A { user { id } }"),
+            (Pos { line: 3, column: 1, offset: 25 },
+             Pos { line: 3, column: 28, offset: 52 },
+             "query B { user(id: ) { id }"),
+            (Pos { line: 6, column: 1, offset: 64 },
+             Pos { line: 6, column: 22, offset: 85 },
+             "mutation C { logout }"),
+        ]);
+        assert_eq!(items[2].result.as_ref().unwrap().to_string(),
+                   "mutation C {\n  logout\n}\n");
+    }
+
+    #[test]
+    fn unreadable_characters() {
+        let source = "{ a ~ }\n{ b }\nquery { c ~\n{ d }";
+        let results = parse_query_stream::<&str>(source)
+            .map(|item| (item.text, item.result.is_ok()))
+            .collect::<Vec<_>>();
+        assert_eq!(results, [
+            ("{ a ~ }", false),
+            ("{ b }", true),
+            ("query { c ~", false),
+            ("{ d }", true),
+        ]);
+    }
+}
@@ -0,0 +1,170 @@
+//! Fragments and variables used by operations
+//!
+use std::collections::HashSet;
+
+use crate::query::ast::*;
+use crate::query::fragments::collect_spreads;
+
+
+/// Returns fragments spread by the operation, directly or through other
+/// fragments, in the order of the document
+///
+/// Spreads of fragments which aren't defined in `doc` are ignored.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::query::{parse_query, used_fragments};
+///
+/// let doc = parse_query::<&str>("
+///     query Q { user { ...A } }
+///     fragment B on User { id }
+///     fragment A on User { ...B }
+///     fragment C on User { name }
+/// ").unwrap();
+/// let op = doc.operation(Some("Q")).unwrap();
+/// let names = used_fragments(op, &doc).iter()
+///     .map(|frag| frag.name)
+///     .collect::<Vec<_>>();
+/// assert_eq!(names, ["B", "A"]);
+/// ```
+pub fn used_fragments<'d, 'a, T>(op: &'d OperationDefinition<'a, T>,
+    doc: &'d Document<'a, T>)
+    -> Vec<&'d FragmentDefinition<'a, T>>
+    where T: Text<'a>,
+{
+    let mut used = HashSet::new();
+    let mut queue = Vec::new();
+    collect_spreads(op.selection_set(), &mut queue);
+    while let Some(name) = queue.pop() {
+        if !used.insert(name.as_ref()) {
+            continue;
+        }
+        if let Some(frag) = doc.fragment(name.as_ref()) {
+            collect_spreads(&frag.selection_set, &mut queue);
+        }
+    }
+    doc.fragments()
+        .filter(|frag| used.contains(frag.name.as_ref()))
+        .collect()
+}
+
+/// Returns names of variables used by the operation, including the ones
+/// used in the fragments it spreads
+///
+/// Each name is returned once, in the order of first use: the operation
+/// itself first, then the fragments in the order of the document.
+pub fn used_variables<'d, 'a, T>(op: &'d OperationDefinition<'a, T>,
+    doc: &'d Document<'a, T>)
+    -> Vec<&'d T::Value>
+    where T: Text<'a>,
+{
+    let mut names = Vec::new();
+    let directives = match *op {
+        OperationDefinition::SelectionSet(_) => &[][..],
+        OperationDefinition::Query(ref q) => &q.directives[..],
+        OperationDefinition::Mutation(ref m) => &m.directives[..],
+        OperationDefinition::Subscription(ref s) => &s.directives[..],
+    };
+    directive_variables(directives, &mut names);
+    selection_set_variables(op.selection_set(), &mut names);
+    for frag in used_fragments(op, doc) {
+        directive_variables(&frag.directives, &mut names);
+        selection_set_variables(&frag.selection_set, &mut names);
+    }
+    names
+}
+
+fn selection_set_variables<'d, 'a, T>(selection_set: &'d SelectionSet<'a, T>,
+    names: &mut Vec<&'d T::Value>)
+    where T: Text<'a>,
+{
+    for item in &selection_set.items {
+        match *item {
+            Selection::Field(ref field) => {
+                for (_, value) in &field.arguments {
+                    value_variables(value, names);
+                }
+                directive_variables(&field.directives, names);
+                selection_set_variables(&field.selection_set, names);
+            }
+            Selection::FragmentSpread(ref spread) => {
+                directive_variables(&spread.directives, names);
+            }
+            Selection::InlineFragment(ref frag) => {
+                directive_variables(&frag.directives, names);
+                selection_set_variables(&frag.selection_set, names);
+            }
+        }
+    }
+}
+
+fn directive_variables<'d, 'a, T>(directives: &'d [Directive<'a, T>],
+    names: &mut Vec<&'d T::Value>)
+    where T: Text<'a>,
+{
+    for dir in directives {
+        for (_, value) in &dir.arguments {
+            value_variables(value, names);
+        }
+    }
+}
+
+fn value_variables<'d, 'a, T>(value: &'d Value<'a, T>,
+    names: &mut Vec<&'d T::Value>)
+    where T: Text<'a>,
+{
+    match *value {
+        Value::Variable(ref name) if !names.contains(&name) => {
+            names.push(name);
+        }
+        Value::List(ref items) => {
+            for item in items {
+                value_variables(item, names);
+            }
+        }
+        Value::Object(ref fields) => {
+            for value in fields.values() {
+                value_variables(value, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{used_fragments, used_variables};
+    use crate::query::parse_query;
+
+    const DOCUMENT: &str = "
+        query Q($id: ID, $first: Int) @cached(ttl: $ttl) {
+            user(id: $id) { ...User ... @include(if: $more) { bio } }
+        }
+        fragment Unused on User { posts(first: $unused) }
+        fragment Friends on User { friends(first: $first) { ...User } }
+        fragment User on User {
+            name ...Friends
+            avatar(size: {width: $size, height: [$size, $id]})
+        }
+        { viewer { ...Missing } }
+    ";
+
+    #[test]
+    fn fragments() {
+        let doc = parse_query::<&str>(DOCUMENT).unwrap();
+        let names = |op| used_fragments(op, &doc).iter()
+            .map(|frag| frag.name)
+            .collect::<Vec<_>>();
+        let ops = doc.operations().collect::<Vec<_>>();
+        assert_eq!(names(ops[0]), ["Friends", "User"]);
+        assert!(names(ops[1]).is_empty());
+    }
+
+    #[test]
+    fn variables() {
+        let doc = parse_query::<&str>(DOCUMENT).unwrap();
+        let op = doc.operation(Some("Q")).unwrap();
+        assert_eq!(used_variables(op, &doc),
+                   [&"ttl", &"id", &"more", &"first", &"size"]);
+    }
+}
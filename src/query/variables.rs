@@ -0,0 +1,380 @@
+//! Substitution of variable values into query documents
+//!
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use thiserror::Error;
+
+use crate::position::Pos;
+use crate::query::ast::*;
+
+
+/// Error substituting variables: a non-null (or undefined) variable has
+/// neither a value nor a default value
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("variable ${name} is not provided and has no default value")]
+pub struct MissingVariable {
+    name: String,
+    position: Pos,
+}
+
+impl MissingVariable {
+    /// Returns the name of the variable (without the `$`)
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the position of the field or directive using the variable
+    pub fn position(&self) -> Pos {
+        self.position
+    }
+}
+
+/// Returns the document with all the variables replaced by their values
+///
+/// Every variable used in arguments of fields and directives (including
+/// the ones nested in lists and input objects) is replaced by its value
+/// from `vars` or, if there is none, by the default value from the
+/// variable definition. Arguments and input object fields set to a
+/// variable of a nullable type which has neither are removed, as if they
+/// were omitted, so they stay distinct from an explicit `null`; such
+/// variables in lists become `null`. Variable definitions are removed
+/// from operations.
+///
+/// Fragments are substituted with the same values as operations, so with
+/// multiple operations the variables of all of them share values.
+pub fn apply_variables<'a, T>(doc: &Document<'a, T>,
+    vars: &HashMap<String, Value<'a, T>>)
+    -> Result<Document<'a, T>, MissingVariable>
+    where T: Text<'a> + Clone,
+{
+    let mut defaults = HashMap::new();
+    let mut nullable = HashSet::new();
+    for def in &doc.definitions {
+        if let Definition::Operation(ref op) = *def {
+            for var in variable_definitions(op) {
+                if let Some(ref value) = var.default_value {
+                    defaults.entry(var.name.as_ref()).or_insert(value);
+                } else if !is_non_null(&var.var_type) {
+                    nullable.insert(var.name.as_ref());
+                }
+            }
+        }
+    }
+    let values = Values { vars, defaults, nullable };
+    let mut result = doc.clone();
+    for def in &mut result.definitions {
+        match *def {
+            Definition::Operation(ref mut op) => {
+                let (variable_definitions, directives, selection_set) =
+                    match *op {
+                        OperationDefinition::SelectionSet(ref mut set) => {
+                            values.apply_selection_set(set)?;
+                            continue;
+                        }
+                        OperationDefinition::Query(ref mut q) => (
+                            &mut q.variable_definitions,
+                            &mut q.directives,
+                            &mut q.selection_set,
+                        ),
+                        OperationDefinition::Mutation(ref mut m) => (
+                            &mut m.variable_definitions,
+                            &mut m.directives,
+                            &mut m.selection_set,
+                        ),
+                        OperationDefinition::Subscription(ref mut s) => (
+                            &mut s.variable_definitions,
+                            &mut s.directives,
+                            &mut s.selection_set,
+                        ),
+                    };
+                variable_definitions.clear();
+                values.apply_directives(directives)?;
+                values.apply_selection_set(selection_set)?;
+            }
+            Definition::Fragment(ref mut frag) => {
+                values.apply_directives(&mut frag.directives)?;
+                values.apply_selection_set(&mut frag.selection_set)?;
+            }
+        }
+    }
+    Ok(result)
+}
+
+pub(crate) fn variable_definitions<'b, 'a, T>(op: &'b OperationDefinition<'a, T>)
+    -> &'b [VariableDefinition<'a, T>]
+    where T: Text<'a>,
+{
+    match *op {
+        OperationDefinition::SelectionSet(_) => &[],
+        OperationDefinition::Query(ref q) => &q.variable_definitions,
+        OperationDefinition::Mutation(ref m) => &m.variable_definitions,
+        OperationDefinition::Subscription(ref s) => &s.variable_definitions,
+    }
+}
+
+/// Returns the declared types of the variables of the operation, by name
+pub fn extract_variables<'b, 'a, T>(op: &'b OperationDefinition<'a, T>)
+    -> HashMap<&'b T::Value, &'b Type<'a, T>>
+    where T: Text<'a>,
+          T::Value: Hash,
+{
+    variable_definitions(op).iter()
+        .map(|var| (&var.name, &var.var_type))
+        .collect()
+}
+
+/// Returns the default values of the variables of the operation, by name
+///
+/// Variables declared without a default value map to `None`.
+pub fn variable_default_values<'b, 'a, T>(op: &'b OperationDefinition<'a, T>)
+    -> HashMap<&'b T::Value, Option<&'b Value<'a, T>>>
+    where T: Text<'a>,
+          T::Value: Hash,
+{
+    variable_definitions(op).iter()
+        .map(|var| (&var.name, var.default_value.as_ref()))
+        .collect()
+}
+
+struct Values<'v, 'a, T: Text<'a>> {
+    vars: &'v HashMap<String, Value<'a, T>>,
+    defaults: HashMap<&'v str, &'v Value<'a, T>>,
+    nullable: HashSet<&'v str>,
+}
+
+impl<'v, 'a, T> Values<'v, 'a, T>
+    where T: Text<'a> + Clone,
+{
+    fn apply_selection_set(&self, selection_set: &mut SelectionSet<'a, T>)
+        -> Result<(), MissingVariable>
+    {
+        for item in &mut selection_set.items {
+            match *item {
+                Selection::Field(ref mut field) => {
+                    self.apply_arguments(&mut field.arguments,
+                                         field.position)?;
+                    self.apply_directives(&mut field.directives)?;
+                    self.apply_selection_set(&mut field.selection_set)?;
+                }
+                Selection::FragmentSpread(ref mut spread) => {
+                    self.apply_directives(&mut spread.directives)?;
+                }
+                Selection::InlineFragment(ref mut frag) => {
+                    self.apply_directives(&mut frag.directives)?;
+                    self.apply_selection_set(&mut frag.selection_set)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_directives(&self, directives: &mut [Directive<'a, T>])
+        -> Result<(), MissingVariable>
+    {
+        for dir in directives {
+            self.apply_arguments(&mut dir.arguments, dir.position)?;
+        }
+        Ok(())
+    }
+
+    fn apply_arguments(&self,
+        arguments: &mut Vec<(T::Value, Value<'a, T>)>,
+        position: Pos)
+        -> Result<(), MissingVariable>
+    {
+        let mut i = 0;
+        while i < arguments.len() {
+            if self.apply_value(&mut arguments[i].1, position)? {
+                i += 1;
+            } else {
+                arguments.remove(i);
+            }
+        }
+        Ok(())
+    }
+
+    /// Substitutes the variables in the value, returns `false` if the
+    /// value is a variable which has no value, so it should be omitted
+    fn apply_value(&self, value: &mut Value<'a, T>, position: Pos)
+        -> Result<bool, MissingVariable>
+    {
+        match *value {
+            Value::Variable(ref name) => {
+                let name = name.as_ref();
+                let new_value = match self.vars.get(name)
+                    .or_else(|| self.defaults.get(name).copied())
+                {
+                    Some(new_value) => new_value.clone(),
+                    None if self.nullable.contains(name) => return Ok(false),
+                    None => return Err(MissingVariable {
+                        name: name.to_string(),
+                        position,
+                    }),
+                };
+                *value = new_value;
+            }
+            Value::List(ref mut items) => {
+                for item in items {
+                    if !self.apply_value(item, position)? {
+                        *item = Value::Null;
+                    }
+                }
+            }
+            Value::Object(ref mut fields) => {
+                let mut omitted = Vec::new();
+                for (name, value) in fields.iter_mut() {
+                    if !self.apply_value(value, position)? {
+                        omitted.push(name.clone());
+                    }
+                }
+                fields.retain(|name, _| !omitted.contains(name));
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::{apply_variables, MissingVariable};
+    use super::{extract_variables, variable_default_values};
+    use crate::position::Pos;
+    use crate::query::{parse_query, Definition, Type, Value};
+
+    const QUERY: &str = "
+        query Users($limit: Int = 10, $role: Role!, $tags: [String]) {
+            users(limit: $limit, filter: {role: $role, tags: $tags}) {
+                name @include(if: $verbose)
+                ...Friends
+            }
+        }
+        fragment Friends on User { friends(first: $limit) { name } }
+    ";
+
+    #[test]
+    fn provided_and_defaulted() {
+        let doc = parse_query::<String>(QUERY).unwrap();
+        let mut vars = HashMap::new();
+        vars.insert("role".to_string(), Value::Enum("ADMIN".to_string()));
+        vars.insert("tags".to_string(), Value::List(vec![
+            Value::String("a".into()),
+        ]));
+        vars.insert("verbose".to_string(), Value::Boolean(false));
+        let expected = parse_query::<String>("
+            query Users {
+                users(limit: 10, filter: {role: ADMIN, tags: [\"a\"]}) {
+                    name @include(if: false)
+                    ...Friends
+                }
+            }
+            fragment Friends on User { friends(first: 10) { name } }
+        ").unwrap();
+        assert_eq!(apply_variables(&doc, &vars).unwrap().to_string(),
+                   expected.to_string());
+
+        vars.insert("limit".to_string(), Value::Int(5.into()));
+        let result = apply_variables(&doc, &vars).unwrap().to_string();
+        assert!(result.contains("users(limit: 5,"));
+        assert!(result.contains("friends(first: 5)"));
+    }
+
+    #[test]
+    fn missing_variable() {
+        let doc = parse_query::<String>(QUERY).unwrap();
+        let mut vars = HashMap::new();
+        vars.insert("tags".to_string(), Value::Null);
+        vars.insert("verbose".to_string(), Value::Boolean(true));
+        let err = apply_variables(&doc, &vars).unwrap_err();
+        assert_eq!(err, MissingVariable {
+            name: "role".into(),
+            position: Pos::new(3, 13),
+        });
+        assert_eq!(err.name(), "role");
+        assert_eq!(err.position(), Pos::new(3, 13));
+        assert_eq!(err.to_string(),
+                   "variable $role is not provided and has no default value");
+    }
+
+    #[test]
+    fn nested_input_objects() {
+        let doc = parse_query::<String>("
+            mutation Create($name: String!, $tags: [String], $nick: String,
+                            $public: Boolean = true) {
+                create(input: {
+                    user: {name: $name, nick: $nick, tags: [$name, $tags]},
+                    settings: {public: $public},
+                }) { id }
+            }
+        ").unwrap();
+        let mut vars = HashMap::new();
+        vars.insert("name".to_string(), Value::String("Ann".into()));
+        let expected = parse_query::<String>("
+            mutation Create {
+                create(input: {
+                    user: {name: \"Ann\", tags: [\"Ann\", null]},
+                    settings: {public: true},
+                }) { id }
+            }
+        ").unwrap();
+        assert_eq!(apply_variables(&doc, &vars).unwrap().to_string(),
+                   expected.to_string());
+
+        let err = apply_variables(&doc, &HashMap::new()).unwrap_err();
+        assert_eq!(err.name(), "name");
+        assert_eq!(err.position(), Pos::new(4, 17));
+    }
+
+    #[test]
+    fn declared_variables() {
+        let doc = parse_query::<String>(QUERY).unwrap();
+        let op = match doc.definitions[0] {
+            Definition::Operation(ref op) => op,
+            _ => unreachable!(),
+        };
+        let limit = "limit".to_string();
+        let role = "role".to_string();
+        let tags = "tags".to_string();
+        let types = extract_variables(op);
+        assert_eq!(types.len(), 3);
+        assert_eq!(types[&limit].to_string(), "Int");
+        assert!(matches!(types[&role], Type::NonNullType(_)));
+        assert_eq!(types[&tags].to_string(), "[String]");
+        let defaults = variable_default_values(op);
+        assert_eq!(defaults.len(), 3);
+        assert_eq!(defaults[&limit], Some(&Value::Int(10.into())));
+        assert_eq!(defaults[&role], None);
+        assert_eq!(defaults[&tags], None);
+
+        let doc = parse_query::<String>("{ a }").unwrap();
+        let op = match doc.definitions[0] {
+            Definition::Operation(ref op) => op,
+            _ => unreachable!(),
+        };
+        assert!(extract_variables(op).is_empty());
+        assert!(variable_default_values(op).is_empty());
+    }
+
+    #[test]
+    fn explicit_null() {
+        let doc = parse_query::<String>("
+            query ($a: Int, $b: Int, $c: Int = 1) {
+                f(x: null, y: $a, z: $b, w: $c, v: {a: $a, b: null})
+                g(list: [$a, null]) @skip(if: $b)
+            }
+        ").unwrap();
+        let mut vars = HashMap::new();
+        vars.insert("b".to_string(), Value::Null);
+        let expected = parse_query::<String>("
+            query {
+                f(x: null, z: null, w: 1, v: {b: null})
+                g(list: [null, null]) @skip(if: null)
+            }
+        ").unwrap();
+        assert_eq!(apply_variables(&doc, &vars).unwrap().to_string(),
+                   expected.to_string());
+    }
+}
@@ -0,0 +1,499 @@
+//! Depth-first traversal of the query AST
+//!
+//! The traversal order follows the order of the source text, which is also
+//! the order used by graphql-js: an operation visits its variable
+//! definitions, then its directives, then its selection set; a field visits
+//! its arguments, then its directives, then its selection set.
+//!
+use crate::query::ast::*;
+use crate::query::path::PathKey;
+use crate::traverse::{walk, Visitable, Walker};
+
+
+/// A reference to any node of the query AST that can be visited
+#[derive(Debug)]
+pub enum QueryAstNode<'ast, T: Text<'ast>> {
+    Document(&'ast Document<'ast, T>),
+    OperationDefinition(&'ast OperationDefinition<'ast, T>),
+    FragmentDefinition(&'ast FragmentDefinition<'ast, T>),
+    VariableDefinition(&'ast VariableDefinition<'ast, T>),
+    SelectionSet(&'ast SelectionSet<'ast, T>),
+    Field(&'ast Field<'ast, T>),
+    FragmentSpread(&'ast FragmentSpread<'ast, T>),
+    InlineFragment(&'ast InlineFragment<'ast, T>),
+    Directive(&'ast Directive<'ast, T>),
+    /// A `name: value` pair of field or directive arguments
+    Argument(&'ast T::Value, &'ast Value<'ast, T>),
+    /// A `name: value` pair inside of an input object literal
+    ObjectField(&'ast T::Value, &'ast Value<'ast, T>),
+    Value(&'ast Value<'ast, T>),
+}
+
+impl<'ast, T: Text<'ast>> Clone for QueryAstNode<'ast, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'ast, T: Text<'ast>> Copy for QueryAstNode<'ast, T> {}
+
+/// A visitor which is called for every node of the query AST
+///
+/// `enter` is called before children of the node are visited and `leave`
+/// is called after all of them have been visited.
+pub trait QueryVisitor<'ast, T: Text<'ast>> {
+    fn enter(&mut self, _node: QueryAstNode<'ast, T>) {}
+    fn leave(&mut self, _node: QueryAstNode<'ast, T>) {}
+}
+
+impl<'ast, T, V> QueryVisitor<'ast, T> for &mut V
+    where T: Text<'ast>,
+          V: QueryVisitor<'ast, T> + ?Sized,
+{
+    fn enter(&mut self, node: QueryAstNode<'ast, T>) {
+        (**self).enter(node)
+    }
+    fn leave(&mut self, node: QueryAstNode<'ast, T>) {
+        (**self).leave(node)
+    }
+}
+
+impl<'ast, T, V> QueryVisitor<'ast, T> for Box<V>
+    where T: Text<'ast>,
+          V: QueryVisitor<'ast, T> + ?Sized,
+{
+    fn enter(&mut self, node: QueryAstNode<'ast, T>) {
+        (**self).enter(node)
+    }
+    fn leave(&mut self, node: QueryAstNode<'ast, T>) {
+        (**self).leave(node)
+    }
+}
+
+/// Runs multiple visitors in a single traversal
+///
+/// Every node is passed to all of the visitors in the order they were
+/// added, both on `enter` and on `leave`.
+#[derive(Debug, Clone, Default)]
+pub struct ParallelVisitor<V> {
+    visitors: Vec<V>,
+}
+
+impl<V> ParallelVisitor<V> {
+    pub fn new() -> ParallelVisitor<V> {
+        ParallelVisitor { visitors: Vec::new() }
+    }
+
+    /// Adds a visitor which will be run after all previously added ones
+    pub fn push(&mut self, visitor: V) {
+        self.visitors.push(visitor);
+    }
+
+    pub fn visitors(&self) -> &[V] {
+        &self.visitors
+    }
+
+    pub fn visitors_mut(&mut self) -> &mut [V] {
+        &mut self.visitors
+    }
+
+    pub fn into_inner(self) -> Vec<V> {
+        self.visitors
+    }
+}
+
+impl<V> From<Vec<V>> for ParallelVisitor<V> {
+    fn from(visitors: Vec<V>) -> Self {
+        ParallelVisitor { visitors }
+    }
+}
+
+impl<'ast, T, V> QueryVisitor<'ast, T> for ParallelVisitor<V>
+    where T: Text<'ast>,
+          V: QueryVisitor<'ast, T>,
+{
+    fn enter(&mut self, node: QueryAstNode<'ast, T>) {
+        for visitor in &mut self.visitors {
+            visitor.enter(node);
+        }
+    }
+    fn leave(&mut self, node: QueryAstNode<'ast, T>) {
+        for visitor in &mut self.visitors {
+            visitor.leave(node);
+        }
+    }
+}
+
+impl<'ast, T, V> Walker<QueryAstNode<'ast, T>> for V
+    where T: Text<'ast>,
+          V: QueryVisitor<'ast, T> + ?Sized,
+{
+    fn enter(&mut self, node: QueryAstNode<'ast, T>) {
+        QueryVisitor::enter(self, node)
+    }
+    fn leave(&mut self, node: QueryAstNode<'ast, T>) {
+        QueryVisitor::leave(self, node)
+    }
+}
+
+impl<'ast, T: Text<'ast>> QueryAstNode<'ast, T> {
+    /// Calls `f` for every child of the node in the order of the source,
+    /// along with the key of the child in the node
+    pub(crate) fn for_each_keyed_child(self,
+        f: &mut dyn FnMut(PathKey, QueryAstNode<'ast, T>))
+    {
+        match self {
+            QueryAstNode::Document(doc) => {
+                for (i, def) in doc.definitions.iter().enumerate() {
+                    f(PathKey::item("definitions", i), match *def {
+                        Definition::Operation(ref op) => {
+                            QueryAstNode::OperationDefinition(op)
+                        }
+                        Definition::Fragment(ref frag) => {
+                            QueryAstNode::FragmentDefinition(frag)
+                        }
+                    });
+                }
+            }
+            QueryAstNode::OperationDefinition(op) => {
+                let (vars, dirs, sel) = match *op {
+                    OperationDefinition::SelectionSet(ref sel) => {
+                        (&[][..], &[][..], sel)
+                    }
+                    OperationDefinition::Query(ref q) => (
+                        &q.variable_definitions[..], &q.directives[..],
+                        &q.selection_set),
+                    OperationDefinition::Mutation(ref m) => (
+                        &m.variable_definitions[..], &m.directives[..],
+                        &m.selection_set),
+                    OperationDefinition::Subscription(ref s) => (
+                        &s.variable_definitions[..], &s.directives[..],
+                        &s.selection_set),
+                };
+                variable_definitions(vars, f);
+                directives(dirs, f);
+                f(PathKey::field("selectionSet"),
+                  QueryAstNode::SelectionSet(sel));
+            }
+            QueryAstNode::FragmentDefinition(frag) => {
+                #[cfg(feature = "fragment-arguments")]
+                variable_definitions(&frag.variable_definitions, f);
+                directives(&frag.directives, f);
+                f(PathKey::field("selectionSet"),
+                  QueryAstNode::SelectionSet(&frag.selection_set));
+            }
+            QueryAstNode::VariableDefinition(var) => {
+                if let Some(ref value) = var.default_value {
+                    f(PathKey::field("defaultValue"),
+                      QueryAstNode::Value(value));
+                }
+            }
+            QueryAstNode::SelectionSet(sel) => {
+                for (i, item) in sel.items.iter().enumerate() {
+                    f(PathKey::item("selections", i), match *item {
+                        Selection::Field(ref field) => {
+                            QueryAstNode::Field(field)
+                        }
+                        Selection::FragmentSpread(ref spread) => {
+                            QueryAstNode::FragmentSpread(spread)
+                        }
+                        Selection::InlineFragment(ref frag) => {
+                            QueryAstNode::InlineFragment(frag)
+                        }
+                    });
+                }
+            }
+            QueryAstNode::Field(field) => {
+                arguments(&field.arguments, f);
+                directives(&field.directives, f);
+                if !field.selection_set.items.is_empty() {
+                    f(PathKey::field("selectionSet"),
+                      QueryAstNode::SelectionSet(&field.selection_set));
+                }
+            }
+            QueryAstNode::FragmentSpread(spread) => {
+                #[cfg(feature = "fragment-arguments")]
+                arguments(&spread.arguments, f);
+                directives(&spread.directives, f);
+            }
+            QueryAstNode::InlineFragment(frag) => {
+                directives(&frag.directives, f);
+                f(PathKey::field("selectionSet"),
+                  QueryAstNode::SelectionSet(&frag.selection_set));
+            }
+            QueryAstNode::Directive(dir) => arguments(&dir.arguments, f),
+            QueryAstNode::Argument(_, value)
+            | QueryAstNode::ObjectField(_, value) => {
+                f(PathKey::field("value"), QueryAstNode::Value(value));
+            }
+            QueryAstNode::Value(value) => match *value {
+                Value::List(ref items) => {
+                    for (i, item) in items.iter().enumerate() {
+                        f(PathKey::item("values", i),
+                          QueryAstNode::Value(item));
+                    }
+                }
+                Value::Object(ref fields) => {
+                    for (i, (name, value)) in fields.iter().enumerate() {
+                        f(PathKey::item("fields", i),
+                          QueryAstNode::ObjectField(name, value));
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+}
+
+impl<'ast, T: Text<'ast>> Visitable for QueryAstNode<'ast, T> {
+    fn for_each_child(self, f: &mut dyn FnMut(Self)) {
+        self.for_each_keyed_child(&mut |_, child| f(child));
+    }
+}
+
+type KeyedChild<'f, 'ast, T> = dyn FnMut(PathKey, QueryAstNode<'ast, T>) + 'f;
+
+fn variable_definitions<'ast, T: Text<'ast>>(
+    vars: &'ast [VariableDefinition<'ast, T>], f: &mut KeyedChild<'_, 'ast, T>)
+{
+    for (i, var) in vars.iter().enumerate() {
+        f(PathKey::item("variableDefinitions", i),
+          QueryAstNode::VariableDefinition(var));
+    }
+}
+
+fn directives<'ast, T: Text<'ast>>(dirs: &'ast [Directive<'ast, T>],
+    f: &mut KeyedChild<'_, 'ast, T>)
+{
+    for (i, dir) in dirs.iter().enumerate() {
+        f(PathKey::item("directives", i), QueryAstNode::Directive(dir));
+    }
+}
+
+fn arguments<'ast, T: Text<'ast>>(args: &'ast [(T::Value, Value<'ast, T>)],
+    f: &mut KeyedChild<'_, 'ast, T>)
+{
+    for (i, (name, value)) in args.iter().enumerate() {
+        f(PathKey::item("arguments", i), QueryAstNode::Argument(name, value));
+    }
+}
+
+/// Visit every node of the document in depth-first order
+pub fn visit<'ast, T, V>(doc: &'ast Document<'ast, T>, visitor: &mut V)
+    where T: Text<'ast>,
+          V: QueryVisitor<'ast, T> + ?Sized,
+{
+    walk(QueryAstNode::Document(doc), visitor);
+}
+
+/// A visitor which collects directives together with the nodes they are
+/// applied to
+///
+/// The nodes are operations, fragment definitions, fields, fragment
+/// spreads and inline fragments.
+#[derive(Debug)]
+pub struct DirectiveCollector<'ast, T: Text<'ast>> {
+    parents: Vec<QueryAstNode<'ast, T>>,
+    directives: Vec<(&'ast Directive<'ast, T>, QueryAstNode<'ast, T>)>,
+}
+
+impl<'ast, T: Text<'ast>> DirectiveCollector<'ast, T> {
+    pub fn new() -> DirectiveCollector<'ast, T> {
+        DirectiveCollector { parents: Vec::new(), directives: Vec::new() }
+    }
+
+    /// Returns the directives in the order of the document
+    pub fn into_directives(self)
+        -> Vec<(&'ast Directive<'ast, T>, QueryAstNode<'ast, T>)>
+    {
+        self.directives
+    }
+}
+
+impl<'ast, T: Text<'ast>> Default for DirectiveCollector<'ast, T> {
+    fn default() -> Self {
+        DirectiveCollector::new()
+    }
+}
+
+fn has_directives<'ast, T: Text<'ast>>(node: &QueryAstNode<'ast, T>)
+    -> bool
+{
+    matches!(*node,
+        QueryAstNode::OperationDefinition(_)
+        | QueryAstNode::FragmentDefinition(_)
+        | QueryAstNode::Field(_)
+        | QueryAstNode::FragmentSpread(_)
+        | QueryAstNode::InlineFragment(_))
+}
+
+impl<'ast, T: Text<'ast>> QueryVisitor<'ast, T>
+    for DirectiveCollector<'ast, T>
+{
+    fn enter(&mut self, node: QueryAstNode<'ast, T>) {
+        match node {
+            QueryAstNode::Directive(dir) => {
+                let parent = *self.parents.last()
+                    .expect("directives are applied to other nodes");
+                self.directives.push((dir, parent));
+            }
+            node if has_directives(&node) => self.parents.push(node),
+            _ => {}
+        }
+    }
+    fn leave(&mut self, node: QueryAstNode<'ast, T>) {
+        if has_directives(&node) {
+            self.parents.pop();
+        }
+    }
+}
+
+/// Returns every directive applied in the document, and the node it's
+/// applied to
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::query::{parse_query, collect_directives};
+/// use graphql_parser::query::QueryAstNode;
+///
+/// let doc = parse_query::<&str>("{ a @live }").unwrap();
+/// let directives = collect_directives(&doc);
+/// assert_eq!(directives[0].0.name, "live");
+/// assert!(matches!(directives[0].1, QueryAstNode::Field(f) if f.name == "a"));
+/// ```
+pub fn collect_directives<'ast, T>(doc: &'ast Document<'ast, T>)
+    -> Vec<(&'ast Directive<'ast, T>, QueryAstNode<'ast, T>)>
+    where T: Text<'ast>,
+{
+    let mut collector = DirectiveCollector::new();
+    visit(doc, &mut collector);
+    collector.into_directives()
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::{collect_directives, visit, QueryAstNode, QueryVisitor};
+    use crate::query::{parse_query, Text, Value, Directive, Document};
+    use crate::query::{Definition, OperationDefinition, FragmentDefinition};
+    use crate::query::{SelectionSet, Field, FragmentSpread, InlineFragment};
+
+    #[derive(Default)]
+    struct Trace(Vec<String>);
+
+    impl<'ast, T: Text<'ast>> QueryVisitor<'ast, T> for Trace {
+        fn enter(&mut self, node: QueryAstNode<'ast, T>) {
+            let name = match node {
+                QueryAstNode::Document(_) => "document".into(),
+                QueryAstNode::OperationDefinition(_) => "operation".into(),
+                QueryAstNode::FragmentDefinition(f) => {
+                    format!("fragment {}", f.name.as_ref())
+                }
+                QueryAstNode::VariableDefinition(v) => {
+                    format!("${}", v.name.as_ref())
+                }
+                QueryAstNode::SelectionSet(_) => "{".into(),
+                QueryAstNode::Field(f) => f.name.as_ref().to_string(),
+                QueryAstNode::FragmentSpread(s) => {
+                    format!("...{}", s.fragment_name.as_ref())
+                }
+                QueryAstNode::InlineFragment(_) => "...".into(),
+                QueryAstNode::Directive(d) => format!("@{}", d.name.as_ref()),
+                QueryAstNode::Argument(n, _) => format!("{}:", n.as_ref()),
+                QueryAstNode::ObjectField(n, _) => format!("{}:", n.as_ref()),
+                QueryAstNode::Value(v) => v.to_string(),
+            };
+            self.0.push(name);
+        }
+        fn leave(&mut self, node: QueryAstNode<'ast, T>) {
+            if let QueryAstNode::SelectionSet(_) = node {
+                self.0.push("}".into());
+            }
+        }
+    }
+
+    #[test]
+    fn visit_order() {
+        let doc = parse_query::<&str>("
+            query Q($x: Int = 1) @dir {
+                a(b: [{c: $x}]) @skip(if: false) { d }
+                ...F
+                ... on T { e }
+            }
+            fragment F on T { f }
+        ").unwrap();
+        let mut trace = Trace::default();
+        visit(&doc, &mut trace);
+        assert_eq!(trace.0, [
+            "document", "operation", "$x", "1", "@dir", "{",
+            "a", "b:", "[{c: $x}]", "{c: $x}", "c:", "$x",
+            "@skip", "if:", "false", "{", "d", "}",
+            "...F", "...", "{", "e", "}", "}",
+            "fragment F", "{", "f", "}",
+        ]);
+    }
+
+    #[test]
+    fn visit_built_document() {
+        let object = vec![("c", Value::Variable("x"))].into_iter()
+            .collect();
+        let doc = Document::<&str> {
+            definitions: vec![
+                Definition::Operation(OperationDefinition::query(
+                    SelectionSet::of(vec![
+                        Field::new("a")
+                            .argument("b", Value::List(vec![
+                                Value::Object(object)]))
+                            .directive(Directive::new("skip")
+                                .argument("if", Value::Boolean(false)))
+                            .selection(Field::new("d"))
+                            .into(),
+                        FragmentSpread::new("F").into(),
+                        InlineFragment::new(Some("T"), SelectionSet::of(
+                            vec![Field::new("e").into()])).into(),
+                    ]))),
+                Definition::Fragment(FragmentDefinition::new("F", "T",
+                    SelectionSet::of(vec![Field::new("f").into()]))),
+            ],
+        };
+        let mut trace = Trace::default();
+        visit(&doc, &mut trace);
+        assert_eq!(trace.0, [
+            "document", "operation", "{",
+            "a", "b:", "[{c: $x}]", "{c: $x}", "c:", "$x",
+            "@skip", "if:", "false", "{", "d", "}",
+            "...F", "...", "{", "e", "}", "}",
+            "fragment F", "{", "f", "}",
+        ]);
+    }
+
+    #[test]
+    fn directives() {
+        let doc = parse_query::<&str>("
+            query Q($show: Boolean!) @cached {
+                user @include(if: $show) {
+                    ...F @skip(if: false)
+                    ... on User { name @lowercase }
+                }
+            }
+            fragment F on User { id }
+        ").unwrap();
+        let found = collect_directives(&doc).into_iter()
+            .map(|(dir, node)| {
+                let parent = match node {
+                    QueryAstNode::OperationDefinition(_) => "operation",
+                    QueryAstNode::Field(f) => f.name,
+                    QueryAstNode::FragmentSpread(s) => s.fragment_name,
+                    _ => "other",
+                };
+                (dir.name, dir.position.line, parent)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(found, [
+            ("cached", 2, "operation"),
+            ("include", 3, "user"),
+            ("skip", 4, "F"),
+            ("lowercase", 5, "name"),
+        ]);
+    }
+}
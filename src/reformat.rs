@@ -0,0 +1,526 @@
+//! Formatting sources in the style of Prettier, keeping comments
+//!
+use crate::common::unquote_block_string;
+use crate::document::{parse_document, ParseError};
+use crate::format::is_block_string_compatible;
+use crate::lexer::{Lexer, Token, TokenKind};
+
+
+/// Keywords starting definitions of a document
+const DEFINITION_KEYWORDS: &[&str] = &[
+    "query", "mutation", "subscription", "fragment", "schema", "scalar",
+    "type", "interface", "union", "enum", "input", "directive", "extend",
+];
+
+/// Names which are followed by another name in the same definition
+const NAME_PREFIXES: &[&str] = &[
+    "query", "mutation", "subscription", "fragment", "scalar", "type",
+    "interface", "union", "enum", "input", "extend", "on", "implements",
+];
+
+/// Formats the source as Prettier does, keeping the comments
+///
+/// Unlike `Display` of the AST, this works on the tokens of the source, so
+/// comments stay where they are written: a comment at the end of a line
+/// stays at the end of that line and a comment on its own line keeps its
+/// own line. Everything else is written in a consistent way:
+///
+/// * definitions are separated by an empty line;
+/// * selections, fields, enum values and input fields go one per line,
+///   single empty lines between them are kept;
+/// * arguments, lists and input objects are written on a single line,
+///   unless they contain a comment or a description, in which case every
+///   item goes on its own line;
+/// * commas are written only between items on the same line and leading
+///   `|` and `&` are dropped;
+/// * block strings are reindented.
+///
+/// Long lines aren't wrapped. Formatting the output again doesn't change
+/// it. The source must be a valid document, with any kind of definitions.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::reformat;
+///
+/// let source = "query Q($id:ID){ # the user\n user(id:$id){name,\n\n\n id}}";
+/// assert_eq!(reformat(source).unwrap(), "\
+/// query Q($id: ID) { # the user
+///   user(id: $id) {
+///     name
+///
+///     id
+///   }
+/// }
+/// ");
+/// ```
+pub fn reformat(source: &str) -> Result<String, ParseError> {
+    parse_document::<&str>(source)?;
+    let tokens = Lexer::new(source)
+        .map(|token| token.expect("document is parsed"))
+        .collect::<Vec<_>>();
+    let mut printer = Printer {
+        multiline: multiline_brackets(&tokens),
+        out: String::with_capacity(source.len()),
+        frames: Vec::new(),
+        prev: None,
+        prev2: None,
+        description: false,
+        last_line: 0,
+        after_comment: false,
+    };
+    for (idx, token) in tokens.iter().enumerate() {
+        printer.token(idx, token);
+    }
+    printer.trim_end();
+    printer.out.push('\n');
+    Ok(printer.out)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Context {
+    /// Selection set or body of a type or schema definition
+    Block,
+    /// Arguments or variable definitions
+    Arguments,
+    Object,
+    List,
+}
+
+#[derive(Debug)]
+struct Frame {
+    context: Context,
+    multiline: bool,
+}
+
+struct Printer<'a> {
+    /// Whether the bracket at the index contains comments or descriptions
+    multiline: Vec<bool>,
+    out: String,
+    frames: Vec<Frame>,
+    prev: Option<Token<'a>>,
+    prev2: Option<Token<'a>>,
+    /// Whether the previous token is a description
+    description: bool,
+    /// Line of the source where the previous token or comment ends
+    last_line: usize,
+    /// Whether the line is ended by a comment
+    after_comment: bool,
+}
+
+/// Finds brackets which must be written on multiple lines because of
+/// comments or descriptions between them
+///
+/// The result is indexed as the tokens and is `true` at the opening
+/// brackets of such items.
+fn multiline_brackets(tokens: &[Token]) -> Vec<bool> {
+    let mut result = vec![false; tokens.len()];
+    let mut open = Vec::new();
+    let mut prev = None;
+    for (idx, token) in tokens.iter().enumerate() {
+        match (token.kind, token.value) {
+            (TokenKind::Comment, _) => {
+                if let Some(&top) = open.last() {
+                    result[top] = true;
+                }
+                continue;
+            }
+            (TokenKind::Punctuator, "{") | (TokenKind::Punctuator, "(")
+            | (TokenKind::Punctuator, "[") => open.push(idx),
+            (TokenKind::Punctuator, "}") | (TokenKind::Punctuator, ")")
+            | (TokenKind::Punctuator, "]") => {
+                let opening = open.pop().expect("brackets are balanced");
+                if let Some(&top) = open.last() {
+                    result[top] |= result[opening];
+                }
+            }
+            (TokenKind::String, _) | (TokenKind::BlockString, _) => {
+                let in_arguments = open.last()
+                    .is_some_and(|&top| tokens[top].value == "(");
+                if in_arguments && !matches!(prev, Some(":") | Some("=")) {
+                    result[*open.last().unwrap()] = true;
+                }
+            }
+            _ => {}
+        }
+        prev = Some(token.value);
+    }
+    result
+}
+
+fn is_punctuator(token: Option<&Token>, values: &[&str]) -> bool {
+    token.is_some_and(|token| {
+        token.kind == TokenKind::Punctuator && values.contains(&token.value)
+    })
+}
+
+fn is_name(token: Option<&Token>, names: &[&str]) -> bool {
+    token.is_some_and(|token| {
+        token.kind == TokenKind::Name && names.contains(&token.value)
+    })
+}
+
+/// Checks whether a value, a type or a directive may end at the token
+fn ends_value(token: Option<&Token>) -> bool {
+    match token {
+        Some(token) => match token.kind {
+            TokenKind::Punctuator => {
+                matches!(token.value, "]" | "}" | "!" | ")")
+            }
+            TokenKind::Comment => false,
+            _ => true,
+        },
+        None => false,
+    }
+}
+
+impl<'a> Printer<'a> {
+    fn context(&self) -> Option<Context> {
+        self.frames.last().map(|frame| frame.context)
+    }
+
+    fn level(&self) -> usize {
+        self.frames.iter().filter(|frame| frame.multiline).count()
+    }
+
+    fn trim_end(&mut self) {
+        let len = self.out.trim_end_matches(' ').len();
+        self.out.truncate(len);
+    }
+
+    fn newline(&mut self, empty_line: bool) {
+        self.trim_end();
+        self.out.push('\n');
+        if empty_line {
+            self.out.push('\n');
+        }
+        for _ in 0..self.level() {
+            self.out.push_str("  ");
+        }
+    }
+
+    fn after_opening(&self) -> bool {
+        is_punctuator(self.prev.as_ref(), &["{", "(", "["])
+    }
+
+    fn token(&mut self, idx: usize, token: &Token<'a>) {
+        let empty_line = token.span.0.line > self.last_line + 1;
+        if token.kind == TokenKind::Comment {
+            if self.out.is_empty() {
+            } else if token.span.0.line == self.last_line
+                && !self.after_comment
+            {
+                self.out.push(' ');
+            } else {
+                let empty_line = empty_line && !self.after_opening();
+                self.newline(empty_line);
+            }
+            self.out.push_str(token.value);
+            self.last_line = token.span.1.line;
+            self.after_comment = true;
+            return;
+        }
+        let prev = self.prev.as_ref();
+        if token.kind == TokenKind::Punctuator {
+            let leading_separator = match token.value {
+                "|" => is_punctuator(prev, &["="]) || is_name(prev, &["on"]),
+                "&" => is_name(prev, &["implements"]),
+                _ => false,
+            };
+            if leading_separator {
+                return;
+            }
+            if matches!(token.value, "}" | ")" | "]") {
+                self.close(token);
+                return;
+            }
+        }
+        let description = self.is_description(token);
+        self.separate(token, empty_line);
+        match token.kind {
+            TokenKind::BlockString => self.block_string(token.value),
+            _ => self.out.push_str(token.value),
+        }
+        if token.kind == TokenKind::Punctuator {
+            let context = match token.value {
+                "(" => Some(Context::Arguments),
+                "[" => Some(Context::List),
+                "{" if self.opens_object() => Some(Context::Object),
+                "{" => Some(Context::Block),
+                _ => None,
+            };
+            if let Some(context) = context {
+                let multiline = context == Context::Block
+                    || self.multiline[idx];
+                self.frames.push(Frame { context, multiline });
+            }
+        }
+        self.advance(token);
+        self.description = description;
+    }
+
+    fn advance(&mut self, token: &Token<'a>) {
+        self.prev2 = self.prev.take();
+        self.prev = Some(*token);
+        self.last_line = token.span.1.line;
+        self.after_comment = false;
+        self.description = false;
+    }
+
+    fn close(&mut self, token: &Token<'a>) {
+        let frame = self.frames.pop().expect("brackets are balanced");
+        if self.after_opening() {
+        } else if frame.multiline {
+            self.newline(false);
+        } else if frame.context == Context::Object {
+            self.out.push(' ');
+        }
+        self.out.push_str(token.value);
+        self.advance(token);
+    }
+
+    /// Checks whether the curly bracket at the current token starts an
+    /// input object value rather than a selection set or a block of
+    /// fields
+    fn opens_object(&self) -> bool {
+        let prev = self.prev.as_ref();
+        match self.context() {
+            Some(Context::List) => true,
+            Some(Context::Arguments) | Some(Context::Object) => {
+                is_punctuator(prev, &[":", "="])
+            }
+            _ => is_punctuator(prev, &["="]),
+        }
+    }
+
+    fn is_description(&self, token: &Token<'a>) -> bool {
+        matches!(token.kind, TokenKind::String | TokenKind::BlockString)
+            && matches!(self.context(), None | Some(Context::Block)
+                                      | Some(Context::Arguments))
+            && !is_punctuator(self.prev.as_ref(), &[":", "="])
+    }
+
+    /// Checks whether the token starts a definition or an item of the
+    /// current block or list
+    fn starts_item(&self, token: &Token<'a>) -> bool {
+        let prev = self.prev.as_ref();
+        let continues = is_punctuator(prev,
+            &[":", "@", "...", "=", "$", "|", "&"]);
+        match self.context() {
+            None => match token.kind {
+                TokenKind::String | TokenKind::BlockString => true,
+                TokenKind::Punctuator => {
+                    token.value == "{"
+                        && (prev.is_none() || is_punctuator(prev, &["}"]))
+                }
+                TokenKind::Name => {
+                    DEFINITION_KEYWORDS.contains(&token.value)
+                        && !continues
+                        && !self.description
+                        && !is_name(prev, NAME_PREFIXES)
+                }
+                _ => false,
+            },
+            Some(Context::Block) => {
+                let starts = matches!(token.kind,
+                    TokenKind::Name | TokenKind::String
+                    | TokenKind::BlockString)
+                    || token.value == "...";
+                let fragment_condition = is_name(prev, &["on"])
+                    && is_punctuator(self.prev2.as_ref(), &["..."]);
+                self.description
+                    || starts && !continues && !fragment_condition
+            }
+            Some(_) => {
+                let starts = token.kind != TokenKind::Punctuator
+                    || matches!(token.value, "$" | "[" | "{");
+                self.description || starts && ends_value(prev)
+            }
+        }
+    }
+
+    fn separate(&mut self, token: &Token<'a>, empty_line: bool) {
+        if self.out.is_empty() {
+            return;
+        }
+        let multiline = self.frames.last().is_none_or(|f| f.multiline);
+        let starts = self.starts_item(token);
+        if self.after_opening() {
+            if multiline {
+                self.newline(false);
+            } else if self.context() == Some(Context::Object) {
+                self.out.push(' ');
+            }
+        } else if self.context().is_none() && starts {
+            let attached = self.after_comment && !empty_line;
+            self.newline(!attached && !self.description);
+        } else if starts && multiline {
+            let empty_line = empty_line && !self.description;
+            self.newline(empty_line);
+        } else if starts {
+            self.out.push_str(", ");
+        } else if self.after_comment || self.description {
+            self.newline(false);
+        } else if self.spaced(token) {
+            self.out.push(' ');
+        }
+    }
+
+    /// Checks whether a space goes between the previous token and this
+    /// one on the same line
+    fn spaced(&self, token: &Token<'a>) -> bool {
+        let prev = self.prev.as_ref();
+        if token.kind == TokenKind::Punctuator
+            && matches!(token.value, ":" | "!" | "(" | ")" | "]")
+        {
+            return false;
+        }
+        if is_punctuator(prev, &["..."]) {
+            return token.value == "on" || token.value == "@"
+                || token.value == "{";
+        }
+        !is_punctuator(prev, &["(", "[", "@", "$"])
+    }
+
+    /// Writes a block string with its lines indented as the current line
+    fn block_string(&mut self, raw: &str) {
+        let value = match unquote_block_string(raw) {
+            Ok(value) if is_block_string_compatible(&value) => value,
+            _ => {
+                self.out.push_str(raw);
+                return;
+            }
+        };
+        self.out.push_str(r#"""""#);
+        for line in value.split('\n') {
+            self.newline(false);
+            self.out.push_str(&line.replace(r#"""""#, r#"\""""#));
+        }
+        self.newline(false);
+        self.out.push_str(r#"""""#);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::reformat;
+    use crate::query::parse_query;
+    use crate::schema::parse_schema;
+
+    const QUERIES: &[&str] = &[
+        "query Q($id: ID = 1, $f: [Int!]! = [1,2]) @live { user(id: $id) \
+         { ...F ... on User @include(if: true) { name } } }",
+        "# leading\n{a # trailing\n\n\n# own line\nb(x: {a: 1, b: [{c: \
+         \"d\"}]}) { c } }\nfragment F on T { x }",
+        "{ a(x: 1 # one\n y: 2) b(list: [\n1 # one\n 2]) }",
+        "query { ... { a } ...on T { b } }\n\n# last",
+        "mutation M { do(input: {}, list: []) { ok } }",
+    ];
+
+    const SCHEMAS: &[&str] = &[
+        "schema { query: Q }\n\"\"\"\n  The query\n\"\"\"\ntype Q implements \
+         & A & B @key(fields: \"id\") { # fields\n \"first\" a(\"arg\" x: \
+         Int = 1 y: String): [Int!]! @deprecated b: Int }",
+        "union U = | A | B\nenum E { A # first\n B @deprecated }\n\
+         input I { a: Int = 1, b: I2 = {c: [1]} }",
+        "directive @d(a: Int) repeatable on | FIELD | QUERY\n\
+         scalar S @specifiedBy(url: \"x\")\nextend type T { a: Int }\n\
+         extend schema @d",
+        "type A\ninterface I { a(\n# comment\nx: Int): Int }",
+    ];
+
+    #[test]
+    fn idempotent() {
+        for source in QUERIES.iter().chain(SCHEMAS) {
+            let once = reformat(source).unwrap();
+            assert_eq!(reformat(&once).unwrap(), once, "{}", source);
+        }
+    }
+
+    #[test]
+    fn same_ast() {
+        for source in QUERIES {
+            let formatted = reformat(source).unwrap();
+            assert_eq!(parse_query::<&str>(&formatted).unwrap().to_string(),
+                       parse_query::<&str>(source).unwrap().to_string());
+        }
+        for source in SCHEMAS {
+            let formatted = reformat(source).unwrap();
+            assert_eq!(parse_schema::<&str>(&formatted).unwrap().to_string(),
+                       parse_schema::<&str>(source).unwrap().to_string());
+        }
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(reformat(QUERIES[1]).unwrap(), "\
+# leading
+{
+  a # trailing
+
+  # own line
+  b(x: { a: 1, b: [{ c: \"d\" }] }) {
+    c
+  }
+}
+
+fragment F on T {
+  x
+}
+");
+        assert_eq!(reformat(QUERIES[2]).unwrap(), "\
+{
+  a(
+    x: 1 # one
+    y: 2
+  )
+  b(
+    list: [
+      1 # one
+      2
+    ]
+  )
+}
+");
+    }
+
+    #[test]
+    fn schema() {
+        assert_eq!(reformat(SCHEMAS[0]).unwrap(), "\
+schema {
+  query: Q
+}
+
+\"\"\"
+The query
+\"\"\"
+type Q implements A & B @key(fields: \"id\") { # fields
+  \"first\"
+  a(
+    \"arg\"
+    x: Int = 1
+    y: String
+  ): [Int!]! @deprecated
+  b: Int
+}
+");
+        assert_eq!(reformat(SCHEMAS[1]).unwrap(), "\
+union U = A | B
+
+enum E {
+  A # first
+  B @deprecated
+}
+
+input I {
+  a: Int = 1
+  b: I2 = { c: [1] }
+}
+");
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(reformat("{ a ").is_err());
+        assert!(reformat("").is_err());
+    }
+}
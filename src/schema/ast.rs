@@ -2,16 +2,93 @@ use std::str::FromStr;
 
 use thiserror::Error;
 
-pub use crate::common::{Directive, Type, Value, Text};
+pub use crate::common::{Directive, Float, Number, ObjectFields, Type, Value};
+pub use crate::common::Text;
+pub use crate::common::{get_named_type, is_list, is_non_null, unwrap_non_null};
 use crate::position::Pos;
 
+
+/// The reason of `@deprecated` when the `reason` argument is omitted
+pub const DEFAULT_DEPRECATION_REASON: &str = "No longer supported";
+
+/// Returns the reason of the `@deprecated` directive if there is one
+///
+/// `@deprecated(reason: null)` doesn't deprecate the item.
+pub(crate) fn deprecation_reason<'d, 'a, T>(directives: &'d [Directive<'a, T>])
+    -> Option<&'d str>
+    where T: Text<'a>,
+{
+    let dir = directives.iter()
+        .find(|dir| dir.name.as_ref() == "deprecated")?;
+    match dir.get_argument("reason") {
+        None => Some(DEFAULT_DEPRECATION_REASON),
+        Some(_) => dir.get_string("reason"),
+    }
+}
+
+/// The description of a type, field, argument or directive
+///
+/// Like `Value::BlockString` for values, this records whether the source
+/// used a block string, so that the description is printed back the same
+/// way. Dereferences to the text of the description.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Description {
+    pub value: String,
+    /// Whether the description was written as `"""block string"""`
+    pub block: bool,
+}
+
+impl Description {
+    /// Creates a description written as a regular string
+    pub fn new(value: impl Into<String>) -> Self {
+        Description { value: value.into(), block: false }
+    }
+
+    /// Creates a description written as a block string
+    pub fn block(value: impl Into<String>) -> Self {
+        Description { value: value.into(), block: true }
+    }
+}
+
+impl std::ops::Deref for Description {
+    type Target = str;
+    fn deref(&self) -> &str {
+        &self.value
+    }
+}
+
+impl From<String> for Description {
+    fn from(value: String) -> Self {
+        Description::new(value)
+    }
+}
+
+impl<'a> From<&'a str> for Description {
+    fn from(value: &'a str) -> Self {
+        Description::new(value)
+    }
+}
+
 #[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct Document<'a, T: Text<'a>>
     where T: Text<'a>
 {
     pub definitions: Vec<Definition<'a, T>>,
 }
 
+/// A schema document and the name of its source, e.g. a file name
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaFile<'a, T: Text<'a>> {
+    pub name: String,
+    pub document: Document<'a, T>,
+}
+
 impl<'a> Document<'a, String> {
     pub fn into_static(self) -> Document<'static, String> {
         // To support both reference and owned values in the AST,
@@ -33,14 +110,40 @@ impl<'a> Document<'a, String> {
 
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub enum Definition<'a, T: Text<'a>> {
     SchemaDefinition(SchemaDefinition<'a, T>),
+    SchemaExtension(SchemaExtension<'a, T>),
     TypeDefinition(TypeDefinition<'a, T>),
     TypeExtension(TypeExtension<'a, T>),
     DirectiveDefinition(DirectiveDefinition<'a, T>),
 }
 
+impl<'a, T> Definition<'a, T>
+    where T: Text<'a>
+{
+    /// Returns the position of the definition
+    pub fn position(&self) -> Pos {
+        match *self {
+            Definition::SchemaDefinition(ref d) => d.position,
+            Definition::SchemaExtension(ref d) => d.position,
+            Definition::TypeDefinition(ref d) => d.position(),
+            Definition::TypeExtension(ref d) => d.position(),
+            Definition::DirectiveDefinition(ref d) => d.position,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct SchemaDefinition<'a, T: Text<'a>> {
     pub position: Pos,
     pub directives: Vec<Directive<'a, T>>,
@@ -49,7 +152,28 @@ pub struct SchemaDefinition<'a, T: Text<'a>> {
     pub subscription: Option<T::Value>,
 }
 
+/// `extend schema`, which adds directives or root operation types to the
+/// schema
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
+pub struct SchemaExtension<'a, T: Text<'a>> {
+    pub position: Pos,
+    pub directives: Vec<Directive<'a, T>>,
+    pub query: Option<T::Value>,
+    pub mutation: Option<T::Value>,
+    pub subscription: Option<T::Value>,
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub enum TypeDefinition<'a, T: Text<'a>> {
     Scalar(ScalarType<'a, T>),
     Object(ObjectType<'a, T>),
@@ -59,7 +183,40 @@ pub enum TypeDefinition<'a, T: Text<'a>> {
     InputObject(InputObjectType<'a, T>),
 }
 
+impl<'a, T> TypeDefinition<'a, T>
+    where T: Text<'a>
+{
+    /// Returns the name of the defined type
+    pub fn name(&self) -> &T::Value {
+        match *self {
+            TypeDefinition::Scalar(ref t) => &t.name,
+            TypeDefinition::Object(ref t) => &t.name,
+            TypeDefinition::Interface(ref t) => &t.name,
+            TypeDefinition::Union(ref t) => &t.name,
+            TypeDefinition::Enum(ref t) => &t.name,
+            TypeDefinition::InputObject(ref t) => &t.name,
+        }
+    }
+
+    /// Returns the position of the type definition
+    pub fn position(&self) -> Pos {
+        match *self {
+            TypeDefinition::Scalar(ref t) => t.position,
+            TypeDefinition::Object(ref t) => t.position,
+            TypeDefinition::Interface(ref t) => t.position,
+            TypeDefinition::Union(ref t) => t.position,
+            TypeDefinition::Enum(ref t) => t.position,
+            TypeDefinition::InputObject(ref t) => t.position,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub enum TypeExtension<'a, T: Text<'a>> {
     Scalar(ScalarTypeExtension<'a, T>),
     Object(ObjectTypeExtension<'a, T>),
@@ -69,10 +226,43 @@ pub enum TypeExtension<'a, T: Text<'a>> {
     InputObject(InputObjectTypeExtension<'a, T>),
 }
 
+impl<'a, T> TypeExtension<'a, T>
+    where T: Text<'a>
+{
+    /// Returns the name of the extended type
+    pub fn name(&self) -> &T::Value {
+        match *self {
+            TypeExtension::Scalar(ref t) => &t.name,
+            TypeExtension::Object(ref t) => &t.name,
+            TypeExtension::Interface(ref t) => &t.name,
+            TypeExtension::Union(ref t) => &t.name,
+            TypeExtension::Enum(ref t) => &t.name,
+            TypeExtension::InputObject(ref t) => &t.name,
+        }
+    }
+
+    /// Returns the position of the type extension
+    pub fn position(&self) -> Pos {
+        match *self {
+            TypeExtension::Scalar(ref t) => t.position,
+            TypeExtension::Object(ref t) => t.position,
+            TypeExtension::Interface(ref t) => t.position,
+            TypeExtension::Union(ref t) => t.position,
+            TypeExtension::Enum(ref t) => t.position,
+            TypeExtension::InputObject(ref t) => t.position,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct ScalarType<'a, T: Text<'a>> {
     pub position: Pos,
-    pub description: Option<String>,
+    pub description: Option<Description>,
     pub name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
 }
@@ -91,6 +281,11 @@ impl<'a, T> ScalarType<'a, T>
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct ScalarTypeExtension<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: T::Value,
@@ -110,9 +305,14 @@ impl<'a, T> ScalarTypeExtension<'a, T>
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct ObjectType<'a, T: Text<'a>> {
     pub position: Pos,
-    pub description: Option<String>,
+    pub description: Option<Description>,
     pub name: T::Value,
     pub implements_interfaces: Vec<T::Value>,
     pub directives: Vec<Directive<'a, T>>,
@@ -135,6 +335,11 @@ impl<'a, T> ObjectType<'a, T>
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct ObjectTypeExtension<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: T::Value,
@@ -158,29 +363,98 @@ impl<'a, T> ObjectTypeExtension<'a, T>
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct Field<'a, T: Text<'a>> {
     pub position: Pos,
-    pub description: Option<String>,
+    pub description: Option<Description>,
     pub name: T::Value,
     pub arguments: Vec<InputValue<'a, T>>,
     pub field_type: Type<'a, T>,
     pub directives: Vec<Directive<'a, T>>,
 }
 
+impl<'a, T> Field<'a, T>
+    where T: Text<'a>
+{
+    pub fn new(name: T::Value, field_type: Type<'a, T>) -> Self {
+        Self {
+            position: Pos::default(),
+            description: None,
+            name,
+            arguments: vec![],
+            field_type,
+            directives: vec![],
+        }
+    }
+
+    /// Returns the reason the field is deprecated for, `None` if it's not
+    /// deprecated
+    pub fn deprecation(&self) -> Option<String> {
+        deprecation_reason(&self.directives).map(String::from)
+    }
+
+    /// Returns the definition of the argument named `name`
+    pub fn get_argument(&self, name: &str) -> Option<&InputValue<'a, T>> {
+        self.arguments.iter().find(|arg| arg.name.as_ref() == name)
+    }
+
+    /// Returns names and definitions of the arguments in definition order
+    pub fn iter_arguments(&self)
+        -> impl Iterator<Item=(&str, &InputValue<'a, T>)>
+    {
+        self.arguments.iter().map(|arg| (arg.name.as_ref(), arg))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct InputValue<'a, T: Text<'a>> {
     pub position: Pos,
-    pub description: Option<String>,
+    pub description: Option<Description>,
     pub name: T::Value,
     pub value_type: Type<'a, T>,
     pub default_value: Option<Value<'a, T>>,
     pub directives: Vec<Directive<'a, T>>,
 }
 
+impl<'a, T> InputValue<'a, T>
+    where T: Text<'a>
+{
+    pub fn new(name: T::Value, value_type: Type<'a, T>) -> Self {
+        Self {
+            position: Pos::default(),
+            description: None,
+            name,
+            value_type,
+            default_value: None,
+            directives: vec![],
+        }
+    }
+
+    /// Returns the reason the argument is deprecated for, `None` if it's not
+    /// deprecated
+    pub fn deprecation(&self) -> Option<String> {
+        deprecation_reason(&self.directives).map(String::from)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct InterfaceType<'a, T: Text<'a>> {
     pub position: Pos,
-    pub description: Option<String>,
+    pub description: Option<Description>,
     pub name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
     pub fields: Vec<Field<'a, T>>,
@@ -201,6 +475,11 @@ impl<'a, T> InterfaceType<'a, T>
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct InterfaceTypeExtension<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: T::Value,
@@ -222,9 +501,14 @@ where T: Text<'a>
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct UnionType<'a, T: Text<'a>> {
     pub position: Pos,
-    pub description: Option<String>,
+    pub description: Option<Description>,
     pub name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
     pub types: Vec<T::Value>,
@@ -245,6 +529,11 @@ where T: Text<'a>
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct UnionTypeExtension<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: T::Value,
@@ -266,9 +555,14 @@ where T: Text<'a>
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct EnumType<'a, T: Text<'a>> {
     pub position: Pos,
-    pub description: Option<String>,
+    pub description: Option<Description>,
     pub name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
     pub values: Vec<EnumValue<'a, T>>,
@@ -289,9 +583,14 @@ where T: Text<'a>
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct EnumValue<'a, T: Text<'a>> {
     pub position: Pos,
-    pub description: Option<String>,
+    pub description: Option<Description>,
     pub name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
 }
@@ -307,9 +606,20 @@ where T: Text<'a>
             directives: vec![],
         }
     }
+
+    /// Returns the reason the enum value is deprecated for, `None` if it's not
+    /// deprecated
+    pub fn deprecation(&self) -> Option<String> {
+        deprecation_reason(&self.directives).map(String::from)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct EnumTypeExtension<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: T::Value,
@@ -331,9 +641,14 @@ where T: Text<'a>
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct InputObjectType<'a, T: Text<'a>> {
     pub position: Pos,
-    pub description: Option<String>,
+    pub description: Option<Description>,
     pub name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
     pub fields: Vec<InputValue<'a, T>>,
@@ -354,6 +669,11 @@ where T: Text<'a>
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct InputObjectTypeExtension<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: T::Value,
@@ -375,6 +695,7 @@ where T: Text<'a>
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DirectiveLocation {
     // executable
     Query,
@@ -400,9 +721,14 @@ pub enum DirectiveLocation {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T::Value: serde::Serialize",
+    deserialize = "T::Value: serde::Deserialize<'de>",
+)))]
 pub struct DirectiveDefinition<'a, T: Text<'a>> {
     pub position: Pos,
-    pub description: Option<String>,
+    pub description: Option<Description>,
     pub name: T::Value,
     pub arguments: Vec<InputValue<'a, T>>,
     pub repeatable: bool,
@@ -422,6 +748,11 @@ where T: Text<'a>
             locations: vec![],
         }
     }
+
+    /// Returns the definition of the argument named `name`
+    pub fn get_argument(&self, name: &str) -> Option<&InputValue<'a, T>> {
+        self.arguments.iter().find(|arg| arg.name.as_ref() == name)
+    }
 }
 
 impl DirectiveLocation {
@@ -519,3 +850,77 @@ impl FromStr for DirectiveLocation {
         Ok(val)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::schema::parse_schema;
+
+    #[test]
+    fn deprecation() {
+        let doc = parse_schema::<String>(r#"
+            type Query {
+                explicit: Int @deprecated(reason: "Use `current`")
+                default: Int @deprecated
+                current(old: Int @deprecated(reason: "gone")): Int
+                undone: Int @deprecated(reason: null)
+            }
+            enum Color { RED GREEN @deprecated }
+        "#).unwrap();
+        let fields = match doc.definitions[0] {
+            Definition::TypeDefinition(TypeDefinition::Object(ref obj)) => {
+                &obj.fields
+            }
+            _ => unreachable!(),
+        };
+        let reasons = fields.iter()
+            .map(|field| field.deprecation())
+            .collect::<Vec<_>>();
+        assert_eq!(reasons, [
+            Some("Use `current`".to_string()),
+            Some(DEFAULT_DEPRECATION_REASON.to_string()),
+            None,
+            None,
+        ]);
+        assert_eq!(fields[2].arguments[0].deprecation(),
+                   Some("gone".to_string()));
+        let values = match doc.definitions[1] {
+            Definition::TypeDefinition(TypeDefinition::Enum(ref enm)) => {
+                &enm.values
+            }
+            _ => unreachable!(),
+        };
+        assert_eq!(values[0].deprecation(), None);
+        assert_eq!(values[1].deprecation().as_deref(),
+                   Some("No longer supported"));
+    }
+
+    #[test]
+    fn get_argument() {
+        let doc = parse_schema::<&str>(r#"
+            type Alien {
+                iq: Int
+                name(surname: Boolean, title: String): String
+            }
+            directive @tag(name: String!) on FIELD
+        "#).unwrap();
+        let alien = match doc.definitions[0] {
+            Definition::TypeDefinition(TypeDefinition::Object(ref obj)) => obj,
+            _ => unreachable!(),
+        };
+        let name = &alien.fields[1];
+        let surname = name.get_argument("surname").unwrap();
+        assert_eq!(surname.value_type, Type::NamedType("Boolean"));
+        assert!(name.get_argument("unknown").is_none());
+        assert!(alien.fields[0].get_argument("surname").is_none());
+        let names = name.iter_arguments()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["surname", "title"]);
+        let tag = match doc.definitions[1] {
+            Definition::DirectiveDefinition(ref dir) => dir,
+            _ => unreachable!(),
+        };
+        assert_eq!(tag.get_argument("name").unwrap().position.line, 6);
+    }
+}
@@ -0,0 +1,773 @@
+//! Fluent builders for constructing schema documents in code
+//!
+//! Builders are started with the `build` function of the node type and
+//! collected into a document with `schema_document()`. All positions of the
+//! built nodes are `Pos::default()` and all the collections not mentioned
+//! are empty:
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::schema::{EnumType, Field, ObjectType, Type};
+//! use graphql_parser::schema::builder::schema_document;
+//!
+//! let doc = schema_document()
+//!     .definition(ObjectType::build("User")
+//!         .field(Field::build("id", Type::non_null(Type::named("ID"))))
+//!         .field(Field::build("role", Type::named("Role"))))
+//!     .definition(EnumType::build("Role").value("ADMIN").value("USER"))
+//!     .build();
+//! assert_eq!(doc.to_string(), "\
+//! type User {
+//!   id: ID!
+//!   role: Role
+//! }
+//!
+//! enum Role {
+//!   ADMIN
+//!   USER
+//! }
+//! ");
+//! ```
+//!
+use crate::schema::ast::*;
+
+
+/// Builds a schema `Document`, see `schema_document()`
+#[derive(Debug, Clone, Default)]
+pub struct SchemaDocumentBuilder<'a> {
+    schema: Option<SchemaDefinition<'a, String>>,
+    definitions: Vec<Definition<'a, String>>,
+}
+
+/// Builds a `ScalarType`, see `ScalarType::build`
+#[derive(Debug, Clone)]
+pub struct ScalarTypeBuilder<'a> {
+    scalar: ScalarType<'a, String>,
+}
+
+/// Builds a `Field`, see `Field::build`
+#[derive(Debug, Clone)]
+pub struct FieldBuilder<'a> {
+    field: Field<'a, String>,
+}
+
+/// Builds an `InputValue`, see `InputValue::build`
+#[derive(Debug, Clone)]
+pub struct InputValueBuilder<'a> {
+    value: InputValue<'a, String>,
+}
+
+/// Builds an `EnumValue`, see `EnumValue::build`
+#[derive(Debug, Clone)]
+pub struct EnumValueBuilder<'a> {
+    value: EnumValue<'a, String>,
+}
+
+/// Builds an `ObjectType`, see `ObjectType::build`
+#[derive(Debug, Clone)]
+pub struct ObjectTypeBuilder<'a> {
+    object: ObjectType<'a, String>,
+}
+
+/// Builds an `InterfaceType`, see `InterfaceType::build`
+#[derive(Debug, Clone)]
+pub struct InterfaceTypeBuilder<'a> {
+    interface: InterfaceType<'a, String>,
+}
+
+/// Builds a `UnionType`, see `UnionType::build`
+#[derive(Debug, Clone)]
+pub struct UnionTypeBuilder<'a> {
+    union: UnionType<'a, String>,
+}
+
+/// Builds an `EnumType`, see `EnumType::build`
+#[derive(Debug, Clone)]
+pub struct EnumTypeBuilder<'a> {
+    enum_type: EnumType<'a, String>,
+}
+
+/// Builds an `InputObjectType`, see `InputObjectType::build`
+#[derive(Debug, Clone)]
+pub struct InputObjectTypeBuilder<'a> {
+    input: InputObjectType<'a, String>,
+}
+
+/// Builds a `DirectiveDefinition`, see `DirectiveDefinition::build`
+#[derive(Debug, Clone)]
+pub struct DirectiveDefinitionBuilder<'a> {
+    directive: DirectiveDefinition<'a, String>,
+}
+
+/// Starts building a schema document
+pub fn schema_document<'a>() -> SchemaDocumentBuilder<'a> {
+    SchemaDocumentBuilder::default()
+}
+
+impl<'a> SchemaDocumentBuilder<'a> {
+    /// Sets the query root type of the `schema` definition
+    pub fn query(mut self, name: &str) -> Self {
+        self.schema.get_or_insert_with(Default::default).query =
+            Some(name.into());
+        self
+    }
+
+    /// Sets the mutation root type of the `schema` definition
+    pub fn mutation(mut self, name: &str) -> Self {
+        self.schema.get_or_insert_with(Default::default).mutation =
+            Some(name.into());
+        self
+    }
+
+    /// Sets the subscription root type of the `schema` definition
+    pub fn subscription(mut self, name: &str) -> Self {
+        self.schema.get_or_insert_with(Default::default).subscription =
+            Some(name.into());
+        self
+    }
+
+    /// Adds a type or directive definition (or its builder)
+    pub fn definition<D>(mut self, definition: D) -> Self
+        where D: Into<Definition<'a, String>>,
+    {
+        self.definitions.push(definition.into());
+        self
+    }
+
+    /// Returns the document, the `schema` definition, if any root type is
+    /// set, goes first
+    pub fn build(self) -> Document<'a, String> {
+        Document {
+            definitions: self.schema
+                .map(Definition::SchemaDefinition)
+                .into_iter()
+                .chain(self.definitions)
+                .collect(),
+        }
+    }
+}
+
+impl<'a> ScalarType<'a, String> {
+    /// Starts building a scalar type
+    pub fn build(name: &str) -> ScalarTypeBuilder<'a> {
+        ScalarTypeBuilder { scalar: ScalarType::new(name.into()) }
+    }
+}
+
+impl<'a> ScalarTypeBuilder<'a> {
+    pub fn description(mut self, description: &str) -> Self {
+        self.scalar.description = Some(description.into());
+        self
+    }
+
+    pub fn directive<D>(mut self, directive: D) -> Self
+        where D: Into<Directive<'a, String>>,
+    {
+        self.scalar.directives.push(directive.into());
+        self
+    }
+
+    pub fn build(self) -> ScalarType<'a, String> {
+        self.scalar
+    }
+}
+
+impl<'a> Field<'a, String> {
+    /// Starts building a field
+    pub fn build(name: &str, field_type: Type<'a, String>)
+        -> FieldBuilder<'a>
+    {
+        FieldBuilder { field: Field::new(name.into(), field_type) }
+    }
+}
+
+impl<'a> FieldBuilder<'a> {
+    pub fn description(mut self, description: &str) -> Self {
+        self.field.description = Some(description.into());
+        self
+    }
+
+    pub fn argument<V: Into<InputValueBuilder<'a>>>(mut self, argument: V)
+        -> Self
+    {
+        self.field.arguments.push(argument.into().build());
+        self
+    }
+
+    pub fn directive<D>(mut self, directive: D) -> Self
+        where D: Into<Directive<'a, String>>,
+    {
+        self.field.directives.push(directive.into());
+        self
+    }
+
+    pub fn build(self) -> Field<'a, String> {
+        self.field
+    }
+}
+
+impl<'a> From<Field<'a, String>> for FieldBuilder<'a> {
+    fn from(field: Field<'a, String>) -> FieldBuilder<'a> {
+        FieldBuilder { field }
+    }
+}
+
+impl<'a> InputValue<'a, String> {
+    /// Starts building an argument or a field of an input object
+    pub fn build(name: &str, value_type: Type<'a, String>)
+        -> InputValueBuilder<'a>
+    {
+        InputValueBuilder { value: InputValue::new(name.into(), value_type) }
+    }
+}
+
+impl<'a> InputValueBuilder<'a> {
+    pub fn description(mut self, description: &str) -> Self {
+        self.value.description = Some(description.into());
+        self
+    }
+
+    pub fn default_value(mut self, value: Value<'a, String>) -> Self {
+        self.value.default_value = Some(value);
+        self
+    }
+
+    pub fn directive<D>(mut self, directive: D) -> Self
+        where D: Into<Directive<'a, String>>,
+    {
+        self.value.directives.push(directive.into());
+        self
+    }
+
+    pub fn build(self) -> InputValue<'a, String> {
+        self.value
+    }
+}
+
+impl<'a> From<InputValue<'a, String>> for InputValueBuilder<'a> {
+    fn from(value: InputValue<'a, String>) -> InputValueBuilder<'a> {
+        InputValueBuilder { value }
+    }
+}
+
+impl<'a> EnumValue<'a, String> {
+    /// Starts building an enum value
+    pub fn build(name: &str) -> EnumValueBuilder<'a> {
+        EnumValueBuilder { value: EnumValue::new(name.into()) }
+    }
+}
+
+impl<'a> EnumValueBuilder<'a> {
+    pub fn description(mut self, description: &str) -> Self {
+        self.value.description = Some(description.into());
+        self
+    }
+
+    pub fn directive<D>(mut self, directive: D) -> Self
+        where D: Into<Directive<'a, String>>,
+    {
+        self.value.directives.push(directive.into());
+        self
+    }
+
+    pub fn build(self) -> EnumValue<'a, String> {
+        self.value
+    }
+}
+
+impl<'a> From<&str> for EnumValueBuilder<'a> {
+    fn from(name: &str) -> EnumValueBuilder<'a> {
+        EnumValue::build(name)
+    }
+}
+
+impl<'a> From<EnumValue<'a, String>> for EnumValueBuilder<'a> {
+    fn from(value: EnumValue<'a, String>) -> EnumValueBuilder<'a> {
+        EnumValueBuilder { value }
+    }
+}
+
+impl<'a> ObjectType<'a, String> {
+    /// Starts building an object type
+    pub fn build(name: &str) -> ObjectTypeBuilder<'a> {
+        ObjectTypeBuilder { object: ObjectType::new(name.into()) }
+    }
+}
+
+impl<'a> ObjectTypeBuilder<'a> {
+    pub fn description(mut self, description: &str) -> Self {
+        self.object.description = Some(description.into());
+        self
+    }
+
+    /// Adds an interface to the `implements` list
+    pub fn implements(mut self, interface: &str) -> Self {
+        self.object.implements_interfaces.push(interface.into());
+        self
+    }
+
+    pub fn directive<D>(mut self, directive: D) -> Self
+        where D: Into<Directive<'a, String>>,
+    {
+        self.object.directives.push(directive.into());
+        self
+    }
+
+    pub fn field<F: Into<FieldBuilder<'a>>>(mut self, field: F) -> Self {
+        self.object.fields.push(field.into().build());
+        self
+    }
+
+    pub fn build(self) -> ObjectType<'a, String> {
+        self.object
+    }
+}
+
+impl<'a> InterfaceType<'a, String> {
+    /// Starts building an interface type
+    pub fn build(name: &str) -> InterfaceTypeBuilder<'a> {
+        InterfaceTypeBuilder { interface: InterfaceType::new(name.into()) }
+    }
+}
+
+impl<'a> InterfaceTypeBuilder<'a> {
+    pub fn description(mut self, description: &str) -> Self {
+        self.interface.description = Some(description.into());
+        self
+    }
+
+    pub fn directive<D>(mut self, directive: D) -> Self
+        where D: Into<Directive<'a, String>>,
+    {
+        self.interface.directives.push(directive.into());
+        self
+    }
+
+    pub fn field<F: Into<FieldBuilder<'a>>>(mut self, field: F) -> Self {
+        self.interface.fields.push(field.into().build());
+        self
+    }
+
+    pub fn build(self) -> InterfaceType<'a, String> {
+        self.interface
+    }
+}
+
+impl<'a> UnionType<'a, String> {
+    /// Starts building a union type
+    pub fn build(name: &str) -> UnionTypeBuilder<'a> {
+        UnionTypeBuilder { union: UnionType::new(name.into()) }
+    }
+}
+
+impl<'a> UnionTypeBuilder<'a> {
+    pub fn description(mut self, description: &str) -> Self {
+        self.union.description = Some(description.into());
+        self
+    }
+
+    pub fn directive<D>(mut self, directive: D) -> Self
+        where D: Into<Directive<'a, String>>,
+    {
+        self.union.directives.push(directive.into());
+        self
+    }
+
+    /// Adds a member type
+    pub fn member(mut self, name: &str) -> Self {
+        self.union.types.push(name.into());
+        self
+    }
+
+    pub fn build(self) -> UnionType<'a, String> {
+        self.union
+    }
+}
+
+impl<'a> EnumType<'a, String> {
+    /// Starts building an enum type
+    pub fn build(name: &str) -> EnumTypeBuilder<'a> {
+        EnumTypeBuilder { enum_type: EnumType::new(name.into()) }
+    }
+}
+
+impl<'a> EnumTypeBuilder<'a> {
+    pub fn description(mut self, description: &str) -> Self {
+        self.enum_type.description = Some(description.into());
+        self
+    }
+
+    pub fn directive<D>(mut self, directive: D) -> Self
+        where D: Into<Directive<'a, String>>,
+    {
+        self.enum_type.directives.push(directive.into());
+        self
+    }
+
+    /// Adds a value, either by name or a (built) enum value
+    pub fn value<V: Into<EnumValueBuilder<'a>>>(mut self, value: V) -> Self {
+        self.enum_type.values.push(value.into().build());
+        self
+    }
+
+    pub fn build(self) -> EnumType<'a, String> {
+        self.enum_type
+    }
+}
+
+impl<'a> InputObjectType<'a, String> {
+    /// Starts building an input object type
+    pub fn build(name: &str) -> InputObjectTypeBuilder<'a> {
+        InputObjectTypeBuilder { input: InputObjectType::new(name.into()) }
+    }
+}
+
+impl<'a> InputObjectTypeBuilder<'a> {
+    pub fn description(mut self, description: &str) -> Self {
+        self.input.description = Some(description.into());
+        self
+    }
+
+    pub fn directive<D>(mut self, directive: D) -> Self
+        where D: Into<Directive<'a, String>>,
+    {
+        self.input.directives.push(directive.into());
+        self
+    }
+
+    pub fn field<V: Into<InputValueBuilder<'a>>>(mut self, field: V) -> Self {
+        self.input.fields.push(field.into().build());
+        self
+    }
+
+    pub fn build(self) -> InputObjectType<'a, String> {
+        self.input
+    }
+}
+
+impl<'a> DirectiveDefinition<'a, String> {
+    /// Starts building a directive definition, `name` is without the `@`
+    pub fn build(name: &str) -> DirectiveDefinitionBuilder<'a> {
+        DirectiveDefinitionBuilder {
+            directive: DirectiveDefinition::new(name.into()),
+        }
+    }
+}
+
+impl<'a> DirectiveDefinitionBuilder<'a> {
+    pub fn description(mut self, description: &str) -> Self {
+        self.directive.description = Some(description.into());
+        self
+    }
+
+    pub fn argument<V: Into<InputValueBuilder<'a>>>(mut self, argument: V)
+        -> Self
+    {
+        self.directive.arguments.push(argument.into().build());
+        self
+    }
+
+    pub fn repeatable(mut self) -> Self {
+        self.directive.repeatable = true;
+        self
+    }
+
+    pub fn location(mut self, location: DirectiveLocation) -> Self {
+        self.directive.locations.push(location);
+        self
+    }
+
+    pub fn build(self) -> DirectiveDefinition<'a, String> {
+        self.directive
+    }
+}
+
+macro_rules! impl_into_definition {
+    ($( $builder: ident => $variant: ident, )*) => {
+        $(
+            impl<'a> From<$builder<'a>> for Definition<'a, String> {
+                fn from(builder: $builder<'a>) -> Definition<'a, String> {
+                    Definition::TypeDefinition(
+                        TypeDefinition::$variant(builder.build()))
+                }
+            }
+        )*
+    };
+}
+
+impl_into_definition!(
+    ScalarTypeBuilder => Scalar,
+    ObjectTypeBuilder => Object,
+    InterfaceTypeBuilder => Interface,
+    UnionTypeBuilder => Union,
+    EnumTypeBuilder => Enum,
+    InputObjectTypeBuilder => InputObject,
+);
+
+impl<'a> From<DirectiveDefinitionBuilder<'a>> for Definition<'a, String> {
+    fn from(builder: DirectiveDefinitionBuilder<'a>) -> Definition<'a, String>
+    {
+        Definition::DirectiveDefinition(builder.build())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::Number;
+    use crate::query::builder::directive;
+    use crate::schema::*;
+    use crate::schema::builder::{FieldBuilder, InputValueBuilder};
+    use crate::schema::builder::schema_document;
+    use crate::validation::test_harness::TEST_SCHEMA;
+
+    fn named<'a>(name: &str) -> Type<'a, String> {
+        Type::named(name)
+    }
+
+    fn arg<'a>(name: &str, typ: &str) -> InputValueBuilder<'a> {
+        InputValue::build(name, named(typ))
+    }
+
+    fn name_field<'a>() -> FieldBuilder<'a> {
+        Field::build("name", named("String"))
+            .argument(arg("surname", "Boolean"))
+    }
+
+    fn string_field<'a>(name: &str, arg: InputValueBuilder<'a>)
+        -> FieldBuilder<'a>
+    {
+        Field::build(name, named("String")).argument(arg)
+    }
+
+    fn zero<'a>() -> Value<'a, String> {
+        Value::Int(Number::from(0))
+    }
+
+    #[test]
+    fn test_schema() {
+        let non_null_int = || Type::non_null(named("Int"));
+        let doc = Document {
+            definitions: vec![
+                InterfaceType::build("Being").field(name_field()).into(),
+                InterfaceType::build("Mammal")
+                    .field(Field::build("mother", named("Mammal")))
+                    .field(Field::build("father", named("Mammal")))
+                    .into(),
+                InterfaceType::build("Pet").field(name_field()).into(),
+                InterfaceType::build("Canine")
+                    .field(name_field())
+                    .field(Field::build("mother", named("Canine")))
+                    .field(Field::build("father", named("Canine")))
+                    .into(),
+                EnumType::build("DogCommand")
+                    .value("SIT").value("HEEL").value("DOWN")
+                    .into(),
+                ObjectType::build("Dog")
+                    .implements("Being").implements("Pet")
+                    .implements("Mammal").implements("Canine")
+                    .field(name_field())
+                    .field(Field::build("nickname", named("String")))
+                    .field(Field::build("barkVolume", named("Int")))
+                    .field(Field::build("barks", named("Boolean")))
+                    .field(Field::build("doesKnowCommand", named("Boolean"))
+                        .argument(arg("dogCommand", "DogCommand")))
+                    .field(Field::build("isHouseTrained", named("Boolean"))
+                        .argument(arg("atOtherHomes", "Boolean")
+                            .default_value(Value::Boolean(true))))
+                    .field(Field::build("isAtLocation", named("Boolean"))
+                        .argument(arg("x", "Int"))
+                        .argument(arg("y", "Int")))
+                    .field(Field::build("mother", named("Dog")))
+                    .field(Field::build("father", named("Dog")))
+                    .into(),
+                ObjectType::build("Cat")
+                    .implements("Being").implements("Pet")
+                    .field(name_field())
+                    .field(Field::build("nickname", named("String")))
+                    .field(Field::build("meows", named("Boolean")))
+                    .field(Field::build("meowsVolume", named("Int")))
+                    .field(Field::build("furColor", named("FurColor")))
+                    .into(),
+                UnionType::build("CatOrDog").member("Cat").member("Dog")
+                    .into(),
+                InterfaceType::build("Intelligent")
+                    .field(Field::build("iq", named("Int")))
+                    .into(),
+                ObjectType::build("Human")
+                    .implements("Being").implements("Intelligent")
+                    .field(name_field())
+                    .field(Field::build("pets", Type::list(named("Pet"))))
+                    .field(Field::build("relatives",
+                        Type::list(named("Human"))))
+                    .field(Field::build("iq", named("Int")))
+                    .into(),
+                ObjectType::build("Alien")
+                    .implements("Being").implements("Intelligent")
+                    .field(Field::build("iq", named("Int")))
+                    .field(name_field())
+                    .field(Field::build("numEyes", named("Int")))
+                    .into(),
+                UnionType::build("DogOrHuman").member("Dog").member("Human")
+                    .into(),
+                UnionType::build("HumanOrAlien")
+                    .member("Human").member("Alien")
+                    .into(),
+                EnumType::build("FurColor")
+                    .value("BROWN").value("BLACK").value("TAN")
+                    .value("SPOTTED").value("NO_FUR").value("UNKNOWN")
+                    .into(),
+                InputObjectType::build("ComplexInput")
+                    .field(InputValue::build("requiredField",
+                        Type::non_null(named("Boolean"))))
+                    .field(InputValue::build("nonNullField",
+                            Type::non_null(named("Boolean")))
+                        .default_value(Value::Boolean(false)))
+                    .field(arg("intField", "Int"))
+                    .field(arg("stringField", "String"))
+                    .field(arg("booleanField", "Boolean"))
+                    .field(InputValue::build("stringListField",
+                        Type::list(named("String"))))
+                    .into(),
+                ObjectType::build("ComplicatedArgs")
+                    .field(string_field("intArgField", arg("intArg", "Int")))
+                    .field(string_field("nonNullIntArgField",
+                        InputValue::build("nonNullIntArg", non_null_int())))
+                    .field(string_field("stringArgField",
+                        arg("stringArg", "String")))
+                    .field(string_field("booleanArgField",
+                        arg("booleanArg", "Boolean")))
+                    .field(string_field("enumArgField",
+                        arg("enumArg", "FurColor")))
+                    .field(string_field("floatArgField",
+                        arg("floatArg", "Float")))
+                    .field(string_field("idArgField", arg("idArg", "ID")))
+                    .field(string_field("stringListArgField",
+                        InputValue::build("stringListArg",
+                            Type::list(named("String")))))
+                    .field(string_field("stringListNonNullArgField",
+                        InputValue::build("stringListNonNullArg",
+                            Type::list(Type::non_null(named("String"))))))
+                    .field(string_field("complexArgField",
+                        arg("complexArg", "ComplexInput")))
+                    .field(string_field("multipleReqs",
+                            InputValue::build("req1", non_null_int()))
+                        .argument(InputValue::build("req2", non_null_int())))
+                    .field(string_field("nonNullFieldWithDefault",
+                        InputValue::build("arg", non_null_int())
+                            .default_value(zero())))
+                    .field(string_field("multipleOpts",
+                            arg("opt1", "Int").default_value(zero()))
+                        .argument(arg("opt2", "Int").default_value(zero())))
+                    .field(string_field("multipleOptAndReq",
+                            InputValue::build("req1", non_null_int()))
+                        .argument(InputValue::build("req2", non_null_int()))
+                        .argument(arg("opt1", "Int").default_value(zero()))
+                        .argument(arg("opt2", "Int").default_value(zero())))
+                    .into(),
+                ObjectType::build("QueryRoot")
+                    .field(Field::build("human", named("Human"))
+                        .argument(arg("id", "ID")))
+                    .field(Field::build("alien", named("Alien")))
+                    .field(Field::build("dog", named("Dog")))
+                    .field(Field::build("cat", named("Cat")))
+                    .field(Field::build("pet", named("Pet")))
+                    .field(Field::build("catOrDog", named("CatOrDog")))
+                    .field(Field::build("dogOrHuman", named("DogOrHuman")))
+                    .field(Field::build("humanOrAlien",
+                        named("HumanOrAlien")))
+                    .field(Field::build("complicatedArgs",
+                        named("ComplicatedArgs")))
+                    .into(),
+                Definition::SchemaDefinition(SchemaDefinition {
+                    query: Some("QueryRoot".into()),
+                    ..SchemaDefinition::default()
+                }),
+                DirectiveDefinition::build("onField")
+                    .location(DirectiveLocation::Field)
+                    .into(),
+                DirectiveDefinition::build("directiveWithArgs")
+                    .argument(InputValue::build("required", non_null_int()))
+                    .argument(arg("optional", "String"))
+                    .location(DirectiveLocation::Field)
+                    .into(),
+            ],
+        };
+        let expected = parse_schema::<String>(TEST_SCHEMA).unwrap();
+        assert_eq!(doc.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn document() {
+        let doc = schema_document()
+            .definition(DirectiveDefinition::build("cached")
+                .description("Caches the result")
+                .argument(InputValue::build("ttl", named("Int"))
+                    .default_value(Value::Int(Number::from(60))))
+                .repeatable()
+                .location(DirectiveLocation::FieldDefinition)
+                .location(DirectiveLocation::Object))
+            .definition(ScalarType::build("Date")
+                .description("ISO 8601 date")
+                .directive(directive("specifiedBy").argument("url",
+                    Value::String("https://example.com".into()))))
+            .definition(InterfaceType::build("Node")
+                .field(Field::build("id", Type::non_null(named("ID")))))
+            .definition(ObjectType::build("Post")
+                .implements("Node")
+                .directive(directive("cached"))
+                .field(Field::build("id", Type::non_null(named("ID"))))
+                .field(Field::build("published", named("Date"))
+                    .description("Publication date")
+                    .directive(directive("deprecated"))))
+            .definition(UnionType::build("SearchResult")
+                .member("Post"))
+            .definition(EnumType::build("Order")
+                .value(EnumValue::build("ASC").description("Oldest first"))
+                .value("DESC"))
+            .definition(InputObjectType::build("PostFilter")
+                .field(InputValue::build("order", named("Order"))
+                    .default_value(Value::Enum("DESC".into()))))
+            .definition(ObjectType::build("Query")
+                .field(Field::build("search",
+                        Type::non_null(Type::list(named("SearchResult"))))
+                    .argument(arg("filter", "PostFilter"))))
+            .query("Query")
+            .build();
+        assert_eq!(doc.to_string(), r#"schema {
+  query: Query
+}
+
+"Caches the result"
+directive @cached(ttl: Int = 60) repeatable on FIELD_DEFINITION | OBJECT
+
+"ISO 8601 date"
+scalar Date @specifiedBy(url: "https://example.com")
+
+interface Node {
+  id: ID!
+}
+
+type Post implements Node @cached {
+  id: ID!
+  "Publication date"
+  published: Date @deprecated
+}
+
+union SearchResult = Post
+
+enum Order {
+  "Oldest first"
+  ASC
+  DESC
+}
+
+input PostFilter {
+  order: Order = DESC
+}
+
+type Query {
+  search(filter: PostFilter): [SearchResult]!
+}
+"#);
+    }
+}
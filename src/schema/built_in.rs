@@ -0,0 +1,182 @@
+//! Definitions of the scalars and directives built into every schema
+//!
+use crate::position::Pos;
+use crate::schema::ast::*;
+use crate::schema::grammar::parse_schema;
+use crate::schema::js_print::{SPECIFIED_DIRECTIVES, SPECIFIED_SCALARS};
+
+
+/// Specified scalars and directives with the descriptions graphql-js
+/// gives them
+pub(crate) const BUILT_IN: &str = include_str!("built_in.graphql");
+
+/// `@defer` and `@stream` of the incremental delivery proposal, which
+/// validation knows about although graphql-js 16 doesn't define them
+const INCREMENTAL_DELIVERY: &str = "
+directive @defer(if: Boolean! = true, label: String)
+  on FRAGMENT_SPREAD | INLINE_FRAGMENT
+directive @stream(if: Boolean! = true, label: String, initialCount: Int! = 0)
+  on FIELD
+";
+
+/// Returns the definitions of the source, all at `Pos::zero()`
+fn built_in_definitions<'a, T>(source: &'a str) -> Vec<Definition<'a, T>>
+    where T: Text<'a>,
+{
+    let mut doc = parse_schema::<T>(source)
+        .expect("built-in definitions are valid");
+    for def in &mut doc.definitions {
+        match *def {
+            Definition::TypeDefinition(TypeDefinition::Scalar(ref mut s)) => {
+                s.position = Pos::zero();
+            }
+            Definition::DirectiveDefinition(ref mut dir) => {
+                dir.position = Pos::zero();
+                for arg in &mut dir.arguments {
+                    arg.position = Pos::zero();
+                }
+            }
+            _ => unreachable!("only scalars and directives are built in"),
+        }
+    }
+    doc.definitions
+}
+
+/// Returns the definitions of the specified directives along with `@defer`
+/// and `@stream`
+pub(crate) fn built_in_directives<'a, T>() -> Vec<DirectiveDefinition<'a, T>>
+    where T: Text<'a>,
+{
+    built_in_definitions::<T>(BUILT_IN).into_iter()
+        .chain(built_in_definitions::<T>(INCREMENTAL_DELIVERY))
+        .filter_map(|def| match def {
+            Definition::DirectiveDefinition(dir) => Some(dir),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the name of the type or directive definition, and whether it
+/// is a directive
+fn defined_name<'d, 'a, T>(def: &'d Definition<'a, T>)
+    -> Option<(&'d str, bool)>
+    where T: Text<'a>,
+{
+    match *def {
+        Definition::TypeDefinition(ref t) => Some((t.name().as_ref(), false)),
+        Definition::DirectiveDefinition(ref d) => {
+            Some((d.name.as_ref(), true))
+        }
+        _ => None,
+    }
+}
+
+/// Adds definitions of the specified scalars and directives which the
+/// document doesn't define
+///
+/// These are the `String`, `Int`, `Float`, `Boolean` and `ID` scalars and
+/// the `@include`, `@skip`, `@deprecated`, `@specifiedBy` and `@oneOf`
+/// directives. They are appended to the document at `Pos::zero()`, so
+/// `strip_builtin_definitions` can tell them from the ones written in the
+/// source.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::schema::{parse_schema, with_builtin_definitions};
+///
+/// let doc = parse_schema::<&str>("type Query { a: Int }").unwrap();
+/// let doc = with_builtin_definitions(doc);
+/// assert_eq!(doc.definitions.len(), 11);
+/// ```
+pub fn with_builtin_definitions<'a, T>(mut doc: Document<'a, T>)
+    -> Document<'a, T>
+    where T: Text<'a>,
+{
+    let missing = built_in_definitions::<T>(BUILT_IN).into_iter()
+        .filter(|def| {
+            let name = defined_name(def);
+            !doc.definitions.iter().any(|d| defined_name(d) == name)
+        })
+        .collect::<Vec<_>>();
+    doc.definitions.extend(missing);
+    doc
+}
+
+/// Removes definitions added by `with_builtin_definitions`
+///
+/// Scalars and directives defined in the source are kept, even if they
+/// are the built-in ones.
+pub fn strip_builtin_definitions<'a, T>(mut doc: Document<'a, T>)
+    -> Document<'a, T>
+    where T: Text<'a>,
+{
+    doc.definitions.retain(|def| {
+        let built_in = match *def {
+            Definition::TypeDefinition(TypeDefinition::Scalar(ref s)) => {
+                SPECIFIED_SCALARS.contains(&s.name.as_ref())
+            }
+            Definition::DirectiveDefinition(ref d) => {
+                SPECIFIED_DIRECTIVES.contains(&d.name.as_ref())
+            }
+            _ => false,
+        };
+        !built_in || def.position().line != 0
+    });
+    doc
+}
+
+#[cfg(test)]
+mod test {
+    use super::{strip_builtin_definitions, with_builtin_definitions};
+    use crate::schema::{parse_schema, Definition, Document};
+
+    fn names<'a>(doc: &Document<'a, &'a str>) -> Vec<String> {
+        doc.definitions.iter()
+            .filter_map(|def| match *def {
+                Definition::TypeDefinition(ref t) => {
+                    Some(t.name().to_string())
+                }
+                Definition::DirectiveDefinition(ref d) => {
+                    Some(format!("@{}", d.name))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn missing_definitions() {
+        let doc = parse_schema::<&str>("
+            type Query { a: String }
+            scalar String
+            directive @deprecated(reason: String) on FIELD_DEFINITION
+        ").unwrap();
+        let doc = with_builtin_definitions(doc);
+        assert_eq!(names(&doc), [
+            "Query", "String", "@deprecated",
+            "Int", "Float", "Boolean", "ID",
+            "@include", "@skip", "@specifiedBy", "@oneOf",
+        ]);
+        assert!(doc.definitions[3..].iter()
+            .all(|def| def.position().line == 0));
+        let again = with_builtin_definitions(doc.clone());
+        assert_eq!(again, doc);
+    }
+
+    #[test]
+    fn round_trip() {
+        let source = "\
+type Query {
+  a: Int @deprecated
+}
+
+scalar String
+";
+        let doc = parse_schema::<&str>(source).unwrap();
+        let with_built_in = with_builtin_definitions(doc.clone());
+        assert!(with_built_in.to_string().contains("scalar Int"));
+        let stripped = strip_builtin_definitions(with_built_in);
+        assert_eq!(stripped, doc);
+        assert_eq!(stripped.to_string(), source);
+    }
+}
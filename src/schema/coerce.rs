@@ -0,0 +1,425 @@
+//! Coercion of literal values to input types
+//!
+use std::convert::TryFrom;
+
+use thiserror::Error;
+
+use crate::common::Arguments;
+use crate::schema::ast::*;
+use crate::schema::SchemaIndex;
+
+
+/// Error coercing a value to an input type
+///
+/// This structure is opaque for forward compatibility.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{}", message)]
+pub struct CoercionError {
+    path: String,
+    message: String,
+}
+
+impl CoercionError {
+    /// Returns the path to the invalid part of the value, e.g.
+    /// `filter.ids[1]`, empty if the whole value is invalid
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+/// Coerces a literal value to the input type as the specification
+/// requires for arguments
+///
+/// A single value is wrapped into a list for list types, integers are
+/// converted to floats for `Float` and to strings for `ID`, and missing
+/// input object fields get their default values. Variables are kept as
+/// is, since they are coerced separately. Values of custom scalars are not
+/// checked.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::query::Value;
+/// use graphql_parser::schema::{parse_schema, coerce_input_value};
+/// use graphql_parser::schema::{SchemaIndex, Type};
+///
+/// let doc = parse_schema::<&str>("type Query { a: Int }").unwrap();
+/// let schema = SchemaIndex::new(&doc);
+/// let list = Type::ListType(Box::new(Type::NamedType("Float")));
+/// let value = coerce_input_value(&Value::Int(1.into()), &list, &schema);
+/// assert_eq!(value.unwrap(), Value::List(vec![Value::Float(1.0.into())]));
+/// ```
+pub fn coerce_input_value<'a, T>(value: &Value<'a, T>, ty: &Type<'a, T>,
+    schema: &SchemaIndex<'a, T>)
+    -> Result<Value<'a, T>, CoercionError>
+    where T: Text<'a> + Clone,
+{
+    Coercion { schema, path: String::new() }.coerce(value, ty)
+}
+
+/// Coerces the arguments of a field or a directive to their definitions
+///
+/// Arguments are returned in the order of the definitions. An argument
+/// which is given, even as `null`, is coerced to its type, an argument
+/// which is omitted gets its default value or is left out if there is
+/// none. Omitting a non-null argument without a default value is an
+/// error.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::query::Value;
+/// use graphql_parser::schema::{parse_schema, coerce_arguments};
+/// use graphql_parser::schema::SchemaIndex;
+///
+/// let doc = parse_schema::<&str>("directive @d(x: Int = 1) on FIELD")
+///     .unwrap();
+/// let schema = SchemaIndex::new(&doc);
+/// let args = &schema.find_directive("d").unwrap().arguments;
+/// let omitted = coerce_arguments(&[], args, &schema);
+/// assert_eq!(omitted.unwrap(), [("x", Value::Int(1.into()))]);
+/// let null = coerce_arguments(&[("x", Value::Null)], args, &schema);
+/// assert_eq!(null.unwrap(), [("x", Value::Null)]);
+/// ```
+pub fn coerce_arguments<'a, T>(arguments: &[(T::Value, Value<'a, T>)],
+    definitions: &[InputValue<'a, T>], schema: &SchemaIndex<'a, T>)
+    -> Result<Arguments<'a, T>, CoercionError>
+    where T: Text<'a> + Clone,
+{
+    let unknown = arguments.iter().find(|(name, _)| {
+        !definitions.iter().any(|d| d.name.as_ref() == name.as_ref())
+    });
+    if let Some((name, _)) = unknown {
+        return Err(CoercionError {
+            path: String::new(),
+            message: format!("argument {} is not defined", name.as_ref()),
+        });
+    }
+    let given = |name: &str| {
+        arguments.iter()
+            .find(|(n, _)| n.as_ref() == name)
+            .map(|(_, value)| value)
+    };
+    Coercion { schema, path: String::new() }
+        .input_values(definitions, given, "argument")
+}
+
+struct Coercion<'s, 'a, T: Text<'a>> {
+    schema: &'s SchemaIndex<'a, T>,
+    path: String,
+}
+
+impl<'s, 'a, T: Text<'a> + Clone> Coercion<'s, 'a, T> {
+    fn error(&self, message: String) -> CoercionError {
+        let message = if self.path.is_empty() {
+            message
+        } else {
+            format!("at {}: {}", self.path, message)
+        };
+        CoercionError { path: self.path.clone(), message }
+    }
+
+    fn unexpected(&self, value: &Value<'a, T>, ty: &Type<'a, T>)
+        -> CoercionError
+    {
+        self.error(format!("expected a value of type {}, found {}",
+                           ty, value))
+    }
+
+    /// Runs `f` with the path extended by `segment`
+    fn nested<R>(&mut self, segment: &str, f: impl FnOnce(&mut Self) -> R)
+        -> R
+    {
+        let len = self.path.len();
+        self.path.push_str(segment);
+        let result = f(self);
+        self.path.truncate(len);
+        result
+    }
+
+    fn coerce(&mut self, value: &Value<'a, T>, ty: &Type<'a, T>)
+        -> Result<Value<'a, T>, CoercionError>
+    {
+        match (ty, value) {
+            (_, Value::Variable(_)) => Ok(value.clone()),
+            (Type::NonNullType(_), Value::Null) => {
+                Err(self.unexpected(value, ty))
+            }
+            (Type::NonNullType(inner), _) => self.coerce(value, inner),
+            (_, Value::Null) => Ok(Value::Null),
+            (Type::ListType(inner), Value::List(items)) => {
+                let mut result = Vec::with_capacity(items.len());
+                for (i, item) in items.iter().enumerate() {
+                    result.push(self.nested(&format!("[{}]", i),
+                        |c| c.coerce(item, inner))?);
+                }
+                Ok(Value::List(result))
+            }
+            (Type::ListType(inner), _) => {
+                Ok(Value::List(vec![self.coerce(value, inner)?]))
+            }
+            (Type::NamedType(name), _) => self.named(value, ty, name.as_ref()),
+        }
+    }
+
+    fn named(&mut self, value: &Value<'a, T>, ty: &Type<'a, T>, name: &str)
+        -> Result<Value<'a, T>, CoercionError>
+    {
+        let coerced = match (name, value) {
+            ("Int", Value::Int(n)) => n.as_i64()
+                .filter(|n| i32::try_from(*n).is_ok())
+                .map(|_| value.clone()),
+            ("Float", Value::Int(n)) => {
+                n.as_i64().map(|n| Value::Float((n as f64).into()))
+            }
+            ("Float", Value::Float(_))
+            | ("Boolean", Value::Boolean(_))
+            | ("String", Value::String(_))
+            | ("String", Value::BlockString(_))
+            | ("ID", Value::String(_))
+            | ("ID", Value::BlockString(_))
+            => Some(value.clone()),
+            ("ID", Value::Int(n)) => {
+                n.as_i64().map(|n| Value::String(n.to_string()))
+            }
+            ("Int", _) | ("Float", _) | ("Boolean", _) | ("String", _)
+            | ("ID", _)
+            => None,
+            _ => return self.defined(value, ty, name),
+        };
+        coerced.ok_or_else(|| self.unexpected(value, ty))
+    }
+
+    fn defined(&mut self, value: &Value<'a, T>, ty: &Type<'a, T>,
+        name: &str)
+        -> Result<Value<'a, T>, CoercionError>
+    {
+        match (self.schema.find_type(name), value) {
+            (None, _) => {
+                Err(self.error(format!("unknown input type {}", name)))
+            }
+            (Some(TypeDefinition::Scalar(_)), _) => Ok(value.clone()),
+            (Some(TypeDefinition::Enum(enm)), Value::Enum(val)) => {
+                if enm.values.iter().any(|v| v.name.as_ref() == val.as_ref())
+                {
+                    Ok(value.clone())
+                } else {
+                    Err(self.error(format!("value {} is not defined in \
+                                            enum {}", val.as_ref(), name)))
+                }
+            }
+            (Some(TypeDefinition::InputObject(input)),
+             Value::Object(fields)) => self.input_object(input, fields),
+            (Some(TypeDefinition::Enum(_)), _)
+            | (Some(TypeDefinition::InputObject(_)), _)
+            => Err(self.unexpected(value, ty)),
+            (Some(_), _) => {
+                Err(self.error(format!("{} is not an input type", name)))
+            }
+        }
+    }
+
+    fn input_object(&mut self, input: &InputObjectType<'a, T>,
+        fields: &ObjectFields<'a, T>)
+        -> Result<Value<'a, T>, CoercionError>
+    {
+        let unknown = fields.keys().find(|name| {
+            !input.fields.iter().any(|f| f.name.as_ref() == name.as_ref())
+        });
+        if let Some(name) = unknown {
+            return Err(self.error(format!("field {} is not defined in {}",
+                name.as_ref(), input.name.as_ref())));
+        }
+        let given = |name: &str| fields.get(name);
+        let values = self.input_values(&input.fields, given, "field")?;
+        Ok(Value::Object(values.into_iter().collect()))
+    }
+
+    /// Coerces the given values of input object fields or arguments,
+    /// telling an explicit `null` from an omitted value
+    fn input_values<'v>(&mut self, definitions: &[InputValue<'a, T>],
+        given: impl Fn(&str) -> Option<&'v Value<'a, T>>, kind: &str)
+        -> Result<Arguments<'a, T>, CoercionError>
+        where 'a: 'v,
+    {
+        let mut result = Vec::new();
+        for def in definitions {
+            let name = def.name.as_ref();
+            let segment = if self.path.is_empty() {
+                name.to_string()
+            } else {
+                format!(".{}", name)
+            };
+            let value = match given(name) {
+                Some(value) => value,
+                None => match def.default_value {
+                    Some(ref default) => default,
+                    None if is_non_null(&def.value_type) => {
+                        return Err(self.nested(&segment, |c| c.error(
+                            format!("required {} of type {} is missing",
+                                    kind, def.value_type))));
+                    }
+                    None => continue,
+                },
+            };
+            let value = self.nested(&segment,
+                |c| c.coerce(value, &def.value_type))?;
+            result.push((def.name.clone(), value));
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{coerce_arguments, coerce_input_value};
+    use crate::query::{parse_query, Definition, OperationDefinition};
+    use crate::query::{Selection, Value};
+    use crate::schema::{parse_schema, SchemaIndex, Type, TypeDefinition};
+
+    const SCHEMA: &str = "
+        type Query { a: Int }
+        enum Color { RED GREEN }
+        scalar Date
+        input Filter {
+            ids: [ID!]!
+            color: Color = RED
+            limit: Int
+            range: Range
+        }
+        input Range { from: Date!, to: Date }
+    ";
+
+    /// Coerces the argument value to the type of the variable
+    fn coerce(value: &str, ty: &str) -> Result<String, String> {
+        let doc = parse_schema::<&str>(SCHEMA).unwrap();
+        let schema = SchemaIndex::new(&doc);
+        let source = format!("query($v: {}) {{ f(a: {}) }}", ty, value);
+        let query = parse_query::<&str>(&source).unwrap();
+        let op = match query.definitions[0] {
+            Definition::Operation(OperationDefinition::Query(ref q)) => q,
+            _ => unreachable!(),
+        };
+        let ty = &op.variable_definitions[0].var_type;
+        let value = match op.selection_set.items[0] {
+            Selection::Field(ref field) => &field.arguments[0].1,
+            _ => unreachable!(),
+        };
+        coerce_input_value(value, ty, &schema)
+            .map(|value| value.to_string())
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn lists() {
+        assert_eq!(coerce("1", "[Int]"), Ok("[1]".into()));
+        assert_eq!(coerce("[1, 2]", "[Int]"), Ok("[1, 2]".into()));
+        assert_eq!(coerce("[[1], 2]", "[[Int]]"), Ok("[[1], [2]]".into()));
+        assert_eq!(coerce("null", "[Int]"), Ok("null".into()));
+        assert_eq!(coerce("[1, null]", "[Int!]"), Err("at [1]: expected a \
+            value of type Int!, found null".into()));
+    }
+
+    #[test]
+    fn non_null() {
+        assert_eq!(coerce("null", "Int!"),
+                   Err("expected a value of type Int!, found null".into()));
+        assert_eq!(coerce("1", "Int!"), Ok("1".into()));
+        assert_eq!(coerce("$var", "Int!"), Ok("$var".into()));
+    }
+
+    #[test]
+    fn scalars() {
+        assert_eq!(coerce("1", "Float"), Ok("1.0".into()));
+        assert_eq!(coerce("1.5", "Int"),
+                   Err("expected a value of type Int, found 1.5".into()));
+        assert_eq!(coerce("3000000000", "Int"), Err("expected a value of \
+            type Int, found 3000000000".into()));
+        assert_eq!(coerce("7", "ID"), Ok("\"7\"".into()));
+        assert_eq!(coerce("\"x\"", "Boolean"),
+                   Err("expected a value of type Boolean, found \"x\"".into()));
+        assert_eq!(coerce("{a: 1}", "Date"), Ok("{a: 1}".into()));
+    }
+
+    #[test]
+    fn enums() {
+        assert_eq!(coerce("GREEN", "Color"), Ok("GREEN".into()));
+        assert_eq!(coerce("BLUE", "Color"),
+                   Err("value BLUE is not defined in enum Color".into()));
+        assert_eq!(coerce("\"RED\"", "Color"),
+                   Err("expected a value of type Color, found \"RED\"".into()));
+    }
+
+    #[test]
+    fn input_objects() {
+        assert_eq!(coerce("{ids: 1, limit: 10}", "Filter"),
+                   Ok("{color: RED, ids: [\"1\"], limit: 10}".into()));
+        assert_eq!(coerce("{limit: 10}", "Filter"), Err("at ids: required \
+            field of type [ID!]! is missing".into()));
+        assert_eq!(coerce("{ids: [], range: {to: 1}}", "Filter"), Err("at \
+            range.from: required field of type Date! is missing".into()));
+        assert_eq!(coerce("{ids: [], size: 1}", "Filter"),
+                   Err("field size is not defined in Filter".into()));
+        assert_eq!(coerce("{a: 1}", "Query"),
+                   Err("Query is not an input type".into()));
+    }
+
+    #[test]
+    fn explicit_null() {
+        assert_eq!(coerce("{ids: [], color: null}", "Filter"),
+                   Ok("{color: null, ids: []}".into()));
+        assert_eq!(coerce("{ids: []}", "Filter"),
+                   Ok("{color: RED, ids: []}".into()));
+        assert_eq!(coerce("{ids: null}", "Filter"), Err("at ids: expected \
+            a value of type [ID!]!, found null".into()));
+        assert_eq!(coerce("{ids: [], limit: null}", "Filter"),
+                   Ok("{color: RED, ids: [], limit: null}".into()));
+    }
+
+    #[test]
+    fn arguments() {
+        let doc = parse_schema::<&str>("
+            type Query { f(x: Int = 1, y: [Int]!, z: Color): Int }
+            enum Color { RED }
+        ").unwrap();
+        let schema = SchemaIndex::new(&doc);
+        let defs = match schema.find_type("Query") {
+            Some(TypeDefinition::Object(query)) => &query.fields[0].arguments,
+            _ => unreachable!(),
+        };
+        let coerce = |query: &'static str| {
+            let query = parse_query::<&str>(query).unwrap();
+            let field = query.all_fields().next().unwrap();
+            coerce_arguments(&field.arguments, defs, &schema)
+                .map(|args| args.iter()
+                     .map(|(name, value)| format!("{}: {}", name, value))
+                     .collect::<Vec<_>>())
+                .map_err(|err| err.to_string())
+        };
+        assert_eq!(coerce("{ f(x: null, y: 2, z: null) }"),
+                   Ok(vec!["x: null".into(), "y: [2]".into(),
+                           "z: null".into()]));
+        assert_eq!(coerce("{ f(y: [2]) }"),
+                   Ok(vec!["x: 1".into(), "y: [2]".into()]));
+        assert_eq!(coerce("{ f(y: null) }"),
+                   Err("at y: expected a value of type [Int]!, \
+                        found null".into()));
+        assert_eq!(coerce("{ f }"),
+                   Err("at y: required argument of type [Int]! \
+                        is missing".into()));
+        assert_eq!(coerce("{ f(y: 1, w: 2) }"),
+                   Err("argument w is not defined".into()));
+    }
+
+    #[test]
+    fn types_built_in_code() {
+        let doc = parse_schema::<&str>(SCHEMA).unwrap();
+        let schema = SchemaIndex::new(&doc);
+        let ty = Type::NonNullType(Box::new(
+            Type::ListType(Box::new(Type::NamedType("Int")))));
+        assert_eq!(coerce_input_value(&Value::Int(1.into()), &ty, &schema),
+                   Ok(Value::List(vec![Value::Int(1.into())])));
+        let list = Value::List(vec![Value::Int(1.into()), Value::Null,
+                                    Value::Boolean(true)]);
+        let err = coerce_input_value(&list, &ty, &schema).unwrap_err();
+        assert_eq!(err.path(), "[2]");
+    }
+}
@@ -0,0 +1,815 @@
+//! Changes between two versions of a schema
+//!
+use std::fmt;
+
+use crate::position::Pos;
+use crate::schema::ast::*;
+use crate::schema::SchemaIndex;
+
+
+/// How a schema change affects existing clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Criticality {
+    /// Queries valid for the old schema may be invalid for the new one
+    Breaking,
+    /// Queries stay valid, but results may differ, e.g. a new enum value
+    /// may be returned
+    Dangerous,
+    /// Nothing changes for existing queries
+    Safe,
+}
+
+/// What was changed, with the names of the changed elements
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ChangeKind {
+    /// The root type of an operation, e.g. `mutation`, was replaced
+    RootTypeChanged { operation: String, old: String, new: String },
+    RootTypeRemoved { operation: String, name: String },
+    TypeAdded { name: String },
+    TypeRemoved { name: String },
+    /// A type was replaced by a type of another kind, e.g. an object type
+    /// by an interface
+    TypeKindChanged { name: String, old: String, new: String },
+    /// A field of an object type, an interface or an input object was
+    /// added
+    FieldAdded { type_name: String, field_name: String },
+    FieldRemoved { type_name: String, field_name: String },
+    FieldTypeChanged {
+        type_name: String,
+        field_name: String,
+        old: String,
+        new: String,
+    },
+    /// An argument was added to a field or a directive, `parent` is
+    /// `Type.field` or `@directive`
+    ArgumentAdded { parent: String, argument_name: String },
+    ArgumentRemoved { parent: String, argument_name: String },
+    ArgumentTypeChanged {
+        parent: String,
+        argument_name: String,
+        old: String,
+        new: String,
+    },
+    /// An argument or an input field lost its default value and must be
+    /// given now
+    MadeRequired { parent: String, name: String },
+    /// A default value of an argument or an input field was changed,
+    /// `None` means there is no default value
+    DefaultValueChanged {
+        parent: String,
+        name: String,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    EnumValueAdded { enum_name: String, value: String },
+    EnumValueRemoved { enum_name: String, value: String },
+    UnionMemberAdded { union_name: String, member: String },
+    UnionMemberRemoved { union_name: String, member: String },
+    InterfaceAdded { type_name: String, interface: String },
+    InterfaceRemoved { type_name: String, interface: String },
+    DirectiveAdded { name: String },
+    DirectiveRemoved { name: String },
+    DirectiveLocationRemoved { name: String, location: String },
+    DirectiveRepeatableRemoved { name: String },
+    /// A field, an argument, an input field or an enum value was marked
+    /// as `@deprecated`, `parent` is the path of its type, field or
+    /// directive
+    DeprecationAdded { parent: String, name: String, reason: String },
+}
+
+/// A single change found by `diff_schemas`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaChange {
+    pub kind: ChangeKind,
+    pub criticality: Criticality,
+    /// Path to the changed element, e.g. `Type.field.argument`,
+    /// `Enum.VALUE` or `@directive.argument`
+    pub path: String,
+    /// Position of the changed element in the new schema, `None` if it
+    /// was removed
+    pub position: Option<Pos>,
+    pub message: String,
+}
+
+impl SchemaChange {
+    /// Returns `true` if queries valid for the old schema may be invalid
+    /// for the new one
+    pub fn is_breaking(&self) -> bool {
+        self.criticality == Criticality::Breaking
+    }
+
+    /// Returns `true` if existing queries stay valid but may get
+    /// different results, e.g. a new enum value
+    pub fn is_dangerous(&self) -> bool {
+        self.criticality == Criticality::Dangerous
+    }
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}: {}", self.criticality, self.message)
+    }
+}
+
+/// Returns changes from the `old` to the `new` schema
+///
+/// This is the same as `diff_schemas`.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::schema::{parse_schema, schema_diff, ChangeKind};
+///
+/// let old = parse_schema::<&str>("type Query { a(x: Int): Int }").unwrap();
+/// let new = parse_schema::<&str>("type Query { a(x: ID): Int }").unwrap();
+/// assert_eq!(schema_diff(&old, &new)[0].kind,
+///     ChangeKind::ArgumentTypeChanged {
+///         parent: "Query.a".into(),
+///         argument_name: "x".into(),
+///         old: "Int".into(),
+///         new: "ID".into(),
+///     });
+/// ```
+pub fn schema_diff<'a, T>(old: &'a Document<'a, T>,
+    new: &'a Document<'a, T>)
+    -> Vec<SchemaChange>
+    where T: Text<'a> + PartialEq,
+{
+    diff_schemas(old, new)
+}
+
+/// Returns changes from the `old` to the `new` schema
+///
+/// Changes are returned in the order of definitions of the old schema,
+/// followed by the definitions added in the new one. Type extensions are
+/// not taken into account.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::schema::{parse_schema, diff_schemas, Criticality};
+///
+/// let old = parse_schema::<&str>("type Query { a: Int b: Int }").unwrap();
+/// let new = parse_schema::<&str>("type Query { a: Int }").unwrap();
+/// let changes = diff_schemas(&old, &new);
+/// assert_eq!(changes[0].criticality, Criticality::Breaking);
+/// assert_eq!(changes[0].path, "Query.b");
+/// ```
+pub fn diff_schemas<'a, T>(old: &'a Document<'a, T>,
+    new: &'a Document<'a, T>)
+    -> Vec<SchemaChange>
+    where T: Text<'a> + PartialEq,
+{
+    let mut diff = Diff { changes: Vec::new() };
+    let old_index = SchemaIndex::new(old);
+    let new_index = SchemaIndex::new(new);
+    diff.root_types(&old_index, &new_index);
+    for def in &old.definitions {
+        match *def {
+            Definition::TypeDefinition(ref old_type) => {
+                let name = old_type.name().as_ref();
+                match new_index.find_type(name) {
+                    Some(new_type) => diff.types(old_type, new_type),
+                    None => diff.push_kind(
+                        ChangeKind::TypeRemoved { name: name.into() },
+                        Criticality::Breaking, name, None,
+                        format!("Type '{}' was removed", name)),
+                }
+            }
+            Definition::DirectiveDefinition(ref old_dir) => {
+                let name = old_dir.name.as_ref();
+                let path = format!("@{}", name);
+                match new_index.find_directive(name) {
+                    Some(new_dir) => diff.directives(&path, old_dir, new_dir),
+                    None => diff.push_kind(
+                        ChangeKind::DirectiveRemoved { name: name.into() },
+                        Criticality::Breaking, &path, None,
+                        format!("Directive '{}' was removed", path)),
+                }
+            }
+            Definition::SchemaDefinition(_)
+            | Definition::SchemaExtension(_)
+            | Definition::TypeExtension(_)
+            => {}
+        }
+    }
+    for def in &new.definitions {
+        match *def {
+            Definition::TypeDefinition(ref new_type) => {
+                let name = new_type.name().as_ref();
+                if old_index.find_type(name).is_none() {
+                    diff.push_kind(
+                        ChangeKind::TypeAdded { name: name.into() },
+                        Criticality::Safe, name, Some(new_type.position()),
+                        format!("Type '{}' was added", name));
+                }
+            }
+            Definition::DirectiveDefinition(ref new_dir) => {
+                let name = new_dir.name.as_ref();
+                if old_index.find_directive(name).is_none() {
+                    let path = format!("@{}", name);
+                    diff.push_kind(
+                        ChangeKind::DirectiveAdded { name: name.into() },
+                        Criticality::Safe, &path, Some(new_dir.position),
+                        format!("Directive '{}' was added", path));
+                }
+            }
+            Definition::SchemaDefinition(_)
+            | Definition::SchemaExtension(_)
+            | Definition::TypeExtension(_)
+            => {}
+        }
+    }
+    diff.changes
+}
+
+struct Diff {
+    changes: Vec<SchemaChange>,
+}
+
+fn kind<'a, T: Text<'a>>(typ: &TypeDefinition<'a, T>) -> &'static str {
+    match *typ {
+        TypeDefinition::Scalar(_) => "scalar",
+        TypeDefinition::Object(_) => "object",
+        TypeDefinition::Interface(_) => "interface",
+        TypeDefinition::Union(_) => "union",
+        TypeDefinition::Enum(_) => "enum",
+        TypeDefinition::InputObject(_) => "input object",
+    }
+}
+
+/// Returns `true` if every value of the `new` output type is also a value
+/// of the `old` one
+fn is_safe_output_change<'a, T>(old: &Type<'a, T>, new: &Type<'a, T>)
+    -> bool
+    where T: Text<'a>,
+{
+    match (old, new) {
+        (Type::NamedType(old), Type::NamedType(new)) => old == new,
+        (Type::ListType(old), Type::ListType(new)) => {
+            is_safe_output_change(old, new)
+        }
+        (Type::NonNullType(old), Type::NonNullType(new)) => {
+            is_safe_output_change(old, new)
+        }
+        (old, Type::NonNullType(new)) => is_safe_output_change(old, new),
+        _ => false,
+    }
+}
+
+/// Returns `true` if every value of the `old` input type is also accepted
+/// by the `new` one
+fn is_safe_input_change<'a, T>(old: &Type<'a, T>, new: &Type<'a, T>)
+    -> bool
+    where T: Text<'a>,
+{
+    match (old, new) {
+        (Type::NamedType(old), Type::NamedType(new)) => old == new,
+        (Type::ListType(old), Type::ListType(new)) => {
+            is_safe_input_change(old, new)
+        }
+        (Type::NonNullType(old), Type::NonNullType(new)) => {
+            is_safe_input_change(old, new)
+        }
+        (Type::NonNullType(old), new) => is_safe_input_change(old, new),
+        _ => false,
+    }
+}
+
+fn is_required<'a, T: Text<'a>>(value: &InputValue<'a, T>) -> bool {
+    match value.value_type {
+        Type::NonNullType(_) => value.default_value.is_none(),
+        _ => false,
+    }
+}
+
+/// Returns the kind of the change adding an argument or an input field
+fn added_kind(parent: &str, what: &str, name: &str) -> ChangeKind {
+    if what == "Argument" {
+        ChangeKind::ArgumentAdded {
+            parent: parent.into(),
+            argument_name: name.into(),
+        }
+    } else {
+        ChangeKind::FieldAdded {
+            type_name: parent.into(),
+            field_name: name.into(),
+        }
+    }
+}
+
+/// Returns the kind of the change removing an argument or an input field
+fn removed_kind(parent: &str, what: &str, name: &str) -> ChangeKind {
+    if what == "Argument" {
+        ChangeKind::ArgumentRemoved {
+            parent: parent.into(),
+            argument_name: name.into(),
+        }
+    } else {
+        ChangeKind::FieldRemoved {
+            type_name: parent.into(),
+            field_name: name.into(),
+        }
+    }
+}
+
+impl Diff {
+    fn push_kind(&mut self, kind: ChangeKind, criticality: Criticality,
+        path: &str, position: Option<Pos>, message: String)
+    {
+        self.changes.push(SchemaChange {
+            kind,
+            criticality,
+            path: path.into(),
+            position,
+            message,
+        });
+    }
+
+    fn deprecation<'a, T: Text<'a>>(&mut self, parent: &str, name: &str,
+        what: &str, old: &[Directive<'a, T>], new: &[Directive<'a, T>],
+        position: Pos)
+    {
+        if deprecation_reason(old).is_some() {
+            return;
+        }
+        if let Some(reason) = deprecation_reason(new) {
+            let path = format!("{}.{}", parent, name);
+            self.push_kind(ChangeKind::DeprecationAdded {
+                parent: parent.into(),
+                name: name.into(),
+                reason: reason.into(),
+            }, Criticality::Safe, &path, Some(position), format!(
+                "{} '{}' was deprecated: {}", what, path, reason));
+        }
+    }
+
+    fn root_types<'a, T: Text<'a>>(&mut self, old: &SchemaIndex<'a, T>,
+        new: &SchemaIndex<'a, T>)
+    {
+        let roots = [
+            ("query", old.query_type(), new.query_type()),
+            ("mutation", old.mutation_type(), new.mutation_type()),
+            ("subscription", old.subscription_type(),
+             new.subscription_type()),
+        ];
+        for &(operation, old, new) in &roots {
+            let position = new.map(|t| t.position());
+            let old = old.map(|t| t.name().as_ref());
+            let new = new.map(|t| t.name().as_ref());
+            match (old, new) {
+                (Some(old), Some(new)) if old != new => {
+                    self.push_kind(ChangeKind::RootTypeChanged {
+                        operation: operation.into(),
+                        old: old.into(),
+                        new: new.into(),
+                    }, Criticality::Breaking, operation, position, format!(
+                        "Root {} type changed from '{}' to '{}'",
+                        operation, old, new));
+                }
+                (Some(old), None) => {
+                    self.push_kind(ChangeKind::RootTypeRemoved {
+                        operation: operation.into(),
+                        name: old.into(),
+                    }, Criticality::Breaking, operation, None, format!(
+                        "Root {} type '{}' was removed", operation, old));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn types<'a, T>(&mut self, old: &TypeDefinition<'a, T>,
+        new: &TypeDefinition<'a, T>)
+        where T: Text<'a> + PartialEq,
+    {
+        let name = old.name().as_ref();
+        match (old, new) {
+            (TypeDefinition::Object(old), TypeDefinition::Object(new)) => {
+                self.interfaces::<T>(name, &old.implements_interfaces,
+                                     &new.implements_interfaces,
+                                     new.position);
+                self.fields(name, &old.fields, &new.fields);
+            }
+            (TypeDefinition::Interface(old),
+             TypeDefinition::Interface(new)) => {
+                self.fields(name, &old.fields, &new.fields);
+            }
+            (TypeDefinition::Union(old), TypeDefinition::Union(new)) => {
+                self.union_members::<T>(name, &old.types, &new.types,
+                                        new.position);
+            }
+            (TypeDefinition::Enum(old), TypeDefinition::Enum(new)) => {
+                self.enum_values(name, &old.values, &new.values);
+            }
+            (TypeDefinition::InputObject(old),
+             TypeDefinition::InputObject(new)) => {
+                self.input_values(name, "Input field",
+                                  &old.fields, &new.fields);
+            }
+            (TypeDefinition::Scalar(_), TypeDefinition::Scalar(_)) => {}
+            _ => {
+                self.push_kind(ChangeKind::TypeKindChanged {
+                    name: name.into(),
+                    old: kind(old).into(),
+                    new: kind(new).into(),
+                }, Criticality::Breaking, name, Some(new.position()),
+                    format!("Type '{}' changed from {} to {}",
+                            name, kind(old), kind(new)));
+            }
+        }
+    }
+
+    fn interfaces<'a, T: Text<'a>>(&mut self, name: &str,
+        old: &[T::Value], new: &[T::Value], position: Pos)
+    {
+        for iface in old.iter().filter(|i| !new.contains(i)) {
+            self.push_kind(ChangeKind::InterfaceRemoved {
+                type_name: name.into(),
+                interface: iface.as_ref().into(),
+            }, Criticality::Breaking, name, Some(position), format!(
+                "Type '{}' no longer implements interface '{}'",
+                name, iface.as_ref()));
+        }
+        for iface in new.iter().filter(|i| !old.contains(i)) {
+            self.push_kind(ChangeKind::InterfaceAdded {
+                type_name: name.into(),
+                interface: iface.as_ref().into(),
+            }, Criticality::Safe, name, Some(position), format!(
+                "Type '{}' now implements interface '{}'",
+                name, iface.as_ref()));
+        }
+    }
+
+    fn fields<'a, T: Text<'a> + PartialEq>(&mut self, name: &str,
+        old: &[Field<'a, T>], new: &[Field<'a, T>])
+    {
+        for old_field in old {
+            let path = format!("{}.{}", name, old_field.name.as_ref());
+            let new_field = match new.iter()
+                .find(|f| f.name == old_field.name)
+            {
+                Some(new_field) => new_field,
+                None => {
+                    self.push_kind(ChangeKind::FieldRemoved {
+                        type_name: name.into(),
+                        field_name: old_field.name.as_ref().into(),
+                    }, Criticality::Breaking, &path, None,
+                        format!("Field '{}' was removed", path));
+                    continue;
+                }
+            };
+            if old_field.field_type != new_field.field_type {
+                let criticality = if is_safe_output_change(
+                    &old_field.field_type, &new_field.field_type)
+                {
+                    Criticality::Safe
+                } else {
+                    Criticality::Breaking
+                };
+                self.push_kind(ChangeKind::FieldTypeChanged {
+                    type_name: name.into(),
+                    field_name: old_field.name.as_ref().into(),
+                    old: old_field.field_type.to_string(),
+                    new: new_field.field_type.to_string(),
+                }, criticality, &path, Some(new_field.position), format!(
+                    "Field '{}' changed type from '{}' to '{}'",
+                    path, old_field.field_type, new_field.field_type));
+            }
+            self.input_values(&path, "Argument",
+                              &old_field.arguments, &new_field.arguments);
+            self.deprecation(name, old_field.name.as_ref(), "Field",
+                             &old_field.directives, &new_field.directives,
+                             new_field.position);
+        }
+        for new_field in new {
+            if !old.iter().any(|f| f.name == new_field.name) {
+                let path = format!("{}.{}", name, new_field.name.as_ref());
+                self.push_kind(ChangeKind::FieldAdded {
+                    type_name: name.into(),
+                    field_name: new_field.name.as_ref().into(),
+                }, Criticality::Safe, &path, Some(new_field.position),
+                    format!("Field '{}' was added", path));
+            }
+        }
+    }
+
+    fn input_values<'a, T>(&mut self, name: &str, what: &str,
+        old: &[InputValue<'a, T>], new: &[InputValue<'a, T>])
+        where T: Text<'a> + PartialEq,
+    {
+        for old_value in old {
+            let path = format!("{}.{}", name, old_value.name.as_ref());
+            let new_value = match new.iter()
+                .find(|v| v.name == old_value.name)
+            {
+                Some(new_value) => new_value,
+                None => {
+                    let kind = removed_kind(name, what,
+                                            old_value.name.as_ref());
+                    self.push_kind(kind, Criticality::Breaking, &path, None,
+                        format!("{} '{}' was removed", what, path));
+                    continue;
+                }
+            };
+            if old_value.value_type != new_value.value_type {
+                let criticality = if is_safe_input_change(
+                    &old_value.value_type, &new_value.value_type)
+                {
+                    Criticality::Safe
+                } else {
+                    Criticality::Breaking
+                };
+                let kind = if what == "Argument" {
+                    ChangeKind::ArgumentTypeChanged {
+                        parent: name.into(),
+                        argument_name: old_value.name.as_ref().into(),
+                        old: old_value.value_type.to_string(),
+                        new: new_value.value_type.to_string(),
+                    }
+                } else {
+                    ChangeKind::FieldTypeChanged {
+                        type_name: name.into(),
+                        field_name: old_value.name.as_ref().into(),
+                        old: old_value.value_type.to_string(),
+                        new: new_value.value_type.to_string(),
+                    }
+                };
+                self.push_kind(kind, criticality, &path,
+                    Some(new_value.position), format!(
+                        "{} '{}' changed type from '{}' to '{}'", what,
+                        path, old_value.value_type, new_value.value_type));
+            } else if is_required(new_value) && !is_required(old_value) {
+                self.push_kind(ChangeKind::MadeRequired {
+                    parent: name.into(),
+                    name: old_value.name.as_ref().into(),
+                }, Criticality::Breaking, &path, Some(new_value.position),
+                    format!("{} '{}' is now required", what, path));
+            }
+            if old_value.default_value != new_value.default_value {
+                let old = old_value.default_value.as_ref()
+                    .map(|value| value.to_string());
+                let new = new_value.default_value.as_ref()
+                    .map(|value| value.to_string());
+                let message = format!(
+                    "Default value of {} '{}' changed from {} to {}",
+                    what.to_lowercase(), path,
+                    old.as_ref().map_or("none", |value| &value[..]),
+                    new.as_ref().map_or("none", |value| &value[..]));
+                self.push_kind(ChangeKind::DefaultValueChanged {
+                    parent: name.into(),
+                    name: old_value.name.as_ref().into(),
+                    old,
+                    new,
+                }, Criticality::Dangerous, &path, Some(new_value.position),
+                    message);
+            }
+            self.deprecation(name, old_value.name.as_ref(), what,
+                             &old_value.directives, &new_value.directives,
+                             new_value.position);
+        }
+        for new_value in new {
+            if !old.iter().any(|v| v.name == new_value.name) {
+                let path = format!("{}.{}", name, new_value.name.as_ref());
+                let kind = added_kind(name, what, new_value.name.as_ref());
+                if is_required(new_value) {
+                    self.push_kind(kind, Criticality::Breaking, &path,
+                        Some(new_value.position),
+                        format!("Required {} '{}' was added",
+                                what.to_lowercase(), path));
+                } else {
+                    // A new argument usually comes with a change of the
+                    // resolver, which may affect existing queries
+                    let criticality = if what == "Argument" {
+                        Criticality::Dangerous
+                    } else {
+                        Criticality::Safe
+                    };
+                    self.push_kind(kind, criticality, &path,
+                        Some(new_value.position),
+                        format!("{} '{}' was added", what, path));
+                }
+            }
+        }
+    }
+
+    fn union_members<'a, T: Text<'a>>(&mut self, name: &str,
+        old: &[T::Value], new: &[T::Value], position: Pos)
+    {
+        for member in old.iter().filter(|m| !new.contains(m)) {
+            self.push_kind(ChangeKind::UnionMemberRemoved {
+                union_name: name.into(),
+                member: member.as_ref().into(),
+            }, Criticality::Breaking, name, Some(position), format!(
+                "Member '{}' was removed from union '{}'",
+                member.as_ref(), name));
+        }
+        for member in new.iter().filter(|m| !old.contains(m)) {
+            self.push_kind(ChangeKind::UnionMemberAdded {
+                union_name: name.into(),
+                member: member.as_ref().into(),
+            }, Criticality::Dangerous, name, Some(position), format!(
+                "Member '{}' was added to union '{}'",
+                member.as_ref(), name));
+        }
+    }
+
+    fn enum_values<'a, T: Text<'a>>(&mut self, name: &str,
+        old: &[EnumValue<'a, T>], new: &[EnumValue<'a, T>])
+    {
+        for value in old {
+            let path = format!("{}.{}", name, value.name.as_ref());
+            match new.iter().find(|v| v.name == value.name) {
+                Some(new_value) => {
+                    self.deprecation(name, value.name.as_ref(),
+                                     "Enum value", &value.directives,
+                                     &new_value.directives,
+                                     new_value.position);
+                }
+                None => {
+                    self.push_kind(ChangeKind::EnumValueRemoved {
+                        enum_name: name.into(),
+                        value: value.name.as_ref().into(),
+                    }, Criticality::Breaking, &path, None,
+                        format!("Enum value '{}' was removed", path));
+                }
+            }
+        }
+        for value in new {
+            if !old.iter().any(|v| v.name == value.name) {
+                let path = format!("{}.{}", name, value.name.as_ref());
+                self.push_kind(ChangeKind::EnumValueAdded {
+                    enum_name: name.into(),
+                    value: value.name.as_ref().into(),
+                }, Criticality::Dangerous, &path, Some(value.position),
+                    format!("Enum value '{}' was added", path));
+            }
+        }
+    }
+
+    fn directives<'a, T: Text<'a> + PartialEq>(&mut self, path: &str,
+        old: &DirectiveDefinition<'a, T>, new: &DirectiveDefinition<'a, T>)
+    {
+        self.input_values(path, "Argument", &old.arguments, &new.arguments);
+        for location in &old.locations {
+            if !new.locations.contains(location) {
+                self.push_kind(ChangeKind::DirectiveLocationRemoved {
+                    name: new.name.as_ref().into(),
+                    location: location.as_str().into(),
+                }, Criticality::Breaking, path, Some(new.position),
+                    format!("Location {} was removed from directive '{}'",
+                            location.as_str(), path));
+            }
+        }
+        if old.repeatable && !new.repeatable {
+            self.push_kind(ChangeKind::DirectiveRepeatableRemoved {
+                name: new.name.as_ref().into(),
+            }, Criticality::Breaking, path, Some(new.position),
+                format!("Directive '{}' is no longer repeatable", path));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{diff_schemas, schema_diff};
+    use super::{ChangeKind, Criticality, SchemaChange};
+    use crate::schema::parse_schema;
+    use crate::validation::test_harness::TEST_SCHEMA;
+
+    fn diff(old: &str, new: &str) -> Vec<(Criticality, String, String)> {
+        let old = parse_schema::<&str>(old).unwrap();
+        let new = parse_schema::<&str>(new).unwrap();
+        diff_schemas(&old, &new).into_iter()
+            .map(|SchemaChange { criticality, path, message, .. }| {
+                (criticality, path, message)
+            })
+            .collect()
+    }
+
+    fn change(criticality: Criticality, path: &str, message: &str)
+        -> (Criticality, String, String)
+    {
+        (criticality, path.into(), message.into())
+    }
+
+    #[test]
+    fn removed_field() {
+        assert_eq!(diff("
+            type Query { user: User }
+            type User { id: ID! name: String }
+        ", "
+            type Query { user: User }
+            type User { id: ID! }
+        "), [
+            change(Criticality::Breaking, "User.name",
+                   "Field 'User.name' was removed"),
+        ]);
+    }
+
+    #[test]
+    fn added_optional_argument() {
+        assert_eq!(diff("
+            type Query { users(first: Int): [String] }
+        ", "
+            type Query { users(first: Int, after: String): [String] }
+        "), [
+            change(Criticality::Dangerous, "Query.users.after",
+                   "Argument 'Query.users.after' was added"),
+        ]);
+    }
+
+    #[test]
+    fn classified_changes() {
+        use self::Criticality::*;
+
+        assert_eq!(diff("
+            type Query { a(x: Int = 1, y: Int): String! b: Int c: Old }
+            type Old { a: Int }
+            enum E { A B }
+            union U = Old
+            input I { a: Int }
+            directive @d(a: Int) on FIELD | QUERY
+        ", "
+            type Query { a(x: Int = 2, y: Int!): String b: Int! c: Int }
+            enum E { A C }
+            union U = Old | New
+            type New { a: Int }
+            input I { a: Int, b: Int! }
+            directive @d(a: Int) on FIELD
+            directive @e on FIELD
+        "), [
+            change(Breaking, "Query.a",
+                   "Field 'Query.a' changed type from 'String!' to 'String'"),
+            change(Dangerous, "Query.a.x",
+                   "Default value of argument 'Query.a.x' \
+                    changed from 1 to 2"),
+            change(Breaking, "Query.a.y",
+                   "Argument 'Query.a.y' changed type from 'Int' to 'Int!'"),
+            change(Safe, "Query.b",
+                   "Field 'Query.b' changed type from 'Int' to 'Int!'"),
+            change(Breaking, "Query.c",
+                   "Field 'Query.c' changed type from 'Old' to 'Int'"),
+            change(Breaking, "Old", "Type 'Old' was removed"),
+            change(Breaking, "E.B", "Enum value 'E.B' was removed"),
+            change(Dangerous, "E.C", "Enum value 'E.C' was added"),
+            change(Dangerous, "U", "Member 'New' was added to union 'U'"),
+            change(Breaking, "I.b",
+                   "Required input field 'I.b' was added"),
+            change(Breaking, "@d",
+                   "Location QUERY was removed from directive '@d'"),
+            change(Safe, "New", "Type 'New' was added"),
+            change(Safe, "@e", "Directive '@e' was added"),
+        ]);
+    }
+
+    #[test]
+    fn change_kinds() {
+        use self::ChangeKind::*;
+
+        let modified = TEST_SCHEMA
+            .replace("  barkVolume: Int\n", "")
+            .replace("  meows: Boolean\n", "  meows: Boolean!\n  age: Int\n")
+            .replace("isAtLocation(x: Int, y: Int)",
+                     "isAtLocation(x: Float, z: Int)")
+            .replace("union CatOrDog = Cat | Dog\n", "")
+            + "scalar Date\ndirective @tag on OBJECT\n";
+        let old = parse_schema::<&str>(TEST_SCHEMA).unwrap();
+        let new = parse_schema::<&str>(&modified).unwrap();
+        let kinds = schema_diff(&old, &new).into_iter()
+            .map(|change| change.kind)
+            .collect::<Vec<_>>();
+        let s = |s: &str| s.to_string();
+        assert_eq!(kinds, [
+            FieldRemoved { type_name: s("Dog"), field_name: s("barkVolume") },
+            ArgumentTypeChanged { parent: s("Dog.isAtLocation"),
+                                  argument_name: s("x"),
+                                  old: s("Int"), new: s("Float") },
+            ArgumentRemoved { parent: s("Dog.isAtLocation"),
+                              argument_name: s("y") },
+            ArgumentAdded { parent: s("Dog.isAtLocation"),
+                            argument_name: s("z") },
+            FieldTypeChanged { type_name: s("Cat"), field_name: s("meows"),
+                               old: s("Boolean"), new: s("Boolean!") },
+            FieldAdded { type_name: s("Cat"), field_name: s("age") },
+            TypeRemoved { name: s("CatOrDog") },
+            TypeAdded { name: s("Date") },
+            DirectiveAdded { name: s("tag") },
+        ]);
+    }
+
+    #[test]
+    fn breaking_changes() {
+        let old = parse_schema::<&str>(TEST_SCHEMA).unwrap();
+        let modified = TEST_SCHEMA
+            .replace("  pets: [Pet]\n", "")
+            .replace("  relatives: [Human]\n",
+                     "  relatives: [Human]\n  age: Int\n");
+        let new = parse_schema::<&str>(&modified).unwrap();
+        let changes = diff_schemas(&old, &new);
+        let pets = changes.iter().find(|c| c.path == "Human.pets").unwrap();
+        assert!(pets.is_breaking());
+        assert!(!pets.is_dangerous());
+        let age = changes.iter().find(|c| c.path == "Human.age").unwrap();
+        assert!(!age.is_breaking());
+        assert!(!age.is_dangerous());
+    }
+}
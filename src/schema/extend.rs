@@ -0,0 +1,707 @@
+//! Merging of type extensions into the extended types
+//!
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::position::Pos;
+use crate::schema::ast::*;
+
+
+/// Options of `apply_extensions_with`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExtensionOptions {
+    /// Turn the first extension of a type which isn't defined into its
+    /// definition instead of failing
+    ///
+    /// Federated subgraphs extend types defined in other subgraphs. The
+    /// same goes for `extend schema` without a schema definition, which
+    /// then also gets the `Query`, `Mutation` and `Subscription` object
+    /// types as the missing root operation types.
+    pub allow_undefined: bool,
+}
+
+/// Error applying a type extension: the extended type isn't defined, is
+/// of a different kind or already has a field or value the extension adds
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("{message}")]
+pub struct ExtensionError {
+    message: String,
+    name: String,
+    position: Pos,
+    definition_position: Option<Pos>,
+}
+
+impl ExtensionError {
+    /// Returns the name of the extended type, or `schema`
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the position of the type extension
+    pub fn position(&self) -> Pos {
+        self.position
+    }
+
+    /// Returns the position of the extended type, or of the field or
+    /// value which the extension duplicates
+    ///
+    /// This is `None` if the extended type isn't defined.
+    pub fn definition_position(&self) -> Option<Pos> {
+        self.definition_position
+    }
+
+    /// Returns `true` if the extended type isn't defined
+    ///
+    /// These errors are skipped with `ExtensionOptions::allow_undefined`.
+    pub fn is_undefined_type(&self) -> bool {
+        self.definition_position.is_none()
+    }
+}
+
+/// Returns the keyword which defines or extends the kind of the type
+pub(crate) fn definition_keyword<'a, T: Text<'a>>(def: &TypeDefinition<'a, T>)
+    -> &'static str
+{
+    match *def {
+        TypeDefinition::Scalar(_) => "scalar",
+        TypeDefinition::Object(_) => "type",
+        TypeDefinition::Interface(_) => "interface",
+        TypeDefinition::Union(_) => "union",
+        TypeDefinition::Enum(_) => "enum",
+        TypeDefinition::InputObject(_) => "input",
+    }
+}
+
+fn extension_keyword<'a, T: Text<'a>>(ext: &TypeExtension<'a, T>)
+    -> &'static str
+{
+    match *ext {
+        TypeExtension::Scalar(_) => "scalar",
+        TypeExtension::Object(_) => "type",
+        TypeExtension::Interface(_) => "interface",
+        TypeExtension::Union(_) => "union",
+        TypeExtension::Enum(_) => "enum",
+        TypeExtension::InputObject(_) => "input",
+    }
+}
+
+/// A named member of a type and its position: `("field", name, position)`
+pub(crate) type Member<'d> = (&'static str, &'d str, Pos);
+
+/// Returns the fields, values, union members and interfaces of the type
+pub(crate) fn definition_members<'d, 'a, T>(def: &'d TypeDefinition<'a, T>)
+    -> Vec<Member<'d>>
+    where T: Text<'a>,
+{
+    let position = def.position();
+    match *def {
+        TypeDefinition::Scalar(_) => Vec::new(),
+        TypeDefinition::Object(ref obj) => {
+            [named("interface", &obj.implements_interfaces, position),
+             fields(&obj.fields)].concat()
+        }
+        TypeDefinition::Interface(ref iface) => {
+            fields(&iface.fields)
+        }
+        TypeDefinition::Union(ref union) => {
+            named("member", &union.types, position)
+        }
+        TypeDefinition::Enum(ref enm) => values(&enm.values),
+        TypeDefinition::InputObject(ref input) => {
+            input_fields(&input.fields)
+        }
+    }
+}
+
+/// Returns the fields, values, union members and interfaces added by the
+/// extension
+pub(crate) fn extension_members<'d, 'a, T>(ext: &'d TypeExtension<'a, T>)
+    -> Vec<Member<'d>>
+    where T: Text<'a>,
+{
+    let position = ext.position();
+    match *ext {
+        TypeExtension::Scalar(_) => Vec::new(),
+        TypeExtension::Object(ref obj) => {
+            [named("interface", &obj.implements_interfaces, position),
+             fields(&obj.fields)].concat()
+        }
+        TypeExtension::Interface(ref iface) => {
+            fields(&iface.fields)
+        }
+        TypeExtension::Union(ref union) => {
+            named("member", &union.types, position)
+        }
+        TypeExtension::Enum(ref enm) => values(&enm.values),
+        TypeExtension::InputObject(ref input) => {
+            input_fields(&input.fields)
+        }
+    }
+}
+
+fn named<'d, V: AsRef<str>>(what: &'static str, names: &'d [V], pos: Pos)
+    -> Vec<Member<'d>>
+{
+    names.iter().map(|name| (what, name.as_ref(), pos)).collect()
+}
+
+fn fields<'d, 'a, T: Text<'a>>(fields: &'d [Field<'a, T>])
+    -> Vec<Member<'d>>
+{
+    fields.iter().map(|f| ("field", f.name.as_ref(), f.position)).collect()
+}
+
+fn input_fields<'d, 'a, T: Text<'a>>(fields: &'d [InputValue<'a, T>])
+    -> Vec<Member<'d>>
+{
+    fields.iter().map(|f| ("field", f.name.as_ref(), f.position)).collect()
+}
+
+fn values<'d, 'a, T: Text<'a>>(values: &'d [EnumValue<'a, T>])
+    -> Vec<Member<'d>>
+{
+    values.iter().map(|v| ("value", v.name.as_ref(), v.position)).collect()
+}
+
+fn roots<'d, 'a, T: Text<'a>>(query: &'d Option<T::Value>,
+    mutation: &'d Option<T::Value>, subscription: &'d Option<T::Value>,
+    position: Pos)
+    -> Vec<Member<'d>>
+{
+    let roots = [("query", query), ("mutation", mutation),
+                 ("subscription", subscription)];
+    roots.iter()
+        .filter(|(_, typ)| typ.is_some())
+        .map(|&(operation, _)| ("root type", operation, position))
+        .collect()
+}
+
+/// The definition which extensions are merged into
+struct Base<'d> {
+    /// Index of the definition, or of the first extension if it's
+    /// turned into the definition
+    index: usize,
+    keyword: &'static str,
+    position: Pos,
+    members: HashMap<(&'static str, &'d str), Pos>,
+}
+
+impl<'d> Base<'d> {
+    fn new(index: usize, keyword: &'static str, position: Pos,
+        members: Vec<Member<'d>>)
+        -> Base<'d>
+    {
+        Base {
+            index, keyword, position,
+            members: members.into_iter()
+                .map(|(what, name, pos)| ((what, name), pos))
+                .collect(),
+        }
+    }
+
+    /// Adds the members of an extension, failing on the first one which
+    /// the type already has
+    fn extend(&mut self, extension: &str, type_name: &str,
+        position: Pos, members: Vec<Member<'d>>)
+        -> Result<(), ExtensionError>
+    {
+        for (what, name, pos) in members {
+            if let Some(&existing) = self.members.get(&(what, name)) {
+                return Err(ExtensionError {
+                    message: format!(
+                        "cannot apply `{}`: {} {} of {} is already \
+                         defined at {}",
+                        extension, what, name, type_name, existing),
+                    name: type_name.to_string(),
+                    position,
+                    definition_position: Some(existing),
+                });
+            }
+            self.members.insert((what, name), pos);
+        }
+        Ok(())
+    }
+}
+
+/// Indexes of the definitions which the extensions are merged into, and
+/// keywords of the types
+struct Bases {
+    types: HashMap<String, (usize, &'static str)>,
+    schema: Option<usize>,
+}
+
+fn check<'a, T>(doc: &Document<'a, T>, options: &ExtensionOptions)
+    -> Result<Bases, ExtensionError>
+    where T: Text<'a>,
+{
+    let mut types = HashMap::new();
+    let mut schema = None;
+    for (index, def) in doc.definitions.iter().enumerate() {
+        match *def {
+            Definition::TypeDefinition(ref typ) => {
+                types.entry(typ.name().as_ref())
+                    .or_insert_with(|| Base::new(index,
+                        definition_keyword(typ), typ.position(),
+                        definition_members(typ)));
+            }
+            Definition::SchemaDefinition(ref s) if schema.is_none() => {
+                schema = Some(Base::new(index, "schema", s.position,
+                    roots::<T>(&s.query, &s.mutation, &s.subscription,
+                               s.position)));
+            }
+            _ => {}
+        }
+    }
+    for (index, def) in doc.definitions.iter().enumerate() {
+        match *def {
+            Definition::TypeExtension(ref ext) => {
+                let name = ext.name().as_ref();
+                let keyword = extension_keyword(ext);
+                let extension = format!("extend {} {}", keyword, name);
+                let base = match types.get_mut(name) {
+                    Some(base) => base,
+                    None if options.allow_undefined => {
+                        types.insert(name, Base::new(index, keyword,
+                            ext.position(), extension_members(ext)));
+                        continue;
+                    }
+                    None => return Err(ExtensionError {
+                        message: format!(
+                            "cannot apply `{}`: type {} is not defined",
+                            extension, name),
+                        name: name.to_string(),
+                        position: ext.position(),
+                        definition_position: None,
+                    }),
+                };
+                if base.keyword != keyword {
+                    return Err(ExtensionError {
+                        message: format!("cannot apply `{}` to {} {}",
+                            extension, base.keyword, name),
+                        name: name.to_string(),
+                        position: ext.position(),
+                        definition_position: Some(base.position),
+                    });
+                }
+                base.extend(&extension, name, ext.position(),
+                            extension_members(ext))?;
+            }
+            Definition::SchemaExtension(ref ext) => {
+                let members = roots::<T>(&ext.query, &ext.mutation,
+                    &ext.subscription, ext.position);
+                match schema {
+                    Some(ref mut base) => {
+                        base.extend("extend schema", "schema",
+                                    ext.position, members)?;
+                    }
+                    None if options.allow_undefined => {
+                        schema = Some(Base::new(index, "schema",
+                                                ext.position, members));
+                    }
+                    None => return Err(ExtensionError {
+                        message: "cannot apply `extend schema`: \
+                                  schema is not defined".into(),
+                        name: "schema".into(),
+                        position: ext.position,
+                        definition_position: None,
+                    }),
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(Bases {
+        types: types.into_iter()
+            .map(|(name, base)| {
+                (name.to_string(), (base.index, base.keyword))
+            })
+            .collect(),
+        schema: schema.map(|base| base.index),
+    })
+}
+
+/// Merges all type extensions of the document into the extended types
+///
+/// Fields, enum values, union members, implemented interfaces and
+/// directives of an extension are appended to the ones of the type, and
+/// the extension is removed from the document. The type may be defined
+/// after its extensions. Schema extensions are merged into the schema
+/// definition the same way. If any extension can't be applied, the
+/// document is left unchanged.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::schema::{parse_schema, apply_extensions};
+///
+/// let mut doc = parse_schema::<String>("
+///     type Query { a: Int }
+///     extend type Query { b: Int }
+/// ").unwrap();
+/// apply_extensions(&mut doc).unwrap();
+/// assert_eq!(doc.to_string(), "type Query {\n  a: Int\n  b: Int\n}\n");
+/// ```
+pub fn apply_extensions<'a, T>(doc: &mut Document<'a, T>)
+    -> Result<(), ExtensionError>
+    where T: Text<'a>,
+{
+    apply_extensions_with(doc, &ExtensionOptions::default())
+}
+
+/// Merges all type extensions of the document with the given options
+pub fn apply_extensions_with<'a, T>(doc: &mut Document<'a, T>,
+    options: &ExtensionOptions)
+    -> Result<(), ExtensionError>
+    where T: Text<'a>,
+{
+    let bases = check(doc, options)?;
+    let mut schema_from_extension = false;
+    let mut extensions = Vec::new();
+    let mut definitions = Vec::with_capacity(doc.definitions.len());
+    for (index, def) in doc.definitions.drain(..).enumerate() {
+        match def {
+            Definition::TypeExtension(ext) => {
+                let (base, _) = bases.types[ext.name().as_ref()];
+                if base == index {
+                    let def = Definition::TypeDefinition(into_definition(ext));
+                    definitions.push((index, def));
+                } else {
+                    extensions.push((base, Definition::TypeExtension(ext)));
+                }
+            }
+            Definition::SchemaExtension(ext) => {
+                let base = bases.schema.expect("schema is checked");
+                if base == index {
+                    schema_from_extension = true;
+                    let def = Definition::SchemaDefinition(SchemaDefinition {
+                        position: ext.position,
+                        directives: ext.directives,
+                        query: ext.query,
+                        mutation: ext.mutation,
+                        subscription: ext.subscription,
+                    });
+                    definitions.push((index, def));
+                } else {
+                    extensions.push((base, Definition::SchemaExtension(ext)));
+                }
+            }
+            def => definitions.push((index, def)),
+        }
+    }
+    for (base, ext) in extensions {
+        let position = definitions
+            .binary_search_by_key(&base, |&(index, _)| index)
+            .expect("extended definition is kept");
+        match (&mut definitions[position].1, ext) {
+            (Definition::TypeDefinition(typ),
+             Definition::TypeExtension(ext)) => merge(typ, ext),
+            (Definition::SchemaDefinition(schema),
+             Definition::SchemaExtension(ext)) => {
+                schema.directives.extend(ext.directives);
+                schema.query = schema.query.take().or(ext.query);
+                schema.mutation = schema.mutation.take().or(ext.mutation);
+                schema.subscription = schema.subscription.take()
+                    .or(ext.subscription);
+            }
+            _ => unreachable!("kinds are checked before merging"),
+        }
+    }
+    if schema_from_extension {
+        let implicit = |name: &'static str| match bases.types.get(name) {
+            Some(&(_, "type")) => Some(T::Value::from(name)),
+            _ => None,
+        };
+        for (_, def) in &mut definitions {
+            if let Definition::SchemaDefinition(ref mut schema) = *def {
+                schema.query = schema.query.take()
+                    .or_else(|| implicit("Query"));
+                schema.mutation = schema.mutation.take()
+                    .or_else(|| implicit("Mutation"));
+                schema.subscription = schema.subscription.take()
+                    .or_else(|| implicit("Subscription"));
+            }
+        }
+    }
+    doc.definitions = definitions.into_iter().map(|(_, def)| def).collect();
+    Ok(())
+}
+
+/// Returns the definition of a type which is only defined by extensions
+fn into_definition<'a, T>(ext: TypeExtension<'a, T>) -> TypeDefinition<'a, T>
+    where T: Text<'a>,
+{
+    match ext {
+        TypeExtension::Scalar(ext) => TypeDefinition::Scalar(ScalarType {
+            position: ext.position,
+            description: None,
+            name: ext.name,
+            directives: ext.directives,
+        }),
+        TypeExtension::Object(ext) => TypeDefinition::Object(ObjectType {
+            position: ext.position,
+            description: None,
+            name: ext.name,
+            implements_interfaces: ext.implements_interfaces,
+            directives: ext.directives,
+            fields: ext.fields,
+        }),
+        TypeExtension::Interface(ext) => {
+            TypeDefinition::Interface(InterfaceType {
+                position: ext.position,
+                description: None,
+                name: ext.name,
+                directives: ext.directives,
+                fields: ext.fields,
+            })
+        }
+        TypeExtension::Union(ext) => TypeDefinition::Union(UnionType {
+            position: ext.position,
+            description: None,
+            name: ext.name,
+            directives: ext.directives,
+            types: ext.types,
+        }),
+        TypeExtension::Enum(ext) => TypeDefinition::Enum(EnumType {
+            position: ext.position,
+            description: None,
+            name: ext.name,
+            directives: ext.directives,
+            values: ext.values,
+        }),
+        TypeExtension::InputObject(ext) => {
+            TypeDefinition::InputObject(InputObjectType {
+                position: ext.position,
+                description: None,
+                name: ext.name,
+                directives: ext.directives,
+                fields: ext.fields,
+            })
+        }
+    }
+}
+
+fn merge<'a, T>(typ: &mut TypeDefinition<'a, T>, ext: TypeExtension<'a, T>)
+    where T: Text<'a>,
+{
+    match (typ, ext) {
+        (TypeDefinition::Scalar(typ), TypeExtension::Scalar(ext)) => {
+            typ.directives.extend(ext.directives);
+        }
+        (TypeDefinition::Object(typ), TypeExtension::Object(ext)) => {
+            typ.implements_interfaces.extend(ext.implements_interfaces);
+            typ.directives.extend(ext.directives);
+            typ.fields.extend(ext.fields);
+        }
+        (TypeDefinition::Interface(typ), TypeExtension::Interface(ext)) => {
+            typ.directives.extend(ext.directives);
+            typ.fields.extend(ext.fields);
+        }
+        (TypeDefinition::Union(typ), TypeExtension::Union(ext)) => {
+            typ.directives.extend(ext.directives);
+            typ.types.extend(ext.types);
+        }
+        (TypeDefinition::Enum(typ), TypeExtension::Enum(ext)) => {
+            typ.directives.extend(ext.directives);
+            typ.values.extend(ext.values);
+        }
+        (TypeDefinition::InputObject(typ),
+         TypeExtension::InputObject(ext)) => {
+            typ.directives.extend(ext.directives);
+            typ.fields.extend(ext.fields);
+        }
+        _ => unreachable!("kinds are checked before merging"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{apply_extensions, apply_extensions_with, ExtensionOptions};
+    use crate::position::Pos;
+    use crate::schema::{parse_schema, Definition, Document, TypeDefinition};
+    use crate::validation::test_harness::TEST_SCHEMA;
+
+    fn parse(extensions: &str) -> Document<'static, String> {
+        parse_schema::<String>(&format!("{}\n{}", TEST_SCHEMA, extensions))
+            .unwrap()
+            .into_static()
+    }
+
+    #[test]
+    fn extend_human() {
+        let mut doc = parse("
+            extend type Human @key(fields: \"name\") { newField: String }
+        ");
+        let count = doc.definitions.len();
+        apply_extensions(&mut doc).unwrap();
+        assert_eq!(doc.definitions.len(), count - 1);
+        let human = doc.definitions.iter()
+            .find_map(|def| match *def {
+                Definition::TypeDefinition(TypeDefinition::Object(ref obj))
+                    if obj.name == "Human" => Some(obj),
+                _ => None,
+            })
+            .unwrap();
+        let fields = human.fields.iter()
+            .map(|field| field.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(fields, ["name", "pets", "relatives", "iq", "newField"]);
+        assert_eq!(human.directives[0].name, "key");
+        assert!(!doc.definitions.iter().any(|def| {
+            matches!(*def, Definition::TypeExtension(_))
+        }));
+    }
+
+    #[test]
+    fn all_kinds() {
+        let mut doc = parse_schema::<&str>("
+            extend union U = B
+            scalar S
+            union U = A
+            enum E { X }
+            input I { a: Int }
+            interface N { id: ID }
+            extend scalar S @specifiedBy(url: \"x\")
+            extend enum E { Y }
+            extend input I { b: Int }
+            extend interface N { name: String }
+        ").unwrap();
+        apply_extensions(&mut doc).unwrap();
+        assert_eq!(doc.to_string(), "\
+scalar S @specifiedBy(url: \"x\")
+
+union U = A | B
+
+enum E {
+  X
+  Y
+}
+
+input I {
+  a: Int
+  b: Int
+}
+
+interface N {
+  id: ID
+  name: String
+}
+");
+    }
+
+    #[test]
+    fn errors() {
+        let mut doc = parse("extend type Martian { name: String }");
+        let before = doc.clone();
+        let err = apply_extensions(&mut doc).unwrap_err();
+        assert_eq!(err.to_string(), "cannot apply `extend type Martian`: \
+                                     type Martian is not defined");
+        assert_eq!(err.name(), "Martian");
+        assert_eq!(doc, before);
+
+        let mut doc = parse_schema::<&str>("enum E { A }\nextend type E @x")
+            .unwrap();
+        let err = apply_extensions(&mut doc).unwrap_err();
+        assert_eq!(err.to_string(), "cannot apply `extend type E` to enum E");
+        assert_eq!(err.position(), Pos::new(2, 8));
+    }
+
+    #[test]
+    fn duplicates() {
+        let mut doc = parse_schema::<&str>("\
+type User { id: ID }
+extend type User { name: String }
+extend type User { id: ID! }
+").unwrap();
+        let err = apply_extensions(&mut doc).unwrap_err();
+        assert_eq!(err.to_string(), "cannot apply `extend type User`: \
+                                     field id of User is already defined \
+                                     at 1:13");
+        assert_eq!(err.position(), Pos::new(3, 8));
+        assert_eq!(err.definition_position(), Some(Pos::new(1, 13)));
+        assert!(!err.is_undefined_type());
+
+        let mut doc = parse_schema::<&str>("\
+enum E { A }
+extend enum E { B }
+extend enum E { B }
+").unwrap();
+        let err = apply_extensions(&mut doc).unwrap_err();
+        assert_eq!(err.definition_position(), Some(Pos::new(2, 17)));
+
+        let mut doc = parse_schema::<&str>("\
+union U = A
+extend union U = A
+").unwrap();
+        let err = apply_extensions(&mut doc).unwrap_err();
+        assert_eq!(err.to_string(), "cannot apply `extend union U`: \
+                                     member A of U is already defined \
+                                     at 1:1");
+
+        let mut doc = parse_schema::<&str>("\
+schema { query: Q }
+extend schema { query: R }
+").unwrap();
+        let err = apply_extensions(&mut doc).unwrap_err();
+        assert_eq!(err.name(), "schema");
+        assert_eq!(err.definition_position(), Some(Pos::new(1, 1)));
+    }
+
+    #[test]
+    fn schema_extension() {
+        let mut doc = parse_schema::<&str>("
+            extend schema @link(url: \"x\") { mutation: M }
+            schema @a { query: Q }
+            type Q { a: Int }
+            type M { b: Int }
+        ").unwrap();
+        apply_extensions(&mut doc).unwrap();
+        assert_eq!(doc.to_string(), "\
+schema @a @link(url: \"x\") {
+  query: Q
+  mutation: M
+}
+
+type Q {
+  a: Int
+}
+
+type M {
+  b: Int
+}
+");
+        let mut doc = parse_schema::<&str>("extend schema @link")
+            .unwrap();
+        let err = apply_extensions(&mut doc).unwrap_err();
+        assert_eq!(err.to_string(),
+                   "cannot apply `extend schema`: schema is not defined");
+        assert!(err.is_undefined_type());
+    }
+
+    #[test]
+    fn allow_undefined() {
+        let mut doc = parse_schema::<&str>("
+            extend schema @link(url: \"x\")
+            extend type Query { me: User }
+            type User @key(fields: \"id\") { id: ID! }
+            extend type Query { user(id: ID!): User }
+        ").unwrap();
+        let err = apply_extensions(&mut doc).unwrap_err();
+        assert!(err.is_undefined_type());
+        let options = ExtensionOptions { allow_undefined: true };
+        apply_extensions_with(&mut doc, &options).unwrap();
+        assert_eq!(doc.to_string(), "\
+schema @link(url: \"x\") {
+  query: Query
+}
+
+type Query {
+  me: User
+  user(id: ID!): User
+}
+
+type User @key(fields: \"id\") {
+  id: ID!
+}
+");
+    }
+}
@@ -1,6 +1,7 @@
 use std::fmt;
 
 use crate::format::{Displayable, Formatter, Style, format_directives};
+use crate::format::{FormatOptions, sorted, to_string};
 use crate::common::Text;
 
 use crate::schema::ast::*;
@@ -11,24 +12,27 @@ impl<'a, T> Document<'a, T>
 {
     /// Format a document according to style
     pub fn format(&self, style: &Style) -> String {
-        let mut formatter = Formatter::new(style);
-        self.display(&mut formatter);
-        formatter.into_string()
+        to_string(self, style)
+    }
+
+    /// Format a document on a single line with as little whitespace as
+    /// possible
+    pub fn to_minified_string(&self) -> String {
+        let mut style = Style::default();
+        style.minify(true);
+        to_string(self, &style)
     }
-}
 
-fn to_string<T: Displayable>(v: &T) -> String {
-    let style = Style::default();
-    let mut formatter = Formatter::new(&style);
-    v.display(&mut formatter);
-    formatter.into_string()
+    /// Format a document according to formatting options
+    pub fn format_with(&self, options: &FormatOptions) -> String {
+        to_string(self, &Style::from(options))
+    }
 }
 
-fn description<'a>(description: &Option<String>, f: &mut Formatter) {
+fn description(description: &Option<Description>, f: &mut Formatter) {
     if let Some(ref descr) = *description {
         f.indent();
-        f.write_quoted(descr.as_ref());
-        f.endline();
+        f.write_description(descr, descr.block);
     }
 }
 
@@ -37,7 +41,17 @@ impl<'a, T> Displayable for Document<'a, T>
     where T: Text<'a>,
 {
     fn display(&self, f: &mut Formatter) {
-        for item in &self.definitions {
+        let definitions = sorted(&self.definitions, f.sort_definitions(),
+            |def| match *def {
+                Definition::SchemaDefinition(_) => None,
+                Definition::SchemaExtension(_) => None,
+                Definition::TypeDefinition(ref t) => Some(t.name().as_ref()),
+                Definition::TypeExtension(ref e) => Some(e.name().as_ref()),
+                Definition::DirectiveDefinition(ref d) => {
+                    Some(d.name.as_ref())
+                }
+            });
+        for item in definitions {
             item.display(f);
         }
     }
@@ -50,6 +64,7 @@ impl<'a, T> Displayable for Definition<'a, T>
         f.margin();
         match *self {
             Definition::SchemaDefinition(ref s) => s.display(f),
+            Definition::SchemaExtension(ref s) => s.display(f),
             Definition::TypeDefinition(ref t) => t.display(f),
             Definition::TypeExtension(ref e) => e.display(f),
             Definition::DirectiveDefinition(ref d) => d.display(f),
@@ -88,6 +103,37 @@ impl<'a, T> Displayable for SchemaDefinition<'a, T>
     }
 }
 
+impl<'a, T> Displayable for SchemaExtension<'a, T>
+    where T: Text<'a>,
+{
+    fn display(&self, f: &mut Formatter) {
+        f.indent();
+        f.write("extend schema");
+        format_directives(&self.directives, f);
+        let operations = [
+            ("query", &self.query),
+            ("mutation", &self.mutation),
+            ("subscription", &self.subscription),
+        ];
+        if operations.iter().all(|(_, typ)| typ.is_none()) {
+            f.endline();
+            return;
+        }
+        f.write(" ");
+        f.start_block();
+        for (operation, typ) in operations.iter() {
+            if let Some(typ) = typ {
+                f.indent();
+                f.write(operation);
+                f.write(": ");
+                f.write(typ.as_ref());
+                f.endline();
+            }
+        }
+        f.end_block();
+    }
+}
+
 impl<'a, T> Displayable for TypeDefinition<'a, T> 
     where T: Text<'a>,
 {
@@ -134,7 +180,7 @@ fn format_fields<'a, T>(fields: &[Field<'a, T>], f: &mut Formatter)
     if !fields.is_empty() {
         f.write(" ");
         f.start_block();
-        for fld in fields {
+        for fld in sorted(fields, f.sort_fields(), |fld| fld.name.as_ref()) {
             fld.display(f);
         }
         f.end_block();
@@ -189,8 +235,8 @@ impl<'a, T> Displayable for InputValue<'a, T>
 {
     fn display(&self, f: &mut Formatter) {
         if let Some(ref descr) = self.description {
-            f.write_quoted(descr.as_ref());
-            f.write(" ");
+            f.write_description(descr, descr.block);
+            f.indent();
         }
         f.write(self.name.as_ref());
         f.write(": ");
@@ -206,7 +252,22 @@ impl<'a, T> Displayable for InputValue<'a, T>
 fn format_arguments<'a, T>(arguments: &[InputValue<'a, T>], f: &mut Formatter) 
     where T: Text<'a>,
 {
-    if !arguments.is_empty() {
+    let arguments = sorted(arguments, f.sort_arguments(),
+                           |arg| arg.name.as_ref());
+    if arguments.iter().any(|arg| arg.description.is_some()) {
+        // descriptions don't fit on a single line
+        f.write("(");
+        f.endline();
+        f.push_indent();
+        for arg in arguments {
+            f.indent();
+            arg.display(f);
+            f.endline();
+        }
+        f.pop_indent();
+        f.indent();
+        f.write(")");
+    } else if !arguments.is_empty() {
         f.write("(");
         arguments[0].display(f);
         for arg in &arguments[1..] {
@@ -310,12 +371,11 @@ impl<'a, T> Displayable for EnumType<'a, T>
         if !self.values.is_empty() {
             f.write(" ");
             f.start_block();
-            for val in &self.values {
+            let values = sorted(&self.values, f.sort_fields(),
+                                |val| val.name.as_ref());
+            for val in values {
+                description(&val.description, f);
                 f.indent();
-                if let Some(ref descr) = val.description {
-                    f.write_quoted(descr.as_ref());
-                    f.write(" ");
-                }
                 f.write(val.name.as_ref());
                 format_directives(&val.directives, f);
                 f.endline();
@@ -338,12 +398,11 @@ impl<'a, T> Displayable for EnumTypeExtension<'a, T>
         if !self.values.is_empty() {
             f.write(" ");
             f.start_block();
-            for val in &self.values {
+            let values = sorted(&self.values, f.sort_fields(),
+                                |val| val.name.as_ref());
+            for val in values {
+                description(&val.description, f);
                 f.indent();
-                if let Some(ref descr) = val.description {
-                    f.write_quoted(descr.as_ref());
-                    f.write(" ");
-                }
                 f.write(val.name.as_ref());
                 format_directives(&val.directives, f);
                 f.endline();
@@ -361,7 +420,7 @@ fn format_inputs<'a, T>(fields: &[InputValue<'a, T>], f: &mut Formatter)
     if !fields.is_empty() {
         f.write(" ");
         f.start_block();
-        for fld in fields {
+        for fld in sorted(fields, f.sort_fields(), |fld| fld.name.as_ref()) {
             f.indent();
             fld.display(f);
             f.endline();
@@ -445,6 +504,7 @@ impl_display!(
     Document,
     Definition,
     SchemaDefinition,
+    SchemaExtension,
     TypeDefinition,
     TypeExtension,
     ScalarType,
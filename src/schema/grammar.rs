@@ -4,9 +4,11 @@ use combine::error::StreamError;
 use combine::combinator::{many, many1, eof, optional, position, choice};
 use combine::combinator::{sep_by1};
 
+use crate::parse_options::ParseOptions;
+use crate::position::Pos;
 use crate::tokenizer::{Kind as T, Token, TokenStream};
 use crate::helpers::{punct, ident, kind, name};
-use crate::common::{directives, string, default_value, parse_type, Text};
+use crate::common::{directives, unquoted, default_value, parse_type, Text};
 use crate::schema::error::{ParseError};
 use crate::schema::ast::*;
 
@@ -26,48 +28,94 @@ pub fn schema<'a, S>(input: &mut TokenStream<'a>)
             .skip(punct("}")),
     )
     .flat_map(|(position, directives, operations): (_, _, Vec<(Token, _)>)| {
-        let mut query = None;
-        let mut mutation = None;
-        let mut subscription = None;
-        let mut err = Errors::empty(position);
-        for (oper, type_name) in operations {
-            match oper.value {
-                "query" if query.is_some() => {
-                    err.add_error(Error::unexpected_static_message(
-                        "duplicate `query` operation"));
-                }
-                "query" => {
-                    query = Some(type_name);
-                }
-                "mutation" if mutation.is_some() => {
-                    err.add_error(Error::unexpected_static_message(
-                        "duplicate `mutation` operation"));
-                }
-                "mutation" => {
-                    mutation = Some(type_name);
-                }
-                "subscription" if subscription.is_some() => {
-                    err.add_error(Error::unexpected_static_message(
-                        "duplicate `subscription` operation"));
-                }
-                "subscription" => {
-                    subscription = Some(type_name);
-                }
-                _ => {
-                    err.add_error(Error::unexpected_token(oper));
-                    err.add_error(
-                        Error::expected_static_message("query"));
-                    err.add_error(
-                        Error::expected_static_message("mutation"));
-                    err.add_error(
-                        Error::expected_static_message("subscription"));
-                }
+        let (query, mutation, subscription) =
+            operation_types::<S>(position, operations)?;
+        Ok(SchemaDefinition {
+            position, directives, query, mutation, subscription,
+        })
+    })
+    .parse_stream(input)
+}
+
+/// Returns the query, mutation and subscription types from the list of
+/// `operation: Type` pairs
+#[allow(clippy::type_complexity)]
+fn operation_types<'a, S>(position: Pos, operations: Vec<(Token<'a>, S::Value)>)
+    -> Result<(Option<S::Value>, Option<S::Value>, Option<S::Value>),
+              Errors<Token<'a>, Token<'a>, Pos>>
+    where S: Text<'a>,
+{
+    let mut query = None;
+    let mut mutation = None;
+    let mut subscription = None;
+    let mut err = Errors::empty(position);
+    for (oper, type_name) in operations {
+        match oper.value {
+            "query" if query.is_some() => {
+                err.add_error(Error::unexpected_static_message(
+                    "duplicate `query` operation"));
+            }
+            "query" => {
+                query = Some(type_name);
+            }
+            "mutation" if mutation.is_some() => {
+                err.add_error(Error::unexpected_static_message(
+                    "duplicate `mutation` operation"));
+            }
+            "mutation" => {
+                mutation = Some(type_name);
+            }
+            "subscription" if subscription.is_some() => {
+                err.add_error(Error::unexpected_static_message(
+                    "duplicate `subscription` operation"));
+            }
+            "subscription" => {
+                subscription = Some(type_name);
+            }
+            _ => {
+                err.add_error(Error::unexpected_token(oper));
+                err.add_error(
+                    Error::expected_static_message("query"));
+                err.add_error(
+                    Error::expected_static_message("mutation"));
+                err.add_error(
+                    Error::expected_static_message("subscription"));
             }
         }
-        if !err.errors.is_empty() {
-            return Err(err);
+    }
+    if !err.errors.is_empty() {
+        return Err(err);
+    }
+    Ok((query, mutation, subscription))
+}
+
+pub fn schema_extension<'a, S>(input: &mut TokenStream<'a>)
+    -> ParseResult<SchemaExtension<'a, S>, TokenStream<'a>>
+    where S: Text<'a>,
+{
+    (
+        position().skip(ident("schema")),
+        parser(directives),
+        optional(punct("{")
+            .with(many1((
+                kind(T::Name).skip(punct(":")),
+                name::<'a, S>(),
+            )))
+            .skip(punct("}"))),
+    )
+    .flat_map(|(position, directives, operations)
+        : (_, _, Option<Vec<(Token, _)>>)|
+    {
+        if directives.is_empty() && operations.is_none() {
+            let mut e = Errors::empty(position);
+            e.add_error(Error::expected_static_message(
+                "Schema extension should contain at least \
+                 one directive or operation type."));
+            return Err(e);
         }
-        Ok(SchemaDefinition {
+        let (query, mutation, subscription) =
+            operation_types::<S>(position, operations.unwrap_or_default())?;
+        Ok(SchemaExtension {
             position, directives, query, mutation, subscription,
         })
     })
@@ -124,13 +172,24 @@ pub fn implements_interfaces<'a, X>(input: &mut TokenStream<'a>)
         .parse_stream(input)
 }
 
+pub fn description<'a>(input: &mut TokenStream<'a>)
+    -> ParseResult<Description, TokenStream<'a>>
+{
+    choice((
+        parser(|input| unquoted(input, T::StringValue))
+            .map(Description::new),
+        parser(|input| unquoted(input, T::BlockString))
+            .map(Description::block),
+    )).parse_stream(input)
+}
+
 pub fn input_value<'a, X>(input: &mut TokenStream<'a>)
     -> ParseResult<InputValue<'a, X>, TokenStream<'a>>
     where X: Text<'a>,
 {
     (
         position(),
-        optional(parser(string)),
+        optional(parser(description)),
         name::<'a, X>(),
         punct(":").with(parser(parse_type)),
         optional(punct("=").with(parser(default_value))),
@@ -160,7 +219,7 @@ pub fn field<'a, S>(input: &mut TokenStream<'a>)
 {
     (
         position(),
-        optional(parser(string)),
+        optional(parser(description)),
         name::<'a, S>(),
         parser(arguments_definition),
         punct(":").with(parser(parse_type)),
@@ -341,7 +400,7 @@ pub fn enum_values<'a, T>(input: &mut TokenStream<'a>)
     .with(many1(
         (
             position(),
-            optional(parser(string)),
+            optional(parser(description)),
             name::<'a, T>(),
             parser(directives),
         )
@@ -493,7 +552,7 @@ pub fn described_definition<'a, T>(input: &mut TokenStream<'a>)
 {
     use self::TypeDefinition::*;
     (
-        optional(parser(string)),
+        optional(parser(description)),
         choice((
             choice((
                 parser(scalar_type).map(Scalar),
@@ -522,6 +581,7 @@ pub fn described_definition<'a, T>(input: &mut TokenStream<'a>)
                 T(InputObject(ref mut o)) => o.description = descr,
                 DirectiveDefinition(ref mut d) => d.description = descr,
                 SchemaDefinition(_) => unreachable!(),
+                SchemaExtension(_) => unreachable!(),
                 TypeExtension(_) => unreachable!(),
             }
             def
@@ -533,15 +593,14 @@ pub fn type_extension<'a, T>(input: &mut TokenStream<'a>)
     -> ParseResult<TypeExtension<'a, T>, TokenStream<'a>>
     where T: Text<'a>,
 {
-    ident("extend")
-    .with(choice((
+    choice((
         parser(scalar_type_extension).map(TypeExtension::Scalar),
         parser(object_type_extension).map(TypeExtension::Object),
         parser(interface_type_extension).map(TypeExtension::Interface),
         parser(union_type_extension).map(TypeExtension::Union),
         parser(enum_type_extension).map(TypeExtension::Enum),
         parser(input_object_type_extension).map(TypeExtension::InputObject),
-    )))
+    ))
     .parse_stream(input)
 }
 
@@ -552,7 +611,10 @@ pub fn definition<'a, T>(input: &mut TokenStream<'a>)
 {
     choice((
         parser(schema).map(Definition::SchemaDefinition),
-        parser(type_extension).map(Definition::TypeExtension),
+        ident("extend").with(choice((
+            parser(schema_extension).map(Definition::SchemaExtension),
+            parser(type_extension).map(Definition::TypeExtension),
+        ))),
         parser(described_definition),
     )).parse_stream(input)
 }
@@ -561,7 +623,15 @@ pub fn definition<'a, T>(input: &mut TokenStream<'a>)
 pub fn parse_schema<'a, T>(s: &'a str) -> Result<Document<'a, T>, ParseError>
     where T: Text<'a>,
 {
-    let mut tokens = TokenStream::new(s);
+    parse_schema_with(s, &ParseOptions::default())
+}
+
+/// Parses a piece of schema language with the given options
+pub fn parse_schema_with<'a, T>(s: &'a str, options: &ParseOptions)
+    -> Result<Document<'a, T>, ParseError>
+    where T: Text<'a>,
+{
+    let mut tokens = TokenStream::with_recursion_limit(s, options.max_depth);
     let (doc, _) = many1(parser(definition))
         .map(|d| Document { definitions: d })
         .skip(eof())
@@ -571,15 +641,46 @@ pub fn parse_schema<'a, T>(s: &'a str) -> Result<Document<'a, T>, ParseError>
     Ok(doc)
 }
 
+/// Parses a piece of schema language read from the named source, e.g. a
+/// file
+///
+/// Parse errors are prefixed with the name, and the name is kept in the
+/// result for `merge_schema_files`.
+pub fn parse_schema_named<'a, T>(s: &'a str, name: &str)
+    -> Result<SchemaFile<'a, T>, ParseError>
+    where T: Text<'a>,
+{
+    match parse_schema(s) {
+        Ok(document) => Ok(SchemaFile { name: name.into(), document }),
+        Err(e) => Err(e.with_source_name(name)),
+    }
+}
+
+/// Parses a schema from a `Bytes` buffer
+///
+/// Names in the resulting document are slices of the buffer, so they are
+/// not copied. Returns an error if the buffer isn't valid UTF-8.
+#[cfg(feature = "bytes")]
+pub fn parse_schema_bytes(source: bytes::Bytes)
+    -> Result<Document<'static, bytestring::ByteString>, ParseError>
+{
+    use std::convert::TryFrom;
+
+    let source = bytestring::ByteString::try_from(source)?;
+    let doc = parse_schema::<&str>(&source)?;
+    Ok(doc.map(|name| source.slice_ref(name)))
+}
+
 
 #[cfg(test)]
 mod test {
     use crate::position::Pos;
     use crate::schema::grammar::*;
-    use super::parse_schema;
+    use crate::parse_options::ParseOptions;
+    use super::{parse_schema, parse_schema_with};
 
-    fn ast(s: &str) -> Document<String> {
-        parse_schema::<String>(&s).unwrap().to_owned()
+    fn ast(s: &str) -> Document<'_, String> {
+        parse_schema::<String>(s).unwrap().to_owned()
     }
 
     #[test]
@@ -588,7 +689,7 @@ mod test {
             definitions: vec![
                 Definition::SchemaDefinition(
                     SchemaDefinition {
-                        position: Pos { line: 1, column: 1 },
+                        position: Pos::new(1, 1),
                         directives: vec![],
                         query: Some("Query".into()),
                         mutation: None,
@@ -598,4 +699,71 @@ mod test {
             ],
         });
     }
+
+    #[test]
+    fn schema_extension() {
+        let doc = ast("extend schema @link(url: \"https://example.com\")\n\
+                       extend schema { mutation: M }");
+        assert_eq!(doc.definitions.len(), 2);
+        match doc.definitions[0] {
+            Definition::SchemaExtension(ref ext) => {
+                assert_eq!(ext.position, Pos::new(1, 8));
+                assert_eq!(ext.directives[0].name, "link");
+                assert_eq!(ext.query, None);
+            }
+            _ => panic!("expected schema extension"),
+        }
+        match doc.definitions[1] {
+            Definition::SchemaExtension(ref ext) => {
+                assert!(ext.directives.is_empty());
+                assert_eq!(ext.mutation, Some("M".into()));
+            }
+            _ => panic!("expected schema extension"),
+        }
+        let err = parse_schema::<&str>("extend schema").unwrap_err();
+        assert!(err.to_string().contains("Schema extension should contain \
+                                          at least one directive"));
+    }
+
+    #[test]
+    fn empty_extensions() {
+        assert!(parse_schema::<&str>("extend type T implements I").is_ok());
+        for source in &[
+            "extend schema", "extend schema {}", "extend scalar S",
+            "extend type T", "extend type T {}", "extend interface I",
+            "extend union U", "extend enum E", "extend enum E {}",
+            "extend input I", "extend input I {}",
+        ] {
+            assert!(parse_schema::<&str>(source).is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn repeatable_directive() {
+        let doc = ast("directive @tag(name: String!) repeatable on FIELD | OBJECT\n\
+                       directive @once on FIELD\n");
+        let flags = doc.definitions.iter().map(|def| match *def {
+            Definition::DirectiveDefinition(ref d) => d.repeatable,
+            _ => panic!("expected directive definition"),
+        }).collect::<Vec<_>>();
+        assert_eq!(flags, [true, false]);
+        assert_eq!(doc.to_string(), "\
+            directive @tag(name: String!) repeatable on FIELD | OBJECT\n\
+            \n\
+            directive @once on FIELD\n");
+    }
+
+    #[test]
+    fn max_depth() {
+        let schema = "type Query { a(b: [[Int]] = [[1]]): Int }";
+        let options = ParseOptions { max_depth: 3 };
+        let err = parse_schema_with::<&str>(schema, &options).unwrap_err();
+        assert!(err.is_depth_limit_exceeded());
+        let options = ParseOptions { max_depth: 4 };
+        assert!(parse_schema_with::<&str>(schema, &options).is_ok());
+        let deep = format!("type Query {{ a: {}Int{} }}",
+                           "[".repeat(10_000), "]".repeat(10_000));
+        assert!(parse_schema::<&str>(&deep).unwrap_err()
+                .is_depth_limit_exceeded());
+    }
 }
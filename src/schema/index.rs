@@ -0,0 +1,507 @@
+//! Name-based lookup of schema definitions
+//!
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::schema::ast::*;
+use crate::schema::built_in::built_in_directives;
+
+
+/// An index of type and directive definitions of a schema document
+///
+/// Most of the tools working with both a query and a schema need to find
+/// types by name many times, so this is built once per schema document.
+/// Type extensions are not merged into the indexed types, but interfaces
+/// and union members they add are taken into account by `possible_types`
+/// and the related methods.
+#[derive(Debug)]
+pub struct SchemaIndex<'ast, T: Text<'ast>> {
+    types: HashMap<&'ast str, &'ast TypeDefinition<'ast, T>>,
+    directives: HashMap<&'ast str, &'ast DirectiveDefinition<'ast, T>>,
+    /// Built-in directives which the document doesn't define, owned by the
+    /// index as there is no document to borrow them from
+    built_in_directives: Arc<Vec<DirectiveDefinition<'ast, T>>>,
+    implementations: HashMap<&'ast str, Vec<&'ast str>>,
+    union_extensions: HashMap<&'ast str, Vec<&'ast str>>,
+    query: Option<&'ast str>,
+    mutation: Option<&'ast str>,
+    subscription: Option<&'ast str>,
+}
+
+// not derived, as that would require `T: Clone`
+impl<'ast, T: Text<'ast>> Clone for SchemaIndex<'ast, T> {
+    fn clone(&self) -> Self {
+        SchemaIndex {
+            types: self.types.clone(),
+            directives: self.directives.clone(),
+            built_in_directives: self.built_in_directives.clone(),
+            implementations: self.implementations.clone(),
+            union_extensions: self.union_extensions.clone(),
+            query: self.query,
+            mutation: self.mutation,
+            subscription: self.subscription,
+        }
+    }
+}
+
+impl<'ast, T> SchemaIndex<'ast, T>
+    where T: Text<'ast>,
+{
+    pub fn new(doc: &'ast Document<'ast, T>) -> SchemaIndex<'ast, T> {
+        let mut index = SchemaIndex {
+            types: HashMap::new(),
+            directives: HashMap::new(),
+            built_in_directives: Arc::new(Vec::new()),
+            implementations: HashMap::new(),
+            union_extensions: HashMap::new(),
+            query: Some("Query"),
+            mutation: Some("Mutation"),
+            subscription: Some("Subscription"),
+        };
+        for def in &doc.definitions {
+            match *def {
+                Definition::SchemaDefinition(ref schema) => {
+                    index.query = schema.query.as_ref().map(|n| n.as_ref());
+                    index.mutation = schema.mutation.as_ref()
+                        .map(|n| n.as_ref());
+                    index.subscription = schema.subscription.as_ref()
+                        .map(|n| n.as_ref());
+                }
+                Definition::TypeDefinition(ref typ) => {
+                    index.types.insert(typ.name().as_ref(), typ);
+                    if let TypeDefinition::Object(ref obj) = *typ {
+                        index.add_implementations(obj.name.as_ref(),
+                            &obj.implements_interfaces);
+                    }
+                }
+                Definition::DirectiveDefinition(ref dir) => {
+                    index.directives.insert(dir.name.as_ref(), dir);
+                }
+                Definition::SchemaExtension(ref ext) => {
+                    if let Some(ref query) = ext.query {
+                        index.query = Some(query.as_ref());
+                    }
+                    if let Some(ref mutation) = ext.mutation {
+                        index.mutation = Some(mutation.as_ref());
+                    }
+                    if let Some(ref subscription) = ext.subscription {
+                        index.subscription = Some(subscription.as_ref());
+                    }
+                }
+                Definition::TypeExtension(TypeExtension::Object(ref ext)) => {
+                    index.add_implementations(ext.name.as_ref(),
+                        &ext.implements_interfaces);
+                }
+                Definition::TypeExtension(TypeExtension::Union(ref ext)) => {
+                    index.union_extensions.entry(ext.name.as_ref())
+                        .or_default()
+                        .extend(ext.types.iter().map(|t| t.as_ref()));
+                }
+                Definition::TypeExtension(_) => {}
+            }
+        }
+        index
+    }
+
+    /// Indexes the document along with the built-in directives it doesn't
+    /// define
+    ///
+    /// These are `@include`, `@skip`, `@deprecated`, `@specifiedBy`,
+    /// `@oneOf`, and `@defer` and `@stream` from the incremental delivery
+    /// proposal. They are returned by `find_directive` as if the document
+    /// defined them, see also `with_builtin_definitions`.
+    pub fn with_builtin_directives(doc: &'ast Document<'ast, T>)
+        -> SchemaIndex<'ast, T>
+    {
+        let mut index = SchemaIndex::new(doc);
+        let missing = built_in_directives::<T>().into_iter()
+            .filter(|dir| !index.directives.contains_key(dir.name.as_ref()))
+            .collect();
+        index.built_in_directives = Arc::new(missing);
+        index
+    }
+
+    fn add_implementations(&mut self, object: &'ast str,
+        interfaces: &'ast [T::Value])
+    {
+        for iface in interfaces {
+            let objects = self.implementations.entry(iface.as_ref())
+                .or_default();
+            if !objects.contains(&object) {
+                objects.push(object);
+            }
+        }
+    }
+
+    /// Returns a type definition by name
+    pub fn find_type(&self, name: &str)
+        -> Option<&'ast TypeDefinition<'ast, T>>
+    {
+        self.types.get(name).copied()
+    }
+
+    /// Returns a directive definition by name (without the `@`)
+    pub fn find_directive(&self, name: &str)
+        -> Option<&DirectiveDefinition<'ast, T>>
+    {
+        self.defined_directive(name).or_else(|| {
+            self.built_in_directives.iter()
+                .find(|dir| dir.name.as_ref() == name)
+        })
+    }
+
+    /// Returns a directive definition by name if it's in the document
+    /// rather than built in
+    pub(crate) fn defined_directive(&self, name: &str)
+        -> Option<&'ast DirectiveDefinition<'ast, T>>
+    {
+        self.directives.get(name).copied()
+    }
+
+    /// Returns the root type for queries
+    ///
+    /// This is the type named in the `schema` definition, or the type named
+    /// `Query` if there is no schema definition.
+    pub fn query_type(&self) -> Option<&'ast TypeDefinition<'ast, T>> {
+        self.query.and_then(|name| self.find_type(name))
+    }
+
+    /// Returns the root type for mutations
+    pub fn mutation_type(&self) -> Option<&'ast TypeDefinition<'ast, T>> {
+        self.mutation.and_then(|name| self.find_type(name))
+    }
+
+    /// Returns the root type for subscriptions
+    pub fn subscription_type(&self)
+        -> Option<&'ast TypeDefinition<'ast, T>>
+    {
+        self.subscription.and_then(|name| self.find_type(name))
+    }
+
+    /// Returns names of the object types which can be of the type
+    ///
+    /// That is the type itself for an object type, the objects
+    /// implementing an interface (in the order of the document) and the
+    /// members of a union. Other types have no possible types.
+    pub fn possible_types(&self, name: &str) -> Vec<&'ast str> {
+        match self.find_type(name) {
+            Some(TypeDefinition::Object(obj)) => vec![obj.name.as_ref()],
+            Some(TypeDefinition::Interface(_)) => {
+                self.implementations.get(name).cloned().unwrap_or_default()
+            }
+            Some(TypeDefinition::Union(union)) => {
+                let mut members = union.types.iter()
+                    .map(|name| name.as_ref())
+                    .collect::<Vec<_>>();
+                for &name in self.union_extensions.get(name)
+                    .into_iter().flatten()
+                {
+                    if !members.contains(&name) {
+                        members.push(name);
+                    }
+                }
+                members
+            }
+            Some(TypeDefinition::Scalar(_))
+            | Some(TypeDefinition::Enum(_))
+            | Some(TypeDefinition::InputObject(_))
+            | None
+                => Vec::new(),
+        }
+    }
+
+    /// Returns the object types which can be of the type
+    ///
+    /// Same as `possible_types`, but returns the definitions: the members
+    /// of a union, the objects implementing an interface or the object
+    /// type itself. Names which aren't defined as object types are
+    /// skipped.
+    ///
+    /// ```rust
+    /// # extern crate graphql_parser;
+    /// use graphql_parser::schema::{parse_schema, SchemaIndex};
+    ///
+    /// let doc = parse_schema::<&str>("
+    ///     type Dog { name: String }
+    ///     type Cat { name: String }
+    ///     union Pet = Dog | Cat
+    /// ").unwrap();
+    /// let schema = SchemaIndex::new(&doc);
+    /// let pets = schema.possible_object_types("Pet");
+    /// assert_eq!(pets.len(), 2);
+    /// assert_eq!(pets[1].name, "Cat");
+    /// ```
+    pub fn possible_object_types(&self, name: &str)
+        -> Vec<&'ast ObjectType<'ast, T>>
+    {
+        self.possible_types(name).into_iter()
+            .filter_map(|name| self.object_type(name))
+            .collect()
+    }
+
+    fn object_type(&self, name: &str) -> Option<&'ast ObjectType<'ast, T>> {
+        match self.find_type(name) {
+            Some(TypeDefinition::Object(obj)) => Some(obj),
+            _ => None,
+        }
+    }
+
+    /// Returns the object types implementing the interface
+    ///
+    /// Objects are returned in the order of the document, including the
+    /// ones declaring the interface in a type extension.
+    ///
+    /// ```rust
+    /// # extern crate graphql_parser;
+    /// use graphql_parser::schema::{parse_schema, SchemaIndex};
+    ///
+    /// let doc = parse_schema::<&str>("
+    ///     interface Pet { name: String }
+    ///     type Dog implements Pet { name: String }
+    ///     type Cat { name: String }
+    ///     extend type Cat implements Pet
+    /// ").unwrap();
+    /// let schema = SchemaIndex::new(&doc);
+    /// let names = schema.implementors("Pet").iter()
+    ///     .map(|obj| obj.name)
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(names, ["Dog", "Cat"]);
+    /// ```
+    pub fn implementors(&self, interface: &str)
+        -> Vec<&'ast ObjectType<'ast, T>>
+    {
+        self.implementations.get(interface).into_iter().flatten()
+            .filter_map(|name| self.object_type(name))
+            .collect()
+    }
+
+    /// Checks whether an object type is one of the possible types of an
+    /// interface or union
+    pub fn is_possible_type(&self, abstract_type: &str, object_type: &str)
+        -> bool
+    {
+        is_abstract(self.find_type(abstract_type))
+            && self.possible_types(abstract_type).contains(&object_type)
+    }
+
+    /// Checks whether an object could be of both types
+    ///
+    /// Equal types always overlap. An interface or union overlaps with
+    /// each of its possible types, and two abstract types overlap if they
+    /// have a possible type in common. Distinct object types never
+    /// overlap. This is the check the `PossibleFragmentSpreads` rule
+    /// makes for every fragment.
+    pub fn do_types_overlap(&self, a: &str, b: &str) -> bool {
+        if a == b {
+            return true;
+        }
+        match (is_abstract(self.find_type(a)),
+               is_abstract(self.find_type(b)))
+        {
+            (true, true) => {
+                let b_possible = self.possible_types(b);
+                self.possible_types(a).iter()
+                    .any(|name| b_possible.contains(name))
+            }
+            (true, false) => self.is_possible_type(a, b),
+            (false, true) => self.is_possible_type(b, a),
+            (false, false) => false,
+        }
+    }
+
+    /// Iterates over all type definitions, in no particular order
+    pub fn types(&self)
+        -> impl Iterator<Item=&'ast TypeDefinition<'ast, T>> + '_
+    {
+        self.types.values().copied()
+    }
+}
+
+fn is_abstract<'ast, T>(typ: Option<&TypeDefinition<'ast, T>>) -> bool
+    where T: Text<'ast>,
+{
+    matches!(typ, Some(TypeDefinition::Interface(_))
+                  | Some(TypeDefinition::Union(_)))
+}
+
+/// Checks whether a fragment with the type condition applies to an object
+/// of the concrete type
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::schema::{parse_schema, fragment_applies, SchemaIndex};
+///
+/// let doc = parse_schema::<&str>("
+///     interface Pet { name: String }
+///     type Dog implements Pet { name: String }
+///     type Cat implements Pet { name: String }
+/// ").unwrap();
+/// let schema = SchemaIndex::new(&doc);
+/// assert!(fragment_applies("Pet", "Dog", &schema));
+/// assert!(!fragment_applies("Cat", "Dog", &schema));
+/// ```
+pub fn fragment_applies<'ast, T>(type_condition: &str, concrete_type: &str,
+    schema: &SchemaIndex<'ast, T>)
+    -> bool
+    where T: Text<'ast>,
+{
+    type_condition == concrete_type
+        || schema.is_possible_type(type_condition, concrete_type)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{fragment_applies, SchemaIndex};
+    use crate::schema::{apply_extensions, parse_schema};
+    use crate::schema::{ObjectType, TypeDefinition};
+    use crate::validation::test_harness::TEST_SCHEMA;
+
+    #[test]
+    fn root_types() {
+        let doc = parse_schema::<&str>("
+            schema { query: Root }
+            type Root { a: Int }
+            type Mutation { b: Int }
+        ").unwrap();
+        let index = SchemaIndex::new(&doc);
+        assert_eq!(index.query_type().map(|t| *t.name()), Some("Root"));
+        // without explicit schema definition `Mutation` is not a root type
+        assert!(index.mutation_type().is_none());
+        assert!(index.find_type("Mutation").is_some());
+    }
+
+    #[test]
+    fn default_root_types() {
+        let doc = parse_schema::<&str>("
+            type Query { a: Int }
+            type Mutation { b: Int }
+            directive @dir on FIELD
+        ").unwrap();
+        let index = SchemaIndex::new(&doc);
+        assert_eq!(index.query_type().map(|t| *t.name()), Some("Query"));
+        assert_eq!(index.mutation_type().map(|t| *t.name()),
+                   Some("Mutation"));
+        assert!(index.subscription_type().is_none());
+        assert!(index.find_directive("dir").is_some());
+    }
+
+    #[test]
+    fn builtin_directives() {
+        let doc = parse_schema::<&str>("
+            type Query { a: Int }
+            directive @skip(when: Boolean) on FIELD
+        ").unwrap();
+        assert!(SchemaIndex::new(&doc).find_directive("include").is_none());
+        let index = SchemaIndex::with_builtin_directives(&doc);
+        let args = |name: &str| index.find_directive(name).unwrap()
+            .arguments.iter().map(|a| a.name).collect::<Vec<_>>();
+        assert_eq!(args("skip"), ["when"]);
+        assert_eq!(args("include"), ["if"]);
+        assert_eq!(args("stream"), ["if", "label", "initialCount"]);
+        assert!(index.defined_directive("include").is_none());
+        assert!(index.find_directive("unknown").is_none());
+    }
+
+    #[test]
+    fn positions() {
+        let doc = parse_schema::<&str>(TEST_SCHEMA).unwrap();
+        let index = SchemaIndex::new(&doc);
+        let line = |line: usize| TEST_SCHEMA.lines().nth(line - 1).unwrap();
+        let human = index.find_type("Human").unwrap();
+        assert_eq!(human.position().column, 1);
+        assert!(line(human.position().line).starts_with("type Human"));
+
+        let command = match index.find_type("DogCommand") {
+            Some(TypeDefinition::Enum(t)) => t,
+            _ => unreachable!(),
+        };
+        let heel = &command.values[1];
+        assert_eq!(heel.position.column, 3);
+        assert_eq!(line(heel.position.line), "  HEEL");
+
+        let input = match index.find_type("ComplexInput") {
+            Some(TypeDefinition::InputObject(t)) => t,
+            _ => unreachable!(),
+        };
+        let field = &input.fields[1];
+        assert_eq!(field.position.column, 3);
+        assert!(line(field.position.line).starts_with("  nonNullField"));
+    }
+
+    #[test]
+    fn fragment_conditions() {
+        let doc = parse_schema::<&str>(TEST_SCHEMA).unwrap();
+        let index = SchemaIndex::new(&doc);
+        assert!(fragment_applies("Pet", "Dog", &index));
+        assert!(fragment_applies("Dog", "Dog", &index));
+        assert!(!fragment_applies("Cat", "Dog", &index));
+        assert!(fragment_applies("CatOrDog", "Dog", &index));
+        assert!(!fragment_applies("HumanOrAlien", "Dog", &index));
+        assert!(!fragment_applies("Unknown", "Dog", &index));
+        assert_eq!(index.possible_types("Pet"), ["Dog", "Cat"]);
+    }
+
+    fn names<'a>(objects: Vec<&ObjectType<'a, &'a str>>) -> Vec<&'a str> {
+        objects.iter().map(|obj| obj.name).collect()
+    }
+
+    #[test]
+    fn abstract_types() {
+        let doc = parse_schema::<&str>(TEST_SCHEMA).unwrap();
+        let index = SchemaIndex::new(&doc);
+        assert_eq!(names(index.implementors("Pet")), ["Dog", "Cat"]);
+        assert_eq!(names(index.implementors("Intelligent")),
+                   ["Human", "Alien"]);
+        assert!(index.implementors("Dog").is_empty());
+        assert_eq!(names(index.possible_object_types("Pet")),
+                   ["Dog", "Cat"]);
+        assert_eq!(names(index.possible_object_types("CatOrDog")),
+                   ["Cat", "Dog"]);
+        assert_eq!(names(index.possible_object_types("HumanOrAlien")),
+                   ["Human", "Alien"]);
+        assert_eq!(names(index.possible_object_types("Dog")), ["Dog"]);
+        assert!(index.possible_object_types("DogCommand").is_empty());
+        assert!(index.is_possible_type("Pet", "Cat"));
+        assert!(index.is_possible_type("CatOrDog", "Dog"));
+        assert!(!index.is_possible_type("Pet", "Human"));
+        assert!(!index.is_possible_type("Dog", "Dog"));
+
+        assert!(index.do_types_overlap("Pet", "Dog"));
+        assert!(index.do_types_overlap("Dog", "Pet"));
+        assert!(index.do_types_overlap("Dog", "Dog"));
+        assert!(index.do_types_overlap("Pet", "CatOrDog"));
+        assert!(index.do_types_overlap("DogOrHuman", "Intelligent"));
+        assert!(!index.do_types_overlap("Human", "Alien"));
+        assert!(!index.do_types_overlap("Pet", "HumanOrAlien"));
+        assert!(!index.do_types_overlap("Cat", "DogOrHuman"));
+    }
+
+    #[test]
+    fn extensions() {
+        let source = "
+            interface Named { name: String }
+            type A implements Named { name: String }
+            type B { name: String }
+            type C { name: String }
+            union U = A
+            extend type B implements Named
+            extend union U = B
+        ";
+        let doc = parse_schema::<&str>(source).unwrap();
+        let index = SchemaIndex::new(&doc);
+        assert_eq!(index.possible_types("Named"), ["A", "B"]);
+        assert_eq!(index.possible_types("U"), ["A", "B"]);
+        assert!(index.do_types_overlap("U", "B"));
+        assert!(!index.do_types_overlap("C", "Named"));
+
+        let mut merged = parse_schema::<&str>(source).unwrap();
+        apply_extensions(&mut merged).unwrap();
+        let merged_index = SchemaIndex::new(&merged);
+        assert_eq!(merged_index.possible_types("Named"), ["A", "B"]);
+        assert_eq!(merged_index.possible_types("U"), ["A", "B"]);
+        assert_eq!(merged_index.implementors("Named").len(), 2);
+        assert_eq!(names(index.possible_object_types("U")), ["A", "B"]);
+        assert_eq!(names(merged_index.possible_object_types("U")),
+                   ["A", "B"]);
+    }
+}
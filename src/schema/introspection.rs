@@ -0,0 +1,847 @@
+//! Conversion between introspection query results and schema documents
+//!
+use serde_json::{json, Map, Value as JsonValue};
+use thiserror::Error;
+
+use combine::{eof, parser, Parser};
+
+use crate::common::default_value;
+use crate::position::Pos;
+use crate::schema::ast::*;
+use crate::schema::built_in::BUILT_IN;
+use crate::schema::grammar::parse_schema;
+use crate::schema::js_print::{Schema, SPECIFIED_DIRECTIVES, SPECIFIED_SCALARS};
+use crate::tokenizer::TokenStream;
+
+
+type Object = Map<String, JsonValue>;
+
+/// Introspection types with the descriptions graphql-js gives them
+const INTROSPECTION_TYPES: &str = include_str!("introspection.graphql");
+
+/// Error converting an introspection result
+///
+/// This structure is opaque for forward compatibility.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("invalid introspection result: {}", message)]
+pub struct IntrospectionError {
+    message: String,
+}
+
+fn error<R>(message: String) -> Result<R, IntrospectionError> {
+    Err(IntrospectionError { message })
+}
+
+/// Returns the schema document described by the result of the standard
+/// introspection query
+///
+/// Both the whole response (`{"data": {"__schema": ...}}`) and its `data`
+/// are accepted. Introspection types, built-in scalars and directives
+/// defined by the specification are omitted, deprecation, `specifiedByURL`
+/// and `isOneOf` are turned into the corresponding directives. Positions
+/// of all the definitions are `Pos::default()`.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// # extern crate serde_json;
+/// use graphql_parser::schema::from_introspection;
+///
+/// let result = serde_json::json!({"__schema": {
+///     "queryType": {"name": "Query"},
+///     "types": [{
+///         "kind": "OBJECT",
+///         "name": "Query",
+///         "fields": [{
+///             "name": "hello",
+///             "args": [],
+///             "type": {"kind": "SCALAR", "name": "String", "ofType": null},
+///         }],
+///         "interfaces": [],
+///     }],
+///     "directives": [],
+/// }});
+/// let doc = from_introspection(&result).unwrap();
+/// assert_eq!(doc.to_string(), "\
+/// schema {
+///   query: Query
+/// }
+///
+/// type Query {
+///   hello: String
+/// }
+/// ");
+/// ```
+pub fn from_introspection(json: &JsonValue)
+    -> Result<Document<'static, String>, IntrospectionError>
+{
+    let data = json.get("data").unwrap_or(json);
+    let schema = match data.get("__schema").and_then(JsonValue::as_object) {
+        Some(schema) => schema,
+        None => return error("`__schema` object is missing".into()),
+    };
+    let mut definitions = Vec::new();
+    definitions.push(Definition::SchemaDefinition(SchemaDefinition {
+        position: Pos::default(),
+        directives: Vec::new(),
+        query: Some(root_type(schema, "queryType")?
+            .ok_or_else(|| IntrospectionError {
+                message: "`queryType` is missing".into(),
+            })?),
+        mutation: root_type(schema, "mutationType")?,
+        subscription: root_type(schema, "subscriptionType")?,
+    }));
+    for dir in array(schema, "directives", "schema")? {
+        let dir = object(dir, "directive")?;
+        let name = string(dir, "name", "directive")?;
+        if !SPECIFIED_DIRECTIVES.contains(&name) {
+            definitions.push(Definition::DirectiveDefinition(
+                directive(dir, name)?));
+        }
+    }
+    for typ in array(schema, "types", "schema")? {
+        let typ = object(typ, "type")?;
+        let name = string(typ, "name", "type")?;
+        if !name.starts_with("__") && !SPECIFIED_SCALARS.contains(&name) {
+            definitions.push(Definition::TypeDefinition(
+                type_definition(typ, name)?));
+        }
+    }
+    Ok(Document { definitions })
+}
+
+fn object<'j>(value: &'j JsonValue, what: &str)
+    -> Result<&'j Object, IntrospectionError>
+{
+    match value.as_object() {
+        Some(object) => Ok(object),
+        None => error(format!("{} must be an object", what)),
+    }
+}
+
+fn string<'j>(object: &'j Object, key: &str, what: &str)
+    -> Result<&'j str, IntrospectionError>
+{
+    match object.get(key).and_then(JsonValue::as_str) {
+        Some(value) => Ok(value),
+        None => error(format!("`{}` of {} must be a string", key, what)),
+    }
+}
+
+fn optional_string(object: &Object, key: &str, what: &str)
+    -> Result<Option<String>, IntrospectionError>
+{
+    match object.get(key) {
+        None | Some(JsonValue::Null) => Ok(None),
+        Some(JsonValue::String(value)) => Ok(Some(value.clone())),
+        Some(_) => error(format!("`{}` of {} must be a string", key, what)),
+    }
+}
+
+fn optional_description(object: &Object, what: &str)
+    -> Result<Option<Description>, IntrospectionError>
+{
+    Ok(optional_string(object, "description", what)?.map(Description::from))
+}
+
+/// Returns the array, an absent or `null` array is empty
+fn array<'j>(object: &'j Object, key: &str, what: &str)
+    -> Result<&'j [JsonValue], IntrospectionError>
+{
+    match object.get(key) {
+        None | Some(JsonValue::Null) => Ok(&[]),
+        Some(JsonValue::Array(items)) => Ok(items),
+        Some(_) => error(format!("`{}` of {} must be an array", key, what)),
+    }
+}
+
+fn root_type(schema: &Object, key: &str)
+    -> Result<Option<String>, IntrospectionError>
+{
+    match schema.get(key) {
+        None | Some(JsonValue::Null) => Ok(None),
+        Some(root) => {
+            let root = object(root, key)?;
+            Ok(Some(string(root, "name", key)?.into()))
+        }
+    }
+}
+
+fn type_ref(value: &JsonValue, what: &str)
+    -> Result<Type<'static, String>, IntrospectionError>
+{
+    let value = object(value, what)?;
+    let of_type = |value: &Object| match value.get("ofType") {
+        Some(inner) if !inner.is_null() => type_ref(inner, what),
+        _ => error(format!("wrapped type of {} is missing", what)),
+    };
+    match string(value, "kind", what)? {
+        "NON_NULL" => Ok(Type::NonNullType(Box::new(of_type(value)?))),
+        "LIST" => Ok(Type::ListType(Box::new(of_type(value)?))),
+        "SCALAR" | "OBJECT" | "INTERFACE" | "UNION" | "ENUM"
+        | "INPUT_OBJECT" => Ok(Type::NamedType(
+            string(value, "name", what)?.into())),
+        kind => error(format!("unknown type kind `{}` in {}", kind, what)),
+    }
+}
+
+fn parse_default(source: &str, what: &str)
+    -> Result<Value<'static, String>, IntrospectionError>
+{
+    parser(default_value::<String>)
+        .skip(eof())
+        .parse(TokenStream::new(source))
+        .map(|(value, _)| value.into_owned())
+        .or_else(|e| error(format!("invalid default value of {}: {}",
+                                   what, e)))
+}
+
+fn deprecation(object: &Object, what: &str)
+    -> Result<Vec<Directive<'static, String>>, IntrospectionError>
+{
+    if object.get("isDeprecated").and_then(JsonValue::as_bool) != Some(true)
+    {
+        return Ok(Vec::new());
+    }
+    let directive = Directive::new("deprecated".into());
+    Ok(vec![match optional_string(object, "deprecationReason", what)? {
+        Some(ref reason) if reason != DEFAULT_DEPRECATION_REASON => {
+            directive.argument("reason".into(), Value::String(reason.clone()))
+        }
+        _ => directive,
+    }])
+}
+
+fn input_values(owner: &Object, key: &str, parent: &str)
+    -> Result<Vec<InputValue<'static, String>>, IntrospectionError>
+{
+    array(owner, key, parent)?.iter()
+        .map(|value| {
+            let value = object(value, parent)?;
+            let name = string(value, "name", parent)?;
+            let what = format!("{}.{}", parent, name);
+            let default_value = optional_string(value, "defaultValue",
+                                                &what)?
+                .map(|source| parse_default(&source, &what))
+                .transpose()?;
+            Ok(InputValue {
+                position: Pos::default(),
+                description: optional_description(value, &what)?,
+                name: name.into(),
+                value_type: type_ref(&value["type"], &what)?,
+                default_value,
+                directives: deprecation(value, &what)?,
+            })
+        })
+        .collect()
+}
+
+fn fields(typ: &Object, parent: &str)
+    -> Result<Vec<Field<'static, String>>, IntrospectionError>
+{
+    array(typ, "fields", parent)?.iter()
+        .map(|field| {
+            let field = object(field, parent)?;
+            let name = string(field, "name", parent)?;
+            let what = format!("{}.{}", parent, name);
+            Ok(Field {
+                position: Pos::default(),
+                description: optional_description(field, &what)?,
+                name: name.into(),
+                arguments: input_values(field, "args", &what)?,
+                field_type: type_ref(&field["type"], &what)?,
+                directives: deprecation(field, &what)?,
+            })
+        })
+        .collect()
+}
+
+/// Returns names of the types in the array
+fn names(typ: &Object, key: &str, parent: &str)
+    -> Result<Vec<String>, IntrospectionError>
+{
+    array(typ, key, parent)?.iter()
+        .map(|item| Ok(string(object(item, parent)?, "name", parent)?.into()))
+        .collect()
+}
+
+fn type_definition(typ: &Object, name: &str)
+    -> Result<TypeDefinition<'static, String>, IntrospectionError>
+{
+    let description = optional_description(typ, name)?;
+    Ok(match string(typ, "kind", name)? {
+        "SCALAR" => {
+            let mut scalar = ScalarType::new(name.into());
+            scalar.description = description;
+            if let Some(url) = optional_string(typ, "specifiedByURL", name)? {
+                scalar.directives.push(Directive::new("specifiedBy".into())
+                    .argument("url".into(), Value::String(url)));
+            }
+            TypeDefinition::Scalar(scalar)
+        }
+        "OBJECT" => {
+            let mut object = ObjectType::new(name.into());
+            object.description = description;
+            object.implements_interfaces = names(typ, "interfaces", name)?;
+            object.fields = fields(typ, name)?;
+            TypeDefinition::Object(object)
+        }
+        "INTERFACE" => {
+            let mut interface = InterfaceType::new(name.into());
+            interface.description = description;
+            interface.fields = fields(typ, name)?;
+            TypeDefinition::Interface(interface)
+        }
+        "UNION" => {
+            let mut union = UnionType::new(name.into());
+            union.description = description;
+            union.types = names(typ, "possibleTypes", name)?;
+            TypeDefinition::Union(union)
+        }
+        "ENUM" => {
+            let mut enm = EnumType::new(name.into());
+            enm.description = description;
+            for value in array(typ, "enumValues", name)? {
+                let value = object(value, name)?;
+                let value_name = string(value, "name", name)?;
+                let what = format!("{}.{}", name, value_name);
+                let mut enum_value = EnumValue::new(value_name.into());
+                enum_value.description = optional_description(value, &what)?;
+                enum_value.directives = deprecation(value, &what)?;
+                enm.values.push(enum_value);
+            }
+            TypeDefinition::Enum(enm)
+        }
+        "INPUT_OBJECT" => {
+            let mut input = InputObjectType::new(name.into());
+            input.description = description;
+            input.fields = input_values(typ, "inputFields", name)?;
+            if typ.get("isOneOf").and_then(JsonValue::as_bool) == Some(true) {
+                input.directives.push(Directive::new("oneOf".into()));
+            }
+            TypeDefinition::InputObject(input)
+        }
+        kind => return error(format!("unknown kind `{}` of type {}",
+                                     kind, name)),
+    })
+}
+
+fn directive(dir: &Object, name: &str)
+    -> Result<DirectiveDefinition<'static, String>, IntrospectionError>
+{
+    let what = format!("@{}", name);
+    let mut result = DirectiveDefinition::new(name.into());
+    result.description = optional_description(dir, &what)?;
+    result.arguments = input_values(dir, "args", &what)?;
+    result.repeatable = dir.get("isRepeatable")
+        .and_then(JsonValue::as_bool)
+        .unwrap_or(false);
+    for location in array(dir, "locations", &what)? {
+        let location = location.as_str()
+            .and_then(|location| location.parse().ok());
+        match location {
+            Some(location) => result.locations.push(location),
+            None => return error(format!("invalid location of {}", what)),
+        }
+    }
+    Ok(result)
+}
+
+/// Returns the result of the standard introspection query for the schema
+///
+/// The result is the `data` of the response as returned by graphql-js for
+/// the schema built from the document, with all the options of
+/// `getIntrospectionQuery` enabled. It includes the introspection types
+/// and the built-in scalars and directives, type extensions are merged
+/// into the extended types. Types are listed in the order graphql-js
+/// collects them, starting from the ones defined in the document.
+///
+/// The document is expected to be valid, undefined types are left out and
+/// references to them have a `null` kind.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// # extern crate serde_json;
+/// use graphql_parser::schema::{parse_schema, to_introspection};
+///
+/// let doc = parse_schema::<&str>("type Query { ids: [ID!] }").unwrap();
+/// let result = to_introspection(&doc);
+/// let query = &result["__schema"]["types"][0];
+/// assert_eq!(query["name"], "Query");
+/// assert_eq!(query["fields"][0]["type"], serde_json::json!({
+///     "kind": "LIST",
+///     "name": null,
+///     "ofType": {
+///         "kind": "NON_NULL",
+///         "name": null,
+///         "ofType": {"kind": "SCALAR", "name": "ID", "ofType": null},
+///     },
+/// }));
+/// ```
+pub fn to_introspection<'a, T>(doc: &Document<'a, T>) -> JsonValue
+    where T: Text<'a>,
+{
+    let built_in = parse_schema::<T>(BUILT_IN)
+        .expect("built-in definitions are valid");
+    let meta = parse_schema::<T>(INTROSPECTION_TYPES)
+        .expect("introspection types are valid");
+    let mut schema = Schema::new(doc);
+    for def in built_in.definitions.iter().chain(&meta.definitions) {
+        if let Definition::TypeDefinition(ref typ) = *def {
+            // specified scalars can't be redefined
+            schema.types.insert(typ.name().as_ref(), typ);
+        }
+    }
+    let roots = root_types(doc, &schema);
+    let directives = directive_definitions(doc, &built_in);
+    let mut introspection = Introspection { schema, types: Vec::new() };
+    introspection.collect_types(doc, &roots, &directives);
+    let root = |name: Option<&str>| name.map(|name| json!({"name": name}));
+    json!({"__schema": {
+        "description": null,
+        "queryType": root(roots[0]),
+        "mutationType": root(roots[1]),
+        "subscriptionType": root(roots[2]),
+        "types": introspection.types.iter()
+            .map(|name| introspection.full_type(name))
+            .collect::<Vec<_>>(),
+        "directives": directives.iter()
+            .map(|dir| introspection.directive(dir))
+            .collect::<Vec<_>>(),
+    }})
+}
+
+/// Returns directives of the document followed by the built-in ones it
+/// doesn't redefine
+fn directive_definitions<'t, 'a, T>(doc: &'t Document<'a, T>,
+    built_in: &'t Document<'a, T>)
+    -> Vec<&'t DirectiveDefinition<'a, T>>
+    where T: Text<'a>,
+{
+    let directives = |doc: &'t Document<'a, T>| {
+        doc.definitions.iter().filter_map(|def| match *def {
+            Definition::DirectiveDefinition(ref dir) => Some(dir),
+            _ => None,
+        })
+    };
+    let mut result = directives(doc).collect::<Vec<_>>();
+    for dir in directives(built_in) {
+        if result.iter().all(|d| d.name.as_ref() != dir.name.as_ref()) {
+            result.push(dir);
+        }
+    }
+    result
+}
+
+/// Returns names of the query, mutation and subscription types
+///
+/// Without a schema definition or extension, these are the types named
+/// `Query`, `Mutation` and `Subscription`.
+fn root_types<'t, 'a, T>(doc: &'t Document<'a, T>,
+    schema: &Schema<'t, 'a, T>)
+    -> [Option<&'t str>; 3]
+    where T: Text<'a>,
+{
+    let mut roots = [None; 3];
+    let mut defined = false;
+    for def in &doc.definitions {
+        let names = match *def {
+            Definition::SchemaDefinition(ref s) => {
+                [&s.query, &s.mutation, &s.subscription]
+            }
+            Definition::SchemaExtension(ref s) => {
+                [&s.query, &s.mutation, &s.subscription]
+            }
+            _ => continue,
+        };
+        defined = true;
+        for (root, name) in roots.iter_mut().zip(&names) {
+            if let Some(ref name) = **name {
+                *root = Some(name.as_ref());
+            }
+        }
+    }
+    if !defined {
+        let names = ["Query", "Mutation", "Subscription"];
+        for (root, name) in roots.iter_mut().zip(&names) {
+            if let Some((&name, _)) = schema.types.get_key_value(name) {
+                *root = Some(name);
+            }
+        }
+    }
+    roots
+}
+
+fn named_type<'t, 'a, T: Text<'a>>(typ: &'t Type<'a, T>) -> &'t str {
+    match *typ {
+        Type::NamedType(ref name) => name.as_ref(),
+        Type::ListType(ref inner) | Type::NonNullType(ref inner) => {
+            named_type(inner)
+        }
+    }
+}
+
+fn kind<'a, T: Text<'a>>(typ: &TypeDefinition<'a, T>) -> &'static str {
+    match *typ {
+        TypeDefinition::Scalar(_) => "SCALAR",
+        TypeDefinition::Object(_) => "OBJECT",
+        TypeDefinition::Interface(_) => "INTERFACE",
+        TypeDefinition::Union(_) => "UNION",
+        TypeDefinition::Enum(_) => "ENUM",
+        TypeDefinition::InputObject(_) => "INPUT_OBJECT",
+    }
+}
+
+/// Sets `isDeprecated` and `deprecationReason` of the item
+fn set_deprecation<'a, T: Text<'a>>(item: &mut JsonValue,
+    directives: &[Directive<'a, T>])
+{
+    let reason = deprecation_reason(directives);
+    item["isDeprecated"] = json!(reason.is_some());
+    item["deprecationReason"] = json!(reason);
+}
+
+struct Introspection<'t, 'a: 't, T: Text<'a>> {
+    schema: Schema<'t, 'a, T>,
+    /// Names of the types in the order graphql-js collects them
+    types: Vec<&'t str>,
+}
+
+impl<'t, 'a: 't, T: Text<'a>> Introspection<'t, 'a, T> {
+    /// Collects the types as the constructor of `GraphQLSchema` does
+    ///
+    /// Each type defined in the document is moved to the end of the list
+    /// and followed by the types it references which aren't listed yet.
+    fn collect_types(&mut self, doc: &'t Document<'a, T>,
+        roots: &[Option<&'t str>],
+        directives: &[&'t DirectiveDefinition<'a, T>])
+    {
+        let mut defined = Vec::new();
+        for def in &doc.definitions {
+            if let Definition::TypeDefinition(ref typ) = *def {
+                let name = typ.name().as_ref();
+                if !defined.contains(&name) {
+                    defined.push(name);
+                }
+            }
+        }
+        let mut types = defined.clone();
+        for name in defined {
+            types.retain(|&t| t != name);
+            self.collect(name, &mut types);
+        }
+        for &root in roots.iter().flatten() {
+            self.collect(root, &mut types);
+        }
+        for dir in directives {
+            for arg in &dir.arguments {
+                self.collect(named_type(&arg.value_type), &mut types);
+            }
+        }
+        self.collect("__Schema", &mut types);
+        self.types = types;
+    }
+
+    fn collect(&self, name: &str, types: &mut Vec<&'t str>) {
+        let (&name, &typ) = match self.schema.types.get_key_value(name) {
+            Some(entry) if !types.contains(entry.0) => entry,
+            _ => return,
+        };
+        types.push(name);
+        match *typ {
+            TypeDefinition::Union(ref t) => {
+                for member in self.schema.union_members(t) {
+                    self.collect(member.as_ref(), types);
+                }
+            }
+            TypeDefinition::Object(ref t) => {
+                for iface in self.schema.object_interfaces(t) {
+                    self.collect(iface.as_ref(), types);
+                }
+                self.collect_fields(&self.schema.object_fields(t), types);
+            }
+            TypeDefinition::Interface(ref t) => {
+                self.collect_fields(&self.schema.interface_fields(t), types);
+            }
+            TypeDefinition::InputObject(ref t) => {
+                for field in self.schema.input_fields(t) {
+                    self.collect(named_type(&field.value_type), types);
+                }
+            }
+            TypeDefinition::Scalar(_) | TypeDefinition::Enum(_) => {}
+        }
+    }
+
+    fn collect_fields(&self, fields: &[&'t Field<'a, T>],
+        types: &mut Vec<&'t str>)
+    {
+        for field in fields {
+            self.collect(named_type(&field.field_type), types);
+            for arg in &field.arguments {
+                self.collect(named_type(&arg.value_type), types);
+            }
+        }
+    }
+
+    fn full_type(&self, name: &str) -> JsonValue {
+        let typ = self.schema.types[name];
+        let mut result = json!({
+            "kind": kind(typ),
+            "name": name,
+            "description": null,
+            "specifiedByURL": null,
+            "isOneOf": null,
+            "fields": null,
+            "inputFields": null,
+            "interfaces": null,
+            "enumValues": null,
+            "possibleTypes": null,
+        });
+        let description = match *typ {
+            TypeDefinition::Scalar(ref t) => {
+                let url = self.schema.scalar_directives(t).into_iter()
+                    .find(|dir| dir.name.as_ref() == "specifiedBy")
+                    .and_then(|dir| dir.get_string("url"));
+                result["specifiedByURL"] = json!(url);
+                &t.description
+            }
+            TypeDefinition::Object(ref t) => {
+                let interfaces = self.schema.object_interfaces(t).into_iter()
+                    .map(|name| self.named_ref(name.as_ref()))
+                    .collect::<Vec<_>>();
+                result["fields"] = self.fields(&self.schema.object_fields(t));
+                result["interfaces"] = json!(interfaces);
+                &t.description
+            }
+            TypeDefinition::Interface(ref t) => {
+                let fields = self.schema.interface_fields(t);
+                result["fields"] = self.fields(&fields);
+                result["interfaces"] = json!([]);
+                result["possibleTypes"] = self.implementations(name);
+                &t.description
+            }
+            TypeDefinition::Union(ref t) => {
+                let members = self.schema.union_members(t).into_iter()
+                    .map(|name| self.named_ref(name.as_ref()))
+                    .collect::<Vec<_>>();
+                result["possibleTypes"] = json!(members);
+                &t.description
+            }
+            TypeDefinition::Enum(ref t) => {
+                let values = self.schema.enum_values(t).into_iter()
+                    .map(|value| {
+                        let mut item = json!({
+                            "name": value.name.as_ref(),
+                            "description": value.description.as_deref(),
+                        });
+                        set_deprecation(&mut item, &value.directives);
+                        item
+                    })
+                    .collect::<Vec<_>>();
+                result["enumValues"] = json!(values);
+                &t.description
+            }
+            TypeDefinition::InputObject(ref t) => {
+                let one_of = self.schema.input_directives(t).iter()
+                    .any(|dir| dir.name.as_ref() == "oneOf");
+                let fields = self.schema.input_fields(t);
+                result["isOneOf"] = json!(one_of);
+                result["inputFields"] = self.input_values(&fields);
+                &t.description
+            }
+        };
+        result["description"] = json!(description.as_deref());
+        result
+    }
+
+    /// Returns object types implementing the interface in the order of
+    /// the types
+    fn implementations(&self, iface: &str) -> JsonValue {
+        self.types.iter()
+            .filter(|&&name| match *self.schema.types[name] {
+                TypeDefinition::Object(ref t) => {
+                    self.schema.object_interfaces(t).iter()
+                        .any(|i| i.as_ref() == iface)
+                }
+                _ => false,
+            })
+            .map(|name| self.named_ref(name))
+            .collect()
+    }
+
+    fn fields(&self, fields: &[&'t Field<'a, T>]) -> JsonValue {
+        fields.iter()
+            .map(|field| {
+                let args = field.arguments.iter().collect::<Vec<_>>();
+                let mut item = json!({
+                    "name": field.name.as_ref(),
+                    "description": field.description.as_deref(),
+                    "args": self.input_values(&args),
+                    "type": self.type_ref(&field.field_type),
+                });
+                set_deprecation(&mut item, &field.directives);
+                item
+            })
+            .collect()
+    }
+
+    fn input_values(&self, values: &[&'t InputValue<'a, T>]) -> JsonValue {
+        values.iter()
+            .map(|value| {
+                let default = value.default_value.as_ref().map(|default| {
+                    self.schema.default_value(default, &value.value_type)
+                });
+                let mut item = json!({
+                    "name": value.name.as_ref(),
+                    "description": value.description.as_deref(),
+                    "type": self.type_ref(&value.value_type),
+                    "defaultValue": default,
+                });
+                set_deprecation(&mut item, &value.directives);
+                item
+            })
+            .collect()
+    }
+
+    fn directive(&self, dir: &'t DirectiveDefinition<'a, T>) -> JsonValue {
+        let locations = dir.locations.iter()
+            .map(|location| location.as_str())
+            .collect::<Vec<_>>();
+        let args = dir.arguments.iter().collect::<Vec<_>>();
+        json!({
+            "name": dir.name.as_ref(),
+            "description": dir.description.as_deref(),
+            "isRepeatable": dir.repeatable,
+            "locations": locations,
+            "args": self.input_values(&args),
+        })
+    }
+
+    fn named_ref(&self, name: &str) -> JsonValue {
+        let kind = self.schema.types.get(name).map(|&typ| kind(typ));
+        json!({"kind": kind, "name": name, "ofType": null})
+    }
+
+    fn type_ref(&self, typ: &Type<'a, T>) -> JsonValue {
+        match *typ {
+            Type::NamedType(ref name) => self.named_ref(name.as_ref()),
+            Type::ListType(ref inner) => json!({
+                "kind": "LIST",
+                "name": null,
+                "ofType": self.type_ref(inner),
+            }),
+            Type::NonNullType(ref inner) => json!({
+                "kind": "NON_NULL",
+                "name": null,
+                "ofType": self.type_ref(inner),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{from_introspection, to_introspection};
+    use crate::schema::{parse_schema, print_graphql_js_style};
+    use crate::validation::test_harness::TEST_SCHEMA;
+
+    #[test]
+    fn unknown_kind() {
+        let result = json!({"__schema": {
+            "queryType": {"name": "Query"},
+            "types": [{"kind": "TABLE", "name": "Query"}],
+            "directives": [],
+        }});
+        assert_eq!(from_introspection(&result).unwrap_err().to_string(),
+                   "invalid introspection result: \
+                    unknown kind `TABLE` of type Query");
+        let result = json!({"__schema": {
+            "queryType": {"name": "Query"},
+            "types": [{"kind": "OBJECT", "name": "Query", "fields": [{
+                "name": "a",
+                "args": [],
+                "type": {"kind": "ARRAY", "name": null, "ofType": null},
+            }]}],
+            "directives": [],
+        }});
+        assert_eq!(from_introspection(&result).unwrap_err().to_string(),
+                   "invalid introspection result: \
+                    unknown type kind `ARRAY` in Query.a");
+    }
+
+    #[test]
+    fn invalid_default() {
+        let result = json!({"__schema": {
+            "queryType": {"name": "Query"},
+            "types": [{"kind": "OBJECT", "name": "Query", "fields": [{
+                "name": "a",
+                "args": [{
+                    "name": "x",
+                    "type": {"kind": "SCALAR", "name": "Int", "ofType": null},
+                    "defaultValue": "[1",
+                }],
+                "type": {"kind": "SCALAR", "name": "Int", "ofType": null},
+            }]}],
+            "directives": [],
+        }});
+        let err = from_introspection(&result).unwrap_err().to_string();
+        assert!(err.starts_with("invalid introspection result: \
+                                 invalid default value of Query.a.x"), "{}",
+                err);
+    }
+
+    #[test]
+    fn missing_schema() {
+        assert_eq!(from_introspection(&json!({"data": null}))
+                   .unwrap_err().to_string(),
+                   "invalid introspection result: \
+                    `__schema` object is missing");
+    }
+
+    #[test]
+    fn test_schema() {
+        let doc = parse_schema::<&str>(TEST_SCHEMA).unwrap();
+        let result = to_introspection(&doc);
+        let schema = &result["__schema"];
+        assert_eq!(schema["queryType"], json!({"name": "QueryRoot"}));
+        assert_eq!(schema["mutationType"], json!(null));
+        let types = schema["types"].as_array().unwrap();
+        let names = types.iter()
+            .map(|typ| typ["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, [
+            "Being", "String", "Boolean", "Mammal", "Pet", "Canine",
+            "DogCommand", "Dog", "Int", "Cat", "CatOrDog", "Intelligent",
+            "Human", "Alien", "DogOrHuman", "HumanOrAlien", "FurColor",
+            "ComplexInput", "ComplicatedArgs", "Float", "ID", "QueryRoot",
+            "__Schema", "__Type", "__TypeKind", "__Field", "__InputValue",
+            "__EnumValue", "__Directive", "__DirectiveLocation",
+        ]);
+        let implementations = types[0]["possibleTypes"].as_array().unwrap()
+            .iter()
+            .map(|typ| typ["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(implementations, ["Dog", "Cat", "Human", "Alien"]);
+        let dog = &types[7];
+        assert_eq!(dog["fields"][5]["args"][0], json!({
+            "name": "atOtherHomes",
+            "description": null,
+            "type": {"kind": "SCALAR", "name": "Boolean", "ofType": null},
+            "defaultValue": "true",
+            "isDeprecated": false,
+            "deprecationReason": null,
+        }));
+        assert_eq!(dog["isOneOf"], json!(null));
+        let directives = schema["directives"].as_array().unwrap().iter()
+            .map(|dir| dir["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(directives, [
+            "onField", "directiveWithArgs",
+            "include", "skip", "deprecated", "specifiedBy", "oneOf",
+        ]);
+        let rebuilt = from_introspection(&result).unwrap();
+        assert_eq!(print_graphql_js_style(&rebuilt),
+                   print_graphql_js_style(&doc));
+    }
+}
@@ -0,0 +1,547 @@
+//! Schema printing compatible with `printSchema` of graphql-js
+//!
+use std::collections::HashMap;
+
+use crate::common::{Directive, Text, Type, Value};
+use crate::persisted::{block_string, join, string, value, wrap};
+use crate::schema::ast::*;
+
+
+/// Scalars which graphql-js doesn't print
+pub(crate) const SPECIFIED_SCALARS: &[&str] = &[
+    "String", "Int", "Float", "Boolean", "ID",
+];
+
+/// Directives which graphql-js doesn't print
+pub(crate) const SPECIFIED_DIRECTIVES: &[&str] = &[
+    "include", "skip", "deprecated", "specifiedBy", "oneOf",
+];
+
+/// Type definitions of the document along with their extensions
+pub(crate) struct Schema<'t, 'a: 't, T: Text<'a>> {
+    pub(crate) types: HashMap<&'t str, &'t TypeDefinition<'a, T>>,
+    extensions: HashMap<&'t str, Vec<&'t TypeExtension<'a, T>>>,
+}
+
+/// Returns the schema printed as by `printSchema` of graphql-js 16
+///
+/// The document is printed the way graphql-js prints the schema built
+/// from it by `buildSchema`:
+///
+/// * type extensions are merged into the extended types;
+/// * the schema definition goes first and is omitted if the root types
+///   are named `Query`, `Mutation` and `Subscription`;
+/// * built-in scalars and directives are omitted;
+/// * of all directives applied, only `@deprecated`, `@specifiedBy` and
+///   `@oneOf` are printed;
+/// * descriptions are block strings unless they can't be printed as such;
+/// * arguments are put on their own lines if any of them has a
+///   description;
+/// * default values are printed as coerced to their type.
+///
+/// Like graphql-js, definitions are separated by an empty line and there
+/// is no newline at the end.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::schema::{parse_schema, print_graphql_js_style};
+///
+/// let doc = parse_schema::<&str>(r#"
+///     type Query { "Hello" hello(name: String = "world"): String }
+/// "#).unwrap();
+/// assert_eq!(print_graphql_js_style(&doc), "\
+/// type Query {
+///   \"\"\"Hello\"\"\"
+///   hello(name: String = \"world\"): String
+/// }");
+/// ```
+pub fn print_graphql_js_style<'a, T>(doc: &Document<'a, T>) -> String
+    where T: Text<'a>,
+{
+    let schema = Schema::new(doc);
+    let mut items = Vec::new();
+    items.extend(schema_definition(doc));
+    for def in &doc.definitions {
+        if let Definition::DirectiveDefinition(ref dir) = *def {
+            if !SPECIFIED_DIRECTIVES.contains(&dir.name.as_ref()) {
+                items.push(schema.directive_definition(dir));
+            }
+        }
+    }
+    for def in &doc.definitions {
+        if let Definition::TypeDefinition(ref typ) = *def {
+            let name = typ.name().as_ref();
+            if !name.starts_with("__") && !SPECIFIED_SCALARS.contains(&name)
+            {
+                items.push(schema.type_definition(typ));
+            }
+        }
+    }
+    join(items, "\n\n")
+}
+
+fn schema_definition<'a, T>(doc: &Document<'a, T>) -> Option<String>
+    where T: Text<'a>,
+{
+    let schema = doc.definitions.iter().find_map(|def| match *def {
+        Definition::SchemaDefinition(ref schema) => Some(schema),
+        _ => None,
+    })?;
+    let roots = [
+        ("query", &schema.query, "Query"),
+        ("mutation", &schema.mutation, "Mutation"),
+        ("subscription", &schema.subscription, "Subscription"),
+    ];
+    let common_names = roots.iter().all(|&(_, name, common)| {
+        name.as_ref().is_none_or(|name| name.as_ref() == common)
+    });
+    if common_names {
+        return None;
+    }
+    let operations = roots.iter().filter_map(|&(operation, name, _)| {
+        name.as_ref()
+            .map(|name| format!("  {}: {}", operation, name.as_ref()))
+    });
+    Some(format!("schema {{\n{}\n}}", join(operations, "\n")))
+}
+
+impl<'t, 'a: 't, T: Text<'a>> Schema<'t, 'a, T> {
+    pub(crate) fn new(doc: &'t Document<'a, T>) -> Self {
+        let mut schema = Schema {
+            types: HashMap::new(),
+            extensions: HashMap::new(),
+        };
+        for def in &doc.definitions {
+            match *def {
+                Definition::TypeDefinition(ref typ) => {
+                    schema.types.insert(typ.name().as_ref(), typ);
+                }
+                Definition::TypeExtension(ref ext) => {
+                    schema.extensions.entry(ext.name().as_ref())
+                        .or_default()
+                        .push(ext);
+                }
+                _ => {}
+            }
+        }
+        schema
+    }
+
+    /// Returns the items of the type followed by the ones added by its
+    /// extensions
+    fn merged<X, F>(&self, name: &str, items: &'t [X], extension_items: F)
+        -> Vec<&'t X>
+        where F: Fn(&'t TypeExtension<'a, T>) -> Option<&'t [X]>,
+    {
+        let extensions = self.extensions.get(name).into_iter()
+            .flatten()
+            .filter_map(|&ext| extension_items(ext))
+            .flatten();
+        items.iter().chain(extensions).collect()
+    }
+
+    fn directive_definition(&self, dir: &'t DirectiveDefinition<'a, T>)
+        -> String
+    {
+        let locations = dir.locations.iter()
+            .map(|location| location.as_str().to_string());
+        format!("{}directive @{}{}{} on {}",
+            description(&dir.description, "", true),
+            dir.name.as_ref(),
+            self.arguments(&dir.arguments, ""),
+            if dir.repeatable { " repeatable" } else { "" },
+            join(locations, " | "))
+    }
+
+    fn type_definition(&self, typ: &'t TypeDefinition<'a, T>) -> String {
+        let name = typ.name().as_ref();
+        match *typ {
+            TypeDefinition::Scalar(ref t) => {
+                format!("{}scalar {}{}",
+                    description(&t.description, "", true),
+                    name,
+                    specified_by(&self.scalar_directives(t)))
+            }
+            TypeDefinition::Object(ref t) => {
+                let interfaces = self.object_interfaces(t).into_iter()
+                    .map(|name| name.as_ref().to_string());
+                format!("{}type {}{}{}",
+                    description(&t.description, "", true),
+                    name,
+                    wrap(" implements ", &join(interfaces, " & "), ""),
+                    self.fields(&self.object_fields(t)))
+            }
+            TypeDefinition::Interface(ref t) => {
+                format!("{}interface {}{}",
+                    description(&t.description, "", true),
+                    name,
+                    self.fields(&self.interface_fields(t)))
+            }
+            TypeDefinition::Union(ref t) => {
+                let members = self.union_members(t).into_iter()
+                    .map(|name| name.as_ref().to_string());
+                format!("{}union {}{}",
+                    description(&t.description, "", true),
+                    name,
+                    wrap(" = ", &join(members, " | "), ""))
+            }
+            TypeDefinition::Enum(ref t) => {
+                let values = self.enum_values(t).into_iter().enumerate()
+                    .map(|(i, val)| {
+                        format!("{}  {}{}",
+                            description(&val.description, "  ", i == 0),
+                            val.name.as_ref(),
+                            deprecated(&val.directives))
+                    });
+                format!("{}enum {}{}",
+                    description(&t.description, "", true),
+                    name,
+                    block(values))
+            }
+            TypeDefinition::InputObject(ref t) => {
+                let one_of = self.input_directives(t).iter()
+                    .any(|dir| dir.name.as_ref() == "oneOf");
+                let fields = self.input_fields(t).into_iter().enumerate()
+                    .map(|(i, field)| {
+                        format!("{}  {}",
+                            description(&field.description, "  ", i == 0),
+                            self.input_value(field))
+                    });
+                format!("{}input {}{}{}",
+                    description(&t.description, "", true),
+                    name,
+                    if one_of { " @oneOf" } else { "" },
+                    block(fields))
+            }
+        }
+    }
+
+    pub(crate) fn scalar_directives(&self, scalar: &'t ScalarType<'a, T>)
+        -> Vec<&'t Directive<'a, T>>
+    {
+        self.merged(scalar.name.as_ref(), &scalar.directives,
+            |ext| match *ext {
+                TypeExtension::Scalar(ref e) => Some(&e.directives[..]),
+                _ => None,
+            })
+    }
+
+    pub(crate) fn object_interfaces(&self, object: &'t ObjectType<'a, T>)
+        -> Vec<&'t T::Value>
+    {
+        self.merged(object.name.as_ref(), &object.implements_interfaces,
+            |ext| match *ext {
+                TypeExtension::Object(ref e) => {
+                    Some(&e.implements_interfaces[..])
+                }
+                _ => None,
+            })
+    }
+
+    pub(crate) fn object_fields(&self, object: &'t ObjectType<'a, T>)
+        -> Vec<&'t Field<'a, T>>
+    {
+        self.merged(object.name.as_ref(), &object.fields, |ext| match *ext {
+            TypeExtension::Object(ref e) => Some(&e.fields[..]),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn interface_fields(&self, iface: &'t InterfaceType<'a, T>)
+        -> Vec<&'t Field<'a, T>>
+    {
+        self.merged(iface.name.as_ref(), &iface.fields, |ext| match *ext {
+            TypeExtension::Interface(ref e) => Some(&e.fields[..]),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn union_members(&self, union: &'t UnionType<'a, T>)
+        -> Vec<&'t T::Value>
+    {
+        self.merged(union.name.as_ref(), &union.types, |ext| match *ext {
+            TypeExtension::Union(ref e) => Some(&e.types[..]),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn enum_values(&self, enm: &'t EnumType<'a, T>)
+        -> Vec<&'t EnumValue<'a, T>>
+    {
+        self.merged(enm.name.as_ref(), &enm.values, |ext| match *ext {
+            TypeExtension::Enum(ref e) => Some(&e.values[..]),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn input_directives(&self, input: &'t InputObjectType<'a, T>)
+        -> Vec<&'t Directive<'a, T>>
+    {
+        self.merged(input.name.as_ref(), &input.directives,
+            |ext| match *ext {
+                TypeExtension::InputObject(ref e) => Some(&e.directives[..]),
+                _ => None,
+            })
+    }
+
+    pub(crate) fn input_fields(&self, input: &'t InputObjectType<'a, T>)
+        -> Vec<&'t InputValue<'a, T>>
+    {
+        self.merged(input.name.as_ref(), &input.fields, |ext| match *ext {
+            TypeExtension::InputObject(ref e) => Some(&e.fields[..]),
+            _ => None,
+        })
+    }
+
+    fn fields(&self, fields: &[&'t Field<'a, T>]) -> String {
+        block(fields.iter().enumerate().map(|(i, field)| {
+            format!("{}  {}{}: {}{}",
+                description(&field.description, "  ", i == 0),
+                field.name.as_ref(),
+                self.arguments(&field.arguments, "  "),
+                field.field_type,
+                deprecated(&field.directives))
+        }))
+    }
+
+    fn arguments(&self, args: &'t [InputValue<'a, T>], indentation: &str)
+        -> String
+    {
+        if args.is_empty() {
+            return String::new();
+        }
+        // graphql-js treats empty descriptions as missing here
+        let described = args.iter()
+            .any(|arg| !arg.description.as_deref().unwrap_or("").is_empty());
+        if !described {
+            let args = args.iter().map(|arg| self.input_value(arg));
+            return format!("({})", join(args, ", "));
+        }
+        let arg_indentation = format!("  {}", indentation);
+        let lines = args.iter().enumerate().map(|(i, arg)| {
+            format!("{}{}{}",
+                description(&arg.description, &arg_indentation, i == 0),
+                arg_indentation,
+                self.input_value(arg))
+        });
+        format!("(\n{}\n{})",
+            lines.collect::<Vec<_>>().join("\n"), indentation)
+    }
+
+    fn input_value(&self, arg: &'t InputValue<'a, T>) -> String {
+        let default = arg.default_value.as_ref()
+            .map(|val| self.default_value(val, &arg.value_type))
+            .unwrap_or_default();
+        format!("{}: {}{}{}",
+            arg.name.as_ref(),
+            arg.value_type,
+            wrap(" = ", &default, ""),
+            deprecated(&arg.directives))
+    }
+
+    /// Prints the value as coerced to the type and back, as graphql-js
+    /// does with default values
+    pub(crate) fn default_value(&self, val: &'t Value<'a, T>,
+        typ: &'t Type<'a, T>)
+        -> String
+    {
+        match (typ, val) {
+            (Type::NonNullType(inner), _) => {
+                self.default_value(val, inner)
+            }
+            (_, Value::Null) => "null".into(),
+            (Type::ListType(inner), Value::List(items)) => {
+                let items = items.iter()
+                    .map(|item| self.default_value(item, inner));
+                format!("[{}]", join(items, ", "))
+            }
+            (Type::ListType(inner), _) => {
+                format!("[{}]", self.default_value(val, inner))
+            }
+            (Type::NamedType(name), Value::Object(values)) => {
+                let input = match self.types.get(name.as_ref()) {
+                    Some(TypeDefinition::InputObject(input)) => input,
+                    _ => return value(val),
+                };
+                // fields are ordered as in the type, missing ones get
+                // their default values
+                let fields = self.input_fields(input).into_iter()
+                    .filter_map(|field| {
+                        let val = values.get(field.name.as_ref())
+                            .or(field.default_value.as_ref())?;
+                        Some(format!("{}: {}", field.name.as_ref(),
+                            self.default_value(val, &field.value_type)))
+                    });
+                format!("{{{}}}", join(fields, ", "))
+            }
+            (Type::NamedType(name), Value::String(s))
+            if name.as_ref() == "ID" && is_integer(s)
+            => s.clone(),
+            (_, Value::String(s)) | (_, Value::BlockString(s)) => {
+                string(s)
+            }
+            // numbers are printed as JavaScript does, `1.0` becomes `1`
+            // and `-0` becomes `0`
+            (_, Value::Float(num)) => format!("{}", num.as_f64() + 0.0),
+            (_, Value::Int(_)) => val.to_string(),
+            _ => value(val),
+        }
+    }
+}
+
+/// Returns the string value of the directive argument
+///
+/// The outer `Option` is `None` if the argument is missing, the inner one
+/// if it's `null`.
+fn string_argument<'d, 'a, T>(dir: &'d Directive<'a, T>, name: &str)
+    -> Option<Option<&'d str>>
+    where T: Text<'a>,
+{
+    let (_, val) = dir.arguments.iter()
+        .find(|(arg, _)| arg.as_ref() == name)?;
+    match *val {
+        Value::String(ref s) | Value::BlockString(ref s) => Some(Some(s)),
+        _ => Some(None),
+    }
+}
+
+fn deprecated<'a, T: Text<'a>>(directives: &[Directive<'a, T>]) -> String {
+    match deprecation_reason(directives) {
+        None => String::new(),
+        Some(DEFAULT_DEPRECATION_REASON) => " @deprecated".into(),
+        Some(reason) => format!(" @deprecated(reason: {})", string(reason)),
+    }
+}
+
+fn specified_by<'a, T: Text<'a>>(directives: &[&Directive<'a, T>])
+    -> String
+{
+    directives.iter()
+        .find(|dir| dir.name.as_ref() == "specifiedBy")
+        .and_then(|dir| string_argument(dir, "url"))
+        .and_then(|url| url)
+        .map(|url| format!(" @specifiedBy(url: {})", string(url)))
+        .unwrap_or_default()
+}
+
+/// Joins lines into a block, as `printBlock` of graphql-js
+fn block<I: Iterator<Item=String>>(lines: I) -> String {
+    let lines = lines.collect::<Vec<_>>();
+    if lines.is_empty() {
+        String::new()
+    } else {
+        format!(" {{\n{}\n}}", lines.join("\n"))
+    }
+}
+
+fn is_integer(value: &str) -> bool {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    !digits.is_empty()
+        && digits.bytes().all(|c| c.is_ascii_digit())
+        && (digits == "0" || !digits.starts_with('0'))
+}
+
+fn description(description: &Option<Description>, indentation: &str,
+    first_in_block: bool)
+    -> String
+{
+    let description = match *description {
+        Some(ref description) => description,
+        None => return String::new(),
+    };
+    let quoted = if is_printable_as_block_string(description) {
+        block_string(description)
+    } else {
+        string(description)
+    };
+    let prefix = if !indentation.is_empty() && !first_in_block {
+        format!("\n{}", indentation)
+    } else {
+        indentation.to_string()
+    };
+    format!("{}{}\n", prefix,
+        quoted.replace('\n', &format!("\n{}", indentation)))
+}
+
+/// Checks whether the block string would read back as the same value, as
+/// `isPrintableAsBlockString` of graphql-js
+fn is_printable_as_block_string(value: &str) -> bool {
+    if value.is_empty() {
+        return true;
+    }
+    let mut is_empty_line = true;
+    let mut has_indent = false;
+    let mut has_common_indent = true;
+    let mut seen_non_empty_line = false;
+    for c in value.chars() {
+        match c {
+            '\r' => return false,
+            '\n' => {
+                if is_empty_line && !seen_non_empty_line {
+                    // leading empty line
+                    return false;
+                }
+                seen_non_empty_line = true;
+                is_empty_line = true;
+                has_indent = false;
+            }
+            '\t' | ' ' => has_indent = has_indent || is_empty_line,
+            '\u{0}'..='\u{1f}' => return false,
+            _ => {
+                has_common_indent = has_common_indent && has_indent;
+                is_empty_line = false;
+            }
+        }
+    }
+    // trailing empty line, or the indentation common to all lines
+    !(is_empty_line || has_common_indent && seen_non_empty_line)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{print_graphql_js_style, is_printable_as_block_string};
+    use crate::schema::parse_schema;
+
+    fn print(schema: &str) -> String {
+        print_graphql_js_style(&parse_schema::<&str>(schema).unwrap())
+    }
+
+    #[test]
+    fn block_strings() {
+        assert!(is_printable_as_block_string(""));
+        assert!(is_printable_as_block_string("a\n  b"));
+        assert!(is_printable_as_block_string("\"quoted\""));
+        assert!(!is_printable_as_block_string("\na"));
+        assert!(!is_printable_as_block_string("a\n"));
+        assert!(!is_printable_as_block_string("  a\n  b"));
+        assert!(!is_printable_as_block_string("a\r\nb"));
+        assert!(!is_printable_as_block_string("a\u{1}"));
+    }
+
+    #[test]
+    fn default_values() {
+        assert_eq!(print(r#"
+            input Point { x: Float = 1.0, y: Float = 0.5, z: Int = 0 }
+            type Query {
+                a(ids: [ID] = "1", p: Point = {y: 2}, s: String = """x"""): ID
+            }
+        "#), "\
+input Point {
+  x: Float = 1
+  y: Float = 0.5
+  z: Int = 0
+}
+
+type Query {
+  a(ids: [ID] = [1], p: Point = {x: 1, y: 2, z: 0}, s: String = \"x\"): ID
+}");
+    }
+
+    #[test]
+    fn root_types() {
+        assert_eq!(print("schema { query: Query } type Query { a: Int }"),
+                   "type Query {\n  a: Int\n}");
+        assert_eq!(print("type Q { a: Int } schema { query: Q }"),
+                   "schema {\n  query: Q\n}\n\ntype Q {\n  a: Int\n}");
+    }
+}
@@ -0,0 +1,495 @@
+//! Conversion of schema AST between text types
+//!
+use crate::common::map_directives;
+use crate::schema::ast::*;
+
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> Document<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        Document {
+            definitions: self.definitions.into_iter()
+                .map(|def| def.map_text(f))
+                .collect(),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Definition<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> Definition<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        match self {
+            Definition::SchemaDefinition(def) => {
+                Definition::SchemaDefinition(def.map_text(f))
+            }
+            Definition::SchemaExtension(ext) => {
+                Definition::SchemaExtension(ext.map_text(f))
+            }
+            Definition::TypeDefinition(def) => {
+                Definition::TypeDefinition(def.map_text(f))
+            }
+            Definition::TypeExtension(ext) => {
+                Definition::TypeExtension(ext.map_text(f))
+            }
+            Definition::DirectiveDefinition(def) => {
+                Definition::DirectiveDefinition(def.map_text(f))
+            }
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> SchemaDefinition<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F)
+        -> SchemaDefinition<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        SchemaDefinition {
+            position: self.position,
+            directives: map_directives(self.directives, f),
+            query: self.query.map(&mut *f),
+            mutation: self.mutation.map(&mut *f),
+            subscription: self.subscription.map(&mut *f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> SchemaExtension<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F)
+        -> SchemaExtension<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        SchemaExtension {
+            position: self.position,
+            directives: map_directives(self.directives, f),
+            query: self.query.map(&mut *f),
+            mutation: self.mutation.map(&mut *f),
+            subscription: self.subscription.map(&mut *f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> TypeDefinition<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> TypeDefinition<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        match self {
+            TypeDefinition::Scalar(t) => TypeDefinition::Scalar(t.map_text(f)),
+            TypeDefinition::Object(t) => TypeDefinition::Object(t.map_text(f)),
+            TypeDefinition::Interface(t) => {
+                TypeDefinition::Interface(t.map_text(f))
+            }
+            TypeDefinition::Union(t) => TypeDefinition::Union(t.map_text(f)),
+            TypeDefinition::Enum(t) => TypeDefinition::Enum(t.map_text(f)),
+            TypeDefinition::InputObject(t) => {
+                TypeDefinition::InputObject(t.map_text(f))
+            }
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> TypeExtension<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> TypeExtension<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        match self {
+            TypeExtension::Scalar(t) => TypeExtension::Scalar(t.map_text(f)),
+            TypeExtension::Object(t) => TypeExtension::Object(t.map_text(f)),
+            TypeExtension::Interface(t) => {
+                TypeExtension::Interface(t.map_text(f))
+            }
+            TypeExtension::Union(t) => TypeExtension::Union(t.map_text(f)),
+            TypeExtension::Enum(t) => TypeExtension::Enum(t.map_text(f)),
+            TypeExtension::InputObject(t) => {
+                TypeExtension::InputObject(t.map_text(f))
+            }
+        }
+    }
+}
+
+fn map_names<'a, 'b, T, U, F>(names: Vec<T::Value>, f: &mut F)
+    -> Vec<U::Value>
+    where T: Text<'a>,
+          U: Text<'b>,
+          F: FnMut(T::Value) -> U::Value,
+{
+    names.into_iter().map(f).collect()
+}
+
+fn map_fields<'a, 'b, T, U, F>(fields: Vec<Field<'a, T>>, f: &mut F)
+    -> Vec<Field<'b, U>>
+    where T: Text<'a>,
+          U: Text<'b>,
+          F: FnMut(T::Value) -> U::Value,
+{
+    fields.into_iter().map(|field| field.map_text(f)).collect()
+}
+
+fn map_input_values<'a, 'b, T, U, F>(values: Vec<InputValue<'a, T>>,
+    f: &mut F)
+    -> Vec<InputValue<'b, U>>
+    where T: Text<'a>,
+          U: Text<'b>,
+          F: FnMut(T::Value) -> U::Value,
+{
+    values.into_iter().map(|value| value.map_text(f)).collect()
+}
+
+fn map_enum_values<'a, 'b, T, U, F>(values: Vec<EnumValue<'a, T>>,
+    f: &mut F)
+    -> Vec<EnumValue<'b, U>>
+    where T: Text<'a>,
+          U: Text<'b>,
+          F: FnMut(T::Value) -> U::Value,
+{
+    values.into_iter().map(|value| value.map_text(f)).collect()
+}
+
+impl<'a, T: Text<'a>> ScalarType<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> ScalarType<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        ScalarType {
+            position: self.position,
+            description: self.description,
+            name: f(self.name),
+            directives: map_directives(self.directives, f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> ScalarTypeExtension<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F)
+        -> ScalarTypeExtension<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        ScalarTypeExtension {
+            position: self.position,
+            name: f(self.name),
+            directives: map_directives(self.directives, f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> ObjectType<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> ObjectType<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        ObjectType {
+            position: self.position,
+            description: self.description,
+            name: f(self.name),
+            implements_interfaces: map_names::<T, U, F>(
+                self.implements_interfaces, f),
+            directives: map_directives(self.directives, f),
+            fields: map_fields(self.fields, f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> ObjectTypeExtension<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F)
+        -> ObjectTypeExtension<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        ObjectTypeExtension {
+            position: self.position,
+            name: f(self.name),
+            implements_interfaces: map_names::<T, U, F>(
+                self.implements_interfaces, f),
+            directives: map_directives(self.directives, f),
+            fields: map_fields(self.fields, f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Field<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> Field<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        Field {
+            position: self.position,
+            description: self.description,
+            name: f(self.name),
+            arguments: map_input_values(self.arguments, f),
+            field_type: self.field_type.map_text(f),
+            directives: map_directives(self.directives, f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> InputValue<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> InputValue<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        InputValue {
+            position: self.position,
+            description: self.description,
+            name: f(self.name),
+            value_type: self.value_type.map_text(f),
+            default_value: self.default_value.map(|value| value.map_text(f)),
+            directives: map_directives(self.directives, f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> InterfaceType<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> InterfaceType<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        InterfaceType {
+            position: self.position,
+            description: self.description,
+            name: f(self.name),
+            directives: map_directives(self.directives, f),
+            fields: map_fields(self.fields, f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> InterfaceTypeExtension<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F)
+        -> InterfaceTypeExtension<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        InterfaceTypeExtension {
+            position: self.position,
+            name: f(self.name),
+            directives: map_directives(self.directives, f),
+            fields: map_fields(self.fields, f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> UnionType<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> UnionType<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        UnionType {
+            position: self.position,
+            description: self.description,
+            name: f(self.name),
+            directives: map_directives(self.directives, f),
+            types: map_names::<T, U, F>(self.types, f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> UnionTypeExtension<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F)
+        -> UnionTypeExtension<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        UnionTypeExtension {
+            position: self.position,
+            name: f(self.name),
+            directives: map_directives(self.directives, f),
+            types: map_names::<T, U, F>(self.types, f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> EnumType<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> EnumType<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        EnumType {
+            position: self.position,
+            description: self.description,
+            name: f(self.name),
+            directives: map_directives(self.directives, f),
+            values: map_enum_values(self.values, f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> EnumValue<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F) -> EnumValue<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        EnumValue {
+            position: self.position,
+            description: self.description,
+            name: f(self.name),
+            directives: map_directives(self.directives, f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> EnumTypeExtension<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F)
+        -> EnumTypeExtension<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        EnumTypeExtension {
+            position: self.position,
+            name: f(self.name),
+            directives: map_directives(self.directives, f),
+            values: map_enum_values(self.values, f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> InputObjectType<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F)
+        -> InputObjectType<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        InputObjectType {
+            position: self.position,
+            description: self.description,
+            name: f(self.name),
+            directives: map_directives(self.directives, f),
+            fields: map_input_values(self.fields, f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> InputObjectTypeExtension<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F)
+        -> InputObjectTypeExtension<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        InputObjectTypeExtension {
+            position: self.position,
+            name: f(self.name),
+            directives: map_directives(self.directives, f),
+            fields: map_input_values(self.fields, f),
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> DirectiveDefinition<'a, T> {
+    pub(crate) fn map_text<'b, U, F>(self, f: &mut F)
+        -> DirectiveDefinition<'b, U>
+        where U: Text<'b>,
+              F: FnMut(T::Value) -> U::Value,
+    {
+        DirectiveDefinition {
+            position: self.position,
+            description: self.description,
+            name: f(self.name),
+            arguments: map_input_values(self.arguments, f),
+            repeatable: self.repeatable,
+            locations: self.locations,
+        }
+    }
+}
+
+impl_map_text!(Document, Definition, SchemaDefinition, TypeDefinition,
+    TypeExtension, ScalarType, ScalarTypeExtension, ObjectType,
+    ObjectTypeExtension, Field, InputValue, InterfaceType,
+    InterfaceTypeExtension, UnionType, UnionTypeExtension, EnumType,
+    EnumValue, EnumTypeExtension, InputObjectType, InputObjectTypeExtension,
+    DirectiveDefinition);
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use crate::schema::{parse_schema, Document};
+
+    fn parse_owned(source: &str) -> Document<'static, String> {
+        let source = source.to_string();
+        let doc = parse_schema::<&str>(&source).unwrap();
+        doc.into_owned()
+    }
+
+    #[test]
+    fn outlives_source() {
+        fn assert_send_static<T: Send + 'static>(_: &T) {}
+
+        let doc = parse_owned("schema { query: Query }
+            \"The root\"
+            type Query implements Node @key(fields: \"id\") {
+              user(id: ID! = \"1\", kinds: [Kind] = [ADMIN]): User
+            }
+            union Result = User | Error
+            enum Kind { ADMIN USER }
+            input Filter { kind: Kind }
+            directive @key(fields: String!) on OBJECT
+            extend scalar Date @format");
+        assert_send_static(&doc);
+        assert_eq!(doc.to_string(), "\
+schema {
+  query: Query
+}
+
+\"The root\"
+type Query implements Node @key(fields: \"id\") {
+  user(id: ID! = \"1\", kinds: [Kind] = [ADMIN]): User
+}
+
+union Result = User | Error
+
+enum Kind {
+  ADMIN
+  USER
+}
+
+input Filter {
+  kind: Kind
+}
+
+directive @key(fields: String!) on OBJECT
+
+extend scalar Date @format
+");
+    }
+
+    #[test]
+    fn map_between_text_types() {
+        let source = "\"Users\" type User implements Node {
+            id: ID! @deprecated(reason: \"no\")
+            friends(first: Int = 10, order: Order = ASC): [User!]
+        }";
+        let doc = parse_schema::<&str>(source).unwrap();
+        let expected = doc.to_string();
+        let cow: Document<Cow<str>> = doc.map(Cow::Borrowed);
+        assert_eq!(cow.to_string(), expected);
+        let owned: Document<String> = cow.map(Cow::into_owned);
+        assert_eq!(owned.to_string(), expected);
+    }
+
+    #[test]
+    #[cfg(feature = "smolstr")]
+    fn smol_str() {
+        use smol_str::SmolStr;
+
+        use crate::schema::{SchemaIndex, TypeDefinition};
+        use crate::validation::test_harness::TEST_SCHEMA;
+
+        let doc = parse_schema::<SmolStr>(TEST_SCHEMA).unwrap();
+        let index = SchemaIndex::new(&doc);
+        let human = match index.find_type("Human") {
+            Some(TypeDefinition::Object(obj)) => obj,
+            _ => unreachable!(),
+        };
+        assert_eq!(human.name, "Human");
+        assert!(!human.name.is_heap_allocated());
+        assert!(human.fields.iter()
+            .all(|field| !field.name.is_heap_allocated()));
+    }
+}
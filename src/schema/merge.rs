@@ -0,0 +1,703 @@
+//! Merging of schemas split into several documents
+//!
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::position::Pos;
+use crate::schema::ast::*;
+use crate::schema::extend::{apply_extensions, definition_keyword};
+use crate::schema::extend::{definition_members, extension_members};
+
+
+/// Error merging schemas: a type, field, directive or root operation type
+/// is defined differently in both documents
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("{message}")]
+pub struct MergeError {
+    message: String,
+    name: String,
+    positions: (Pos, Pos),
+    sources: Option<Box<(String, String)>>,
+}
+
+/// Options of `merge_schema_files`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeOptions {
+    /// Keep only the first of the scalar and enum definitions which are
+    /// printed exactly the same, instead of reporting a conflict
+    ///
+    /// Enabled by default, as files commonly declare the custom scalars
+    /// they use.
+    pub dedupe_identical: bool,
+}
+
+impl Default for MergeOptions {
+    fn default() -> MergeOptions {
+        MergeOptions {
+            dedupe_identical: true,
+        }
+    }
+}
+
+impl MergeError {
+    fn new(name: String, positions: (Pos, Pos), message: String)
+        -> MergeError
+    {
+        MergeError { message, name, positions, sources: None }
+    }
+
+    /// Returns the name of the conflicting definition
+    ///
+    /// That's `Type` for types, `Type.field` for fields and input fields,
+    /// `@name` for directives and `schema.query`, `schema.mutation` or
+    /// `schema.subscription` for root operation types.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the positions of the conflicting definitions in the base
+    /// document and in the merged one
+    pub fn positions(&self) -> (Pos, Pos) {
+        self.positions
+    }
+
+    /// Returns the names of the sources of both definitions
+    ///
+    /// These are set by `merge_schema_files` only.
+    pub fn sources(&self) -> Option<(&str, &str)> {
+        self.sources.as_ref().map(|s| (&s.0[..], &s.1[..]))
+    }
+}
+
+/// Returns a schema with the definitions of both documents
+///
+/// Types defined in only one of the documents are copied. Types defined
+/// in both must be of the same kind, and are combined: fields, input
+/// fields, enum values, union members and implemented interfaces missing
+/// in the base type are appended to it, and so are directives which
+/// aren't applied to it yet. A field defined in both must have the same
+/// type, the definition from `base` (including its description and
+/// arguments) is kept.
+///
+/// Root operation types of schema definitions and directive definitions
+/// are merged the same way, type extensions are copied as is.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::schema::{parse_schema, merge_schemas};
+///
+/// let users = parse_schema::<String>("type Query { user: String }")
+///     .unwrap();
+/// let posts = parse_schema::<String>("type Query { posts: [String] }")
+///     .unwrap();
+/// let merged = merge_schemas(users, posts).unwrap();
+/// assert_eq!(merged.to_string(),
+///            "type Query {\n  user: String\n  posts: [String]\n}\n");
+/// ```
+pub fn merge_schemas<'a, T>(base: Document<'a, T>, other: Document<'a, T>)
+    -> Result<Document<'a, T>, MergeError>
+    where T: Text<'a>,
+{
+    let mut definitions = base.definitions;
+    for def in other.definitions {
+        match def {
+            Definition::SchemaDefinition(schema) => {
+                let existing = definitions.iter_mut()
+                    .find_map(|def| match *def {
+                        Definition::SchemaDefinition(ref mut s) => Some(s),
+                        _ => None,
+                    });
+                match existing {
+                    Some(existing) => merge_schema_definitions(existing,
+                                                               schema)?,
+                    None => {
+                        definitions.push(Definition::SchemaDefinition(schema))
+                    }
+                }
+            }
+            Definition::TypeDefinition(typ) => {
+                let existing = definitions.iter_mut()
+                    .find_map(|def| match *def {
+                        Definition::TypeDefinition(ref mut t)
+                            if t.name().as_ref() == typ.name().as_ref()
+                        => Some(t),
+                        _ => None,
+                    });
+                match existing {
+                    Some(existing) => merge_types(existing, typ)?,
+                    None => definitions.push(Definition::TypeDefinition(typ)),
+                }
+            }
+            Definition::DirectiveDefinition(dir) => {
+                let existing = definitions.iter()
+                    .find_map(|def| match *def {
+                        Definition::DirectiveDefinition(ref d)
+                            if d.name.as_ref() == dir.name.as_ref()
+                        => Some(d),
+                        _ => None,
+                    });
+                match existing {
+                    Some(existing) => check_directives(existing, &dir)?,
+                    None => {
+                        definitions.push(Definition::DirectiveDefinition(dir))
+                    }
+                }
+            }
+            def @ Definition::SchemaExtension(_)
+            | def @ Definition::TypeExtension(_) => definitions.push(def),
+        }
+    }
+    Ok(Document { definitions })
+}
+
+/// Returns a schema with the definitions of all the files
+///
+/// Unlike `merge_schemas`, each type, directive and the schema must be
+/// defined in a single file, and other files extend them with `extend`.
+/// Extensions are applied across the files, so the result contains no
+/// extensions. Identical scalar and enum definitions are deduplicated,
+/// see `MergeOptions`. Errors point to the conflicting definitions in
+/// their files.
+///
+/// Files are merged in the order of their names, so the result doesn't
+/// depend on the order of `files`.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::schema::{parse_schema_named, merge_schema_files};
+///
+/// let files = vec![
+///     parse_schema_named::<&str>("extend type Query { user: User }
+///                                 type User { id: ID }", "user.graphql"),
+///     parse_schema_named::<&str>("type Query { version: String }",
+///                                "query.graphql"),
+/// ];
+/// let files = files.into_iter().collect::<Result<_, _>>().unwrap();
+/// let merged = merge_schema_files(files, &Default::default()).unwrap();
+/// assert_eq!(merged.to_string(), "\
+/// type Query {
+///   version: String
+///   user: User
+/// }
+///
+/// type User {
+///   id: ID
+/// }
+/// ");
+/// ```
+pub fn merge_schema_files<'a, T>(files: Vec<SchemaFile<'a, T>>,
+    options: &MergeOptions)
+    -> Result<Document<'a, T>, MergeError>
+    where T: Text<'a>,
+{
+    let mut files = files;
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut names = Vec::with_capacity(files.len());
+    let mut definitions = Vec::new();
+    let mut sources = Vec::new();
+    let mut defined = HashMap::new();
+    for (file, SchemaFile { name, document }) in files.into_iter().enumerate()
+    {
+        names.push(name);
+        for def in document.definitions {
+            let key = match def {
+                Definition::SchemaDefinition(_) => "schema".to_string(),
+                Definition::TypeDefinition(ref t) => t.name().as_ref().into(),
+                Definition::DirectiveDefinition(ref d) => {
+                    format!("@{}", d.name.as_ref())
+                }
+                Definition::SchemaExtension(_)
+                | Definition::TypeExtension(_) => {
+                    definitions.push(def);
+                    sources.push(file);
+                    continue;
+                }
+            };
+            let index = match defined.get(&key) {
+                Some(&index) => index,
+                None => {
+                    defined.insert(key, definitions.len());
+                    definitions.push(def);
+                    sources.push(file);
+                    continue;
+                }
+            };
+            let existing: &Definition<'a, T> = &definitions[index];
+            let is_scalar_or_enum = matches!(def,
+                Definition::TypeDefinition(TypeDefinition::Scalar(_))
+                | Definition::TypeDefinition(TypeDefinition::Enum(_)));
+            if options.dedupe_identical && is_scalar_or_enum
+                && existing.to_string() == def.to_string()
+            {
+                continue;
+            }
+            let what = match def {
+                Definition::SchemaDefinition(_) => "schema".to_string(),
+                Definition::TypeDefinition(ref t) => {
+                    format!("type {}", t.name().as_ref())
+                }
+                _ => format!("directive {}", key),
+            };
+            let (base, other) = (existing.position(), def.position());
+            let (base_file, other_file) = (&names[sources[index]],
+                                           &names[file]);
+            return Err(MergeError {
+                message: format!("{} is defined in {} at {} and \
+                                  differently in {} at {}",
+                                 what, base_file, base, other_file, other),
+                name: key,
+                positions: (base, other),
+                sources: Some(Box::new((base_file.clone(),
+                                        other_file.clone()))),
+            });
+        }
+    }
+    let mut doc = Document { definitions };
+    if let Err(err) = apply_extensions(&mut doc) {
+        let extension = source_of(&doc, &sources, err.name(), err.position())
+            .unwrap_or(0);
+        let definition = err.definition_position()
+            .and_then(|pos| source_of(&doc, &sources, err.name(), pos))
+            .unwrap_or(extension);
+        let message = match err.definition_position() {
+            Some(_) => format!("{}: {} in {}", names[extension], err,
+                               names[definition]),
+            None => format!("{}: {}", names[extension], err),
+        };
+        return Err(MergeError {
+            message,
+            name: err.name().into(),
+            positions: (err.definition_position().unwrap_or(err.position()),
+                        err.position()),
+            sources: Some(Box::new((names[definition].clone(),
+                                    names[extension].clone()))),
+        });
+    }
+    Ok(doc)
+}
+
+/// Returns the index of the file with the definition or extension of the
+/// named type (or `schema`) which is at the position or has a field or
+/// value at the position
+fn source_of<'a, T>(doc: &Document<'a, T>, sources: &[usize], name: &str,
+    position: Pos)
+    -> Option<usize>
+    where T: Text<'a>,
+{
+    let positions = |def: &Definition<'a, T>| -> Vec<Pos> {
+        let members = match *def {
+            Definition::TypeDefinition(ref t) if t.name().as_ref() == name => {
+                definition_members(t)
+            }
+            Definition::TypeExtension(ref e) if e.name().as_ref() == name => {
+                extension_members(e)
+            }
+            Definition::SchemaDefinition(_)
+            | Definition::SchemaExtension(_) if name == "schema" => Vec::new(),
+            _ => return Vec::new(),
+        };
+        let mut positions = vec![def.position()];
+        positions.extend(members.into_iter().map(|(_, _, pos)| pos));
+        positions
+    };
+    doc.definitions.iter().zip(sources)
+        .find(|&(def, _)| positions(def).contains(&position))
+        .map(|(_, &file)| file)
+}
+
+fn merge_schema_definitions<'a, T>(base: &mut SchemaDefinition<'a, T>,
+    other: SchemaDefinition<'a, T>)
+    -> Result<(), MergeError>
+    where T: Text<'a>,
+{
+    let roots = vec![
+        ("query", &mut base.query, other.query),
+        ("mutation", &mut base.mutation, other.mutation),
+        ("subscription", &mut base.subscription, other.subscription),
+    ];
+    for (operation, root, other_root) in roots {
+        match (root.as_ref(), other_root) {
+            (Some(a), Some(b)) if a.as_ref() != b.as_ref() => {
+                return Err(MergeError::new(
+                    format!("schema.{}", operation),
+                    (base.position, other.position),
+                    format!("schema {} type is {} at {} and {} at {}",
+                            operation, a.as_ref(), base.position,
+                            b.as_ref(), other.position)));
+            }
+            (None, Some(b)) => *root = Some(b),
+            _ => {}
+        }
+    }
+    merge_directives(&mut base.directives, other.directives);
+    Ok(())
+}
+
+fn merge_types<'a, T>(base: &mut TypeDefinition<'a, T>,
+    other: TypeDefinition<'a, T>)
+    -> Result<(), MergeError>
+    where T: Text<'a>,
+{
+    match (base, other) {
+        (TypeDefinition::Scalar(base), TypeDefinition::Scalar(other)) => {
+            merge_directives(&mut base.directives, other.directives);
+        }
+        (TypeDefinition::Object(base), TypeDefinition::Object(other)) => {
+            merge_names(&mut base.implements_interfaces,
+                        other.implements_interfaces);
+            merge_directives(&mut base.directives, other.directives);
+            merge_fields(base.name.as_ref(), &mut base.fields,
+                         other.fields)?;
+        }
+        (TypeDefinition::Interface(base),
+         TypeDefinition::Interface(other)) => {
+            merge_directives(&mut base.directives, other.directives);
+            merge_fields(base.name.as_ref(), &mut base.fields,
+                         other.fields)?;
+        }
+        (TypeDefinition::Union(base), TypeDefinition::Union(other)) => {
+            merge_directives(&mut base.directives, other.directives);
+            merge_names(&mut base.types, other.types);
+        }
+        (TypeDefinition::Enum(base), TypeDefinition::Enum(other)) => {
+            merge_directives(&mut base.directives, other.directives);
+            for value in other.values {
+                if !base.values.iter()
+                    .any(|v| v.name.as_ref() == value.name.as_ref())
+                {
+                    base.values.push(value);
+                }
+            }
+        }
+        (TypeDefinition::InputObject(base),
+         TypeDefinition::InputObject(other)) => {
+            merge_directives(&mut base.directives, other.directives);
+            merge_input_fields(base.name.as_ref(), &mut base.fields,
+                               other.fields)?;
+        }
+        (base, other) => {
+            let name = base.name().as_ref();
+            return Err(MergeError::new(
+                name.to_string(),
+                (base.position(), other.position()),
+                format!("type {} is defined with `{}` at {} and with `{}` \
+                         at {}",
+                        name, definition_keyword(base), base.position(),
+                        definition_keyword(&other), other.position())));
+        }
+    }
+    Ok(())
+}
+
+fn merge_fields<'a, T>(type_name: &str, fields: &mut Vec<Field<'a, T>>,
+    other: Vec<Field<'a, T>>)
+    -> Result<(), MergeError>
+    where T: Text<'a>,
+{
+    for field in other {
+        let existing = fields.iter()
+            .find(|f| f.name.as_ref() == field.name.as_ref());
+        match existing {
+            Some(existing) => check_types(type_name, field.name.as_ref(),
+                (&existing.field_type, existing.position),
+                (&field.field_type, field.position))?,
+            None => fields.push(field),
+        }
+    }
+    Ok(())
+}
+
+fn merge_input_fields<'a, T>(type_name: &str,
+    fields: &mut Vec<InputValue<'a, T>>, other: Vec<InputValue<'a, T>>)
+    -> Result<(), MergeError>
+    where T: Text<'a>,
+{
+    for field in other {
+        let existing = fields.iter()
+            .find(|f| f.name.as_ref() == field.name.as_ref());
+        match existing {
+            Some(existing) => check_types(type_name, field.name.as_ref(),
+                (&existing.value_type, existing.position),
+                (&field.value_type, field.position))?,
+            None => fields.push(field),
+        }
+    }
+    Ok(())
+}
+
+fn check_types<'a, T>(type_name: &str, field_name: &str,
+    (base, base_position): (&Type<'a, T>, Pos),
+    (other, other_position): (&Type<'a, T>, Pos))
+    -> Result<(), MergeError>
+    where T: Text<'a>,
+{
+    let (base, other) = (base.to_string(), other.to_string());
+    if base == other {
+        return Ok(());
+    }
+    Err(MergeError::new(
+        format!("{}.{}", type_name, field_name),
+        (base_position, other_position),
+        format!("field {}.{} has type {} at {} and type {} at {}",
+                type_name, field_name, base, base_position,
+                other, other_position)))
+}
+
+fn check_directives<'a, T>(base: &DirectiveDefinition<'a, T>,
+    other: &DirectiveDefinition<'a, T>)
+    -> Result<(), MergeError>
+    where T: Text<'a>,
+{
+    let arguments = |dir: &DirectiveDefinition<'a, T>| {
+        dir.arguments.iter()
+            .map(|arg| format!("{}: {}", arg.name.as_ref(), arg.value_type))
+            .collect::<Vec<_>>()
+    };
+    if arguments(base) == arguments(other)
+        && base.repeatable == other.repeatable
+        && base.locations == other.locations
+    {
+        return Ok(());
+    }
+    Err(MergeError::new(
+        format!("@{}", base.name.as_ref()),
+        (base.position, other.position),
+        format!("directive @{} is defined differently at {} and at {}",
+                base.name.as_ref(), base.position, other.position)))
+}
+
+fn merge_names<V: AsRef<str>>(names: &mut Vec<V>, other: Vec<V>) {
+    for name in other {
+        if !names.iter().any(|n| n.as_ref() == name.as_ref()) {
+            names.push(name);
+        }
+    }
+}
+
+fn merge_directives<'a, T>(directives: &mut Vec<Directive<'a, T>>,
+    other: Vec<Directive<'a, T>>)
+    where T: Text<'a>,
+{
+    for dir in other {
+        let text = dir.to_string();
+        if !directives.iter().any(|d| d.to_string() == text) {
+            directives.push(dir);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{merge_schemas, merge_schema_files, MergeOptions};
+    use crate::position::Pos;
+    use crate::schema::{parse_schema, parse_schema_named, Document};
+    use crate::schema::SchemaFile;
+
+    fn files<'a>(files: &[(&str, &'a str)]) -> Vec<SchemaFile<'a, &'a str>> {
+        files.iter()
+            .map(|&(name, source)| parse_schema_named(source, name).unwrap())
+            .collect()
+    }
+
+    fn parse(source: &str) -> Document<'_, &str> {
+        parse_schema(source).unwrap()
+    }
+
+    #[test]
+    fn shared_query() {
+        let users = parse("\
+schema { query: Query }
+type Query @cache { user(id: ID!): User }
+type User { id: ID! }
+");
+        let posts = parse("\
+schema { query: Query mutation: Mutation }
+type Query @cache @auth { user(id: ID!): User posts: [Post] }
+type Post { title: String }
+type Mutation { post(title: String): Post }
+");
+        let merged = merge_schemas(users, posts).unwrap();
+        assert_eq!(merged.to_string(), "\
+schema {
+  query: Query
+  mutation: Mutation
+}
+
+type Query @cache @auth {
+  user(id: ID!): User
+  posts: [Post]
+}
+
+type User {
+  id: ID!
+}
+
+type Post {
+  title: String
+}
+
+type Mutation {
+  post(title: String): Post
+}
+");
+    }
+
+    #[test]
+    fn other_kinds() {
+        let base = parse("\
+type Dog implements Pet { name: String }
+union Animal = Dog
+enum Color { RED }
+input Filter { name: String }
+directive @key(fields: String) on OBJECT
+");
+        let other = parse("\
+type Dog implements Pet & Named { name: String }
+union Animal = Cat | Dog
+enum Color { RED GREEN }
+input Filter { limit: Int }
+directive @key(fields: String) on OBJECT
+extend type Dog { age: Int }
+");
+        let merged = merge_schemas(base, other).unwrap();
+        assert_eq!(merged.to_string(), "\
+type Dog implements Pet & Named {
+  name: String
+}
+
+union Animal = Dog | Cat
+
+enum Color {
+  RED
+  GREEN
+}
+
+input Filter {
+  name: String
+  limit: Int
+}
+
+directive @key(fields: String) on OBJECT
+
+extend type Dog {
+  age: Int
+}
+");
+    }
+
+    #[test]
+    fn conflicts() {
+        let base = "type Query {\n  user: User\n}";
+        let err = merge_schemas(parse(base),
+                                parse("type Query { user: User! }"))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "field Query.user has type User at 2:3 \
+                                     and type User! at 1:14");
+        assert_eq!(err.name(), "Query.user");
+        assert_eq!(err.positions(), (Pos::new(2, 3), Pos::new(1, 14)));
+
+        let err = merge_schemas(parse(base), parse("enum Query { A }"))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "type Query is defined with `type` at \
+                                     1:1 and with `enum` at 1:1");
+
+        let err = merge_schemas(parse("schema { query: Q } type Q { a: Int }"),
+                                parse("schema { query: Query }"))
+            .unwrap_err();
+        assert_eq!(err.name(), "schema.query");
+
+        let err = merge_schemas(parse("directive @a on FIELD"),
+                                parse("directive @a(x: Int) on FIELD"))
+            .unwrap_err();
+        assert_eq!(err.to_string(),
+                   "directive @a is defined differently at 1:1 and at 1:1");
+    }
+
+    const USER: &str = "\
+scalar DateTime
+type User { id: ID! createdAt: DateTime }
+extend type Query { user(id: ID!): User }
+";
+    const POST: &str = "\
+scalar DateTime
+type Post { title: String createdAt: DateTime }
+extend type Query { posts: [Post] }
+extend type User { posts: [Post] }
+";
+    const QUERY: &str = "type Query { version: String }\n";
+
+    #[test]
+    fn files_in_any_order() {
+        let options = MergeOptions::default();
+        let merged = merge_schema_files(files(&[
+            ("user.graphql", USER),
+            ("post.graphql", POST),
+            ("query.graphql", QUERY),
+        ]), &options).unwrap();
+        assert_eq!(merged.to_string(), "\
+scalar DateTime
+
+type Post {
+  title: String
+  createdAt: DateTime
+}
+
+type Query {
+  version: String
+  posts: [Post]
+  user(id: ID!): User
+}
+
+type User {
+  id: ID!
+  createdAt: DateTime
+  posts: [Post]
+}
+");
+        let reversed = merge_schema_files(files(&[
+            ("query.graphql", QUERY),
+            ("post.graphql", POST),
+            ("user.graphql", USER),
+        ]), &options).unwrap();
+        assert_eq!(reversed, merged);
+    }
+
+    #[test]
+    fn file_conflicts() {
+        let options = MergeOptions { dedupe_identical: false };
+        let err = merge_schema_files(files(&[
+            ("user.graphql", USER),
+            ("post.graphql", POST),
+        ]), &options).unwrap_err();
+        assert_eq!(err.to_string(), "type DateTime is defined in \
+            post.graphql at 1:1 and differently in user.graphql at 1:1");
+        assert_eq!(err.sources(), Some(("post.graphql", "user.graphql")));
+
+        let err = merge_schema_files(files(&[
+            ("a.graphql", "enum Role { USER }"),
+            ("b.graphql", "enum Role { USER ADMIN }"),
+        ]), &MergeOptions::default()).unwrap_err();
+        assert_eq!(err.name(), "Role");
+        assert_eq!(err.positions(), (Pos::new(1, 1), Pos::new(1, 1)));
+
+        let err = merge_schema_files(files(&[
+            ("a.graphql", "type Query { a: Int }"),
+            ("b.graphql", "\nextend type Query { b: Int a: Int }"),
+        ]), &MergeOptions::default()).unwrap_err();
+        assert_eq!(err.to_string(), "b.graphql: cannot apply \
+            `extend type Query`: field a of Query is already defined \
+            at 1:14 in a.graphql");
+        assert_eq!(err.sources(), Some(("a.graphql", "b.graphql")));
+        assert_eq!(err.positions(), (Pos::new(1, 14), Pos::new(2, 8)));
+    }
+
+    #[test]
+    fn named_parse_error() {
+        let err = parse_schema_named::<&str>("type {", "user.graphql")
+            .unwrap_err();
+        assert_eq!(err.source_name(), Some("user.graphql"));
+        assert!(err.to_string()
+                .starts_with("schema parse error: user.graphql: Parse error"));
+    }
+}
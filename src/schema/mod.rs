@@ -1,10 +1,40 @@
 //! Schema definition language AST and utility
 //!
 mod ast;
-mod grammar;
+pub mod builder;
+mod built_in;
+mod coerce;
+mod diff;
+mod extend;
+pub(crate) mod grammar;
 mod error;
 mod format;
+mod index;
+#[cfg(feature = "json")]
+mod introspection;
+mod js_print;
+mod map;
+mod merge;
+mod sort;
 
 pub use self::ast::*;
+pub use self::built_in::{strip_builtin_definitions, with_builtin_definitions};
+pub use self::coerce::{coerce_arguments, coerce_input_value, CoercionError};
+pub use self::diff::{diff_schemas, schema_diff};
+pub use self::diff::{ChangeKind, Criticality, SchemaChange};
 pub use self::error::ParseError;
-pub use self::grammar::parse_schema;
+pub use self::extend::{apply_extensions, apply_extensions_with};
+pub use self::extend::{ExtensionError, ExtensionOptions};
+pub use self::grammar::{parse_schema, parse_schema_with, parse_schema_named};
+#[cfg(feature = "bytes")]
+pub use self::grammar::parse_schema_bytes;
+pub use crate::parse_options::ParseOptions;
+#[cfg(feature = "json")]
+pub use self::introspection::{from_introspection, to_introspection};
+#[cfg(feature = "json")]
+pub use self::introspection::IntrospectionError;
+pub use self::index::{fragment_applies, SchemaIndex};
+pub use self::merge::{merge_schemas, merge_schema_files};
+pub use self::merge::{MergeError, MergeOptions};
+pub use self::js_print::print_graphql_js_style;
+pub use self::sort::sort_schema;
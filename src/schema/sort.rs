@@ -0,0 +1,261 @@
+//! Lexicographic sorting of schema documents
+//!
+use std::cmp::Ordering;
+
+use crate::schema::ast::*;
+
+
+/// Returns the document with definitions and their members sorted by name
+///
+/// The schema definition and extensions come first, then directive
+/// definitions, then types, each type followed by its extensions. Fields,
+/// arguments, enum values, union members, implemented interfaces and
+/// directive locations are sorted too, descriptions and directives stay
+/// with the nodes they're attached to.
+///
+/// Names are compared as in `lexicographicSortSchema` of graphql-js, which
+/// orders digits by their numeric value, so `a2` comes before `a10`. The
+/// sort is stable, and sorting a sorted document doesn't change it. To get
+/// the same output as graphql-js for types with extensions, merge the
+/// extensions with `apply_extensions` first.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::schema::{parse_schema, sort_schema};
+///
+/// let doc = parse_schema::<&str>("
+///     type Query { b: Int a(y: Int, x: Int): Int }
+///     enum Color { RED BLUE }
+/// ").unwrap();
+/// assert_eq!(sort_schema(&doc).to_string(), "\
+/// enum Color {
+///   BLUE
+///   RED
+/// }
+///
+/// type Query {
+///   a(x: Int, y: Int): Int
+///   b: Int
+/// }
+/// ");
+/// ```
+pub fn sort_schema<'a, T>(doc: &Document<'a, T>) -> Document<'a, T>
+    where T: Text<'a> + Clone,
+{
+    let mut result = doc.clone();
+    result.definitions.sort_by(|a, b| {
+        let (a_group, a_name) = definition_key(a);
+        let (b_group, b_name) = definition_key(b);
+        a_group.cmp(&b_group)
+            .then_with(|| natural_compare(a_name, b_name))
+            .then_with(|| is_extension(a).cmp(&is_extension(b)))
+    });
+    for def in &mut result.definitions {
+        match *def {
+            Definition::TypeDefinition(ref mut t) => sort_type(t),
+            Definition::TypeExtension(ref mut e) => sort_extension(e),
+            Definition::DirectiveDefinition(ref mut d) => {
+                sort_arguments(&mut d.arguments);
+                d.locations.sort_by(|a, b| {
+                    natural_compare(a.as_str(), b.as_str())
+                });
+            }
+            Definition::SchemaDefinition(_)
+            | Definition::SchemaExtension(_) => {}
+        }
+    }
+    result
+}
+
+fn definition_key<'d, 'a, T>(def: &'d Definition<'a, T>) -> (u8, &'d str)
+    where T: Text<'a>,
+{
+    match *def {
+        Definition::SchemaDefinition(_) => (0, ""),
+        Definition::SchemaExtension(_) => (1, ""),
+        Definition::DirectiveDefinition(ref d) => (2, d.name.as_ref()),
+        Definition::TypeDefinition(ref t) => (3, t.name().as_ref()),
+        Definition::TypeExtension(ref e) => (3, e.name().as_ref()),
+    }
+}
+
+fn is_extension<'a, T: Text<'a>>(def: &Definition<'a, T>) -> bool {
+    matches!(*def, Definition::TypeExtension(_))
+}
+
+fn sort_type<'a, T: Text<'a>>(typ: &mut TypeDefinition<'a, T>) {
+    match *typ {
+        TypeDefinition::Scalar(_) => {}
+        TypeDefinition::Object(ref mut t) => {
+            sort_names::<T>(&mut t.implements_interfaces);
+            sort_fields(&mut t.fields);
+        }
+        TypeDefinition::Interface(ref mut t) => sort_fields(&mut t.fields),
+        TypeDefinition::Union(ref mut t) => sort_names::<T>(&mut t.types),
+        TypeDefinition::Enum(ref mut t) => sort_enum_values(&mut t.values),
+        TypeDefinition::InputObject(ref mut t) => {
+            sort_arguments(&mut t.fields);
+        }
+    }
+}
+
+fn sort_extension<'a, T: Text<'a>>(ext: &mut TypeExtension<'a, T>) {
+    match *ext {
+        TypeExtension::Scalar(_) => {}
+        TypeExtension::Object(ref mut e) => {
+            sort_names::<T>(&mut e.implements_interfaces);
+            sort_fields(&mut e.fields);
+        }
+        TypeExtension::Interface(ref mut e) => sort_fields(&mut e.fields),
+        TypeExtension::Union(ref mut e) => sort_names::<T>(&mut e.types),
+        TypeExtension::Enum(ref mut e) => sort_enum_values(&mut e.values),
+        TypeExtension::InputObject(ref mut e) => {
+            sort_arguments(&mut e.fields);
+        }
+    }
+}
+
+fn sort_names<'a, T: Text<'a>>(names: &mut [T::Value]) {
+    names.sort_by(|a, b| natural_compare(a.as_ref(), b.as_ref()));
+}
+
+fn sort_fields<'a, T: Text<'a>>(fields: &mut [Field<'a, T>]) {
+    fields.sort_by(|a, b| natural_compare(a.name.as_ref(), b.name.as_ref()));
+    for field in fields {
+        sort_arguments(&mut field.arguments);
+    }
+}
+
+fn sort_arguments<'a, T: Text<'a>>(args: &mut [InputValue<'a, T>]) {
+    args.sort_by(|a, b| natural_compare(a.name.as_ref(), b.name.as_ref()));
+}
+
+fn sort_enum_values<'a, T: Text<'a>>(values: &mut [EnumValue<'a, T>]) {
+    values.sort_by(|a, b| natural_compare(a.name.as_ref(), b.name.as_ref()));
+}
+
+/// Compares names as `naturalCompare` of graphql-js
+///
+/// Runs of digits are compared by their numeric value, except that a
+/// leading zero ends the number, other characters by their code.
+fn natural_compare(a: &str, b: &str) -> Ordering {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i].is_ascii_digit() && b[j].is_ascii_digit() {
+            let a_num = number(a, &mut i);
+            let b_num = number(b, &mut j);
+            match a_num.cmp(&b_num) {
+                Ordering::Equal => {}
+                other => return other,
+            }
+        } else {
+            match a[i].cmp(&b[j]) {
+                Ordering::Equal => {}
+                other => return other,
+            }
+            i += 1;
+            j += 1;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+/// Reads the number starting at `pos`, a leading zero is read alone
+fn number(s: &[u8], pos: &mut usize) -> u64 {
+    let mut num = 0u64;
+    loop {
+        num = num.saturating_mul(10)
+            .saturating_add(u64::from(s[*pos] - b'0'));
+        *pos += 1;
+        if num == 0 || *pos >= s.len() || !s[*pos].is_ascii_digit() {
+            return num;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cmp::Ordering;
+
+    use super::{natural_compare, sort_schema};
+    use crate::schema::parse_schema;
+
+    #[test]
+    fn natural_order() {
+        let mut names = vec!["a10", "a2", "B", "a", "a02", "_", "a1b", "a1"];
+        names.sort_by(|a, b| natural_compare(a, b));
+        assert_eq!(names, ["B", "_", "a", "a02", "a1", "a1b", "a2", "a10"]);
+        assert_eq!(natural_compare("a1", "a1"), Ordering::Equal);
+    }
+
+    #[test]
+    fn definitions_and_members() {
+        let doc = parse_schema::<&str>(r#"
+            extend type Query { c: Int }
+            union U = C | A | B
+            "The query"
+            type Query implements Z & Y @key(fields: "b") {
+                "Field b"
+                b(z: Int, "Argument a" a: Int @deprecated): Int
+                a: Int @deprecated(reason: "gone")
+            }
+            directive @key(fields: String!) on OBJECT | INTERFACE | FIELD
+            schema { query: Query }
+            enum E { B A @deprecated }
+            input In { y: Int = 1 x: Int }
+            interface Z { b: Int a: Int }
+            extend schema @a
+        "#).unwrap();
+        assert_eq!(sort_schema(&doc).to_string(), r#"schema {
+  query: Query
+}
+
+extend schema @a
+
+directive @key(fields: String!) on FIELD | INTERFACE | OBJECT
+
+enum E {
+  A @deprecated
+  B
+}
+
+input In {
+  x: Int
+  y: Int = 1
+}
+
+"The query"
+type Query implements Y & Z @key(fields: "b") {
+  a: Int @deprecated(reason: "gone")
+  "Field b"
+  b(
+    "Argument a"
+    a: Int @deprecated
+    z: Int
+  ): Int
+}
+
+extend type Query {
+  c: Int
+}
+
+union U = A | B | C
+
+interface Z {
+  a: Int
+  b: Int
+}
+"#);
+    }
+
+    #[test]
+    fn idempotent() {
+        let doc = parse_schema::<String>(
+            include_str!("../../tests/graphql_js/kitchen_sink.graphql"))
+            .unwrap();
+        let sorted = sort_schema(&doc);
+        assert_eq!(sort_schema(&doc), sorted);
+        assert_eq!(sort_schema(&sorted).to_string(), sorted.to_string());
+    }
+}
@@ -5,9 +5,45 @@ use combine::error::{StreamError};
 use combine::stream::{Resetable};
 use combine::easy::{Error, Errors};
 
+use crate::parse_options::ParseOptions;
 use crate::position::Pos;
 
 
+const DEPTH_LIMIT_EXCEEDED: &str = "Recursion limit exceeded";
+
+/// Returns `true` if parsing failed because of `ParseOptions::max_depth`
+pub(crate) fn is_depth_limit_exceeded(
+    errors: &Errors<Token<'_>, Token<'_>, Pos>)
+    -> bool
+{
+    errors.errors.iter().any(|e| match *e {
+        Error::Message(ref info) => info.to_string() == DEPTH_LIMIT_EXCEEDED,
+        _ => false,
+    })
+}
+
+const INT_OUT_OF_RANGE: &str = "integer out of range: ";
+
+/// Returns an error for the integer literal which doesn't fit `i64`
+pub(crate) fn int_out_of_range_error<'a>(literal: &str)
+    -> Error<Token<'a>, Token<'a>>
+{
+    Error::message_message(format_args!("{}{}", INT_OUT_OF_RANGE, literal))
+}
+
+/// Returns the literal and the position of the integer which failed to
+/// parse because it doesn't fit `i64`
+pub(crate) fn int_out_of_range(errors: &Errors<Token<'_>, Token<'_>, Pos>)
+    -> Option<(String, Pos)>
+{
+    errors.errors.iter().find_map(|e| match *e {
+        Error::Message(ref info) => info.to_string()
+            .strip_prefix(INT_OUT_OF_RANGE)
+            .map(|literal| (literal.to_string(), errors.position)),
+        _ => None,
+    })
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Kind {
     Punctuator,
@@ -29,20 +65,39 @@ pub struct TokenStream<'a> {
     buf: &'a str,
     position: Pos,
     off: usize,
-    next_state: Option<(usize, Token<'a>, usize, Pos)>,
+    next_state: Option<(usize, Token<'a>, usize, Pos, usize)>,
     recursion_limit: usize,
+    keep_comments: bool,
 }
 
 impl TokenStream<'_> {
     pub(crate) fn offset(&self) -> usize {
         self.off
     }
+
+    /// Skips the rest of the current line, used to recover from characters
+    /// which can't start a token
+    pub(crate) fn skip_line(&mut self) {
+        let len = match self.buf[self.off..].find('\n') {
+            Some(index) => index + 1,
+            None => self.buf.len() - self.off,
+        };
+        self.skip(len);
+    }
+
+    /// Skips `len` bytes of the source and the whitespace after them
+    pub(crate) fn skip(&mut self, len: usize) {
+        self.update_position(len);
+        self.next_state = None;
+        self.skip_whitespace();
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Checkpoint {
     position: Pos,
     off: usize,
+    recursion_limit: usize,
 }
 
 impl<'a> StreamOnce for TokenStream<'a> {
@@ -52,10 +107,11 @@ impl<'a> StreamOnce for TokenStream<'a> {
     type Error = Errors<Token<'a>, Token<'a>, Pos>;
 
     fn uncons(&mut self) -> Result<Self::Item, Error<Token<'a>, Token<'a>>> {
-        if let Some((at, tok, off, pos)) = self.next_state {
+        if let Some((at, tok, off, pos, limit)) = self.next_state {
             if at == self.off {
                 self.off = off;
                 self.position = pos;
+                self.recursion_limit = limit;
                 return Ok(tok);
             }
         }
@@ -64,14 +120,17 @@ impl<'a> StreamOnce for TokenStream<'a> {
         let value = &self.buf[self.off-len..self.off];
         self.skip_whitespace();
         let token = Token { kind, value };
-        self.next_state = Some((old_pos, token, self.off, self.position));
+        self.next_state = Some((old_pos, token, self.off, self.position,
+                                self.recursion_limit));
         Ok(token)
     }
 }
 
 impl<'a> Positioned for TokenStream<'a> {
     fn position(&self) -> Self::Position {
-        self.position
+        // only line and column are tracked while tokenizing, the offset
+        // is always the current one
+        Pos { offset: self.off, ..self.position }
     }
 }
 
@@ -81,36 +140,42 @@ impl<'a> Resetable for TokenStream<'a> {
         Checkpoint {
             position: self.position,
             off: self.off,
+            recursion_limit: self.recursion_limit,
         }
     }
     fn reset(&mut self, checkpoint: Checkpoint) {
+        // nesting depth must be restored too, otherwise brackets which are
+        // read again after backtracking are counted twice
         self.position = checkpoint.position;
         self.off = checkpoint.off;
+        self.recursion_limit = checkpoint.recursion_limit;
     }
 }
 
+/// Returns `true` if the character ends the scanned number literal
+pub(crate) fn ends_number(c: char) -> bool {
+    matches!(c,
+        ' ' | '\n' | '\r' | '\t' | ',' | '#' |
+        '!' | '$' | ':' | '=' | '@' | '|' | '&' |
+        '(' | ')' | '[' | ']' | '{' | '}')
+}
+
 // NOTE: we expect that first character is always digit or minus, as returned
 // by tokenizer
 fn check_int(value: &str) -> bool {
     value == "0" || value == "-0" ||
        (!value.starts_with('0') && value != "-" && !value.starts_with("-0")
-       && value[1..].chars().all(|x| x >= '0' && x <= '9'))
+       && value[1..].chars().all(|x| x.is_ascii_digit()))
 }
 
 fn check_dec(value: &str) -> bool {
-    !value.is_empty() && value.chars().all(|x| x >= '0' && x <= '9')
+    !value.is_empty() && value.chars().all(|x| x.is_ascii_digit())
 }
 
 fn check_exp(value: &str) -> bool {
-    if value.is_empty() {
-        return false;
-    }
-    let first = value.chars().next().unwrap();
-    if first != '-' && first != '+' && (first <= '0' || first >= '9') {
-        return false;
-    }
-
-    value[1..].chars().all(|x| x >= '0' && x <= '9')
+    let digits = value.strip_prefix(|c| c == '-' || c == '+')
+        .unwrap_or(value);
+    check_dec(digits)
 }
 
 fn check_float(value: &str, exponent: Option<usize>, real: Option<usize>)
@@ -131,20 +196,38 @@ fn check_float(value: &str, exponent: Option<usize>, real: Option<usize>)
 }
 
 impl<'a> TokenStream<'a> {
-    pub fn new(s: &str) -> TokenStream {
-        Self::with_recursion_limit(s, 50)
+    pub fn new(s: &str) -> TokenStream<'_> {
+        Self::with_recursion_limit(s, ParseOptions::default().max_depth)
     }
 
     /// Specify a limit to recursive parsing. Note that increasing the limit
     /// from the default may represent a security issue since a maliciously
     /// crafted input may cause a stack overflow, crashing the process.
-    pub(crate) fn with_recursion_limit(s: &str, recursion_limit: usize) -> TokenStream {
+    pub(crate) fn with_recursion_limit(s: &str, recursion_limit: usize) -> TokenStream<'_> {
+        let mut me = TokenStream {
+            buf: s,
+            position: Pos::new(1, 1),
+            off: 0,
+            next_state: None,
+            recursion_limit,
+            keep_comments: false,
+        };
+        me.skip_whitespace();
+        me
+    }
+
+    /// Creates a stream which stops at comments instead of skipping them,
+    /// and doesn't limit the nesting
+    ///
+    /// Comments aren't tokens, so they must be skipped by the caller.
+    pub(crate) fn keeping_comments(s: &str) -> TokenStream<'_> {
         let mut me = TokenStream {
             buf: s,
-            position: Pos { line: 1, column: 1 },
+            position: Pos::new(1, 1),
             off: 0,
             next_state: None,
-            recursion_limit
+            recursion_limit: usize::MAX,
+            keep_comments: true,
         };
         me.skip_whitespace();
         me
@@ -174,7 +257,7 @@ impl<'a> TokenStream<'a> {
                 // Check for recursion limit
                 self.recursion_limit = self.recursion_limit
                     .checked_sub(1)
-                    .ok_or_else(|| Error::message_static_message("Recursion limit exceeded"))?;
+                    .ok_or_else(|| Error::message_static_message(DEPTH_LIMIT_EXCEEDED))?;
 
                 self.advance_token(Punctuator, 1)
             },
@@ -206,7 +289,7 @@ impl<'a> TokenStream<'a> {
                 }
             }
             '_' | 'a'..='z' | 'A'..='Z' => {
-                while let Some((idx, cur_char)) = iter.next() {
+                for (idx, cur_char) in iter.by_ref() {
                     match cur_char {
                         '_' | 'a'..='z' | 'A'..='Z' | '0'..='9' => continue,
                         _ => return self.advance_token(Name, idx),
@@ -228,10 +311,7 @@ impl<'a> TokenStream<'a> {
                     };
                     match cur_char {
                         // just scan for now, will validate later on
-                        ' ' | '\n' | '\r' | '\t' | ',' | '#' |
-                        '!' | '$' | ':' | '=' | '@' | '|' | '&' |
-                        '(' | ')' | '[' | ']' | '{' | '}'
-                        => break idx,
+                        c if ends_number(c) => break idx,
                         '.' => real = Some(idx),
                         'e' | 'E' => exponent = Some(idx),
                         _ => {},
@@ -341,8 +421,9 @@ impl<'a> TokenStream<'a> {
                     continue;
                 }
                 //comment
+                '#' if self.keep_comments => break idx,
                 '#' => {
-                    while let Some((_, cur_char)) = iter.next() {
+                    for (_, cur_char) in iter.by_ref() {
                         // TODO(tailhook) ensure SourceCharacter
                         if cur_char == '\r' || cur_char == '\n' {
                             self.position.column = 1;
@@ -398,7 +479,7 @@ mod test {
                 Err(e) => panic!("Parse error at {}: {}", s.position(), e),
             }
         }
-        return r;
+        r
     }
     fn tok_typ(s: &str) -> Vec<Kind> {
         let mut r = Vec::new();
@@ -410,7 +491,7 @@ mod test {
                 Err(e) => panic!("Parse error at {}: {}", s.position(), e),
             }
         }
-        return r;
+        r
     }
 
     #[test]
@@ -500,6 +581,14 @@ mod test {
                 Punctuator, Name, Punctuator]);
         assert_eq!(tok_str("1.23e4"), ["1.23e4"]);
         assert_eq!(tok_typ("1.23e4"), [FloatValue]);
+        assert_eq!(tok_str("0e0"), ["0e0"]);
+        assert_eq!(tok_typ("0e0"), [FloatValue]);
+        assert_eq!(tok_str("1e9"), ["1e9"]);
+        assert_eq!(tok_typ("1e9"), [FloatValue]);
+        assert_eq!(tok_str("1.5E-3"), ["1.5E-3"]);
+        assert_eq!(tok_typ("1.5E-3"), [FloatValue]);
+        assert_eq!(tok_str("-2e09"), ["-2e09"]);
+        assert_eq!(tok_typ("-2e09"), [FloatValue]);
     }
 
     // TODO(tailhook) fix errors in parser and check error message
@@ -513,7 +602,9 @@ mod test {
     #[test] #[should_panic] fn letters_float() { tok_str("0bbc.0"); }
     #[test] #[should_panic] fn letters_float2() { tok_str("0.bbc"); }
     #[test] #[should_panic] fn letters_float3() { tok_str("0.bbce0"); }
-    #[test] #[should_panic] fn no_exp_sign_float() { tok_str("0e0"); }
+    #[test] #[should_panic] fn no_exp_float() { tok_str("0e"); }
+    #[test] #[should_panic] fn no_exp_digits_float() { tok_str("0e+"); }
+    #[test] #[should_panic] fn double_exp_sign_float() { tok_str("0e+-1"); }
     #[test] #[should_panic] fn unterminated_string() { tok_str(r#""hello\""#); }
     #[test] #[should_panic] fn extra_unterminated_string() { tok_str(r#""hello\\\""#); }
 
@@ -0,0 +1,25 @@
+//! Depth-first traversal shared by the AST visitors
+//!
+
+
+/// A reference to a node of an AST which knows its children
+pub(crate) trait Visitable: Copy {
+    /// Calls `f` for every child of the node in the order of the source
+    fn for_each_child(self, f: &mut dyn FnMut(Self));
+}
+
+/// Callbacks of `walk`, usually implemented for a public visitor trait
+pub(crate) trait Walker<N> {
+    fn enter(&mut self, node: N);
+    fn leave(&mut self, node: N);
+}
+
+/// Calls `enter` on the node, walks its children and calls `leave`
+pub(crate) fn walk<N, W>(node: N, walker: &mut W)
+    where N: Visitable,
+          W: Walker<N> + ?Sized,
+{
+    walker.enter(node);
+    node.for_each_child(&mut |child| walk(child, walker));
+    walker.leave(node);
+}
@@ -0,0 +1,352 @@
+//! Depth and complexity of queries, e.g. to reject expensive operations
+//!
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::common::{get_named_type, Text, Type};
+use crate::query::{Document, FragmentMap, OperationType};
+use crate::query::{Selection, SelectionSet, TypeCondition};
+use crate::schema::{SchemaIndex, TypeDefinition};
+use crate::validation::type_info::find_field;
+
+
+/// Error computing depth or complexity: fragments spread each other in
+/// a cycle
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("fragment {fragment} spreads itself, directly or through other \
+         fragments")]
+pub struct CycleError {
+    fragment: String,
+}
+
+impl CycleError {
+    /// Returns the name of the fragment found spreading itself
+    pub fn fragment(&self) -> &str {
+        &self.fragment
+    }
+}
+
+/// Depth or complexity of a single operation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationMetric {
+    /// Name of the operation, `None` for anonymous operations
+    pub name: Option<String>,
+    pub value: usize,
+}
+
+/// Costs of fields used by `query_complexity`
+#[derive(Debug, Clone)]
+pub struct ComplexityConfig<'s, 'ast, S: Text<'ast>> {
+    /// Schema used to find types of fields
+    pub schema: &'s SchemaIndex<'ast, S>,
+    /// Cost of the fields not in `field_costs`
+    pub default_cost: usize,
+    /// Costs of fields by their coordinate, e.g. `Human.pets`
+    pub field_costs: HashMap<String, usize>,
+    /// Multiplier applied to the selection set of list fields, once for
+    /// each level of lists
+    pub list_multiplier: usize,
+}
+
+impl<'s, 'ast, S: Text<'ast>> ComplexityConfig<'s, 'ast, S> {
+    /// Returns config with a cost of 1 for every field and a list
+    /// multiplier of 10
+    pub fn new(schema: &'s SchemaIndex<'ast, S>) -> Self {
+        ComplexityConfig {
+            schema,
+            default_cost: 1,
+            field_costs: HashMap::new(),
+            list_multiplier: 10,
+        }
+    }
+
+    /// Sets the cost of the field, `coordinate` is like `Human.pets`
+    pub fn field_cost(mut self, coordinate: &str, cost: usize) -> Self {
+        self.field_costs.insert(coordinate.into(), cost);
+        self
+    }
+}
+
+/// Returns the maximum nesting depth of fields of each operation
+///
+/// `{ a { b } }` has depth 2. Spreads of `fragments` and inline fragments
+/// don't add a level, spreads of unknown fragments and `__typename` fields
+/// are ignored.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::query::parse_query;
+/// use graphql_parser::validation::query_depth;
+///
+/// let doc = parse_query::<&str>("
+///     query A { user { ...F } }
+///     fragment F on User { friends { name } }
+/// ").unwrap();
+/// let depth = query_depth(&doc, &doc.fragment_map()).unwrap();
+/// assert_eq!(depth[0].name.as_deref(), Some("A"));
+/// assert_eq!(depth[0].value, 3);
+/// ```
+pub fn query_depth<'a, T>(doc: &Document<'a, T>,
+    fragments: &FragmentMap<'_, 'a, T>)
+    -> Result<Vec<OperationMetric>, CycleError>
+    where T: Text<'a>,
+{
+    let mut depth = Depth {
+        fragments,
+        cache: HashMap::new(),
+        stack: Vec::new(),
+    };
+    doc.operations()
+        .map(|op| Ok(OperationMetric {
+            name: op.name().map(Into::into),
+            value: depth.selection_set(op.selection_set())?,
+        }))
+        .collect()
+}
+
+/// Returns the complexity of each operation
+///
+/// Each field costs its `field_costs` or the `default_cost`, plus the
+/// complexity of its selection set, multiplied by the `list_multiplier`
+/// if the field is a list. Fragments are followed as in `query_depth`.
+/// Fields which aren't in the schema cost the `default_cost` and are
+/// not multiplied.
+pub fn query_complexity<'a, 'ast, T, S>(doc: &Document<'a, T>,
+    config: &ComplexityConfig<'_, 'ast, S>)
+    -> Result<Vec<OperationMetric>, CycleError>
+    where T: Text<'a>,
+          S: Text<'ast>,
+{
+    let fragments = doc.fragment_map();
+    let mut complexity = Complexity {
+        config,
+        fragments: &fragments,
+        cache: HashMap::new(),
+        stack: Vec::new(),
+    };
+    doc.operations()
+        .map(|op| {
+            let schema = config.schema;
+            let root = match op.operation_type() {
+                OperationType::Query => schema.query_type(),
+                OperationType::Mutation => schema.mutation_type(),
+                OperationType::Subscription => schema.subscription_type(),
+            };
+            Ok(OperationMetric {
+                name: op.name().map(Into::into),
+                value: complexity.selection_set(op.selection_set(), root)?,
+            })
+        })
+        .collect()
+}
+
+struct Depth<'f, 'd, 'a, T: Text<'a>> {
+    fragments: &'f FragmentMap<'d, 'a, T>,
+    cache: HashMap<&'d str, usize>,
+    stack: Vec<&'d str>,
+}
+
+impl<'f, 'd, 'a, T: Text<'a>> Depth<'f, 'd, 'a, T> {
+    fn selection_set(&mut self, selection_set: &SelectionSet<'a, T>)
+        -> Result<usize, CycleError>
+    {
+        let mut max = 0;
+        for item in &selection_set.items {
+            let depth = match *item {
+                Selection::Field(ref field) => {
+                    if field.name.as_ref() == "__typename" {
+                        continue;
+                    }
+                    1 + self.selection_set(&field.selection_set)?
+                }
+                Selection::FragmentSpread(ref spread) => {
+                    self.fragment(spread.fragment_name.as_ref())?
+                }
+                Selection::InlineFragment(ref frag) => {
+                    self.selection_set(&frag.selection_set)?
+                }
+            };
+            max = max.max(depth);
+        }
+        Ok(max)
+    }
+
+    fn fragment(&mut self, name: &str) -> Result<usize, CycleError> {
+        let frag = match self.fragments.get(name) {
+            Some(frag) => *frag,
+            None => return Ok(0),
+        };
+        let name = frag.name.as_ref();
+        if let Some(&depth) = self.cache.get(name) {
+            return Ok(depth);
+        }
+        if self.stack.contains(&name) {
+            return Err(CycleError { fragment: name.into() });
+        }
+        self.stack.push(name);
+        let depth = self.selection_set(&frag.selection_set)?;
+        self.stack.pop();
+        self.cache.insert(name, depth);
+        Ok(depth)
+    }
+}
+
+struct Complexity<'c, 's, 'ast, 'f, 'd, 'a, S, T>
+    where S: Text<'ast>,
+          T: Text<'a>,
+{
+    config: &'c ComplexityConfig<'s, 'ast, S>,
+    fragments: &'f FragmentMap<'d, 'a, T>,
+    cache: HashMap<&'d str, usize>,
+    stack: Vec<&'d str>,
+}
+
+impl<'c, 's, 'ast, 'f, 'd, 'a, S, T>
+    Complexity<'c, 's, 'ast, 'f, 'd, 'a, S, T>
+    where S: Text<'ast>,
+          T: Text<'a>,
+{
+    fn selection_set(&mut self, selection_set: &SelectionSet<'a, T>,
+        parent: Option<&'ast TypeDefinition<'ast, S>>)
+        -> Result<usize, CycleError>
+    {
+        let mut total = 0usize;
+        for item in &selection_set.items {
+            let cost = match *item {
+                Selection::Field(ref field) => {
+                    let name = field.name.as_ref();
+                    if name == "__typename" {
+                        continue;
+                    }
+                    let def = parent.and_then(|p| find_field(p, name));
+                    let cost = parent
+                        .and_then(|p| {
+                            let coordinate = format!("{}.{}",
+                                p.name().as_ref(), name);
+                            self.config.field_costs.get(&coordinate)
+                        })
+                        .copied()
+                        .unwrap_or(self.config.default_cost);
+                    let field_type = def.and_then(|def| {
+                        self.config.schema.find_type(
+                            get_named_type(&def.field_type).as_ref())
+                    });
+                    let children = self.selection_set(&field.selection_set,
+                                                      field_type)?;
+                    let multiplier = def
+                        .map(|def| self.multiplier(&def.field_type))
+                        .unwrap_or(1);
+                    cost.saturating_add(children.saturating_mul(multiplier))
+                }
+                Selection::FragmentSpread(ref spread) => {
+                    self.fragment(spread.fragment_name.as_ref())?
+                }
+                Selection::InlineFragment(ref frag) => {
+                    let parent = match frag.type_condition {
+                        Some(TypeCondition::On(ref name)) => {
+                            self.config.schema.find_type(name.as_ref())
+                        }
+                        None => parent,
+                    };
+                    self.selection_set(&frag.selection_set, parent)?
+                }
+            };
+            total = total.saturating_add(cost);
+        }
+        Ok(total)
+    }
+
+    fn multiplier(&self, typ: &Type<'ast, S>) -> usize {
+        match *typ {
+            Type::NamedType(_) => 1,
+            Type::NonNullType(ref inner) => self.multiplier(inner),
+            Type::ListType(ref inner) => {
+                self.config.list_multiplier
+                    .saturating_mul(self.multiplier(inner))
+            }
+        }
+    }
+
+    fn fragment(&mut self, name: &str) -> Result<usize, CycleError> {
+        let frag = match self.fragments.get(name) {
+            Some(frag) => *frag,
+            None => return Ok(0),
+        };
+        let name = frag.name.as_ref();
+        if let Some(&cost) = self.cache.get(name) {
+            return Ok(cost);
+        }
+        if self.stack.contains(&name) {
+            return Err(CycleError { fragment: name.into() });
+        }
+        let TypeCondition::On(ref type_name) = frag.type_condition;
+        let parent = self.config.schema.find_type(type_name.as_ref());
+        self.stack.push(name);
+        let cost = self.selection_set(&frag.selection_set, parent)?;
+        self.stack.pop();
+        self.cache.insert(name, cost);
+        Ok(cost)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{query_complexity, query_depth};
+    use super::{ComplexityConfig, CycleError, OperationMetric};
+    use crate::query::parse_query;
+    use crate::schema::{parse_schema, SchemaIndex};
+    use crate::validation::test_harness::TEST_SCHEMA;
+
+    fn metric(name: Option<&str>, value: usize) -> OperationMetric {
+        OperationMetric { name: name.map(Into::into), value }
+    }
+
+    #[test]
+    fn depth() {
+        let doc = parse_query::<&str>("
+            query Deep { dog { ...DogFields } }
+            fragment DogFields on Dog { name mother { ...Parent } }
+            fragment Parent on Dog { father { name __typename } }
+            { human { __typename } pet { ... on Dog { name } } }
+        ").unwrap();
+        assert_eq!(query_depth(&doc, &doc.fragment_map()), Ok(vec![
+            metric(Some("Deep"), 4),
+            metric(None, 2),
+        ]));
+    }
+
+    #[test]
+    fn depth_cycle() {
+        let doc = parse_query::<&str>("
+            { dog { ...A } }
+            fragment A on Dog { mother { ...B } }
+            fragment B on Dog { father { ...A } }
+        ").unwrap();
+        let err = query_depth(&doc, &doc.fragment_map()).unwrap_err();
+        assert_eq!(err, CycleError { fragment: "A".into() });
+        assert_eq!(err.to_string(), "fragment A spreads itself, \
+            directly or through other fragments");
+    }
+
+    #[test]
+    fn complexity() {
+        let schema = parse_schema::<&str>(TEST_SCHEMA).unwrap();
+        let index = SchemaIndex::new(&schema);
+        let doc = parse_query::<&str>("
+            query Pets { human { name pets { name } relatives { ...Pets } } }
+            fragment Pets on Human { pets { name } }
+            query Pet { pet { __typename ... on Dog { barks } } }
+        ").unwrap();
+        let config = ComplexityConfig::new(&index);
+        assert_eq!(query_complexity(&doc, &config), Ok(vec![
+            metric(Some("Pets"), 124),
+            metric(Some("Pet"), 2),
+        ]));
+        let config = config.field_cost("Human.pets", 5);
+        assert_eq!(query_complexity(&doc, &config), Ok(vec![
+            metric(Some("Pets"), 168),
+            metric(Some("Pet"), 2),
+        ]));
+    }
+}
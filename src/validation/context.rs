@@ -0,0 +1,238 @@
+use std::fmt;
+
+use crate::common::Text;
+use crate::position::Pos;
+use crate::query::{self, ParallelVisitor, QueryVisitor};
+use crate::schema;
+use crate::validation::{TypeInfo, ValidationError};
+use crate::validation::rules::*;
+
+
+/// A `QueryVisitor` which checks a validation rule and collects errors
+pub trait ValidationRule<'ast, T: Text<'ast>>: QueryVisitor<'ast, T> {
+    /// Removes and returns errors found so far
+    fn take_errors(&mut self) -> Vec<ValidationError>;
+}
+
+/// A query to validate along with the schema and the rules to check
+///
+/// Rules are added with `add_visitor` and run by `validate` in a single
+/// traversal of the query.
+pub struct ValidationContext<'ast, T: Text<'ast>> {
+    schema: &'ast schema::Document<'ast, T>,
+    query: &'ast query::Document<'ast, T>,
+    type_info: TypeInfo<'ast, T>,
+    visitors: Vec<Box<dyn ValidationRule<'ast, T> + 'ast>>,
+    errors: Vec<ValidationError>,
+}
+
+impl<'ast, T> ValidationContext<'ast, T>
+    where T: Text<'ast>,
+{
+    pub fn new(schema: &'ast schema::Document<'ast, T>,
+        query: &'ast query::Document<'ast, T>)
+        -> ValidationContext<'ast, T>
+    {
+        ValidationContext {
+            schema,
+            query,
+            type_info: TypeInfo::new(schema),
+            visitors: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn schema(&self) -> &'ast schema::Document<'ast, T> {
+        self.schema
+    }
+
+    pub fn query(&self) -> &'ast query::Document<'ast, T> {
+        self.query
+    }
+
+    /// Returns type information for the schema, for constructing rules
+    pub fn type_info(&self) -> &TypeInfo<'ast, T> {
+        &self.type_info
+    }
+
+    /// Registers a rule to be checked by `validate`
+    pub fn add_visitor<V>(&mut self, visitor: V)
+        where V: ValidationRule<'ast, T> + 'ast,
+    {
+        self.visitors.push(Box::new(visitor));
+    }
+
+    /// Records an error which isn't found by any of the rules
+    pub fn report_error<S>(&mut self, message: S, position: Pos)
+        where S: Into<String>,
+    {
+        self.errors.push(ValidationError::new(position, message));
+    }
+
+    /// Returns errors reported so far
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.errors
+    }
+}
+
+impl<'ast, T: Text<'ast>> fmt::Debug for ValidationContext<'ast, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ValidationContext")
+            .field("visitors", &self.visitors.len())
+            .field("errors", &self.errors)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Runs all the rules registered in the context in a single traversal
+///
+/// Returns errors reported to the context followed by errors of every rule
+/// in the order rules were added. The rules are removed from the context,
+/// so calling it again only returns newly reported errors.
+pub fn validate<'ast, T>(ctx: &mut ValidationContext<'ast, T>)
+    -> Vec<ValidationError>
+    where T: Text<'ast>,
+{
+    let mut visitor = ParallelVisitor::from(std::mem::take(&mut ctx.visitors));
+    query::visit(ctx.query, &mut visitor);
+    let mut errors = std::mem::take(&mut ctx.errors);
+    for mut rule in visitor.into_inner() {
+        errors.extend(rule.take_errors());
+    }
+    errors
+}
+
+/// Validates the query against the schema with all the implemented rules
+///
+/// The rules are run in the order of the specification, errors are
+/// returned grouped by rule.
+pub fn validate_query<'ast, T>(query: &'ast query::Document<'ast, T>,
+    schema: &'ast schema::Document<'ast, T>)
+    -> Vec<ValidationError>
+    where T: Text<'ast>,
+{
+    let mut ctx = ValidationContext::new(schema, query);
+    let type_info = ctx.type_info().clone();
+    ctx.add_visitor(SingleFieldSubscriptions::new());
+    ctx.add_visitor(FieldsOnCorrectType::new(type_info.clone()));
+    ctx.add_visitor(PossibleFragmentSpreads::new(type_info.clone()));
+    ctx.add_visitor(KnownDirectives::new(type_info.clone()));
+    ctx.add_visitor(KnownArguments::new(type_info.clone()));
+    ctx.add_visitor(ProvidedRequiredArguments::new(type_info.clone()));
+    ctx.add_visitor(VariablesInAllowedPosition::new(type_info.clone()));
+    ctx.add_visitor(OverlappingFieldsCanBeMerged::new(type_info));
+    validate(&mut ctx)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{validate, validate_query, ValidationContext};
+    use crate::position::Pos;
+    use crate::query::parse_query;
+    use crate::schema::parse_schema;
+    use crate::validation::ValidationError;
+    use crate::validation::{KnownArguments, ProvidedRequiredArguments};
+    use crate::validation::{PossibleFragmentSpreads, SingleFieldSubscriptions};
+    use crate::validation::test_harness::TEST_SCHEMA;
+
+    fn check(query: &str) -> Vec<ValidationError> {
+        let schema = parse_schema::<String>(TEST_SCHEMA).unwrap();
+        let query = parse_query::<String>(query).unwrap();
+        let mut ctx = ValidationContext::new(&schema, &query);
+        let type_info = ctx.type_info().clone();
+        ctx.add_visitor(KnownArguments::new(type_info.clone()));
+        ctx.add_visitor(ProvidedRequiredArguments::new(type_info.clone()));
+        ctx.add_visitor(PossibleFragmentSpreads::new(type_info));
+        ctx.add_visitor(SingleFieldSubscriptions::new());
+        validate(&mut ctx)
+    }
+
+    #[test]
+    fn valid_query() {
+        assert_eq!(check("{
+            dog { ... on Pet { name } }
+            complicatedArgs { multipleReqs(req1: 1, req2: 2) }
+        }"), []);
+    }
+
+    #[test]
+    fn invalid_query() {
+        let errors = check("{
+            human { ... on Cat { name } }
+            complicatedArgs { multipleReqs(req1: 1, unknown: 2) }
+        }");
+        let messages = errors.iter()
+            .map(|e| (e.position, &e.message[..]))
+            .collect::<Vec<_>>();
+        assert_eq!(messages, [
+            (Pos::new(3, 31),
+             "Unknown argument \"unknown\" on field \
+              \"ComplicatedArgs.multipleReqs\"."),
+            (Pos::new(3, 31),
+             "Field \"multipleReqs\" argument \"req2\" of type \"Int!\" \
+              is required, but it was not provided."),
+            (Pos::new(2, 25),
+             "Fragment cannot be spread here as objects of type \
+              \"Human\" can never be of type \"Cat\"."),
+        ]);
+    }
+
+    #[test]
+    fn reported_errors() {
+        let schema = parse_schema::<String>(TEST_SCHEMA).unwrap();
+        let query = parse_query::<String>("{ dog { name } }").unwrap();
+        let mut ctx = ValidationContext::new(&schema, &query);
+        ctx.report_error("custom", Pos::new(1, 1));
+        assert_eq!(ctx.errors().len(), 1);
+        assert_eq!(validate(&mut ctx), [
+            ValidationError::new(Pos::new(1, 1), "custom"),
+        ]);
+        assert_eq!(validate(&mut ctx), []);
+    }
+
+    #[test]
+    fn validate_all_rules() {
+        let schema = parse_schema::<String>(TEST_SCHEMA).unwrap();
+        let valid = parse_query::<String>("{
+            human(id: 4) { name, pets { ... { name } } }
+            catOrDog { ... on Cat { furColor } }
+        }").unwrap();
+        assert_eq!(validate_query(&valid, &schema), []);
+
+        let invalid = parse_query::<String>("{
+            human(id: 4) { name, pets { ... { name } } }
+            alien { unknown }
+            catOrDog { ... on Cat { furColor } }
+        }").unwrap();
+        assert_eq!(validate_query(&invalid, &schema), [
+            ValidationError::new(Pos::new(3, 21),
+                "Cannot query field \"unknown\" on type \"Alien\"."),
+        ]);
+    }
+
+    #[test]
+    fn built_in_directives_on_bare_schema() {
+        let check = |query: &str| {
+            let schema = parse_schema::<String>("
+                type Query { dog: Dog }
+                type Dog { name: String }
+            ").unwrap();
+            let query = parse_query::<String>(query).unwrap();
+            validate_query(&query, &schema)
+        };
+        assert_eq!(check("{ dog @include { name } }"), [
+            ValidationError::new(Pos::new(1, 7),
+                "Directive \"@include\" argument \"if\" of type \"Boolean!\" \
+                 is required, but it was not provided."),
+        ]);
+        assert_eq!(check("{ dog @include(if: true, foo: 1) { name } }"), [
+            ValidationError::new(Pos::new(1, 7),
+                "Unknown argument \"foo\" on directive \"@include\"."),
+        ]);
+        assert_eq!(check("query($b: Boolean) { dog @skip(if: $b) { name } }"), [
+            ValidationError::new(Pos::new(1, 26),
+                "Variable \"$b\" of type \"Boolean\" used in position \
+                 expecting type \"Boolean!\"."),
+        ]);
+    }
+}
@@ -0,0 +1,156 @@
+//! Schema coordinates used by queries, e.g. for usage reporting
+//!
+use std::collections::{BTreeSet, HashSet};
+
+use crate::common::Text;
+use crate::query::{visit, used_fragments, Document, QueryAstNode};
+use crate::query::QueryVisitor;
+use crate::schema::SchemaIndex;
+use crate::validation::TypeInfo;
+
+
+/// Type name used for the fields which aren't found in the schema
+const UNKNOWN_TYPE: &str = "<unknown>";
+
+/// Returns coordinates of the fields and field arguments used by the
+/// operations of the query
+///
+/// Fields are listed as `Type.field`, arguments as `Type.field(arg:)`.
+/// Fields are resolved through fragment spreads and inline fragments,
+/// fragments which no operation spreads are skipped. Fields which can't be
+/// found in the schema are listed as `<unknown>.field`.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::{parse_query, parse_schema};
+/// use graphql_parser::schema::SchemaIndex;
+/// use graphql_parser::validation::collect_field_coordinates;
+///
+/// let schema = parse_schema::<&str>("
+///     type Query { user(id: ID): User }
+///     type User { name: String }
+/// ").unwrap();
+/// let query = parse_query::<&str>("{ user(id: 1) { name } }").unwrap();
+/// let coordinates = collect_field_coordinates(&SchemaIndex::new(&schema),
+///                                             &query);
+/// assert_eq!(coordinates.into_iter().collect::<Vec<_>>(),
+///            ["Query.user", "Query.user(id:)", "User.name"]);
+/// ```
+pub fn collect_field_coordinates<'ast, T>(schema: &SchemaIndex<'ast, T>,
+    query: &'ast Document<'ast, T>)
+    -> BTreeSet<String>
+    where T: Text<'ast>,
+{
+    let used = query.operations()
+        .flat_map(|op| used_fragments(op, query))
+        .map(|frag| frag.name.as_ref())
+        .collect();
+    let mut collector = Coordinates {
+        type_info: TypeInfo::from_index(schema.clone()),
+        used,
+        fields: Vec::new(),
+        in_directive: false,
+        skip: false,
+        coordinates: BTreeSet::new(),
+    };
+    visit(query, &mut collector);
+    collector.coordinates
+}
+
+struct Coordinates<'ast, T: Text<'ast>> {
+    type_info: TypeInfo<'ast, T>,
+    used: HashSet<&'ast str>,
+    /// Coordinates of the fields being visited
+    fields: Vec<String>,
+    in_directive: bool,
+    /// Set while visiting a fragment which no operation spreads
+    skip: bool,
+    coordinates: BTreeSet<String>,
+}
+
+impl<'ast, T> QueryVisitor<'ast, T> for Coordinates<'ast, T>
+    where T: Text<'ast>,
+{
+    fn enter(&mut self, node: QueryAstNode<'ast, T>) {
+        self.type_info.enter(node);
+        match node {
+            QueryAstNode::FragmentDefinition(frag) => {
+                self.skip = !self.used.contains(frag.name.as_ref());
+            }
+            QueryAstNode::Field(field) => {
+                let name = field.name.as_ref();
+                let parent = self.type_info.parent_type()
+                    .filter(|_| {
+                        self.type_info.field_def().is_some()
+                        || name.starts_with("__")
+                    })
+                    .map(|parent| parent.name().as_ref())
+                    .unwrap_or(UNKNOWN_TYPE);
+                let coordinate = format!("{}.{}", parent, name);
+                if !self.skip {
+                    self.coordinates.insert(coordinate.clone());
+                }
+                self.fields.push(coordinate);
+            }
+            QueryAstNode::Directive(_) => self.in_directive = true,
+            QueryAstNode::Argument(name, _) => {
+                if self.skip || self.in_directive {
+                    return;
+                }
+                if let Some(field) = self.fields.last() {
+                    self.coordinates.insert(
+                        format!("{}({}:)", field, name.as_ref()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn leave(&mut self, node: QueryAstNode<'ast, T>) {
+        match node {
+            QueryAstNode::FragmentDefinition(_) => self.skip = false,
+            QueryAstNode::Field(_) => {
+                self.fields.pop();
+            }
+            QueryAstNode::Directive(_) => self.in_directive = false,
+            _ => {}
+        }
+        self.type_info.leave(node);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::collect_field_coordinates;
+    use crate::query::parse_query;
+    use crate::schema::{parse_schema, SchemaIndex};
+    use crate::validation::test_harness::TEST_SCHEMA;
+
+    #[test]
+    fn coordinates() {
+        let schema = parse_schema::<&str>(TEST_SCHEMA).unwrap();
+        let query = parse_query::<&str>("
+            query Q {
+                human(id: 1) { ...HumanFields }
+                pet { __typename ... on Dog { name @skip(if: false) } }
+                missing { field(arg: 1) }
+            }
+            fragment HumanFields on Human { pets { name } }
+            fragment Unused on Human { name }
+        ").unwrap();
+        let coordinates = collect_field_coordinates(&SchemaIndex::new(&schema),
+                                                    &query);
+        assert_eq!(coordinates.into_iter().collect::<Vec<_>>(), [
+            "<unknown>.field",
+            "<unknown>.field(arg:)",
+            "<unknown>.missing",
+            "Dog.name",
+            "Human.pets",
+            "Pet.__typename",
+            "Pet.name",
+            "QueryRoot.human",
+            "QueryRoot.human(id:)",
+            "QueryRoot.pet",
+        ]);
+    }
+}
@@ -0,0 +1,20 @@
+use thiserror::Error;
+
+use crate::position::Pos;
+
+
+/// An error found when validating a document against a schema
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("{position}: {message}")]
+pub struct ValidationError {
+    /// Position of the offending node in the validated document
+    pub position: Pos,
+    /// Human-readable description of the problem
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn new<S: Into<String>>(position: Pos, message: S) -> Self {
+        ValidationError { position, message: message.into() }
+    }
+}
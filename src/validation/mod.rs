@@ -0,0 +1,60 @@
+//! Validation of queries against a schema
+//!
+//! Each validation rule is a `QueryVisitor` which tracks types with a
+//! `TypeInfo` and collects `ValidationError`s. Run a rule with
+//! `query::visit`:
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! use graphql_parser::{parse_query, parse_schema};
+//! use graphql_parser::query::visit;
+//! use graphql_parser::validation::{TypeInfo, ProvidedRequiredArguments};
+//!
+//! let schema = parse_schema::<&str>("
+//!     type Query { user(id: ID!): String }
+//! ").unwrap();
+//! let query = parse_query::<&str>("{ user }").unwrap();
+//! let mut rule = ProvidedRequiredArguments::new(TypeInfo::new(&schema));
+//! visit(&query, &mut rule);
+//! assert_eq!(rule.errors().len(), 1);
+//! ```
+//!
+//! To check multiple rules in a single pass, add them to a
+//! `ValidationContext` and run `validate`:
+//!
+//! ```rust
+//! # extern crate graphql_parser;
+//! # use graphql_parser::{parse_query, parse_schema};
+//! use graphql_parser::validation::{validate, ValidationContext};
+//! use graphql_parser::validation::{KnownArguments, ProvidedRequiredArguments};
+//!
+//! # let schema = parse_schema::<&str>("
+//! #     type Query { user(id: ID!): String }
+//! # ").unwrap();
+//! let query = parse_query::<&str>("{ user(name: 1) }").unwrap();
+//! let mut ctx = ValidationContext::new(&schema, &query);
+//! let type_info = ctx.type_info().clone();
+//! ctx.add_visitor(KnownArguments::new(type_info.clone()));
+//! ctx.add_visitor(ProvidedRequiredArguments::new(type_info));
+//! assert_eq!(validate(&mut ctx).len(), 2);
+//! ```
+//!
+mod complexity;
+mod context;
+mod coordinates;
+mod error;
+mod rules;
+mod schema;
+mod type_info;
+#[cfg(test)]
+pub(crate) mod test_harness;
+
+pub use self::complexity::{query_complexity, query_depth};
+pub use self::complexity::{ComplexityConfig, CycleError, OperationMetric};
+pub use self::coordinates::collect_field_coordinates;
+pub use self::context::{validate, validate_query};
+pub use self::context::{ValidationContext, ValidationRule};
+pub use self::error::ValidationError;
+pub use self::rules::*;
+pub use self::schema::validate_schema;
+pub use self::type_info::TypeInfo;
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::common::{get_named_type, Text};
+use crate::position::Pos;
+use crate::query::{QueryAstNode, QueryVisitor, Value};
+use crate::validation::{TypeInfo, ValidationError, ValidationRule};
+
+
+/// Checks literal values of a custom scalar, returns a reason if the
+/// value is invalid
+pub type ScalarValidator<'ast, T> = fn(&Value<'ast, T>) -> Result<(), String>;
+
+/// Validators of custom scalars by scalar name, see `CustomScalarValues`
+pub struct ScalarValidators<'ast, T: Text<'ast>> {
+    validators: HashMap<String, ScalarValidator<'ast, T>>,
+}
+
+impl<'ast, T: Text<'ast>> ScalarValidators<'ast, T> {
+    pub fn new() -> Self {
+        ScalarValidators { validators: HashMap::new() }
+    }
+
+    /// Registers the validator of the scalar, replacing the previous one
+    pub fn register(mut self, scalar: &str, validator: ScalarValidator<'ast, T>)
+        -> Self
+    {
+        self.validators.insert(scalar.into(), validator);
+        self
+    }
+
+    /// Returns the validator of the scalar, if registered
+    pub fn get(&self, scalar: &str) -> Option<ScalarValidator<'ast, T>> {
+        self.validators.get(scalar).copied()
+    }
+}
+
+impl<'ast, T: Text<'ast>> Default for ScalarValidators<'ast, T> {
+    fn default() -> Self {
+        ScalarValidators::new()
+    }
+}
+
+impl<'ast, T: Text<'ast>> Clone for ScalarValidators<'ast, T> {
+    fn clone(&self) -> Self {
+        ScalarValidators { validators: self.validators.clone() }
+    }
+}
+
+impl<'ast, T: Text<'ast>> fmt::Debug for ScalarValidators<'ast, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.validators.keys()).finish()
+    }
+}
+
+/// Checks literal values of custom scalars with the registered validators
+///
+/// Values of the scalars without a validator, variables and `null` are
+/// not checked. Values are reported at the position of the field,
+/// directive or variable definition they belong to.
+#[derive(Debug)]
+pub struct CustomScalarValues<'ast, T: Text<'ast>> {
+    type_info: TypeInfo<'ast, T>,
+    validators: ScalarValidators<'ast, T>,
+    positions: Vec<Pos>,
+    errors: Vec<ValidationError>,
+}
+
+impl<'ast, T> CustomScalarValues<'ast, T>
+    where T: Text<'ast>,
+{
+    pub fn new(type_info: TypeInfo<'ast, T>,
+        validators: ScalarValidators<'ast, T>)
+        -> Self
+    {
+        CustomScalarValues {
+            type_info,
+            validators,
+            positions: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Returns errors found so far
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.errors
+    }
+
+    pub fn into_errors(self) -> Vec<ValidationError> {
+        self.errors
+    }
+
+    fn check(&mut self, value: &'ast Value<'ast, T>) {
+        let typ = match self.type_info.input_type() {
+            Some(typ) => get_named_type(typ).as_ref(),
+            None => return,
+        };
+        let validator = match self.validators.get(typ) {
+            Some(validator) => validator,
+            None => return,
+        };
+        if let Err(reason) = validator(value) {
+            let position = self.positions.last().copied().unwrap_or_default();
+            self.errors.push(ValidationError::new(position, format!(
+                "Expected value of type \"{}\", found {}; {}",
+                typ, value, reason)));
+        }
+    }
+}
+
+impl<'ast, T> QueryVisitor<'ast, T> for CustomScalarValues<'ast, T>
+    where T: Text<'ast>,
+{
+    fn enter(&mut self, node: QueryAstNode<'ast, T>) {
+        self.type_info.enter(node);
+        match node {
+            QueryAstNode::Field(field) => self.positions.push(field.position),
+            QueryAstNode::Directive(dir) => self.positions.push(dir.position),
+            QueryAstNode::VariableDefinition(var) => {
+                self.positions.push(var.position);
+            }
+            QueryAstNode::Value(value) => match *value {
+                Value::Variable(_) | Value::Null | Value::List(_) => {}
+                _ => self.check(value),
+            },
+            _ => {}
+        }
+    }
+
+    fn leave(&mut self, node: QueryAstNode<'ast, T>) {
+        match node {
+            QueryAstNode::Field(_)
+            | QueryAstNode::Directive(_)
+            | QueryAstNode::VariableDefinition(_)
+            => {
+                self.positions.pop();
+            }
+            _ => {}
+        }
+        self.type_info.leave(node);
+    }
+}
+
+impl<'ast, T> ValidationRule<'ast, T> for CustomScalarValues<'ast, T>
+    where T: Text<'ast>,
+{
+    fn take_errors(&mut self) -> Vec<ValidationError> {
+        std::mem::take(&mut self.errors)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CustomScalarValues, ScalarValidators};
+    use crate::position::Pos;
+    use crate::query::{parse_query, visit, Value};
+    use crate::schema::parse_schema;
+    use crate::validation::{TypeInfo, ValidationError};
+
+    const SCHEMA: &str = "
+        scalar DateTime
+        type Query {
+            events(after: DateTime, on: [DateTime!]): [String]
+        }
+    ";
+
+    fn date(value: &Value<String>) -> Result<(), String> {
+        match *value {
+            Value::String(ref s) if s.len() == 10
+                && s.chars().enumerate().all(|(i, c)| match i {
+                    4 | 7 => c == '-',
+                    _ => c.is_ascii_digit(),
+                })
+                => Ok(()),
+            _ => Err("expected a date like \"2020-01-31\"".into()),
+        }
+    }
+
+    fn validate(query: &str) -> Vec<ValidationError> {
+        let schema = parse_schema::<String>(SCHEMA).unwrap();
+        let query = parse_query::<String>(query).unwrap();
+        let validators = ScalarValidators::new().register("DateTime", date);
+        let mut rule = CustomScalarValues::new(TypeInfo::new(&schema),
+                                               validators);
+        visit(&query, &mut rule);
+        rule.into_errors()
+    }
+
+    #[test]
+    fn valid_dates() {
+        assert_eq!(validate(r#"query ($d: DateTime = "2020-02-29") {
+            events(after: "2020-01-31", on: ["2021-12-01", $d])
+            other: events(after: null, on: $list)
+        }"#), []);
+    }
+
+    #[test]
+    fn invalid_dates() {
+        assert_eq!(validate(r#"query ($d: DateTime = "soon") {
+            events(after: 42, on: ["2021-12-01", "tomorrow"])
+        }"#), [
+            ValidationError::new(Pos::new(1, 8),
+                "Expected value of type \"DateTime\", found \"soon\"; \
+                 expected a date like \"2020-01-31\""),
+            ValidationError::new(Pos::new(2, 13),
+                "Expected value of type \"DateTime\", found 42; \
+                 expected a date like \"2020-01-31\""),
+            ValidationError::new(Pos::new(2, 13),
+                "Expected value of type \"DateTime\", found \"tomorrow\"; \
+                 expected a date like \"2020-01-31\""),
+        ]);
+    }
+}
@@ -0,0 +1,113 @@
+use crate::common::Text;
+use crate::query::{QueryAstNode, QueryVisitor};
+use crate::validation::{TypeInfo, ValidationError, ValidationRule};
+
+
+/// Checks that every selected field is defined on the parent type
+///
+/// `__typename` can be selected on any type, `__schema` and `__type` on
+/// the query root type. Fields of unknown types aren't checked.
+#[derive(Debug)]
+pub struct FieldsOnCorrectType<'ast, T: Text<'ast>> {
+    type_info: TypeInfo<'ast, T>,
+    errors: Vec<ValidationError>,
+}
+
+impl<'ast, T> FieldsOnCorrectType<'ast, T>
+    where T: Text<'ast>,
+{
+    pub fn new(type_info: TypeInfo<'ast, T>) -> Self {
+        FieldsOnCorrectType { type_info, errors: Vec::new() }
+    }
+
+    /// Returns errors found so far
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.errors
+    }
+
+    pub fn into_errors(self) -> Vec<ValidationError> {
+        self.errors
+    }
+}
+
+impl<'ast, T> QueryVisitor<'ast, T> for FieldsOnCorrectType<'ast, T>
+    where T: Text<'ast>,
+{
+    fn enter(&mut self, node: QueryAstNode<'ast, T>) {
+        self.type_info.enter(node);
+        if let QueryAstNode::Field(field) = node {
+            let parent = match self.type_info.parent_type() {
+                Some(parent) => parent,
+                None => return,
+            };
+            let name = field.name.as_ref();
+            let known = self.type_info.field_def().is_some()
+                || name == "__typename"
+                || (name == "__schema" || name == "__type")
+                    && self.type_info.schema().query_type()
+                        .is_some_and(|query| query.name() == parent.name());
+            if !known {
+                self.errors.push(ValidationError::new(field.position,
+                    format!("Cannot query field \"{}\" on type \"{}\".",
+                            name, parent.name().as_ref())));
+            }
+        }
+    }
+
+    fn leave(&mut self, node: QueryAstNode<'ast, T>) {
+        self.type_info.leave(node);
+    }
+}
+
+impl<'ast, T> ValidationRule<'ast, T> for FieldsOnCorrectType<'ast, T>
+    where T: Text<'ast>,
+{
+    fn take_errors(&mut self) -> Vec<ValidationError> {
+        std::mem::take(&mut self.errors)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FieldsOnCorrectType;
+    use crate::position::Pos;
+    use crate::query::{parse_query, visit};
+    use crate::schema::parse_schema;
+    use crate::validation::{TypeInfo, ValidationError};
+    use crate::validation::test_harness::TEST_SCHEMA;
+
+    fn validate(query: &str) -> Vec<ValidationError> {
+        let schema = parse_schema::<String>(TEST_SCHEMA).unwrap();
+        let query = parse_query::<String>(query).unwrap();
+        let mut rule = FieldsOnCorrectType::new(TypeInfo::new(&schema));
+        visit(&query, &mut rule);
+        rule.into_errors()
+    }
+
+    #[test]
+    fn known_fields() {
+        assert_eq!(validate("{
+            __typename
+            __schema { types { name } }
+            dog { name __typename ... on Pet { name } }
+            catOrDog { __typename ... on Cat { meows } }
+        }
+        fragment F on Being { name }"), []);
+    }
+
+    #[test]
+    fn unknown_fields() {
+        assert_eq!(validate("{
+            alien { unknown }
+            catOrDog { name }
+            dog { __schema }
+        }"), [
+            ValidationError::new(Pos::new(2, 21),
+                "Cannot query field \"unknown\" on type \"Alien\"."),
+            ValidationError::new(Pos::new(3, 24),
+                "Cannot query field \"name\" on type \"CatOrDog\"."),
+            ValidationError::new(Pos::new(4, 19),
+                "Cannot query field \"__schema\" on type \"Dog\"."),
+        ]);
+    }
+}
@@ -0,0 +1,157 @@
+use crate::common::{Directive, Text};
+use crate::position::Pos;
+use crate::query::{QueryAstNode, QueryVisitor};
+use crate::validation::{TypeInfo, ValidationError, ValidationRule};
+
+
+/// Checks that every argument passed to a field or a directive is defined
+///
+/// Arguments of unknown fields and directives aren't checked, those are
+/// reported by other rules.
+#[derive(Debug)]
+pub struct KnownArguments<'ast, T: Text<'ast>> {
+    type_info: TypeInfo<'ast, T>,
+    field_positions: Vec<Pos>,
+    directive: Option<&'ast Directive<'ast, T>>,
+    errors: Vec<ValidationError>,
+}
+
+impl<'ast, T> KnownArguments<'ast, T>
+    where T: Text<'ast>,
+{
+    pub fn new(type_info: TypeInfo<'ast, T>) -> Self {
+        KnownArguments {
+            type_info,
+            field_positions: Vec::new(),
+            directive: None,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Returns errors found so far
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.errors
+    }
+
+    pub fn into_errors(self) -> Vec<ValidationError> {
+        self.errors
+    }
+}
+
+impl<'ast, T> QueryVisitor<'ast, T> for KnownArguments<'ast, T>
+    where T: Text<'ast>,
+{
+    fn enter(&mut self, node: QueryAstNode<'ast, T>) {
+        self.type_info.enter(node);
+        match node {
+            QueryAstNode::Field(field) => {
+                self.field_positions.push(field.position);
+            }
+            QueryAstNode::Directive(dir) => {
+                self.directive = Some(dir);
+            }
+            QueryAstNode::Argument(name, _) => {
+                if self.type_info.argument().is_some() {
+                    return;
+                }
+                let name = name.as_ref();
+                if let Some(dir) = self.directive {
+                    if self.type_info.directive().is_some() {
+                        self.errors.push(ValidationError::new(dir.position,
+                            format!("Unknown argument \"{}\" on directive \
+                                     \"@{}\".", name, dir.name.as_ref())));
+                    }
+                } else if let (Some(parent), Some(def), Some(&position)) = (
+                    self.type_info.parent_type(),
+                    self.type_info.field_def(),
+                    self.field_positions.last(),
+                ) {
+                    self.errors.push(ValidationError::new(position,
+                        format!("Unknown argument \"{}\" on field \
+                                 \"{}.{}\".", name,
+                                 parent.name().as_ref(), def.name.as_ref())));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn leave(&mut self, node: QueryAstNode<'ast, T>) {
+        self.type_info.leave(node);
+        match node {
+            QueryAstNode::Field(_) => {
+                self.field_positions.pop();
+            }
+            QueryAstNode::Directive(_) => {
+                self.directive = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<'ast, T> ValidationRule<'ast, T> for KnownArguments<'ast, T>
+    where T: Text<'ast>,
+{
+    fn take_errors(&mut self) -> Vec<ValidationError> {
+        std::mem::take(&mut self.errors)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::KnownArguments;
+    use crate::position::Pos;
+    use crate::query::{parse_query, visit};
+    use crate::schema::parse_schema;
+    use crate::validation::{TypeInfo, ValidationError};
+    use crate::validation::test_harness::TEST_SCHEMA;
+
+    fn validate(query: &str) -> Vec<ValidationError> {
+        let schema = parse_schema::<String>(TEST_SCHEMA).unwrap();
+        let query = parse_query::<String>(query).unwrap();
+        let mut rule = KnownArguments::new(TypeInfo::new(&schema));
+        visit(&query, &mut rule);
+        rule.into_errors()
+    }
+
+    #[test]
+    fn known_arguments() {
+        assert_eq!(validate("{
+            human(id: 4) { name(surname: true) }
+            dog @directiveWithArgs(required: 1, optional: \"x\") {
+                doesKnowCommand(dogCommand: SIT)
+            }
+        }"), []);
+    }
+
+    #[test]
+    fn unknown_field_argument() {
+        assert_eq!(validate("{ human(unknownArg: 1) { name } }"), [
+            ValidationError::new(Pos::new(1, 3),
+                "Unknown argument \"unknownArg\" on field \"QueryRoot.human\"."),
+        ]);
+    }
+
+    #[test]
+    fn unknown_directive_argument() {
+        assert_eq!(validate("{
+            dog @directiveWithArgs(required: 1, unknown: true) { name }
+        }"), [
+            ValidationError::new(Pos::new(2, 17),
+                "Unknown argument \"unknown\" on directive \
+                 \"@directiveWithArgs\"."),
+        ]);
+    }
+
+    #[test]
+    fn unknown_field_or_directive() {
+        assert_eq!(validate("{
+            human { unknownField(arg: 1) }
+            dog @unknownDirective(arg: 1) { name(arg: 1) }
+        }"), [
+            ValidationError::new(Pos::new(3, 45),
+                "Unknown argument \"arg\" on field \"Dog.name\"."),
+        ]);
+    }
+}
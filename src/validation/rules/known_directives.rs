@@ -0,0 +1,392 @@
+use crate::common::{is_list, Directive, Text};
+use crate::query::{OperationDefinition, QueryAstNode, QueryVisitor};
+use crate::schema::{self, Definition, DirectiveLocation, InputValue};
+use crate::schema::{TypeDefinition, TypeExtension};
+use crate::validation::{TypeInfo, ValidationError, ValidationRule};
+
+
+/// Checks that every directive used is defined and is allowed at the
+/// location where it's used
+///
+/// Built-in directives (`@skip`, `@include`, `@deprecated`,
+/// `@specifiedBy`, `@oneOf`, and `@defer` and `@stream` from the
+/// incremental delivery draft) are known without being defined in the
+/// schema, as `TypeInfo::new` adds them. The built-in `@stream` is only
+/// allowed on fields returning lists.
+/// Query documents are checked by visiting them, directives of a schema
+/// document are checked by `check_schema`.
+#[derive(Debug)]
+pub struct KnownDirectives<'ast, T: Text<'ast>> {
+    type_info: TypeInfo<'ast, T>,
+    locations: Vec<DirectiveLocation>,
+    errors: Vec<ValidationError>,
+}
+
+impl<'ast, T> KnownDirectives<'ast, T>
+    where T: Text<'ast>,
+{
+    pub fn new(type_info: TypeInfo<'ast, T>) -> Self {
+        KnownDirectives {
+            type_info,
+            locations: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Returns errors found so far
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.errors
+    }
+
+    pub fn into_errors(self) -> Vec<ValidationError> {
+        self.errors
+    }
+
+    /// Checks all directives used in a schema document
+    ///
+    /// Usually this is the same document the `TypeInfo` was created from,
+    /// but extensions for that schema can be checked too.
+    pub fn check_schema(&mut self, doc: &'ast schema::Document<'ast, T>) {
+        use crate::schema::DirectiveLocation as L;
+        for def in &doc.definitions {
+            match *def {
+                Definition::SchemaDefinition(ref s) => {
+                    self.check_all(&s.directives, L::Schema);
+                }
+                Definition::SchemaExtension(ref s) => {
+                    self.check_all(&s.directives, L::Schema);
+                }
+                Definition::TypeDefinition(ref typ) => match *typ {
+                    TypeDefinition::Scalar(ref s) => {
+                        self.check_all(&s.directives, L::Scalar);
+                    }
+                    TypeDefinition::Object(ref o) => {
+                        self.check_all(&o.directives, L::Object);
+                        self.check_fields(&o.fields);
+                    }
+                    TypeDefinition::Interface(ref i) => {
+                        self.check_all(&i.directives, L::Interface);
+                        self.check_fields(&i.fields);
+                    }
+                    TypeDefinition::Union(ref u) => {
+                        self.check_all(&u.directives, L::Union);
+                    }
+                    TypeDefinition::Enum(ref e) => {
+                        self.check_all(&e.directives, L::Enum);
+                        for value in &e.values {
+                            self.check_all(&value.directives, L::EnumValue);
+                        }
+                    }
+                    TypeDefinition::InputObject(ref i) => {
+                        self.check_all(&i.directives, L::InputObject);
+                        self.check_inputs(&i.fields, L::InputFieldDefinition);
+                    }
+                },
+                Definition::TypeExtension(ref ext) => match *ext {
+                    TypeExtension::Scalar(ref s) => {
+                        self.check_all(&s.directives, L::Scalar);
+                    }
+                    TypeExtension::Object(ref o) => {
+                        self.check_all(&o.directives, L::Object);
+                        self.check_fields(&o.fields);
+                    }
+                    TypeExtension::Interface(ref i) => {
+                        self.check_all(&i.directives, L::Interface);
+                        self.check_fields(&i.fields);
+                    }
+                    TypeExtension::Union(ref u) => {
+                        self.check_all(&u.directives, L::Union);
+                    }
+                    TypeExtension::Enum(ref e) => {
+                        self.check_all(&e.directives, L::Enum);
+                        for value in &e.values {
+                            self.check_all(&value.directives, L::EnumValue);
+                        }
+                    }
+                    TypeExtension::InputObject(ref i) => {
+                        self.check_all(&i.directives, L::InputObject);
+                        self.check_inputs(&i.fields, L::InputFieldDefinition);
+                    }
+                },
+                Definition::DirectiveDefinition(ref d) => {
+                    self.check_inputs(&d.arguments, L::ArgumentDefinition);
+                }
+            }
+        }
+    }
+
+    fn check_fields(&mut self, fields: &[schema::Field<'ast, T>]) {
+        for field in fields {
+            self.check_all(&field.directives, DirectiveLocation::FieldDefinition);
+            self.check_inputs(&field.arguments,
+                              DirectiveLocation::ArgumentDefinition);
+        }
+    }
+
+    fn check_inputs(&mut self, inputs: &[InputValue<'ast, T>],
+        location: DirectiveLocation)
+    {
+        for input in inputs {
+            self.check_all(&input.directives, location.clone());
+        }
+    }
+
+    fn check_all(&mut self, dirs: &[Directive<'ast, T>],
+        location: DirectiveLocation)
+    {
+        for dir in dirs {
+            self.check(dir, &location);
+        }
+    }
+
+    fn check(&mut self, dir: &Directive<'ast, T>, location: &DirectiveLocation)
+    {
+        let name = dir.name.as_ref();
+        let allowed = match self.type_info.schema().find_directive(name) {
+            Some(def) => &def.locations,
+            None => {
+                self.errors.push(ValidationError::new(dir.position,
+                    format!("Unknown directive \"@{}\".", name)));
+                return;
+            }
+        };
+        if !allowed.contains(location) {
+            self.errors.push(ValidationError::new(dir.position,
+                format!("Directive \"@{}\" may not be used on {}.",
+                        name, location.as_str())));
+        }
+    }
+}
+
+impl<'ast, T> KnownDirectives<'ast, T>
+    where T: Text<'ast>,
+{
+    fn check_stream(&mut self, dir: &Directive<'ast, T>) {
+        let schema = self.type_info.schema();
+        if dir.name.as_ref() != "stream"
+            || schema.defined_directive("stream").is_some()
+        {
+            return;
+        }
+        let typ = match self.type_info.field_def() {
+            Some(field) => &field.field_type,
+            None => return,
+        };
+        if !is_list(typ) {
+            self.errors.push(ValidationError::new(dir.position,
+                "Directive \"@stream\" may only be used on list fields."));
+        }
+    }
+}
+
+impl<'ast, T> QueryVisitor<'ast, T> for KnownDirectives<'ast, T>
+    where T: Text<'ast>,
+{
+    fn enter(&mut self, node: QueryAstNode<'ast, T>) {
+        self.type_info.enter(node);
+        let location = match node {
+            QueryAstNode::OperationDefinition(op) => match *op {
+                OperationDefinition::SelectionSet(_)
+                | OperationDefinition::Query(_)
+                    => DirectiveLocation::Query,
+                OperationDefinition::Mutation(_)
+                    => DirectiveLocation::Mutation,
+                OperationDefinition::Subscription(_)
+                    => DirectiveLocation::Subscription,
+            },
+            QueryAstNode::FragmentDefinition(_)
+                => DirectiveLocation::FragmentDefinition,
+            QueryAstNode::Field(_) => DirectiveLocation::Field,
+            QueryAstNode::FragmentSpread(_)
+                => DirectiveLocation::FragmentSpread,
+            QueryAstNode::InlineFragment(_)
+                => DirectiveLocation::InlineFragment,
+            QueryAstNode::Directive(dir) => {
+                if let Some(location) = self.locations.last().cloned() {
+                    self.check(dir, &location);
+                    if location == DirectiveLocation::Field {
+                        self.check_stream(dir);
+                    }
+                }
+                return;
+            }
+            _ => return,
+        };
+        self.locations.push(location);
+    }
+
+    fn leave(&mut self, node: QueryAstNode<'ast, T>) {
+        self.type_info.leave(node);
+        match node {
+            QueryAstNode::OperationDefinition(_)
+            | QueryAstNode::FragmentDefinition(_)
+            | QueryAstNode::Field(_)
+            | QueryAstNode::FragmentSpread(_)
+            | QueryAstNode::InlineFragment(_)
+            => {
+                self.locations.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<'ast, T> ValidationRule<'ast, T> for KnownDirectives<'ast, T>
+    where T: Text<'ast>,
+{
+    fn take_errors(&mut self) -> Vec<ValidationError> {
+        std::mem::take(&mut self.errors)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::KnownDirectives;
+    use crate::position::Pos;
+    use crate::query::{parse_query, visit, QueryAstNode, QueryVisitor};
+    use crate::schema::{parse_schema, with_builtin_definitions};
+    use crate::validation::{TypeInfo, ValidationError};
+    use crate::validation::test_harness::TEST_SCHEMA;
+
+    fn validate(query: &str) -> Vec<ValidationError> {
+        let schema = parse_schema::<String>(TEST_SCHEMA).unwrap();
+        let query = parse_query::<String>(query).unwrap();
+        let mut rule = KnownDirectives::new(TypeInfo::new(&schema));
+        visit(&query, &mut rule);
+        rule.into_errors()
+    }
+
+    fn validate_schema(schema: &str) -> Vec<ValidationError> {
+        let schema = parse_schema::<String>(schema).unwrap();
+        let mut rule = KnownDirectives::new(TypeInfo::new(&schema));
+        rule.check_schema(&schema);
+        rule.into_errors()
+    }
+
+    #[test]
+    fn known_directives() {
+        assert_eq!(validate("{
+            dog @include(if: true) { name }
+            human @skip(if: false) { name }
+            ... @skip(if: true) { cat { name @onField } }
+        }"), []);
+    }
+
+    /// Records the definitions of directive arguments found by `TypeInfo`
+    struct DirectiveArguments<'ast> {
+        type_info: TypeInfo<'ast, String>,
+        found: Vec<String>,
+    }
+
+    impl<'ast> QueryVisitor<'ast, String> for DirectiveArguments<'ast> {
+        fn enter(&mut self, node: QueryAstNode<'ast, String>) {
+            self.type_info.enter(node);
+            if let QueryAstNode::Argument(name, _) = node {
+                if let Some(dir) = self.type_info.directive() {
+                    let typ = self.type_info.argument()
+                        .map(|arg| arg.value_type.to_string());
+                    self.found.push(format!("@{}({}: {})", dir.name, name,
+                        typ.unwrap_or_default()));
+                }
+            }
+        }
+
+        fn leave(&mut self, node: QueryAstNode<'ast, String>) {
+            self.type_info.leave(node);
+        }
+    }
+
+    #[test]
+    fn injected_definitions() {
+        let query = parse_query::<String>("{
+            a @skip(if: true)
+            ... @include(if: false) { a }
+        }").unwrap();
+        let bare = parse_schema::<String>("type Query { a: Int }").unwrap();
+        let schema = with_builtin_definitions(bare.clone());
+        let mut rule = KnownDirectives::new(TypeInfo::new(&schema));
+        visit(&query, &mut rule);
+        assert_eq!(rule.into_errors(), []);
+
+        // the arguments are known with or without injected definitions
+        for schema in &[&schema, &bare] {
+            let mut args = DirectiveArguments {
+                type_info: TypeInfo::new(schema),
+                found: Vec::new(),
+            };
+            visit(&query, &mut args);
+            assert_eq!(args.found,
+                       ["@skip(if: Boolean!)", "@include(if: Boolean!)"]);
+        }
+    }
+
+    #[test]
+    fn defer() {
+        assert_eq!(validate("{
+            dog { ... @defer(label: \"MyLabel\") { name } }
+            ... on QueryRoot @defer(if: false) { human { name } }
+        }"), []);
+        assert_eq!(validate("{ dog @defer { name } }"), [
+            ValidationError::new(Pos::new(1, 7),
+                "Directive \"@defer\" may not be used on FIELD."),
+        ]);
+    }
+
+    #[test]
+    fn stream() {
+        assert_eq!(validate("{
+            human { pets @stream(initialCount: 0) { name } }
+            dog @stream { name }
+            ... @stream { human { name } }
+        }"), [
+            ValidationError::new(Pos::new(3, 17),
+                "Directive \"@stream\" may only be used on list fields."),
+            ValidationError::new(Pos::new(4, 17),
+                "Directive \"@stream\" may not be used on INLINE_FRAGMENT."),
+        ]);
+    }
+
+    #[test]
+    fn unknown_directive() {
+        assert_eq!(validate("{ dog @nonExistent { name } }"), [
+            ValidationError::new(Pos::new(1, 7),
+                "Unknown directive \"@nonExistent\"."),
+        ]);
+    }
+
+    #[test]
+    fn misplaced_directives() {
+        assert_eq!(validate("query Q @include(if: true) {
+            dog { name @deprecated }
+        }"), [
+            ValidationError::new(Pos::new(1, 9),
+                "Directive \"@include\" may not be used on QUERY."),
+            ValidationError::new(Pos::new(2, 24),
+                "Directive \"@deprecated\" may not be used on FIELD."),
+        ]);
+    }
+
+    #[test]
+    fn schema_directives() {
+        assert_eq!(validate_schema("
+            directive @onObject on OBJECT
+            scalar Url @specifiedBy(url: \"https://url.spec.whatwg.org\")
+            type Query @onObject {
+                old(arg: Int @deprecated): Int @deprecated(reason: \"no\")
+            }
+            enum E { A @deprecated }
+        "), []);
+        assert_eq!(validate_schema("
+            directive @onObject on OBJECT
+            type Query @unknown {
+                field: Int @onObject
+            }
+        "), [
+            ValidationError::new(Pos::new(3, 24),
+                "Unknown directive \"@unknown\"."),
+            ValidationError::new(Pos::new(4, 28),
+                "Directive \"@onObject\" may not be used on \
+                 FIELD_DEFINITION."),
+        ]);
+    }
+}
@@ -0,0 +1,21 @@
+mod custom_scalar_values;
+mod fields_on_correct_type;
+mod known_arguments;
+mod known_directives;
+mod overlapping_fields_can_be_merged;
+mod possible_fragment_spreads;
+mod provided_required_arguments;
+mod single_field_subscriptions;
+mod variables_in_allowed_position;
+
+pub use self::custom_scalar_values::CustomScalarValues;
+pub use self::custom_scalar_values::{ScalarValidator, ScalarValidators};
+pub use self::fields_on_correct_type::FieldsOnCorrectType;
+pub use self::known_arguments::KnownArguments;
+pub use self::known_directives::KnownDirectives;
+pub use self::overlapping_fields_can_be_merged::OverlappingFieldsCanBeMerged;
+pub use self::possible_fragment_spreads::PossibleFragmentSpreads;
+pub use self::provided_required_arguments::ProvidedRequiredArguments;
+pub use self::single_field_subscriptions::SingleFieldSubscriptions;
+pub use self::single_field_subscriptions::validate_subscription_single_root;
+pub use self::variables_in_allowed_position::VariablesInAllowedPosition;
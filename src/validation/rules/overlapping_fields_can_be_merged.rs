@@ -0,0 +1,452 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::common::{get_named_type, Text, Type};
+use crate::query::{Definition, Field, FragmentDefinition, QueryAstNode};
+use crate::query::{QueryVisitor, Selection, SelectionSet, TypeCondition};
+use crate::schema::{self, TypeDefinition};
+use crate::validation::type_info::find_field;
+use crate::validation::{TypeInfo, ValidationError, ValidationRule};
+
+
+/// Checks that fields selected with the same response key can be merged
+///
+/// Such fields must select the same field with the same arguments (unless
+/// they are selected on different object types), must not return
+/// conflicting types and their subfields must be mergeable too. Fields
+/// selected through fragments are taken into account.
+#[derive(Debug)]
+pub struct OverlappingFieldsCanBeMerged<'ast, T: Text<'ast>> {
+    type_info: TypeInfo<'ast, T>,
+    fragments: HashMap<&'ast str, &'ast FragmentDefinition<'ast, T>>,
+    // pairs of fields already compared, so each pair is compared (and
+    // reported) once even if it is reachable through many fragments
+    compared: HashSet<(*const Field<'ast, T>, *const Field<'ast, T>, bool)>,
+    errors: Vec<ValidationError>,
+}
+
+/// A field along with the type it's selected on
+#[derive(Debug)]
+struct CollectedField<'ast, T: Text<'ast>> {
+    parent_type: Option<&'ast TypeDefinition<'ast, T>>,
+    field: &'ast Field<'ast, T>,
+    def: Option<&'ast schema::Field<'ast, T>>,
+}
+
+/// Fields grouped by response key, in the order of appearance
+type FieldMap<'ast, T> = Vec<(&'ast str, Vec<CollectedField<'ast, T>>)>;
+
+#[derive(Debug)]
+enum Reason {
+    Message(String),
+    Subfields(Vec<(String, Reason)>),
+}
+
+impl Reason {
+    fn describe(&self) -> String {
+        match *self {
+            Reason::Message(ref message) => message.clone(),
+            Reason::Subfields(ref conflicts) => conflicts.iter()
+                .map(|(key, reason)| {
+                    format!("subfields \"{}\" conflict because {}",
+                            key, reason.describe())
+                })
+                .collect::<Vec<_>>()
+                .join(" and "),
+        }
+    }
+}
+
+fn response_key<'ast, T: Text<'ast>>(field: &'ast Field<'ast, T>)
+    -> &'ast str
+{
+    field.alias.as_ref().unwrap_or(&field.name).as_ref()
+}
+
+fn is_object<'ast, T: Text<'ast>>(typ: &TypeDefinition<'ast, T>) -> bool {
+    matches!(*typ, TypeDefinition::Object(_))
+}
+
+impl<'ast, T> OverlappingFieldsCanBeMerged<'ast, T>
+    where T: Text<'ast>,
+{
+    pub fn new(type_info: TypeInfo<'ast, T>) -> Self {
+        OverlappingFieldsCanBeMerged {
+            type_info,
+            fragments: HashMap::new(),
+            compared: HashSet::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Returns errors found so far
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.errors
+    }
+
+    pub fn into_errors(self) -> Vec<ValidationError> {
+        self.errors
+    }
+
+    fn collect_fields(&self,
+        parent_type: Option<&'ast TypeDefinition<'ast, T>>,
+        selection_set: &'ast SelectionSet<'ast, T>,
+        visited: &mut HashSet<&'ast str>,
+        fields: &mut FieldMap<'ast, T>)
+    {
+        for item in &selection_set.items {
+            match *item {
+                Selection::Field(ref field) => {
+                    let def = parent_type
+                        .and_then(|p| find_field(p, field.name.as_ref()));
+                    let collected = CollectedField { parent_type, field, def };
+                    let key = response_key(field);
+                    match fields.iter_mut().find(|(k, _)| *k == key) {
+                        Some((_, group)) => group.push(collected),
+                        None => fields.push((key, vec![collected])),
+                    }
+                }
+                Selection::InlineFragment(ref frag) => {
+                    let typ = match frag.type_condition {
+                        Some(TypeCondition::On(ref name)) => {
+                            self.type_info.schema().find_type(name.as_ref())
+                        }
+                        None => parent_type,
+                    };
+                    self.collect_fields(typ, &frag.selection_set,
+                                        visited, fields);
+                }
+                Selection::FragmentSpread(ref spread) => {
+                    let name = spread.fragment_name.as_ref();
+                    if !visited.insert(name) {
+                        continue;
+                    }
+                    if let Some(frag) = self.fragments.get(name) {
+                        let TypeCondition::On(ref cond) = frag.type_condition;
+                        let typ = self.type_info.schema()
+                            .find_type(cond.as_ref());
+                        self.collect_fields(typ, &frag.selection_set,
+                                            visited, fields);
+                    }
+                }
+            }
+        }
+    }
+
+    fn collect_subfields(&self, field: &CollectedField<'ast, T>)
+        -> FieldMap<'ast, T>
+    {
+        let typ = field.def.and_then(|def| {
+            self.type_info.schema()
+                .find_type(get_named_type(&def.field_type).as_ref())
+        });
+        let mut fields = Vec::new();
+        self.collect_fields(typ, &field.field.selection_set,
+                            &mut HashSet::new(), &mut fields);
+        fields
+    }
+
+    fn is_leaf(&self, name: &str) -> bool {
+        match self.type_info.schema().find_type(name) {
+            Some(TypeDefinition::Scalar(_)) | Some(TypeDefinition::Enum(_))
+                => true,
+            Some(_) => false,
+            // built-in scalars aren't defined in the schema document
+            None => true,
+        }
+    }
+
+    fn types_conflict(&self, a: &Type<'ast, T>, b: &Type<'ast, T>) -> bool {
+        match (a, b) {
+            (Type::ListType(a), Type::ListType(b))
+            | (Type::NonNullType(a), Type::NonNullType(b))
+                => self.types_conflict(a, b),
+            (Type::ListType(_), _) | (_, Type::ListType(_))
+            | (Type::NonNullType(_), _) | (_, Type::NonNullType(_))
+                => true,
+            (Type::NamedType(a), Type::NamedType(b)) => {
+                a != b
+                    && (self.is_leaf(a.as_ref()) || self.is_leaf(b.as_ref()))
+            }
+        }
+    }
+
+    fn find_conflict(&mut self,
+        a: &CollectedField<'ast, T>, b: &CollectedField<'ast, T>,
+        parents_exclusive: bool)
+        -> Option<Reason>
+    {
+        let (pa, pb) = (a.field as *const _, b.field as *const _);
+        let pair = if pa < pb {
+            (pa, pb, parents_exclusive)
+        } else {
+            (pb, pa, parents_exclusive)
+        };
+        if pa == pb || !self.compared.insert(pair) {
+            return None;
+        }
+        // fields selected on different object types are never both
+        // present in the response, so they only need compatible types
+        let exclusive = parents_exclusive
+            || match (a.parent_type, b.parent_type) {
+                (Some(pa), Some(pb)) => {
+                    pa.name() != pb.name() && is_object(pa) && is_object(pb)
+                }
+                _ => false,
+            };
+        if !exclusive {
+            if a.field.name != b.field.name {
+                return Some(Reason::Message(format!(
+                    "\"{}\" and \"{}\" are different fields",
+                    a.field.name.as_ref(), b.field.name.as_ref())));
+            }
+            // values are compared by their printed form, like graphql-js
+            // does
+            let same_arguments = a.field.arguments.len()
+                    == b.field.arguments.len()
+                && a.field.arguments.iter().all(|(name, value)| {
+                    b.field.arguments.iter().any(|(n, v)| {
+                        n == name && v.to_string() == value.to_string()
+                    })
+                });
+            if !same_arguments {
+                return Some(Reason::Message(
+                    "they have differing arguments".into()));
+            }
+        }
+        if let (Some(da), Some(db)) = (a.def, b.def) {
+            if self.types_conflict(&da.field_type, &db.field_type) {
+                return Some(Reason::Message(format!(
+                    "they return conflicting types \"{}\" and \"{}\"",
+                    da.field_type, db.field_type)));
+            }
+        }
+        if !a.field.selection_set.items.is_empty()
+            && !b.field.selection_set.items.is_empty()
+        {
+            let sub_a = self.collect_subfields(a);
+            let sub_b = self.collect_subfields(b);
+            let conflicts = self.conflicts_between(&sub_a, &sub_b, exclusive);
+            if !conflicts.is_empty() {
+                return Some(Reason::Subfields(conflicts));
+            }
+        }
+        None
+    }
+
+    fn conflicts_between(&mut self,
+        a: &FieldMap<'ast, T>, b: &FieldMap<'ast, T>,
+        parents_exclusive: bool)
+        -> Vec<(String, Reason)>
+    {
+        let mut conflicts = Vec::new();
+        for (key, group_a) in a {
+            let group_b = match b.iter().find(|(k, _)| k == key) {
+                Some((_, group)) => group,
+                None => continue,
+            };
+            for field_a in group_a {
+                for field_b in group_b {
+                    let conflict = self.find_conflict(field_a, field_b,
+                                                      parents_exclusive);
+                    if let Some(reason) = conflict {
+                        conflicts.push((key.to_string(), reason));
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+
+    fn check_selection_set(&mut self,
+        selection_set: &'ast SelectionSet<'ast, T>)
+    {
+        let mut fields = Vec::new();
+        self.collect_fields(self.type_info.parent_type(), selection_set,
+                            &mut HashSet::new(), &mut fields);
+        for (key, group) in &fields {
+            for (i, a) in group.iter().enumerate() {
+                for b in &group[i+1..] {
+                    if let Some(reason) = self.find_conflict(a, b, false) {
+                        self.errors.push(ValidationError::new(
+                            b.field.position,
+                            format!("Fields \"{}\" conflict because {}. \
+                                     Use different aliases on the fields \
+                                     to fetch both if this was intentional.",
+                                    key, reason.describe())));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<'ast, T> QueryVisitor<'ast, T> for OverlappingFieldsCanBeMerged<'ast, T>
+    where T: Text<'ast>,
+{
+    fn enter(&mut self, node: QueryAstNode<'ast, T>) {
+        self.type_info.enter(node);
+        match node {
+            QueryAstNode::Document(doc) => {
+                for def in &doc.definitions {
+                    if let Definition::Fragment(ref frag) = *def {
+                        self.fragments.insert(frag.name.as_ref(), frag);
+                    }
+                }
+            }
+            QueryAstNode::SelectionSet(selection_set) => {
+                self.check_selection_set(selection_set);
+            }
+            _ => {}
+        }
+    }
+
+    fn leave(&mut self, node: QueryAstNode<'ast, T>) {
+        self.type_info.leave(node);
+    }
+}
+
+impl<'ast, T> ValidationRule<'ast, T> for OverlappingFieldsCanBeMerged<'ast, T>
+    where T: Text<'ast>,
+{
+    fn take_errors(&mut self) -> Vec<ValidationError> {
+        std::mem::take(&mut self.errors)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::OverlappingFieldsCanBeMerged;
+    use crate::position::Pos;
+    use crate::query::{parse_query, visit};
+    use crate::schema::parse_schema;
+    use crate::validation::{TypeInfo, ValidationError};
+    use crate::validation::test_harness::TEST_SCHEMA;
+
+    fn validate(query: &str) -> Vec<ValidationError> {
+        let schema = parse_schema::<String>(TEST_SCHEMA).unwrap();
+        let query = parse_query::<String>(query).unwrap();
+        let mut rule = OverlappingFieldsCanBeMerged::new(
+            TypeInfo::new(&schema));
+        visit(&query, &mut rule);
+        rule.into_errors()
+    }
+
+    fn conflict(line: usize, column: usize, key: &str, reason: &str)
+        -> ValidationError
+    {
+        ValidationError::new(Pos::new(line, column),
+            format!("Fields \"{}\" conflict because {}. Use different \
+                     aliases on the fields to fetch both if this was \
+                     intentional.", key, reason))
+    }
+
+    #[test]
+    fn mergeable_fields() {
+        assert_eq!(validate("{
+            dog {
+                name
+                nickname
+                name
+                otherName: name
+                otherName: name
+                doesKnowCommand(dogCommand: SIT)
+                doesKnowCommand(dogCommand: SIT)
+                ...DogName
+            }
+        }
+        fragment DogName on Dog { name otherName: name }"), []);
+    }
+
+    #[test]
+    fn different_fields() {
+        assert_eq!(validate("{ dog { name, name: nickname } }"), [
+            conflict(1, 15, "name",
+                     "\"name\" and \"nickname\" are different fields"),
+        ]);
+    }
+
+    #[test]
+    fn different_arguments() {
+        assert_eq!(validate("{
+            dog {
+                doesKnowCommand(dogCommand: SIT)
+                doesKnowCommand(dogCommand: HEEL)
+                isAtLocation(x: 1, y: 2)
+                isAtLocation(y: 2, x: 1)
+                barks: doesKnowCommand
+                barks: doesKnowCommand(dogCommand: SIT)
+            }
+        }"), [
+            conflict(4, 17, "doesKnowCommand",
+                     "they have differing arguments"),
+            conflict(8, 17, "barks", "they have differing arguments"),
+        ]);
+    }
+
+    #[test]
+    fn conflicts_through_fragments() {
+        // reported once even though the fragment is checked on its own too
+        assert_eq!(validate("{
+            dog { ...DogName name: nickname }
+        }
+        fragment DogName on Dog { name }"), [
+            conflict(2, 30, "name",
+                     "\"name\" and \"nickname\" are different fields"),
+        ]);
+    }
+
+    #[test]
+    fn mutually_exclusive_parents() {
+        assert_eq!(validate("{
+            catOrDog {
+                ... on Cat { volume: meowsVolume name }
+                ... on Dog { volume: barkVolume name: nickname }
+            }
+            pet {
+                ... on Dog { name }
+                ... on Cat { name: nickname }
+            }
+        }"), []);
+        assert_eq!(validate("{
+            catOrDog {
+                ... on Cat { x: meowsVolume }
+                ... on Dog { x: name }
+            }
+            pet {
+                name
+                ... on Dog { name: nickname }
+            }
+        }"), [
+            conflict(4, 30, "x",
+                     "they return conflicting types \"Int\" and \"String\""),
+            conflict(8, 30, "name",
+                     "\"name\" and \"nickname\" are different fields"),
+        ]);
+    }
+
+    #[test]
+    fn conflicting_subfields() {
+        assert_eq!(validate("{
+            dog { name barks: name }
+            dog { name: nickname barks }
+        }"), [
+            conflict(3, 13, "dog",
+                     "subfields \"name\" conflict because \"name\" and \
+                      \"nickname\" are different fields and \
+                      subfields \"barks\" conflict because \"name\" and \
+                      \"barks\" are different fields"),
+        ]);
+    }
+
+    #[test]
+    fn many_repeated_fragments() {
+        let mut query = String::from("{ dog { ...F0 } }\n");
+        for i in 0..30 {
+            query.push_str(&format!(
+                "fragment F{} on Dog {{ mother {{ ...F{} }} \
+                 mother {{ ...F{} }} }}\n", i, i + 1, i + 1));
+        }
+        query.push_str("fragment F30 on Dog { name }\n");
+        assert_eq!(validate(&query), []);
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+
+use crate::common::Text;
+use crate::position::Pos;
+use crate::query::{Definition, FragmentDefinition, QueryAstNode};
+use crate::query::{QueryVisitor, TypeCondition};
+use crate::schema::TypeDefinition;
+use crate::validation::{TypeInfo, ValidationError, ValidationRule};
+
+
+/// Checks that the type condition of every fragment could match the type
+/// the fragment is spread into
+///
+/// An object type only matches itself, an interface matches all the
+/// objects implementing it and a union matches its members. The spread is
+/// possible if the types have at least one possible object type in common.
+#[derive(Debug)]
+pub struct PossibleFragmentSpreads<'ast, T: Text<'ast>> {
+    type_info: TypeInfo<'ast, T>,
+    fragments: HashMap<&'ast str, &'ast FragmentDefinition<'ast, T>>,
+    errors: Vec<ValidationError>,
+}
+
+impl<'ast, T> PossibleFragmentSpreads<'ast, T>
+    where T: Text<'ast>,
+{
+    pub fn new(type_info: TypeInfo<'ast, T>) -> Self {
+        PossibleFragmentSpreads {
+            type_info,
+            fragments: HashMap::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Returns errors found so far
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.errors
+    }
+
+    pub fn into_errors(self) -> Vec<ValidationError> {
+        self.errors
+    }
+
+    fn check(&mut self, position: Pos, fragment: Option<&str>,
+        frag_type: Option<&'ast TypeDefinition<'ast, T>>)
+    {
+        let (parent_type, frag_type) = match
+            (self.type_info.parent_type(), frag_type)
+        {
+            (Some(parent), Some(frag)) => (parent, frag),
+            _ => return,
+        };
+        let overlap = self.type_info.schema().do_types_overlap(
+            parent_type.name().as_ref(), frag_type.name().as_ref());
+        if !overlap {
+            let what = match fragment {
+                Some(name) => format!("Fragment \"{}\"", name),
+                None => "Fragment".into(),
+            };
+            self.errors.push(ValidationError::new(position,
+                format!("{} cannot be spread here as objects of type \
+                         \"{}\" can never be of type \"{}\".", what,
+                        parent_type.name().as_ref(),
+                        frag_type.name().as_ref())));
+        }
+    }
+}
+
+impl<'ast, T> QueryVisitor<'ast, T> for PossibleFragmentSpreads<'ast, T>
+    where T: Text<'ast>,
+{
+    fn enter(&mut self, node: QueryAstNode<'ast, T>) {
+        self.type_info.enter(node);
+        match node {
+            QueryAstNode::Document(doc) => {
+                for def in &doc.definitions {
+                    if let Definition::Fragment(ref frag) = *def {
+                        self.fragments.insert(frag.name.as_ref(), frag);
+                    }
+                }
+            }
+            QueryAstNode::InlineFragment(frag)
+                if frag.type_condition.is_some() =>
+            {
+                let typ = self.type_info.current_type();
+                self.check(frag.position, None, typ);
+            }
+            QueryAstNode::FragmentSpread(spread) => {
+                let name = spread.fragment_name.as_ref();
+                let typ = self.fragments.get(name).and_then(|frag| {
+                    let TypeCondition::On(ref cond) = frag.type_condition;
+                    self.type_info.schema().find_type(cond.as_ref())
+                });
+                self.check(spread.position, Some(name), typ);
+            }
+            _ => {}
+        }
+    }
+
+    fn leave(&mut self, node: QueryAstNode<'ast, T>) {
+        self.type_info.leave(node);
+    }
+}
+
+impl<'ast, T> ValidationRule<'ast, T> for PossibleFragmentSpreads<'ast, T>
+    where T: Text<'ast>,
+{
+    fn take_errors(&mut self) -> Vec<ValidationError> {
+        std::mem::take(&mut self.errors)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PossibleFragmentSpreads;
+    use crate::position::Pos;
+    use crate::query::{parse_query, visit};
+    use crate::schema::parse_schema;
+    use crate::validation::{TypeInfo, ValidationError};
+    use crate::validation::test_harness::TEST_SCHEMA;
+
+    fn validate(query: &str) -> Vec<ValidationError> {
+        let schema = parse_schema::<String>(TEST_SCHEMA).unwrap();
+        let query = parse_query::<String>(query).unwrap();
+        let mut rule = PossibleFragmentSpreads::new(TypeInfo::new(&schema));
+        visit(&query, &mut rule);
+        rule.into_errors()
+    }
+
+    #[test]
+    fn possible_spreads() {
+        assert_eq!(validate("{
+            dog {
+                ... on Dog { barkVolume }
+                ... on Pet { name }
+                ... on CatOrDog { ... on Dog { name } }
+                ...PetFields
+            }
+            pet { ... on Cat { meows } ... on CatOrDog { __typename } }
+            catOrDog { ... on Pet { name } ... on DogOrHuman { __typename } }
+            human { ... on Being { name } ... { name } }
+        }
+        fragment PetFields on Pet { name }"), []);
+    }
+
+    #[test]
+    fn impossible_inline_fragments() {
+        assert_eq!(validate("{
+            human { ... on Cat { name } }
+            dog { ... on HumanOrAlien { __typename } }
+            pet { ... on Intelligent { iq } }
+        }"), [
+            ValidationError::new(Pos::new(2, 25),
+                "Fragment cannot be spread here as objects of type \
+                 \"Human\" can never be of type \"Cat\"."),
+            ValidationError::new(Pos::new(3, 23),
+                "Fragment cannot be spread here as objects of type \
+                 \"Dog\" can never be of type \"HumanOrAlien\"."),
+            ValidationError::new(Pos::new(4, 23),
+                "Fragment cannot be spread here as objects of type \
+                 \"Pet\" can never be of type \"Intelligent\"."),
+        ]);
+    }
+
+    #[test]
+    fn impossible_named_fragments() {
+        assert_eq!(validate("{
+            human { ...CatFields }
+            catOrDog { ...HumanOrAlienFields }
+        }
+        fragment CatFields on Cat { name }
+        fragment HumanOrAlienFields on HumanOrAlien { __typename }"), [
+            ValidationError::new(Pos::new(2, 24),
+                "Fragment \"CatFields\" cannot be spread here as objects \
+                 of type \"Human\" can never be of type \"Cat\"."),
+            ValidationError::new(Pos::new(3, 27),
+                "Fragment \"HumanOrAlienFields\" cannot be spread here as \
+                 objects of type \"CatOrDog\" can never be of type \
+                 \"HumanOrAlien\"."),
+        ]);
+    }
+}
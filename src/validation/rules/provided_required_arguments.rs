@@ -0,0 +1,157 @@
+use crate::common::{Text, Type};
+use crate::position::Pos;
+use crate::query::{QueryAstNode, QueryVisitor, Value};
+use crate::schema::InputValue;
+use crate::validation::{TypeInfo, ValidationError, ValidationRule};
+
+
+/// Checks that all required arguments of fields and directives are provided
+///
+/// An argument is required if it has a non-null type and no default value.
+#[derive(Debug)]
+pub struct ProvidedRequiredArguments<'ast, T: Text<'ast>> {
+    type_info: TypeInfo<'ast, T>,
+    errors: Vec<ValidationError>,
+}
+
+impl<'ast, T> ProvidedRequiredArguments<'ast, T>
+    where T: Text<'ast>,
+{
+    pub fn new(type_info: TypeInfo<'ast, T>) -> Self {
+        ProvidedRequiredArguments { type_info, errors: Vec::new() }
+    }
+
+    /// Returns errors found so far
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.errors
+    }
+
+    pub fn into_errors(self) -> Vec<ValidationError> {
+        self.errors
+    }
+
+}
+
+fn check<'ast, T>(errors: &mut Vec<ValidationError>, position: Pos,
+    what: &str, defs: &[InputValue<'ast, T>],
+    provided: &[(T::Value, Value<'ast, T>)])
+    where T: Text<'ast>,
+{
+    for def in defs {
+        let required = match def.value_type {
+            Type::NonNullType(_) => def.default_value.is_none(),
+            _ => false,
+        };
+        if required && !provided.iter().any(|(name, _)| name == &def.name) {
+            errors.push(ValidationError::new(position, format!(
+                "{} argument \"{}\" of type \"{}\" is required, \
+                 but it was not provided.",
+                what, def.name.as_ref(), def.value_type)));
+        }
+    }
+}
+
+impl<'ast, T> QueryVisitor<'ast, T> for ProvidedRequiredArguments<'ast, T>
+    where T: Text<'ast>,
+{
+    fn enter(&mut self, node: QueryAstNode<'ast, T>) {
+        self.type_info.enter(node);
+        match node {
+            QueryAstNode::Field(field) => {
+                if let Some(def) = self.type_info.field_def() {
+                    let what = format!("Field \"{}\"", field.name.as_ref());
+                    check(&mut self.errors, field.position, &what,
+                          &def.arguments, &field.arguments);
+                }
+            }
+            QueryAstNode::Directive(dir) => {
+                if let Some(def) = self.type_info.directive() {
+                    let what = format!("Directive \"@{}\"",
+                                       dir.name.as_ref());
+                    check(&mut self.errors, dir.position, &what,
+                          &def.arguments, &dir.arguments);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn leave(&mut self, node: QueryAstNode<'ast, T>) {
+        self.type_info.leave(node);
+    }
+}
+
+impl<'ast, T> ValidationRule<'ast, T> for ProvidedRequiredArguments<'ast, T>
+    where T: Text<'ast>,
+{
+    fn take_errors(&mut self) -> Vec<ValidationError> {
+        std::mem::take(&mut self.errors)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ProvidedRequiredArguments;
+    use crate::position::Pos;
+    use crate::query::{parse_query, visit};
+    use crate::schema::parse_schema;
+    use crate::validation::{TypeInfo, ValidationError};
+    use crate::validation::test_harness::TEST_SCHEMA;
+
+    fn validate(schema: &str, query: &str) -> Vec<ValidationError> {
+        let schema = parse_schema::<String>(schema).unwrap();
+        let query = parse_query::<String>(query).unwrap();
+        let mut rule = ProvidedRequiredArguments::new(TypeInfo::new(&schema));
+        visit(&query, &mut rule);
+        rule.into_errors()
+    }
+
+    #[test]
+    fn optional_arguments() {
+        assert_eq!(validate(TEST_SCHEMA, "{ human(id: 4) { name } }"), []);
+        assert_eq!(validate(TEST_SCHEMA, "{ human { name } }"), []);
+        assert_eq!(validate(TEST_SCHEMA, "{
+            complicatedArgs { multipleOpts nonNullFieldWithDefault }
+        }"), []);
+    }
+
+    #[test]
+    fn missing_non_null_argument() {
+        let schema = TEST_SCHEMA.replace("human(id: ID)", "human(id: ID!)");
+        assert_eq!(validate(&schema, "{ human(id: 4) { name } }"), []);
+        assert_eq!(validate(&schema, "{ human { name } }"), [
+            ValidationError::new(Pos::new(1, 3),
+                "Field \"human\" argument \"id\" of type \"ID!\" \
+                 is required, but it was not provided."),
+        ]);
+    }
+
+    #[test]
+    fn missing_multiple_arguments() {
+        let errors = validate(TEST_SCHEMA, "{
+            complicatedArgs { multipleReqs(req2: 1) multipleOptAndReq }
+        }");
+        let messages = errors.iter().map(|e| &e.message[..])
+            .collect::<Vec<_>>();
+        assert_eq!(messages, [
+            "Field \"multipleReqs\" argument \"req1\" of type \"Int!\" \
+             is required, but it was not provided.",
+            "Field \"multipleOptAndReq\" argument \"req1\" of type \"Int!\" \
+             is required, but it was not provided.",
+            "Field \"multipleOptAndReq\" argument \"req2\" of type \"Int!\" \
+             is required, but it was not provided.",
+        ]);
+    }
+
+    #[test]
+    fn directive_arguments() {
+        assert_eq!(validate(TEST_SCHEMA,
+            "{ dog @directiveWithArgs(required: 1) { name } }"), []);
+        assert_eq!(validate(TEST_SCHEMA,
+            "{ dog @directiveWithArgs(optional: \"x\") { name } }"), [
+            ValidationError::new(Pos::new(1, 7),
+                "Directive \"@directiveWithArgs\" argument \"required\" \
+                 of type \"Int!\" is required, but it was not provided."),
+        ]);
+    }
+}
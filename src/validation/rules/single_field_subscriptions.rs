@@ -0,0 +1,229 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::common::Text;
+use crate::query::{Document, Definition, FragmentDefinition, Field};
+use crate::query::evaluate_directives;
+use crate::query::{OperationDefinition, QueryAstNode, QueryVisitor};
+use crate::query::{Selection, SelectionSet};
+use crate::validation::{ValidationError, ValidationRule};
+
+
+/// Checks that every subscription selects exactly one root field
+///
+/// Fields selected through fragments are counted too, except those
+/// excluded by `@skip` or `@include` with a literal condition. The root
+/// field also must not be an introspection field, as those can't produce
+/// a stream of events.
+#[derive(Debug)]
+pub struct SingleFieldSubscriptions<'ast, T: Text<'ast>> {
+    fragments: HashMap<&'ast str, &'ast FragmentDefinition<'ast, T>>,
+    errors: Vec<ValidationError>,
+}
+
+/// Checks that all subscriptions of the document select a single root field
+///
+/// This check doesn't need a schema, so it's usable before the document is
+/// validated as a whole.
+pub fn validate_subscription_single_root<'ast, T>(doc: &'ast Document<'ast, T>)
+    -> Vec<ValidationError>
+    where T: Text<'ast>,
+{
+    let mut rule = SingleFieldSubscriptions::new();
+    crate::query::visit(doc, &mut rule);
+    rule.into_errors()
+}
+
+impl<'ast, T> SingleFieldSubscriptions<'ast, T>
+    where T: Text<'ast>,
+{
+    pub fn new() -> Self {
+        SingleFieldSubscriptions {
+            fragments: HashMap::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Returns errors found so far
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.errors
+    }
+
+    pub fn into_errors(self) -> Vec<ValidationError> {
+        self.errors
+    }
+
+    fn collect_fields(&self, sel: &'ast SelectionSet<'ast, T>,
+        visited: &mut HashSet<&'ast str>,
+        fields: &mut Vec<&'ast Field<'ast, T>>)
+    {
+        for item in &sel.items {
+            let directives = match *item {
+                Selection::Field(ref field) => &field.directives,
+                Selection::FragmentSpread(ref spread) => &spread.directives,
+                Selection::InlineFragment(ref frag) => &frag.directives,
+            };
+            if evaluate_directives(directives) == Some(false) {
+                continue;
+            }
+            match *item {
+                Selection::Field(ref field) => fields.push(field),
+                Selection::InlineFragment(ref frag) => {
+                    self.collect_fields(&frag.selection_set, visited, fields);
+                }
+                Selection::FragmentSpread(ref spread) => {
+                    let name = spread.fragment_name.as_ref();
+                    if !visited.insert(name) {
+                        continue;
+                    }
+                    if let Some(frag) = self.fragments.get(name) {
+                        self.collect_fields(&frag.selection_set,
+                                            visited, fields);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<'ast, T> Default for SingleFieldSubscriptions<'ast, T>
+    where T: Text<'ast>,
+{
+    fn default() -> Self {
+        SingleFieldSubscriptions::new()
+    }
+}
+
+fn response_key<'a, 'ast, T: Text<'ast>>(field: &'a Field<'ast, T>) -> &'a str {
+    field.alias.as_ref().unwrap_or(&field.name).as_ref()
+}
+
+impl<'ast, T> QueryVisitor<'ast, T> for SingleFieldSubscriptions<'ast, T>
+    where T: Text<'ast>,
+{
+    fn enter(&mut self, node: QueryAstNode<'ast, T>) {
+        match node {
+            QueryAstNode::Document(doc) => {
+                for def in &doc.definitions {
+                    if let Definition::Fragment(ref frag) = *def {
+                        self.fragments.insert(frag.name.as_ref(), frag);
+                    }
+                }
+            }
+            QueryAstNode::OperationDefinition(
+                OperationDefinition::Subscription(sub)
+            ) => {
+                let what = match sub.name {
+                    Some(ref name) => {
+                        format!("Subscription \"{}\"", name.as_ref())
+                    }
+                    None => "Anonymous Subscription".into(),
+                };
+                let mut fields = Vec::new();
+                self.collect_fields(&sub.selection_set,
+                                    &mut HashSet::new(), &mut fields);
+                let first_key = fields.first().map(|f| response_key(f));
+                let extra = fields.iter()
+                    .find(|f| Some(response_key(f)) != first_key);
+                if let Some(extra) = extra {
+                    self.errors.push(ValidationError::new(extra.position,
+                        format!("{} must select only one top level field.",
+                                what)));
+                }
+                let introspection = fields.iter()
+                    .find(|f| f.name.as_ref().starts_with("__"));
+                if let Some(field) = introspection {
+                    self.errors.push(ValidationError::new(field.position,
+                        format!("{} must not select an introspection \
+                                 top level field.", what)));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<'ast, T> ValidationRule<'ast, T> for SingleFieldSubscriptions<'ast, T>
+    where T: Text<'ast>,
+{
+    fn take_errors(&mut self) -> Vec<ValidationError> {
+        std::mem::take(&mut self.errors)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::validate_subscription_single_root;
+    use crate::position::Pos;
+    use crate::query::parse_query;
+    use crate::validation::ValidationError;
+
+    fn validate(query: &str) -> Vec<ValidationError> {
+        let query = parse_query::<String>(query).unwrap();
+        validate_subscription_single_root(&query)
+    }
+
+    #[test]
+    fn single_field() {
+        assert_eq!(validate("subscription ImportantEmails {
+            importantEmails
+        }"), []);
+        assert_eq!(validate("subscription {
+            importantEmails
+            ...Emails
+            ... { importantEmails }
+        }
+        fragment Emails on SubscriptionRoot { importantEmails }
+        query { a b }"), []);
+    }
+
+    #[test]
+    fn multiple_fields() {
+        assert_eq!(validate("subscription ImportantEmails {
+            importantEmails
+            notImportantEmails
+        }"), [
+            ValidationError::new(Pos::new(3, 13),
+                "Subscription \"ImportantEmails\" \
+                 must select only one top level field."),
+        ]);
+        assert_eq!(validate("subscription {
+            ...Emails
+        }
+        fragment Emails on SubscriptionRoot {
+            importantEmails
+            spamEmails: importantEmails
+        }"), [
+            ValidationError::new(Pos::new(6, 13),
+                "Anonymous Subscription \
+                 must select only one top level field."),
+        ]);
+    }
+
+    #[test]
+    fn skipped_fields() {
+        assert_eq!(validate("subscription {
+            a @skip(if: true)
+            b
+            ... @include(if: false) { c }
+            ...F @skip(if: true)
+        }
+        fragment F on SubscriptionRoot { d }"), []);
+        assert_eq!(validate("subscription ($v: Boolean!) {
+            a @skip(if: $v)
+            b
+        }"), [
+            ValidationError::new(Pos::new(3, 13),
+                "Anonymous Subscription \
+                 must select only one top level field."),
+        ]);
+    }
+
+    #[test]
+    fn introspection_field() {
+        assert_eq!(validate("subscription { __typename }"), [
+            ValidationError::new(Pos::new(1, 16),
+                "Anonymous Subscription must not select \
+                 an introspection top level field."),
+        ]);
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::{HashMap, HashSet};
+use std::ptr;
+
+use crate::common::{Text, Type};
+use crate::position::Pos;
+use crate::query::{OperationDefinition, QueryAstNode};
+use crate::query::{QueryVisitor, Value, VariableDefinition};
+use crate::validation::{TypeInfo, ValidationError, ValidationRule};
+
+
+/// Checks that the type of every variable is compatible with the type
+/// expected at the place where the variable is used
+///
+/// The variable type must be a subtype of the expected type: a non-null
+/// variable may be used where a nullable value is expected, but not vice
+/// versa, unless the variable or the argument has a default value.
+///
+/// Usages inside fragments are checked against the variables of every
+/// operation the fragment is (possibly transitively) spread into, so the
+/// errors are reported when leaving the document.
+#[derive(Debug)]
+pub struct VariablesInAllowedPosition<'ast, T: Text<'ast>> {
+    type_info: TypeInfo<'ast, T>,
+    operations: Vec<(&'ast [VariableDefinition<'ast, T>], Scope<'ast, T>)>,
+    fragments: HashMap<&'ast str, Scope<'ast, T>>,
+    scope: Scope<'ast, T>,
+    positions: Vec<Pos>,
+    argument_value: Option<&'ast Value<'ast, T>>,
+    in_variable_definition: bool,
+    errors: Vec<ValidationError>,
+}
+
+#[derive(Debug)]
+struct Usage<'ast, T: Text<'ast>> {
+    name: &'ast str,
+    expected: Type<'ast, T>,
+    has_location_default: bool,
+    position: Pos,
+}
+
+#[derive(Debug)]
+struct Scope<'ast, T: Text<'ast>> {
+    usages: Vec<Usage<'ast, T>>,
+    spreads: Vec<&'ast str>,
+}
+
+impl<'ast, T: Text<'ast>> Scope<'ast, T> {
+    fn new() -> Self {
+        Scope { usages: Vec::new(), spreads: Vec::new() }
+    }
+}
+
+impl<'ast, T> VariablesInAllowedPosition<'ast, T>
+    where T: Text<'ast>,
+{
+    pub fn new(type_info: TypeInfo<'ast, T>) -> Self {
+        VariablesInAllowedPosition {
+            type_info,
+            operations: Vec::new(),
+            fragments: HashMap::new(),
+            scope: Scope::new(),
+            positions: Vec::new(),
+            argument_value: None,
+            in_variable_definition: false,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Returns errors found so far
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.errors
+    }
+
+    pub fn into_errors(self) -> Vec<ValidationError> {
+        self.errors
+    }
+
+    fn check_operation(&self, vars: &'ast [VariableDefinition<'ast, T>],
+        scope: &Scope<'ast, T>, errors: &mut Vec<ValidationError>)
+    {
+        let mut visited = HashSet::new();
+        let mut queue = vec![scope];
+        while let Some(scope) = queue.pop() {
+            for usage in &scope.usages {
+                let var = match vars.iter()
+                    .find(|v| v.name.as_ref() == usage.name)
+                {
+                    Some(var) => var,
+                    None => continue,
+                };
+                if !allowed_usage(var, usage) {
+                    errors.push(ValidationError::new(usage.position,
+                        format!("Variable \"${}\" of type \"{}\" used in \
+                                 position expecting type \"{}\".",
+                                usage.name, var.var_type, usage.expected)));
+                }
+            }
+            for name in &scope.spreads {
+                if visited.insert(*name) {
+                    if let Some(fragment) = self.fragments.get(name) {
+                        queue.push(fragment);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn allowed_usage<'ast, T>(var: &VariableDefinition<'ast, T>,
+    usage: &Usage<'ast, T>)
+    -> bool
+    where T: Text<'ast>,
+{
+    match (&var.var_type, &usage.expected) {
+        (Type::NonNullType(_), _) => {}
+        (var_type, Type::NonNullType(expected)) => {
+            let has_default = match var.default_value {
+                Some(Value::Null) | None => false,
+                Some(_) => true,
+            };
+            return (has_default || usage.has_location_default)
+                && is_sub_type(var_type, expected);
+        }
+        _ => {}
+    }
+    is_sub_type(&var.var_type, &usage.expected)
+}
+
+/// Copies the type, which isn't `Clone` unless `T` is
+fn copy_type<'ast, T: Text<'ast>>(typ: &Type<'ast, T>) -> Type<'ast, T> {
+    match *typ {
+        Type::NamedType(ref name) => Type::NamedType(name.clone()),
+        Type::ListType(ref item) => Type::list(copy_type(item)),
+        Type::NonNullType(ref inner) => Type::non_null(copy_type(inner)),
+    }
+}
+
+/// Returns true if a value of the type `sub` can be used where a value of
+/// the type `sup` is expected
+fn is_sub_type<'ast, T>(sub: &Type<'ast, T>, sup: &Type<'ast, T>) -> bool
+    where T: Text<'ast>,
+{
+    match (sub, sup) {
+        (Type::NonNullType(sub), Type::NonNullType(sup)) => {
+            is_sub_type(sub, sup)
+        }
+        (_, Type::NonNullType(_)) => false,
+        (Type::NonNullType(sub), sup) => is_sub_type(sub, sup),
+        (Type::ListType(sub), Type::ListType(sup)) => is_sub_type(sub, sup),
+        (Type::NamedType(sub), Type::NamedType(sup)) => sub == sup,
+        _ => false,
+    }
+}
+
+impl<'ast, T> QueryVisitor<'ast, T> for VariablesInAllowedPosition<'ast, T>
+    where T: Text<'ast>,
+{
+    fn enter(&mut self, node: QueryAstNode<'ast, T>) {
+        self.type_info.enter(node);
+        match node {
+            QueryAstNode::OperationDefinition(_)
+            | QueryAstNode::FragmentDefinition(_) => {
+                self.scope = Scope::new();
+            }
+            QueryAstNode::VariableDefinition(_) => {
+                self.in_variable_definition = true;
+            }
+            QueryAstNode::Field(field) => self.positions.push(field.position),
+            QueryAstNode::Directive(dir) => self.positions.push(dir.position),
+            QueryAstNode::FragmentSpread(spread) => {
+                self.scope.spreads.push(spread.fragment_name.as_ref());
+            }
+            QueryAstNode::Argument(_, value) => {
+                self.argument_value = Some(value);
+            }
+            QueryAstNode::Value(value @ Value::Variable(name))
+                if !self.in_variable_definition =>
+            {
+                let expected = match self.type_info.input_type() {
+                    Some(typ) => copy_type(typ),
+                    None => return,
+                };
+                let direct = self.argument_value
+                    .is_some_and(|arg| ptr::eq(arg, value));
+                let has_location_default = direct && self.type_info.argument()
+                    .is_some_and(|arg| arg.default_value.is_some());
+                let position = self.positions.last().copied()
+                    .unwrap_or_default();
+                self.scope.usages.push(Usage {
+                    name: name.as_ref(),
+                    expected,
+                    has_location_default,
+                    position,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn leave(&mut self, node: QueryAstNode<'ast, T>) {
+        match node {
+            QueryAstNode::Document(_) => {
+                let mut errors = Vec::new();
+                for (vars, scope) in &self.operations {
+                    self.check_operation(vars, scope, &mut errors);
+                }
+                self.errors.extend(errors);
+            }
+            QueryAstNode::OperationDefinition(op) => {
+                let vars = match *op {
+                    OperationDefinition::SelectionSet(_) => &[][..],
+                    OperationDefinition::Query(ref q) => {
+                        &q.variable_definitions[..]
+                    }
+                    OperationDefinition::Mutation(ref m) => {
+                        &m.variable_definitions[..]
+                    }
+                    OperationDefinition::Subscription(ref s) => {
+                        &s.variable_definitions[..]
+                    }
+                };
+                let scope = std::mem::replace(&mut self.scope, Scope::new());
+                self.operations.push((vars, scope));
+            }
+            QueryAstNode::FragmentDefinition(frag) => {
+                let scope = std::mem::replace(&mut self.scope, Scope::new());
+                self.fragments.insert(frag.name.as_ref(), scope);
+            }
+            QueryAstNode::VariableDefinition(_) => {
+                self.in_variable_definition = false;
+            }
+            QueryAstNode::Field(_) | QueryAstNode::Directive(_) => {
+                self.positions.pop();
+            }
+            QueryAstNode::Argument(..) => self.argument_value = None,
+            _ => {}
+        }
+        self.type_info.leave(node);
+    }
+}
+
+impl<'ast, T> ValidationRule<'ast, T> for VariablesInAllowedPosition<'ast, T>
+    where T: Text<'ast>,
+{
+    fn take_errors(&mut self) -> Vec<ValidationError> {
+        std::mem::take(&mut self.errors)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::VariablesInAllowedPosition;
+    use crate::position::Pos;
+    use crate::query::{parse_query, visit};
+    use crate::schema::parse_schema;
+    use crate::validation::{TypeInfo, ValidationError};
+    use crate::validation::test_harness::TEST_SCHEMA;
+
+    fn validate(query: &str) -> Vec<ValidationError> {
+        let schema = parse_schema::<String>(TEST_SCHEMA).unwrap();
+        let query = parse_query::<String>(query).unwrap();
+        let mut rule = VariablesInAllowedPosition::new(
+            TypeInfo::new(&schema));
+        visit(&query, &mut rule);
+        rule.into_errors()
+    }
+
+    #[test]
+    fn allowed_positions() {
+        assert_eq!(validate("
+            query Q($x: String!, $i: Int = 1, $list: [String!]!) {
+                complicatedArgs {
+                    stringArgField(stringArg: $x)
+                    nonNullIntArgField(nonNullIntArg: $i)
+                    stringListArgField(stringListArg: $list)
+                    s: stringListArgField(stringListArg: [$x])
+                    complexArgField(complexArg: {
+                        requiredField: true, stringField: $x})
+                    ...Args
+                }
+            }
+            query R($arg: Int) {
+                complicatedArgs { nonNullFieldWithDefault(arg: $arg) }
+            }
+            fragment Args on ComplicatedArgs {
+                intArgField(intArg: $i) @directiveWithArgs(required: $i)
+            }
+        "), []);
+    }
+
+    #[test]
+    fn nullable_in_non_null_position() {
+        assert_eq!(validate("
+            query Q($x: String, $i: Int, $list: [String]) {
+                complicatedArgs {
+                    nonNullIntArgField(nonNullIntArg: $i)
+                    stringListNonNullArgField(stringListNonNullArg: $list)
+                    complexArgField(complexArg: {requiredField: $x})
+                }
+            }
+        "), [
+            ValidationError::new(Pos::new(4, 21),
+                "Variable \"$i\" of type \"Int\" used in position \
+                 expecting type \"Int!\"."),
+            ValidationError::new(Pos::new(5, 21),
+                "Variable \"$list\" of type \"[String]\" used in position \
+                 expecting type \"[String!]\"."),
+            ValidationError::new(Pos::new(6, 21),
+                "Variable \"$x\" of type \"String\" used in position \
+                 expecting type \"Boolean!\"."),
+        ]);
+    }
+
+    #[test]
+    fn mismatched_types() {
+        assert_eq!(validate("
+            query Q($s: String!, $list: [Int]) {
+                complicatedArgs {
+                    intArgField(intArg: $s)
+                    stringListArgField(stringListArg: $list)
+                    stringArgField(stringArg: $list)
+                }
+            }
+        "), [
+            ValidationError::new(Pos::new(4, 21),
+                "Variable \"$s\" of type \"String!\" used in position \
+                 expecting type \"Int\"."),
+            ValidationError::new(Pos::new(5, 21),
+                "Variable \"$list\" of type \"[Int]\" used in position \
+                 expecting type \"[String]\"."),
+            ValidationError::new(Pos::new(6, 21),
+                "Variable \"$list\" of type \"[Int]\" used in position \
+                 expecting type \"String\"."),
+        ]);
+    }
+
+    #[test]
+    fn usages_in_fragments() {
+        assert_eq!(validate("
+            query Q($i: Int) { complicatedArgs { ...Outer } }
+            query R($i: Int!) { complicatedArgs { ...Outer } }
+            fragment Outer on ComplicatedArgs { ...Inner }
+            fragment Inner on ComplicatedArgs {
+                nonNullIntArgField(nonNullIntArg: $i)
+            }
+        "), [
+            ValidationError::new(Pos::new(6, 17),
+                "Variable \"$i\" of type \"Int\" used in position \
+                 expecting type \"Int!\"."),
+        ]);
+    }
+}
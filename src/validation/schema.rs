@@ -0,0 +1,551 @@
+//! Validation of schema documents
+//!
+use std::collections::HashSet;
+
+use crate::common::Text;
+use crate::position::Pos;
+use crate::schema::{Definition, Document, Field, InputValue, SchemaIndex};
+use crate::schema::{get_named_type, is_non_null, Type, TypeDefinition};
+use crate::validation::ValidationError;
+
+
+const BUILTIN_SCALARS: &[&str] = &["Int", "Float", "String", "Boolean", "ID"];
+
+/// Checks that a schema document defines a valid schema
+///
+/// This includes that every referenced type is defined and has the right
+/// kind, that root types are object types, that objects implement all
+/// fields of their interfaces with compatible types and arguments, and
+/// that objects, interfaces, input objects, unions and enums are not
+/// empty. Types which are extended in the same document are not reported
+/// as empty, but the extensions are not checked otherwise.
+///
+/// ```rust
+/// # extern crate graphql_parser;
+/// use graphql_parser::parse_schema;
+/// use graphql_parser::validation::validate_schema;
+///
+/// let schema = parse_schema::<&str>("
+///     type Query { pet: Pet }
+///     union Pet
+/// ").unwrap();
+/// let errors = validate_schema(&schema);
+/// assert_eq!(errors[0].message,
+///     "Union type Pet must define one or more member types.");
+/// ```
+pub fn validate_schema<'a, T>(doc: &'a Document<'a, T>)
+    -> Vec<ValidationError>
+    where T: Text<'a>,
+{
+    let mut validator = SchemaValidator {
+        index: SchemaIndex::new(doc),
+        extended: doc.definitions.iter().filter_map(|def| match *def {
+            Definition::TypeExtension(ref ext) => Some(ext.name().as_ref()),
+            _ => None,
+        }).collect(),
+        errors: Vec::new(),
+    };
+    validator.check_document(doc);
+    validator.errors
+}
+
+struct SchemaValidator<'a, T: Text<'a>> {
+    index: SchemaIndex<'a, T>,
+    extended: HashSet<&'a str>,
+    errors: Vec<ValidationError>,
+}
+
+impl<'a, T: Text<'a>> SchemaValidator<'a, T> {
+    fn error<S: Into<String>>(&mut self, position: Pos, message: S) {
+        self.errors.push(ValidationError::new(position, message));
+    }
+
+    fn check_document(&mut self, doc: &'a Document<'a, T>) {
+        let mut schema_position = None;
+        let mut types = HashSet::new();
+        let mut directives = HashSet::new();
+        for def in &doc.definitions {
+            match *def {
+                Definition::SchemaDefinition(ref schema) => {
+                    schema_position = Some(schema.position);
+                    let roots = [
+                        ("Query", &schema.query),
+                        ("Mutation", &schema.mutation),
+                        ("Subscription", &schema.subscription),
+                    ];
+                    for (kind, name) in roots {
+                        if let Some(name) = name {
+                            self.check_root(schema.position, kind,
+                                name.as_ref());
+                        }
+                    }
+                }
+                Definition::TypeDefinition(ref typ) => {
+                    let name = typ.name().as_ref();
+                    let position = type_position(typ);
+                    if !types.insert(name) {
+                        self.error(position, format!(
+                            "There can be only one type named \"{}\".",
+                            name));
+                    }
+                    self.check_name(position, name);
+                    self.check_type(typ);
+                }
+                Definition::DirectiveDefinition(ref dir) => {
+                    let name = dir.name.as_ref();
+                    if !directives.insert(name) {
+                        self.error(dir.position, format!(
+                            "There can be only one directive named \"@{}\".",
+                            name));
+                    }
+                    self.check_name(dir.position, name);
+                    self.check_arguments(&format!("@{}", name),
+                        &dir.arguments);
+                    if dir.locations.is_empty() {
+                        self.error(dir.position, format!(
+                            "Directive @{} must include one or more \
+                             locations.", name));
+                    }
+                }
+                Definition::SchemaExtension(ref ext) => {
+                    let roots = [
+                        ("Query", &ext.query),
+                        ("Mutation", &ext.mutation),
+                        ("Subscription", &ext.subscription),
+                    ];
+                    for (kind, name) in roots {
+                        if let Some(name) = name {
+                            self.check_root(ext.position, kind,
+                                name.as_ref());
+                        }
+                    }
+                }
+                Definition::TypeExtension(_) => {}
+            }
+        }
+        if schema_position.is_none() && self.index.query_type().is_none() {
+            self.error(Pos::new(1, 1),
+                "Query root type must be provided.");
+        }
+    }
+
+    fn check_root(&mut self, position: Pos, kind: &str, name: &str) {
+        match self.index.find_type(name) {
+            Some(TypeDefinition::Object(_)) => {}
+            Some(_) => self.error(position, format!(
+                "{} root type must be Object type, it cannot be {}.",
+                kind, name)),
+            None => self.error(position, format!(
+                "Unknown type \"{}\".", name)),
+        }
+    }
+
+    fn check_name(&mut self, position: Pos, name: &str) {
+        if name.starts_with("__") {
+            self.error(position, format!(
+                "Name \"{}\" must not begin with \"__\", which is reserved \
+                 by GraphQL introspection.", name));
+        }
+    }
+
+    fn check_type(&mut self, typ: &'a TypeDefinition<'a, T>) {
+        let name = typ.name().as_ref();
+        let extended = self.extended.contains(name);
+        match *typ {
+            TypeDefinition::Scalar(_) => {}
+            TypeDefinition::Object(ref obj) => {
+                if obj.fields.is_empty() && !extended {
+                    self.error(obj.position, format!(
+                        "Type {} must define one or more fields.", name));
+                }
+                self.check_fields(name, &obj.fields);
+                self.check_interfaces(obj.position, name,
+                    &obj.implements_interfaces, &obj.fields);
+            }
+            TypeDefinition::Interface(ref iface) => {
+                if iface.fields.is_empty() && !extended {
+                    self.error(iface.position, format!(
+                        "Type {} must define one or more fields.", name));
+                }
+                self.check_fields(name, &iface.fields);
+            }
+            TypeDefinition::Union(ref union) => {
+                if union.types.is_empty() && !extended {
+                    self.error(union.position, format!(
+                        "Union type {} must define one or more member \
+                         types.", name));
+                }
+                let mut members = HashSet::new();
+                for member in &union.types {
+                    let member = member.as_ref();
+                    if !members.insert(member) {
+                        self.error(union.position, format!(
+                            "Union type {} can only include type {} once.",
+                            name, member));
+                        continue;
+                    }
+                    match self.index.find_type(member) {
+                        Some(TypeDefinition::Object(_)) => {}
+                        Some(_) => self.error(union.position, format!(
+                            "Union type {} can only include Object types, \
+                             it cannot include {}.", name, member)),
+                        None => self.error(union.position, format!(
+                            "Unknown type \"{}\".", member)),
+                    }
+                }
+            }
+            TypeDefinition::Enum(ref enm) => {
+                if enm.values.is_empty() && !extended {
+                    self.error(enm.position, format!(
+                        "Enum type {} must define one or more values.",
+                        name));
+                }
+                let mut values = HashSet::new();
+                for value in &enm.values {
+                    let value_name = value.name.as_ref();
+                    if !values.insert(value_name) {
+                        self.error(value.position, format!(
+                            "Enum value \"{}.{}\" can only be defined once.",
+                            name, value_name));
+                    }
+                    if matches!(value_name, "true" | "false" | "null") {
+                        self.error(value.position, format!(
+                            "Enum type {} cannot include value: {}.",
+                            name, value_name));
+                    }
+                    self.check_name(value.position, value_name);
+                }
+            }
+            TypeDefinition::InputObject(ref input) => {
+                if input.fields.is_empty() && !extended {
+                    self.error(input.position, format!(
+                        "Input Object type {} must define one or more \
+                         fields.", name));
+                }
+                let mut fields = HashSet::new();
+                for field in &input.fields {
+                    let field_name = field.name.as_ref();
+                    if !fields.insert(field_name) {
+                        self.error(field.position, format!(
+                            "Field \"{}.{}\" can only be defined once.",
+                            name, field_name));
+                    }
+                    self.check_name(field.position, field_name);
+                    self.check_input_type(field.position,
+                        &format!("{}.{}", name, field_name),
+                        &field.value_type);
+                }
+            }
+        }
+    }
+
+    fn check_fields(&mut self, type_name: &str, fields: &'a [Field<'a, T>]) {
+        let mut names = HashSet::new();
+        for field in fields {
+            let name = field.name.as_ref();
+            if !names.insert(name) {
+                self.error(field.position, format!(
+                    "Field \"{}.{}\" can only be defined once.",
+                    type_name, name));
+            }
+            self.check_name(field.position, name);
+            let coordinate = format!("{}.{}", type_name, name);
+            self.check_arguments(&coordinate, &field.arguments);
+            let field_type = get_named_type(&field.field_type).as_ref();
+            match self.kind_of(field_type) {
+                Some(kind) if kind.is_output() => {}
+                Some(_) => self.error(field.position, format!(
+                    "The type of {} must be Output Type but got: {}.",
+                    coordinate, field.field_type)),
+                None => self.error(field.position, format!(
+                    "Unknown type \"{}\".", field_type)),
+            }
+        }
+    }
+
+    fn check_arguments(&mut self, coordinate: &str,
+        arguments: &'a [InputValue<'a, T>])
+    {
+        let mut names = HashSet::new();
+        for arg in arguments {
+            let name = arg.name.as_ref();
+            if !names.insert(name) {
+                self.error(arg.position, format!(
+                    "Argument \"{}({}:)\" can only be defined once.",
+                    coordinate, name));
+            }
+            self.check_name(arg.position, name);
+            self.check_input_type(arg.position,
+                &format!("{}({}:)", coordinate, name), &arg.value_type);
+        }
+    }
+
+    fn check_input_type(&mut self, position: Pos, coordinate: &str,
+        typ: &Type<'a, T>)
+    {
+        let name = get_named_type(typ).as_ref();
+        match self.kind_of(name) {
+            Some(kind) if kind.is_input() => {}
+            Some(_) => self.error(position, format!(
+                "The type of {} must be Input Type but got: {}.",
+                coordinate, typ)),
+            None => self.error(position, format!(
+                "Unknown type \"{}\".", name)),
+        }
+    }
+
+    fn check_interfaces(&mut self, position: Pos, type_name: &str,
+        interfaces: &'a [T::Value], fields: &'a [Field<'a, T>])
+    {
+        let mut names = HashSet::new();
+        for iface_name in interfaces {
+            let iface_name = iface_name.as_ref();
+            if !names.insert(iface_name) {
+                self.error(position, format!(
+                    "Type {} can only implement {} once.",
+                    type_name, iface_name));
+                continue;
+            }
+            let iface = match self.index.find_type(iface_name) {
+                Some(TypeDefinition::Interface(iface)) => iface,
+                Some(_) => {
+                    self.error(position, format!(
+                        "Type {} must only implement Interface types, it \
+                         cannot implement {}.", type_name, iface_name));
+                    continue;
+                }
+                None => {
+                    self.error(position, format!(
+                        "Unknown type \"{}\".", iface_name));
+                    continue;
+                }
+            };
+            for iface_field in &iface.fields {
+                let field_name = iface_field.name.as_ref();
+                let field = match fields.iter()
+                    .find(|f| f.name.as_ref() == field_name)
+                {
+                    Some(field) => field,
+                    None => {
+                        self.error(position, format!(
+                            "Interface field {}.{} expected but {} does \
+                             not provide it.",
+                            iface_name, field_name, type_name));
+                        continue;
+                    }
+                };
+                self.check_implemented_field(type_name, iface_name,
+                    field, iface_field);
+            }
+        }
+    }
+
+    fn check_implemented_field(&mut self, type_name: &str,
+        iface_name: &str, field: &'a Field<'a, T>,
+        iface_field: &'a Field<'a, T>)
+    {
+        let name = field.name.as_ref();
+        if !self.is_sub_type(&field.field_type, &iface_field.field_type) {
+            self.error(field.position, format!(
+                "Interface field {}.{} expects type {} but {}.{} is \
+                 type {}.",
+                iface_name, name, iface_field.field_type,
+                type_name, name, field.field_type));
+        }
+        for iface_arg in &iface_field.arguments {
+            let arg_name = iface_arg.name.as_ref();
+            match field.get_argument(arg_name) {
+                Some(arg) => {
+                    if arg.value_type.to_string()
+                        != iface_arg.value_type.to_string()
+                    {
+                        self.error(arg.position, format!(
+                            "Interface field argument {}.{}({}:) expects \
+                             type {} but {}.{}({}:) is type {}.",
+                            iface_name, name, arg_name,
+                            iface_arg.value_type,
+                            type_name, name, arg_name, arg.value_type));
+                    }
+                }
+                None => self.error(field.position, format!(
+                    "Interface field argument {}.{}({}:) expected but \
+                     {}.{} does not provide it.",
+                    iface_name, name, arg_name, type_name, name)),
+            }
+        }
+        for arg in &field.arguments {
+            let arg_name = arg.name.as_ref();
+            let required = is_non_null(&arg.value_type)
+                && arg.default_value.is_none();
+            if required && !iface_field.arguments.iter()
+                .any(|a| a.name.as_ref() == arg_name)
+            {
+                self.error(arg.position, format!(
+                    "Object field {}.{} includes required argument {} that \
+                     is missing from the Interface field {}.{}.",
+                    type_name, name, arg_name, iface_name, name));
+            }
+        }
+    }
+
+    /// Checks whether a field of type `sub` can implement a field of type
+    /// `sup`
+    fn is_sub_type(&self, sub: &Type<'a, T>, sup: &Type<'a, T>) -> bool {
+        match (sub, sup) {
+            (Type::NonNullType(sub), Type::NonNullType(sup)) => {
+                self.is_sub_type(sub, sup)
+            }
+            (Type::NonNullType(sub), _) => self.is_sub_type(sub, sup),
+            (_, Type::NonNullType(_)) => false,
+            (Type::ListType(sub), Type::ListType(sup)) => {
+                self.is_sub_type(sub, sup)
+            }
+            (Type::NamedType(sub), Type::NamedType(sup)) => {
+                let (sub, sup) = (sub.as_ref(), sup.as_ref());
+                sub == sup || match self.index.find_type(sup) {
+                    Some(TypeDefinition::Interface(_)) => {
+                        match self.index.find_type(sub) {
+                            Some(TypeDefinition::Object(obj)) => {
+                                obj.implements_interfaces.iter()
+                                    .any(|i| i.as_ref() == sup)
+                            }
+                            _ => false,
+                        }
+                    }
+                    Some(TypeDefinition::Union(union)) => {
+                        union.types.iter().any(|t| t.as_ref() == sub)
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    fn kind_of(&self, name: &str) -> Option<Kind> {
+        if BUILTIN_SCALARS.contains(&name) {
+            return Some(Kind::Scalar);
+        }
+        self.index.find_type(name).map(|typ| match *typ {
+            TypeDefinition::Scalar(_) => Kind::Scalar,
+            TypeDefinition::Object(_) => Kind::Object,
+            TypeDefinition::Interface(_) => Kind::Interface,
+            TypeDefinition::Union(_) => Kind::Union,
+            TypeDefinition::Enum(_) => Kind::Enum,
+            TypeDefinition::InputObject(_) => Kind::InputObject,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Scalar,
+    Object,
+    Interface,
+    Union,
+    Enum,
+    InputObject,
+}
+
+impl Kind {
+    fn is_input(self) -> bool {
+        matches!(self, Kind::Scalar | Kind::Enum | Kind::InputObject)
+    }
+
+    fn is_output(self) -> bool {
+        self != Kind::InputObject
+    }
+}
+
+fn type_position<'a, T: Text<'a>>(typ: &TypeDefinition<'a, T>) -> Pos {
+    match *typ {
+        TypeDefinition::Scalar(ref t) => t.position,
+        TypeDefinition::Object(ref t) => t.position,
+        TypeDefinition::Interface(ref t) => t.position,
+        TypeDefinition::Union(ref t) => t.position,
+        TypeDefinition::Enum(ref t) => t.position,
+        TypeDefinition::InputObject(ref t) => t.position,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::validate_schema;
+    use crate::position::Pos;
+    use crate::schema::parse_schema;
+    use crate::validation::ValidationError;
+    use crate::validation::test_harness::TEST_SCHEMA;
+
+    fn validate(schema: &str) -> Vec<ValidationError> {
+        let doc = parse_schema::<&str>(schema).unwrap();
+        validate_schema(&doc)
+    }
+
+    #[test]
+    fn valid_schema() {
+        assert_eq!(validate(TEST_SCHEMA), []);
+    }
+
+    #[test]
+    fn missing_interface_field() {
+        assert_eq!(validate("
+            type Query { node: Node }
+            interface Node { id: ID! name(full: Boolean): String }
+            type User implements Node { id: ID name: String }
+        "), [
+            ValidationError::new(Pos::new(4, 41),
+                "Interface field Node.id expects type ID! but User.id is \
+                 type ID."),
+            ValidationError::new(Pos::new(4, 48),
+                "Interface field argument Node.name(full:) expected but \
+                 User.name does not provide it."),
+        ]);
+        assert_eq!(validate("
+            type Query { node: Node }
+            interface Node { id: ID! }
+            type User implements Node { name: String }
+        "), [
+            ValidationError::new(Pos::new(4, 13),
+                "Interface field Node.id expected but User does not \
+                 provide it."),
+        ]);
+    }
+
+    #[test]
+    fn empty_enum() {
+        assert_eq!(validate("
+            type Query { color: Color }
+            enum Color
+            enum Size
+            extend enum Size { S M L }
+        "), [
+            ValidationError::new(Pos::new(3, 13),
+                "Enum type Color must define one or more values."),
+        ]);
+    }
+
+    #[test]
+    fn wrong_kinds() {
+        assert_eq!(validate("
+            schema { query: Filter }
+            type Query { f(arg: Query): Filter, u: Unknown }
+            input Filter { q: Query }
+            union U = Query | Filter
+        "), [
+            ValidationError::new(Pos::new(2, 13),
+                "Query root type must be Object type, it cannot be Filter."),
+            ValidationError::new(Pos::new(3, 28),
+                "The type of Query.f(arg:) must be Input Type but got: \
+                 Query."),
+            ValidationError::new(Pos::new(3, 26),
+                "The type of Query.f must be Output Type but got: Filter."),
+            ValidationError::new(Pos::new(3, 49),
+                "Unknown type \"Unknown\"."),
+            ValidationError::new(Pos::new(4, 28),
+                "The type of Filter.q must be Input Type but got: Query."),
+            ValidationError::new(Pos::new(5, 13),
+                "Union type U can only include Object types, it cannot \
+                 include Filter."),
+        ]);
+    }
+}
@@ -0,0 +1,7 @@
+//! Schema shared by the tests of validation rules
+//!
+//! This is the schema used by the validation tests of graphql-js, so that
+//! test cases can be ported easily.
+
+pub(crate) const TEST_SCHEMA: &str =
+    include_str!("../../tests/graphql_js/test_schema.graphql");
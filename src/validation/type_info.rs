@@ -0,0 +1,417 @@
+use crate::common::{get_named_type, Directive, Text, Type};
+use crate::query::{QueryAstNode, OperationDefinition, TypeCondition, Value};
+use crate::schema::{self, SchemaIndex, TypeDefinition};
+use crate::schema::{DirectiveDefinition, InputValue};
+
+
+/// Tracks schema types corresponding to the query nodes being visited
+///
+/// Call `enter` and `leave` from the respective methods of a
+/// `QueryVisitor` and then query the type information of the current node.
+/// Every getter returns `None` if the corresponding definition can't be
+/// found in the schema (for example, when the field is unknown).
+#[derive(Debug)]
+pub struct TypeInfo<'ast, T: Text<'ast>> {
+    schema: SchemaIndex<'ast, T>,
+    type_stack: Vec<Option<&'ast TypeDefinition<'ast, T>>>,
+    parent_type_stack: Vec<Option<&'ast TypeDefinition<'ast, T>>>,
+    field_def_stack: Vec<Option<&'ast schema::Field<'ast, T>>>,
+    input_type_stack: Vec<Option<InputType<'ast, T>>>,
+    directive: Option<&'ast Directive<'ast, T>>,
+    argument: Option<&'ast T::Value>,
+}
+
+/// Expected type of an input value
+#[derive(Debug)]
+enum InputType<'ast, T: Text<'ast>> {
+    Defined(&'ast Type<'ast, T>),
+    /// The type of the current argument of a built-in directive, which is
+    /// owned by the schema index
+    BuiltInArgument,
+}
+
+// not derived, as that would require `T: Clone`
+impl<'ast, T: Text<'ast>> Clone for TypeInfo<'ast, T> {
+    fn clone(&self) -> Self {
+        TypeInfo {
+            schema: self.schema.clone(),
+            type_stack: self.type_stack.clone(),
+            parent_type_stack: self.parent_type_stack.clone(),
+            field_def_stack: self.field_def_stack.clone(),
+            input_type_stack: self.input_type_stack.clone(),
+            directive: self.directive,
+            argument: self.argument,
+        }
+    }
+}
+
+impl<'ast, T: Text<'ast>> Clone for InputType<'ast, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'ast, T: Text<'ast>> Copy for InputType<'ast, T> {}
+
+pub(crate) fn find_field<'ast, T>(typ: &'ast TypeDefinition<'ast, T>,
+    name: &str)
+    -> Option<&'ast schema::Field<'ast, T>>
+    where T: Text<'ast>,
+{
+    let fields = match *typ {
+        TypeDefinition::Object(ref o) => &o.fields,
+        TypeDefinition::Interface(ref i) => &i.fields,
+        _ => return None,
+    };
+    fields.iter().find(|f| f.name.as_ref() == name)
+}
+
+/// Returns the type of the items if the type is a list
+fn list_item<'ast, T>(typ: &'ast Type<'ast, T>) -> Option<&'ast Type<'ast, T>>
+    where T: Text<'ast>,
+{
+    match *typ {
+        Type::NonNullType(ref inner) => match **inner {
+            Type::ListType(ref item) => Some(item),
+            _ => None,
+        },
+        Type::ListType(ref item) => Some(item),
+        Type::NamedType(_) => None,
+    }
+}
+
+fn is_composite<'ast, T: Text<'ast>>(typ: &TypeDefinition<'ast, T>) -> bool {
+    match *typ {
+        TypeDefinition::Object(_)
+        | TypeDefinition::Interface(_)
+        | TypeDefinition::Union(_)
+            => true,
+        TypeDefinition::Scalar(_)
+        | TypeDefinition::Enum(_)
+        | TypeDefinition::InputObject(_)
+            => false,
+    }
+}
+
+impl<'ast, T> TypeInfo<'ast, T>
+    where T: Text<'ast>,
+{
+    /// Creates type information for the schema
+    ///
+    /// The built-in directives are known even if the schema doesn't define
+    /// them, see `SchemaIndex::with_builtin_directives`.
+    pub fn new(schema: &'ast schema::Document<'ast, T>) -> TypeInfo<'ast, T> {
+        TypeInfo::from_index(SchemaIndex::with_builtin_directives(schema))
+    }
+
+    pub fn from_index(schema: SchemaIndex<'ast, T>) -> TypeInfo<'ast, T> {
+        TypeInfo {
+            schema,
+            type_stack: Vec::new(),
+            parent_type_stack: Vec::new(),
+            field_def_stack: Vec::new(),
+            input_type_stack: Vec::new(),
+            directive: None,
+            argument: None,
+        }
+    }
+
+    /// Returns the index of the schema used to resolve types
+    pub fn schema(&self) -> &SchemaIndex<'ast, T> {
+        &self.schema
+    }
+
+    /// Returns the (unwrapped) output type of the current node
+    ///
+    /// This is the root type within an operation, the type condition within
+    /// a fragment and the type of the field within a field.
+    pub fn current_type(&self) -> Option<&'ast TypeDefinition<'ast, T>> {
+        self.type_stack.last().copied().flatten()
+    }
+
+    /// Returns the type whose fields are selected by the current selection
+    /// set
+    pub fn parent_type(&self) -> Option<&'ast TypeDefinition<'ast, T>> {
+        self.parent_type_stack.last().copied().flatten()
+    }
+
+    /// Returns the definition of the current field
+    pub fn field_def(&self) -> Option<&'ast schema::Field<'ast, T>> {
+        self.field_def_stack.last().copied().flatten()
+    }
+
+    /// Returns the expected type of the current input value
+    ///
+    /// Within a list value (including the list itself) this is the type of
+    /// the list items, the same way as in graphql-js.
+    pub fn input_type(&self) -> Option<&Type<'ast, T>> {
+        match self.input_type_stack.last().copied().flatten()? {
+            InputType::Defined(typ) => Some(typ),
+            InputType::BuiltInArgument => {
+                self.argument().map(|arg| &arg.value_type)
+            }
+        }
+    }
+
+    /// Returns the definition of the current directive
+    pub fn directive(&self) -> Option<&DirectiveDefinition<'ast, T>> {
+        self.schema.find_directive(self.directive?.name.as_ref())
+    }
+
+    /// Returns the definition of the current argument
+    pub fn argument(&self) -> Option<&InputValue<'ast, T>> {
+        let name = self.argument?.as_ref();
+        match self.directive {
+            Some(_) => self.directive()?.get_argument(name),
+            None => self.field_def()?.get_argument(name),
+        }
+    }
+
+    pub fn enter(&mut self, node: QueryAstNode<'ast, T>) {
+        match node {
+            QueryAstNode::Document(_) => {}
+            QueryAstNode::OperationDefinition(op) => {
+                let typ = match *op {
+                    OperationDefinition::SelectionSet(_)
+                    | OperationDefinition::Query(_)
+                        => self.schema.query_type(),
+                    OperationDefinition::Mutation(_)
+                        => self.schema.mutation_type(),
+                    OperationDefinition::Subscription(_)
+                        => self.schema.subscription_type(),
+                };
+                self.type_stack.push(typ);
+            }
+            QueryAstNode::FragmentDefinition(frag) => {
+                let TypeCondition::On(ref name) = frag.type_condition;
+                let typ = self.schema.find_type(name.as_ref());
+                self.type_stack.push(typ);
+            }
+            QueryAstNode::InlineFragment(frag) => {
+                let typ = match frag.type_condition {
+                    Some(TypeCondition::On(ref name)) => {
+                        self.schema.find_type(name.as_ref())
+                    }
+                    None => self.current_type(),
+                };
+                self.type_stack.push(typ);
+            }
+            QueryAstNode::SelectionSet(_) => {
+                let typ = self.current_type().filter(|t| is_composite(t));
+                self.parent_type_stack.push(typ);
+            }
+            QueryAstNode::Field(field) => {
+                let def = self.parent_type()
+                    .and_then(|p| find_field(p, field.name.as_ref()));
+                let typ = def.and_then(|d| {
+                    let name = get_named_type(&d.field_type);
+                    self.schema.find_type(name.as_ref())
+                });
+                self.field_def_stack.push(def);
+                self.type_stack.push(typ);
+            }
+            QueryAstNode::FragmentSpread(_) => {}
+            QueryAstNode::Directive(dir) => {
+                self.directive = Some(dir);
+            }
+            QueryAstNode::Argument(name, _) => {
+                self.argument = Some(name);
+                let defined = match self.directive {
+                    Some(dir) => {
+                        self.schema.defined_directive(dir.name.as_ref())
+                            .map(|d| d.get_argument(name.as_ref()))
+                    }
+                    None => Some(self.field_def()
+                        .and_then(|f| f.get_argument(name.as_ref()))),
+                };
+                let typ = match defined {
+                    Some(arg) => arg.map(|a| InputType::Defined(&a.value_type)),
+                    None => self.argument().map(|_| InputType::BuiltInArgument),
+                };
+                self.input_type_stack.push(typ);
+            }
+            QueryAstNode::VariableDefinition(var) => {
+                self.input_type_stack.push(
+                    Some(InputType::Defined(&var.var_type)));
+            }
+            QueryAstNode::Value(&Value::List(_)) => {
+                // built-in directives have no list arguments
+                let item = match self.input_type_stack.last() {
+                    Some(&Some(InputType::Defined(typ))) => list_item(typ),
+                    _ => None,
+                };
+                self.input_type_stack.push(item.map(InputType::Defined));
+            }
+            QueryAstNode::Value(_) => {}
+            QueryAstNode::ObjectField(name, _) => {
+                let typ = self.input_type()
+                    .and_then(|t| {
+                        self.schema.find_type(get_named_type(t).as_ref())
+                    })
+                    .and_then(|def| match *def {
+                        TypeDefinition::InputObject(ref obj) => {
+                            obj.fields.iter()
+                                .find(|f| f.name.as_ref() == name.as_ref())
+                        }
+                        _ => None,
+                    })
+                    .map(|f| InputType::Defined(&f.value_type));
+                self.input_type_stack.push(typ);
+            }
+        }
+    }
+
+    pub fn leave(&mut self, node: QueryAstNode<'ast, T>) {
+        match node {
+            QueryAstNode::Document(_) => {}
+            QueryAstNode::OperationDefinition(_)
+            | QueryAstNode::FragmentDefinition(_)
+            | QueryAstNode::InlineFragment(_)
+            => {
+                self.type_stack.pop();
+            }
+            QueryAstNode::SelectionSet(_) => {
+                self.parent_type_stack.pop();
+            }
+            QueryAstNode::Field(_) => {
+                self.field_def_stack.pop();
+                self.type_stack.pop();
+            }
+            QueryAstNode::FragmentSpread(_) => {}
+            QueryAstNode::Directive(_) => {
+                self.directive = None;
+            }
+            QueryAstNode::Argument(..) => {
+                self.argument = None;
+                self.input_type_stack.pop();
+            }
+            QueryAstNode::VariableDefinition(_)
+            | QueryAstNode::Value(&Value::List(_))
+            | QueryAstNode::ObjectField(..)
+            => {
+                self.input_type_stack.pop();
+            }
+            QueryAstNode::Value(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TypeInfo;
+    use crate::query::{self, parse_query, visit, QueryAstNode, QueryVisitor};
+    use crate::schema::{parse_schema, TypeDefinition};
+    use crate::schema::with_builtin_definitions;
+    use crate::validation::test_harness::TEST_SCHEMA;
+
+    struct Trace<'ast> {
+        type_info: TypeInfo<'ast, &'ast str>,
+        trace: Vec<String>,
+    }
+
+    impl<'ast> QueryVisitor<'ast, &'ast str> for Trace<'ast> {
+        fn enter(&mut self, node: QueryAstNode<'ast, &'ast str>) {
+            self.type_info.enter(node);
+            fn name<'a>(t: Option<&TypeDefinition<'a, &'a str>>) -> &'a str {
+                t.map(|t| *t.name()).unwrap_or("?")
+            }
+            match node {
+                QueryAstNode::Field(f) => {
+                    self.trace.push(format!("{}.{}: {}",
+                        name(self.type_info.parent_type()), f.name,
+                        self.type_info.field_def()
+                            .map(|d| d.field_type.to_string())
+                            .unwrap_or_else(|| "?".into())));
+                }
+                QueryAstNode::InlineFragment(_) => {
+                    self.trace.push(format!("... on {}",
+                        name(self.type_info.current_type())));
+                }
+                QueryAstNode::Value(v) => {
+                    self.trace.push(format!("{}: {}", v,
+                        self.type_info.input_type()
+                            .map(|t| t.to_string())
+                            .unwrap_or_else(|| "?".into())));
+                }
+                _ => {}
+            }
+        }
+        fn leave(&mut self, node: QueryAstNode<'ast, &'ast str>) {
+            self.type_info.leave(node);
+        }
+    }
+
+    fn trace(query: &'static str) -> Vec<String> {
+        trace_with(query, false)
+    }
+
+    fn trace_with(query: &'static str, built_in: bool) -> Vec<String> {
+        let schema = parse_schema::<&str>(TEST_SCHEMA).unwrap();
+        let schema = if built_in {
+            with_builtin_definitions(schema)
+        } else {
+            schema
+        };
+        let query: query::Document<&str> = parse_query(query).unwrap();
+        let mut visitor = Trace {
+            type_info: TypeInfo::new(&schema),
+            trace: Vec::new(),
+        };
+        visit(&query, &mut visitor);
+        visitor.trace
+    }
+
+    #[test]
+    fn visit_maintains_type_info() {
+        assert_eq!(trace("{
+            human(id: 4) { name, pets { ... { name } } }
+            alien { unknown }
+            catOrDog { ... on Cat { furColor } }
+        }"), [
+            "QueryRoot.human: Human",
+            "4: ID",
+            "Human.name: String",
+            "Human.pets: [Pet]",
+            "... on Pet",
+            "Pet.name: String",
+            "QueryRoot.alien: Alien",
+            "Alien.unknown: ?",
+            "QueryRoot.catOrDog: CatOrDog",
+            "... on Cat",
+            "Cat.furColor: FurColor",
+        ]);
+    }
+
+    #[test]
+    fn field_arguments() {
+        assert_eq!(trace("{ alien { name(surname: true) } }"), [
+            "QueryRoot.alien: Alien",
+            "Alien.name: String",
+            "true: Boolean",
+        ]);
+    }
+
+    #[test]
+    fn built_in_directive_arguments() {
+        let query = "{ alien @skip(if: true) { iq } }";
+        assert_eq!(trace(query)[1], "true: Boolean!");
+        assert_eq!(trace_with(query, true)[1], "true: Boolean!");
+    }
+
+    #[test]
+    fn input_types() {
+        assert_eq!(&trace("{
+            complicatedArgs {
+                complexArgField(complexArg: {
+                    requiredField: true,
+                    stringListField: [\"a\"]
+                })
+            }
+        }")[2..], [
+            "{requiredField: true, stringListField: [\"a\"]}: ComplexInput",
+            "true: Boolean!",
+            "[\"a\"]: String",
+            "\"a\": String",
+        ]);
+    }
+}
@@ -0,0 +1,123 @@
+extern crate graphql_parser;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use std::fs;
+
+use graphql_parser::{parse_query, parse_schema};
+use graphql_parser::{BlockOrString, FormatOptions, Indent};
+
+fn read(filename: &str) -> String {
+    fs::read_to_string(format!("tests/formatted/{}.graphql", filename))
+        .unwrap()
+}
+
+fn check_schema(source: &str, target: &str, options: &FormatOptions) {
+    let source = read(source);
+    let ast = parse_schema::<String>(&source).unwrap();
+    let formatted = ast.format_with(options);
+    assert_eq!(formatted, read(target));
+    // output of formatting is stable
+    let reparsed = parse_schema::<String>(&formatted).unwrap();
+    assert_eq!(reparsed.format_with(options), formatted);
+}
+
+fn check_query(source: &str, target: &str, options: &FormatOptions) {
+    let source = read(source);
+    let ast = parse_query::<String>(&source).unwrap();
+    let formatted = ast.format_with(options);
+    assert_eq!(formatted, read(target));
+    let reparsed = parse_query::<String>(&formatted).unwrap();
+    assert_eq!(reparsed.format_with(options), formatted);
+}
+
+#[test]
+fn default_options() {
+    let options = FormatOptions::default();
+    let schema = read("schema");
+    let schema = parse_schema::<String>(&schema).unwrap();
+    assert_eq!(schema.format_with(&options), schema.to_string());
+    let query = read("query");
+    let query = parse_query::<String>(&query).unwrap();
+    assert_eq!(query.format_with(&options), query.to_string());
+}
+
+#[test]
+fn schema_sorted() {
+    check_schema("schema", "schema_sorted", &FormatOptions {
+        indent: Indent::Spaces(4),
+        sort_definitions: true,
+        sort_fields: true,
+        sort_arguments: true,
+        description_style: BlockOrString::Auto,
+        ..FormatOptions::default()
+    });
+}
+
+#[test]
+fn schema_fields_only() {
+    check_schema("schema", "schema_fields_sorted", &FormatOptions {
+        sort_fields: true,
+        ..FormatOptions::default()
+    });
+}
+
+#[test]
+fn schema_description_styles() {
+    check_schema("schema", "schema_block_descriptions", &FormatOptions {
+        description_style: BlockOrString::Block,
+        ..FormatOptions::default()
+    });
+    check_schema("schema", "schema_tabs_string_descriptions", &FormatOptions {
+        indent: Indent::Tabs,
+        description_style: BlockOrString::String,
+        ..FormatOptions::default()
+    });
+}
+
+#[test]
+fn query_sorted() {
+    check_query("query", "query_sorted", &FormatOptions {
+        indent: Indent::Spaces(4),
+        sort_definitions: true,
+        sort_fields: true,
+        sort_arguments: true,
+        ..FormatOptions::default()
+    });
+}
+
+#[test]
+fn query_arguments_only() {
+    check_query("query", "query_arguments_sorted", &FormatOptions {
+        indent: Indent::Tabs,
+        sort_arguments: true,
+        ..FormatOptions::default()
+    });
+}
+
+#[test]
+fn query_arguments_single_line() {
+    check_query("arguments", "arguments", &FormatOptions {
+        args_per_line: Some(5),
+        ..FormatOptions::default()
+    });
+}
+
+#[test]
+fn query_arguments_wrapped() {
+    check_query("arguments", "arguments_wrapped", &FormatOptions {
+        args_per_line: Some(4),
+        ..FormatOptions::default()
+    });
+    check_query("arguments", "arguments_trailing_commas", &FormatOptions {
+        args_per_line: Some(2),
+        trailing_commas: true,
+        ..FormatOptions::default()
+    });
+}
+
+#[test]
+fn query_to_string_pretty() {
+    let source = read("arguments");
+    let query = parse_query::<String>(&source).unwrap();
+    assert_eq!(query.to_string_pretty(), read("arguments_pretty"));
+}
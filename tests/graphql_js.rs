@@ -0,0 +1,40 @@
+extern crate graphql_parser;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use std::fs;
+
+use graphql_parser::schema::{apply_extensions, parse_schema, sort_schema};
+use graphql_parser::schema::print_graphql_js_style;
+
+// Run tests/graphql_js/generate.mjs to regenerate the expected output
+
+/// Compares with the output of `printSchema(buildSchema(source))` of
+/// graphql-js 16, which has no newline at the end
+fn check(filename: &str) {
+    let source = fs::read_to_string(
+        format!("tests/graphql_js/{}.graphql", filename)).unwrap();
+    let expected = fs::read_to_string(
+        format!("tests/graphql_js/{}.printed.graphql", filename)).unwrap();
+    let doc = parse_schema::<String>(&source).unwrap();
+    assert_eq!(print_graphql_js_style(&doc), expected);
+}
+
+#[test] fn descriptions() { check("descriptions"); }
+#[test] fn kitchen_sink() { check("kitchen_sink"); }
+
+/// Compares with the output of
+/// `printSchema(lexicographicSortSchema(buildSchema(source)))`
+fn check_sorted(filename: &str) {
+    let source = fs::read_to_string(
+        format!("tests/graphql_js/{}.graphql", filename)).unwrap();
+    let expected = fs::read_to_string(
+        format!("tests/graphql_js/{}.sorted.graphql", filename)).unwrap();
+    let mut doc = parse_schema::<String>(&source).unwrap();
+    apply_extensions(&mut doc).unwrap();
+    let sorted = sort_schema(&doc);
+    assert_eq!(print_graphql_js_style(&sorted), expected);
+    assert_eq!(sort_schema(&sorted).to_string(), sorted.to_string());
+}
+
+#[test] fn descriptions_sorted() { check_sorted("descriptions"); }
+#[test] fn kitchen_sink_sorted() { check_sorted("kitchen_sink"); }
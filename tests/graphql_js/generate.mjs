@@ -0,0 +1,18 @@
+// Writes the output of `printSchema` and `lexicographicSortSchema` of
+// graphql-js for the schemas of this directory:
+//
+//     cd tests && npm install && node graphql_js/generate.mjs
+//
+import { readFileSync, writeFileSync } from 'node:fs';
+import { buildSchema, lexicographicSortSchema, printSchema } from 'graphql';
+
+const dir = new URL('.', import.meta.url);
+
+for (const name of ['descriptions', 'kitchen_sink']) {
+  const source = readFileSync(new URL(`${name}.graphql`, dir), 'utf8');
+  const schema = buildSchema(source);
+  writeFileSync(new URL(`${name}.printed.graphql`, dir),
+    printSchema(schema));
+  writeFileSync(new URL(`${name}.sorted.graphql`, dir),
+    printSchema(lexicographicSortSchema(schema)));
+}
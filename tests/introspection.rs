@@ -0,0 +1,41 @@
+#![cfg(feature = "json")]
+extern crate graphql_parser;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use std::fs;
+
+use graphql_parser::schema::{from_introspection, print_graphql_js_style};
+use graphql_parser::schema::{parse_schema, to_introspection};
+
+// Run tests/introspection/generate.mjs to regenerate the introspection
+// results
+
+fn read_json(filename: &str) -> serde_json::Value {
+    let json = fs::read_to_string(
+        format!("tests/introspection/{}.json", filename)).unwrap();
+    serde_json::from_str(&json).unwrap()
+}
+
+/// Compares the schema rebuilt from the result of `getIntrospectionQuery`
+/// with the output of `printSchema` of graphql-js 16 for the same schema
+fn check(filename: &str) {
+    let expected = fs::read_to_string(
+        format!("tests/graphql_js/{}.printed.graphql", filename)).unwrap();
+    let doc = from_introspection(&read_json(filename)).unwrap();
+    assert_eq!(print_graphql_js_style(&doc), expected);
+}
+
+/// Compares the introspection of the schema with the output of
+/// `introspectionFromSchema` of graphql-js 16
+fn check_introspection(filename: &str) {
+    let source = fs::read_to_string(
+        format!("tests/graphql_js/{}.graphql", filename)).unwrap();
+    let doc = parse_schema::<&str>(&source).unwrap();
+    let result = serde_json::json!({"data": to_introspection(&doc)});
+    assert_eq!(result, read_json(filename));
+}
+
+#[test] fn descriptions() { check("descriptions"); }
+#[test] fn kitchen_sink() { check("kitchen_sink"); }
+#[test] fn descriptions_to_json() { check_introspection("descriptions"); }
+#[test] fn kitchen_sink_to_json() { check_introspection("kitchen_sink"); }
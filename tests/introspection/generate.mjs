@@ -0,0 +1,29 @@
+// Writes the result of `getIntrospectionQuery()` executed by graphql-js
+// against the schemas of ../graphql_js, with all the options which
+// `introspectionFromSchema` enables:
+//
+//     cd tests && npm install && node introspection/generate.mjs
+//
+import { readFileSync, writeFileSync } from 'node:fs';
+import { buildSchema, getIntrospectionQuery, graphqlSync } from 'graphql';
+
+const dir = new URL('.', import.meta.url);
+const query = getIntrospectionQuery({
+  descriptions: true,
+  specifiedByUrl: true,
+  directiveIsRepeatable: true,
+  schemaDescription: true,
+  inputValueDeprecation: true,
+  oneOf: true,
+});
+
+for (const name of ['descriptions', 'kitchen_sink']) {
+  const source = readFileSync(
+    new URL(`../graphql_js/${name}.graphql`, dir), 'utf8');
+  const result = graphqlSync({ schema: buildSchema(source), source: query });
+  if (result.errors) {
+    throw new Error(`${name}: ${result.errors.join('\n')}`);
+  }
+  writeFileSync(new URL(`${name}.json`, dir),
+    JSON.stringify(result, null, 2) + '\n');
+}
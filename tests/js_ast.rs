@@ -0,0 +1,35 @@
+#![cfg(feature = "json")]
+extern crate graphql_parser;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use std::fs;
+
+use graphql_parser::{parse_query, parse_schema};
+use graphql_parser::js_ast::{to_js_ast_json, schema_to_js_ast_json};
+
+// Run tests/js_ast/generate.mjs to regenerate the expected ASTs
+
+fn read(filename: &str) -> String {
+    fs::read_to_string(format!("tests/js_ast/{}", filename)).unwrap()
+}
+
+fn expected(filename: &str) -> serde_json::Value {
+    serde_json::from_str(&read(&format!("{}.json", filename))).unwrap()
+}
+
+fn check_query(filename: &str) {
+    let source = read(&format!("{}.graphql", filename));
+    let ast = parse_query::<&str>(&source).unwrap();
+    assert_eq!(to_js_ast_json(&ast), expected(filename));
+}
+
+fn check_schema(filename: &str) {
+    let source = read(&format!("{}.graphql", filename));
+    let ast = parse_schema::<&str>(&source).unwrap();
+    assert_eq!(schema_to_js_ast_json(&ast), expected(filename));
+}
+
+#[test] fn query() { check_query("query"); }
+#[test] fn operations() { check_query("operations"); }
+#[test] fn schema() { check_schema("schema"); }
+#[test] fn extensions() { check_schema("extensions"); }
@@ -0,0 +1,15 @@
+// Writes the AST of graphql-js for the documents of this directory:
+//
+//     cd tests && npm install && node js_ast/generate.mjs
+//
+import { readFileSync, writeFileSync } from 'node:fs';
+import { parse } from 'graphql';
+
+const dir = new URL('.', import.meta.url);
+
+for (const name of ['query', 'operations', 'schema', 'extensions']) {
+  const source = readFileSync(new URL(`${name}.graphql`, dir), 'utf8');
+  const ast = parse(source, { noLocation: true });
+  writeFileSync(new URL(`${name}.json`, dir),
+    JSON.stringify(ast, null, 2) + '\n');
+}
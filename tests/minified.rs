@@ -0,0 +1,96 @@
+extern crate graphql_parser;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use std::fs;
+
+use graphql_parser::{parse_query, parse_schema};
+
+fn corpus(dir: &str) -> Vec<(String, String)> {
+    let mut files = fs::read_dir(dir).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "graphql"))
+        .map(|path| {
+            let text = fs::read_to_string(&path).unwrap();
+            (path.display().to_string(), text)
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    assert!(!files.is_empty());
+    files
+}
+
+#[test]
+fn minified_queries_reparse() {
+    for (path, text) in corpus("tests/queries") {
+        let ast = parse_query::<String>(&text).unwrap();
+        let minified = ast.to_minified_string();
+        assert!(!minified.contains('\n'), "{}: {}", path, minified);
+        let reparsed = parse_query::<String>(&minified)
+            .unwrap_or_else(|e| panic!("{}: {}\n{}", path, minified, e));
+        assert_eq!(reparsed.to_minified_string(), minified, "{}", path);
+    }
+}
+
+#[test]
+fn minified_schemas_reparse() {
+    for (path, text) in corpus("tests/schemas") {
+        let ast = parse_schema::<String>(&text).unwrap();
+        let minified = ast.to_minified_string();
+        assert!(!minified.contains('\n'), "{}: {}", path, minified);
+        let reparsed = parse_schema::<String>(&minified)
+            .unwrap_or_else(|e| panic!("{}: {}\n{}", path, minified, e));
+        assert_eq!(reparsed.to_minified_string(), minified, "{}", path);
+    }
+}
+
+#[test]
+fn minified_query() {
+    let ast = parse_query::<String>(r#"
+        query Q($id: ID = 1, $flag: Boolean) @live {
+            user(id: $id, names: ["a", "", "b\n\"c\""]) @include(if: $flag) {
+                id, name
+                ... on Admin { level }
+                ...Details @skip(if: false)
+            }
+            text(value: """
+                multi-line
+                  block string
+            """)
+        }
+    "#).unwrap();
+    assert_eq!(ast.to_minified_string(), concat!(
+        r#"query Q($id:ID=1$flag:Boolean)@live{"#,
+        r#"user(id:$id names:["a" "" "b\n\"c\""])@include(if:$flag)"#,
+        r#"{id name...on Admin{level}...Details@skip(if:false)}"#,
+        r#"text(value:"multi-line\n  block string")}"#,
+    ));
+}
+
+#[test]
+fn minified_negative_numbers() {
+    let ast = parse_query::<String>(
+        "{ a(x: [1 -2, -3.5 -4], y: [A -1], z: {b: 1 c: -2}) }").unwrap();
+    assert_eq!(ast.to_minified_string(),
+               "{a(x:[1 -2 -3.5 -4]y:[A -1]z:{b:1 c:-2})}");
+}
+
+#[test]
+fn minified_schema() {
+    let ast = parse_schema::<String>(r#"
+        """
+        A user
+        """
+        type User implements Node & Entity @key(fields: "id") {
+            "The id"
+            id: ID!
+            friends(first: Int = 10, after: String): [User!]!
+        }
+        enum Role { ADMIN USER }
+        union Search = User | Post
+    "#).unwrap();
+    assert_eq!(ast.to_minified_string(), concat!(
+        r#""A user"type User implements Node&Entity@key(fields:"id")"#,
+        r#"{"The id"id:ID!friends(first:Int=10 after:String):[User!]!}"#,
+        r#"enum Role{ADMIN USER}union Search=User|Post"#,
+    ));
+}
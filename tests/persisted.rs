@@ -0,0 +1,50 @@
+extern crate graphql_parser;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use std::fs;
+
+use graphql_parser::parse_query;
+use graphql_parser::persisted::{build_manifest, sha256_hash, Manifest};
+use graphql_parser::query::separate_operations;
+
+fn manifest() -> Manifest {
+    let source = fs::read_to_string("tests/persisted/operations.graphql")
+        .unwrap();
+    let doc = parse_query::<String>(&source).unwrap();
+    let documents = separate_operations(&doc).into_iter()
+        .collect::<Vec<_>>();
+    build_manifest(&documents)
+}
+
+/// To regenerate manifest.json, run
+/// `npx @apollo/generate-persisted-query-manifest` in tests/persisted, which
+/// reads persisted-query-manifest.config.json
+fn expected() -> serde_json::Value {
+    let manifest = fs::read_to_string("tests/persisted/manifest.json")
+        .unwrap();
+    serde_json::from_str(&manifest).unwrap()
+}
+
+#[test]
+fn matches_apollo_manifest() {
+    let manifest = manifest();
+    let expected = expected();
+    assert_eq!(manifest.format, expected["format"]);
+    assert_eq!(manifest.version, expected["version"]);
+    let operations = expected["operations"].as_array().unwrap();
+    assert_eq!(manifest.operations.len(), operations.len());
+    for (op, expected) in manifest.operations.iter().zip(operations) {
+        assert_eq!(op.name, expected["name"]);
+        assert_eq!(op.operation_type, expected["type"]);
+        assert_eq!(op.body, expected["body"]);
+        assert_eq!(op.id, expected["id"]);
+        let reparsed = parse_query::<&str>(&op.body).unwrap();
+        assert_eq!(sha256_hash(&reparsed), op.id);
+    }
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn serialize_manifest() {
+    assert_eq!(serde_json::to_value(manifest()).unwrap(), expected());
+}
@@ -20,3 +20,5 @@ fn test_error(filename: &str) {
 
 #[test] fn invalid_curly_brace() { test_error("invalid_curly_brace"); }
 #[test] fn bad_args() { test_error("bad_args"); }
+#[test] fn lone_surrogate() { test_error("lone_surrogate"); }
+#[test] fn int_out_of_range() { test_error("int_out_of_range"); }
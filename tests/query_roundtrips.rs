@@ -4,7 +4,8 @@ extern crate graphql_parser;
 use std::io::Read;
 use std::fs::File;
 
-use graphql_parser::parse_query;
+use graphql_parser::{parse_query, Style};
+use graphql_parser::query::{Definition, OperationDefinition, Selection};
 
 fn roundtrip(filename: &str) {
     let mut buf = String::with_capacity(1024);
@@ -13,6 +14,10 @@ fn roundtrip(filename: &str) {
     f.read_to_string(&mut buf).unwrap();
     let ast = parse_query::<String>(&buf).unwrap().to_owned();
     assert_eq!(ast.to_string(), buf);
+
+    let mut bytes = Vec::new();
+    ast.write_to(&mut bytes).unwrap();
+    assert_eq!(String::from_utf8(bytes).unwrap(), buf);
 }
 
 fn roundtrip2(filename: &str) {
@@ -45,15 +50,69 @@ fn roundtrip2(filename: &str) {
 #[test] fn query_directive() { roundtrip("query_directive"); }
 #[test] fn mutation_directive() { roundtrip("mutation_directive"); }
 #[test] fn subscription_directive() { roundtrip("subscription_directive"); }
+#[test] fn named_subscription() { roundtrip("named_subscription"); }
 #[test] fn string_literal() { roundtrip("string_literal"); }
 #[test] fn triple_quoted_literal() { roundtrip("triple_quoted_literal"); }
 #[test] fn query_list_arg() { roundtrip("query_list_argument"); }
+#[test] fn negative_numbers() { roundtrip("negative_numbers"); }
 #[test] fn query_object_arg() { roundtrip("query_object_argument"); }
 #[test] fn nested_selection() { roundtrip("nested_selection"); }
 #[test] fn inline_fragment() { roundtrip("inline_fragment"); }
 #[test] fn inline_fragment_dir() { roundtrip("inline_fragment_dir"); }
+#[test] fn field_stream() { roundtrip("field_stream"); }
 #[test] fn fragment_spread() { roundtrip("fragment_spread"); }
 #[test] fn minimal_mutation() { roundtrip("minimal_mutation"); }
+#[test] fn mutation_vars() { roundtrip("mutation_vars"); }
 #[test] fn fragment() { roundtrip("fragment"); }
 #[test] fn directive_args() { roundtrip("directive_args"); }
 #[test] fn kitchen_sink() { roundtrip2("kitchen-sink"); }
+
+#[test]
+fn nested_field() {
+    let ast = parse_query::<&str>("{ a { b(x: 1) { c } } }").unwrap();
+    let field = match ast.definitions[0] {
+        Definition::Operation(OperationDefinition::SelectionSet(ref set)) => {
+            match set.items[0] {
+                Selection::Field(ref field) => field,
+                _ => unreachable!(),
+            }
+        }
+        _ => unreachable!(),
+    };
+    let mut style = Style::default();
+    style.indent(4).nesting_level(1);
+    let mut buf = String::new();
+    field.format_to(&mut buf, &style).unwrap();
+    assert_eq!(buf, "    a {\n        b(x: 1) {\n            c\n        }\n    }\n");
+}
+
+#[test]
+fn escaped_characters() {
+    let query = "{ a(s: \"nul \\u0000 del \\u007F emoji \u{1F600}\") }";
+    let ast = parse_query::<&str>(query).unwrap();
+    let printed = ast.to_string();
+    assert_eq!(printed,
+               "{\n  a(s: \"nul \\u0000 del \\u007F emoji \u{1F600}\")\n}\n");
+    assert_eq!(parse_query::<&str>(&printed).unwrap().to_string(), printed);
+}
+
+#[cfg(feature = "fragment-arguments")]
+#[test]
+fn fragment_arguments() {
+    let query = "\
+query Profile($size: Int) {
+  user {
+    ...Avatar(size: $size, round: true)
+    ...Avatar @skip(if: false)
+  }
+}
+
+fragment Avatar($size: Int = 64, $round: Boolean) on User {
+  avatar(size: $size, round: $round)
+}
+";
+    let ast = parse_query::<&str>(query).unwrap();
+    assert_eq!(ast.to_string(), query);
+    let minified = ast.to_minified_string();
+    assert_eq!(parse_query::<&str>(&minified).unwrap().to_string(), query);
+}
@@ -0,0 +1,34 @@
+extern crate graphql_parser;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use std::fs;
+
+use graphql_parser::{parse_query, parse_schema, reformat};
+
+/// Checks that formatting is idempotent and doesn't change the AST
+fn check(dir: &str, same_ast: fn(&str, &str)) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let source = fs::read_to_string(&path).unwrap();
+        let formatted = reformat(&source).unwrap();
+        assert_eq!(reformat(&formatted).unwrap(), formatted, "{:?}", path);
+        same_ast(&source, &formatted);
+    }
+}
+
+#[test]
+fn queries() {
+    check("tests/queries", |source, formatted| {
+        assert_eq!(parse_query::<&str>(formatted).unwrap().to_string(),
+                   parse_query::<&str>(source).unwrap().to_string());
+    });
+}
+
+#[test]
+fn schemas() {
+    check("tests/schemas", |source, formatted| {
+        assert_eq!(parse_schema::<&str>(formatted).unwrap().to_string(),
+                   parse_schema::<&str>(source).unwrap().to_string());
+    });
+}
+
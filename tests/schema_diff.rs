@@ -0,0 +1,78 @@
+extern crate graphql_parser;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use std::collections::BTreeSet;
+use std::fs;
+
+use graphql_parser::parse_schema;
+use graphql_parser::schema::{diff_schemas, ChangeKind, SchemaChange};
+
+fn changes() -> Vec<SchemaChange> {
+    let old = fs::read_to_string("tests/schema_diff/old.graphql").unwrap();
+    let new = fs::read_to_string("tests/schema_diff/new.graphql").unwrap();
+    let old = parse_schema::<String>(&old).unwrap();
+    let new = parse_schema::<String>(&new).unwrap();
+    diff_schemas(&old, &new)
+}
+
+fn kind_name(kind: &ChangeKind) -> &'static str {
+    match *kind {
+        ChangeKind::RootTypeChanged { .. } => "RootTypeChanged",
+        ChangeKind::RootTypeRemoved { .. } => "RootTypeRemoved",
+        ChangeKind::TypeAdded { .. } => "TypeAdded",
+        ChangeKind::TypeRemoved { .. } => "TypeRemoved",
+        ChangeKind::TypeKindChanged { .. } => "TypeKindChanged",
+        ChangeKind::FieldAdded { .. } => "FieldAdded",
+        ChangeKind::FieldRemoved { .. } => "FieldRemoved",
+        ChangeKind::FieldTypeChanged { .. } => "FieldTypeChanged",
+        ChangeKind::ArgumentAdded { .. } => "ArgumentAdded",
+        ChangeKind::ArgumentRemoved { .. } => "ArgumentRemoved",
+        ChangeKind::ArgumentTypeChanged { .. } => "ArgumentTypeChanged",
+        ChangeKind::MadeRequired { .. } => "MadeRequired",
+        ChangeKind::DefaultValueChanged { .. } => "DefaultValueChanged",
+        ChangeKind::EnumValueAdded { .. } => "EnumValueAdded",
+        ChangeKind::EnumValueRemoved { .. } => "EnumValueRemoved",
+        ChangeKind::UnionMemberAdded { .. } => "UnionMemberAdded",
+        ChangeKind::UnionMemberRemoved { .. } => "UnionMemberRemoved",
+        ChangeKind::InterfaceAdded { .. } => "InterfaceAdded",
+        ChangeKind::InterfaceRemoved { .. } => "InterfaceRemoved",
+        ChangeKind::DirectiveAdded { .. } => "DirectiveAdded",
+        ChangeKind::DirectiveRemoved { .. } => "DirectiveRemoved",
+        ChangeKind::DirectiveLocationRemoved { .. } => {
+            "DirectiveLocationRemoved"
+        }
+        ChangeKind::DirectiveRepeatableRemoved { .. } => {
+            "DirectiveRepeatableRemoved"
+        }
+        ChangeKind::DeprecationAdded { .. } => "DeprecationAdded",
+    }
+}
+
+#[test]
+fn matches_expected_changes() {
+    let actual = changes().iter()
+        .map(|change| {
+            let position = change.position
+                .map(|pos| pos.to_string())
+                .unwrap_or_else(|| "-".into());
+            format!("{:?} {} {} {}", change.criticality, change.path,
+                    position, change.message)
+        })
+        .collect::<Vec<_>>();
+    let expected = fs::read_to_string("tests/schema_diff/changes.txt")
+        .unwrap();
+    assert_eq!(actual, expected.lines().collect::<Vec<_>>());
+}
+
+#[test]
+fn every_kind_is_exercised() {
+    let kinds = changes().iter()
+        .map(|change| kind_name(&change.kind))
+        .collect::<BTreeSet<_>>();
+    assert_eq!(kinds.len(), 24, "{:?}", kinds);
+}
+
+#[test]
+fn deterministic() {
+    assert_eq!(changes(), changes());
+}
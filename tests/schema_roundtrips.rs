@@ -13,6 +13,10 @@ fn roundtrip(filename: &str) {
     f.read_to_string(&mut buf).unwrap();
     let ast = parse_schema::<String>(&buf).unwrap().to_owned();
     assert_eq!(ast.to_string(), buf);
+
+    let mut bytes = Vec::new();
+    ast.write_to(&mut bytes).unwrap();
+    assert_eq!(String::from_utf8(bytes).unwrap(), buf);
 }
 
 fn roundtrip2(filename: &str) {
@@ -46,7 +50,55 @@ fn roundtrip2(filename: &str) {
 #[test] fn extend_enum() { roundtrip("extend_enum"); }
 #[test] fn input_type() { roundtrip("input_type"); }
 #[test] fn extend_input() { roundtrip2("extend_input"); }
+#[test] fn extend_schema() { roundtrip("extend_schema"); }
+#[test] fn extend_all() { roundtrip("extend_all"); }
 #[test] fn directive() { roundtrip("directive"); }
 #[test] fn kitchen_sink() { roundtrip2("kitchen-sink"); }
 #[test] fn directive_descriptions() { roundtrip2("directive_descriptions"); }
 #[test] fn repeatable() {roundtrip("repeatable")}
+#[test] fn block_string_description() { roundtrip("block_string_description"); }
+#[test] fn all_descriptions() { roundtrip("all_descriptions"); }
+#[test] fn negative_defaults() { roundtrip("negative_defaults"); }
+
+#[test]
+fn description_values() {
+    use graphql_parser::schema::{Definition, Description, Document};
+    use graphql_parser::schema::ScalarType;
+    use graphql_parser::schema::TypeDefinition;
+
+    let descriptions = [
+        "single line",
+        "Multi-line\n  indented\n\nwith an empty line",
+        "  first line indented\nsecond",
+        "    all lines\n    indented",
+        "\nleading empty line",
+        "trailing empty line\n",
+        "contains \"\"\" and \\\"\"\" quotes",
+        "ends with a quote\"",
+        "control \u{8}\u{c}\u{1} and astral \u{1F600} characters",
+        "carriage\r\nreturn",
+    ];
+    for (description, &block) in descriptions.iter()
+        .flat_map(|d| [false, true].iter().map(move |b| (d, b)))
+    {
+        let mut scalar = ScalarType::new("Date".to_string());
+        scalar.description = Some(Description {
+            value: description.to_string(),
+            block,
+        });
+        let doc = Document::<String> {
+            definitions: vec![Definition::TypeDefinition(
+                TypeDefinition::Scalar(scalar))],
+        };
+        let printed = doc.to_string();
+        let reparsed = parse_schema::<String>(&printed).unwrap();
+        assert_eq!(reparsed.to_string(), printed);
+        match reparsed.definitions[0] {
+            Definition::TypeDefinition(TypeDefinition::Scalar(ref s)) => {
+                assert_eq!(s.description.as_deref(), Some(*description),
+                           "printed as {}", printed);
+            }
+            _ => unreachable!(),
+        }
+    }
+}
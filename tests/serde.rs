@@ -0,0 +1,60 @@
+#![cfg(feature = "serde")]
+extern crate graphql_parser;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use std::fs;
+
+use graphql_parser::{parse_query, parse_schema, query, schema};
+
+const VALUES_QUERY: &str = r#"
+query Values($int: Int! = -3, $float: [Float!]! = [1.5e3], $e: Color = RED)
+  @live {
+  field(s: "text", b: """block""", n: null, list: [1, [$e]],
+        obj: {nested: {deep: true}}) @skip(if: false)
+}
+"#;
+
+fn read(path: &str) -> String {
+    fs::read_to_string(format!("tests/{}.graphql", path)).unwrap()
+}
+
+fn roundtrip_query(source: &str) {
+    let ast = parse_query::<String>(source).unwrap();
+    let json = serde_json::to_string(&ast).unwrap();
+    let borrowed = parse_query::<&str>(source).unwrap();
+    assert_eq!(serde_json::to_string(&borrowed).unwrap(), json);
+    let decoded: query::Document<String> = serde_json::from_str(&json)
+        .unwrap();
+    assert_eq!(decoded.to_string(), ast.to_string());
+    assert_eq!(decoded, ast);
+}
+
+fn roundtrip_schema(source: &str) {
+    let ast = parse_schema::<String>(source).unwrap();
+    let json = serde_json::to_string(&ast).unwrap();
+    let borrowed = parse_schema::<&str>(source).unwrap();
+    assert_eq!(serde_json::to_string(&borrowed).unwrap(), json);
+    let decoded: schema::Document<String> = serde_json::from_str(&json)
+        .unwrap();
+    assert_eq!(decoded.to_string(), ast.to_string());
+    assert_eq!(decoded, ast);
+}
+
+#[test] fn query_kitchen_sink() { roundtrip_query(&read("queries/kitchen-sink")); }
+#[test] fn query_values() { roundtrip_query(VALUES_QUERY); }
+#[test] fn schema_kitchen_sink() { roundtrip_schema(&read("schemas/kitchen-sink")); }
+#[test] fn schema_extensions() { roundtrip_schema(&read("schemas/extend_input")); }
+
+#[test]
+fn representation() {
+    let ast = parse_query::<&str>("{ a(x: [1, RED, $v, null]) }").unwrap();
+    let json = serde_json::to_value(&ast).unwrap();
+    let field = &json["definitions"][0]["Operation"]["SelectionSet"]
+        ["items"][0]["Field"];
+    assert_eq!(field["position"],
+        serde_json::json!({"line": 1, "column": 3, "offset": 2}));
+    assert_eq!(field["arguments"], serde_json::json!([
+        ["x", {"List": [{"Int": 1}, {"Enum": "RED"}, {"Variable": "v"},
+                        "Null"]}],
+    ]));
+}
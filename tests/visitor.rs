@@ -0,0 +1,174 @@
+extern crate graphql_parser;
+#[cfg(test)] #[macro_use] extern crate pretty_assertions;
+
+use graphql_parser::parse_query;
+use graphql_parser::query::{visit, QueryAstNode, QueryVisitor};
+
+#[derive(Default)]
+struct Trace(Vec<String>);
+
+fn label(node: QueryAstNode<String>) -> String {
+    match node {
+        QueryAstNode::Document(_) => "Document".into(),
+        QueryAstNode::OperationDefinition(op) => {
+            format!("OperationDefinition {}", op.name().unwrap_or("-"))
+        }
+        QueryAstNode::FragmentDefinition(frag) => {
+            format!("FragmentDefinition {}", frag.name)
+        }
+        QueryAstNode::VariableDefinition(var) => {
+            format!("VariableDefinition {}", var.name)
+        }
+        QueryAstNode::SelectionSet(_) => "SelectionSet".into(),
+        QueryAstNode::Field(field) => format!("Field {}", field.name),
+        QueryAstNode::FragmentSpread(spread) => {
+            format!("FragmentSpread {}", spread.fragment_name)
+        }
+        QueryAstNode::InlineFragment(_) => "InlineFragment".into(),
+        QueryAstNode::Directive(dir) => format!("Directive {}", dir.name),
+        QueryAstNode::Argument(name, _) => format!("Argument {}", name),
+        QueryAstNode::ObjectField(name, _) => {
+            format!("ObjectField {}", name)
+        }
+        QueryAstNode::Value(value) => format!("Value {}", value),
+    }
+}
+
+impl<'ast> QueryVisitor<'ast, String> for Trace {
+    fn enter(&mut self, node: QueryAstNode<'ast, String>) {
+        self.0.push(format!("enter {}", label(node)));
+    }
+    fn leave(&mut self, node: QueryAstNode<'ast, String>) {
+        self.0.push(format!("leave {}", label(node)));
+    }
+}
+
+fn trace(query: &str) -> Vec<String> {
+    let doc = parse_query::<String>(query).unwrap();
+    let mut trace = Trace::default();
+    visit(&doc, &mut trace);
+    trace.0
+}
+
+#[test]
+fn field_arguments_and_directives() {
+    assert_eq!(trace("{ a(x: 1) @d { b } }"), [
+        "enter Document",
+        "enter OperationDefinition -",
+        "enter SelectionSet",
+        "enter Field a",
+        "enter Argument x",
+        "enter Value 1",
+        "leave Value 1",
+        "leave Argument x",
+        "enter Directive d",
+        "leave Directive d",
+        "enter SelectionSet",
+        "enter Field b",
+        "leave Field b",
+        "leave SelectionSet",
+        "leave Field a",
+        "leave SelectionSet",
+        "leave OperationDefinition -",
+        "leave Document",
+    ]);
+}
+
+#[test]
+fn variables_and_nested_values() {
+    assert_eq!(trace("query Q($v: [Int] = [1]) @q { f(o: {k: $v}) }"), [
+        "enter Document",
+        "enter OperationDefinition Q",
+        "enter VariableDefinition v",
+        "enter Value [1]",
+        "enter Value 1",
+        "leave Value 1",
+        "leave Value [1]",
+        "leave VariableDefinition v",
+        "enter Directive q",
+        "leave Directive q",
+        "enter SelectionSet",
+        "enter Field f",
+        "enter Argument o",
+        "enter Value {k: $v}",
+        "enter ObjectField k",
+        "enter Value $v",
+        "leave Value $v",
+        "leave ObjectField k",
+        "leave Value {k: $v}",
+        "leave Argument o",
+        "leave Field f",
+        "leave SelectionSet",
+        "leave OperationDefinition Q",
+        "leave Document",
+    ]);
+}
+
+#[test]
+fn fragments() {
+    assert_eq!(trace("
+        { ... @skip(if: false) { a } ...F }
+        fragment F on T @f { b }
+    "), [
+        "enter Document",
+        "enter OperationDefinition -",
+        "enter SelectionSet",
+        "enter InlineFragment",
+        "enter Directive skip",
+        "enter Argument if",
+        "enter Value false",
+        "leave Value false",
+        "leave Argument if",
+        "leave Directive skip",
+        "enter SelectionSet",
+        "enter Field a",
+        "leave Field a",
+        "leave SelectionSet",
+        "leave InlineFragment",
+        "enter FragmentSpread F",
+        "leave FragmentSpread F",
+        "leave SelectionSet",
+        "leave OperationDefinition -",
+        "enter FragmentDefinition F",
+        "enter Directive f",
+        "leave Directive f",
+        "enter SelectionSet",
+        "enter Field b",
+        "leave Field b",
+        "leave SelectionSet",
+        "leave FragmentDefinition F",
+        "leave Document",
+    ]);
+}
+
+#[cfg(feature = "fragment-arguments")]
+#[test]
+fn fragment_arguments() {
+    assert_eq!(trace("
+        { ...F(a: 1) }
+        fragment F($a: Int = 2) on T { c }
+    "), [
+        "enter Document",
+        "enter OperationDefinition -",
+        "enter SelectionSet",
+        "enter FragmentSpread F",
+        "enter Argument a",
+        "enter Value 1",
+        "leave Value 1",
+        "leave Argument a",
+        "leave FragmentSpread F",
+        "leave SelectionSet",
+        "leave OperationDefinition -",
+        "enter FragmentDefinition F",
+        "enter VariableDefinition a",
+        "enter Value 2",
+        "leave Value 2",
+        "leave VariableDefinition a",
+        "enter SelectionSet",
+        "enter Field c",
+        "leave Field c",
+        "leave SelectionSet",
+        "leave FragmentDefinition F",
+        "leave Document",
+    ]);
+}